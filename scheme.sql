@@ -10,7 +10,8 @@ CREATE TABLE flat (
    tamper_alarm_topic TEXT,
    broker_user TEXT NOT NULL,
    broker_pw TEXT NOT NULL,
-   broker_pw_iv TEXT NOT NULL
+   broker_pw_iv TEXT NOT NULL,
+   door_alarm_topic TEXT
 );
 
 CREATE TABLE client_user (
@@ -22,15 +23,207 @@ CREATE TABLE client_user (
    user_type  INTEGER NOT NULL,
    active BOOL NOT NULL,
    flat_id INTEGER,
-   FOREIGN KEY(flat_id) REFERENCES flat(id)
+   totp_secret TEXT,
+   totp_last_step INTEGER,
+   two_factor_failures INTEGER NOT NULL DEFAULT 0,
+   oidc_issuer TEXT,
+   oidc_subject TEXT,
+   must_change_password BOOL NOT NULL,
+   valid_from TEXT,
+   valid_until TEXT,
+   FOREIGN KEY(flat_id) REFERENCES flat(id),
+   UNIQUE(oidc_issuer, oidc_subject)
 );
 
 CREATE TABLE user_session (
    id INTEGER PRIMARY KEY AUTOINCREMENT,
+   token_hash TEXT NOT NULL UNIQUE,
    login_datetime TEXT NOT NULL,
+   expiry_datetime TEXT NOT NULL,
+   last_seen_datetime TEXT NOT NULL,
+   ip_address TEXT,
+   user_agent TEXT,
+   user_id INTEGER NOT NULL,
+   FOREIGN KEY(user_id) REFERENCES client_user(id)
+);
+
+CREATE TABLE recovery_code (
+   id INTEGER PRIMARY KEY AUTOINCREMENT,
+   code_hash TEXT NOT NULL,
+   user_id INTEGER NOT NULL,
+   FOREIGN KEY(user_id) REFERENCES client_user(id)
+);
+
+CREATE TABLE login_throttle (
+   id INTEGER PRIMARY KEY AUTOINCREMENT,
+   subject_type INTEGER NOT NULL,
+   subject TEXT NOT NULL,
+   failed_attempts INTEGER NOT NULL,
+   last_failure_datetime TEXT NOT NULL,
+   locked_until TEXT,
+   UNIQUE(subject_type, subject)
+);
+
+CREATE TABLE api_token (
+   id INTEGER PRIMARY KEY AUTOINCREMENT,
+   name TEXT NOT NULL,
+   token_hash TEXT NOT NULL UNIQUE,
+   scopes TEXT NOT NULL,
+   creation_datetime TEXT NOT NULL,
+   last_used_datetime TEXT,
+   user_id INTEGER NOT NULL,
+   FOREIGN KEY(user_id) REFERENCES client_user(id)
+);
+
+CREATE TABLE guest_code (
+   id INTEGER PRIMARY KEY AUTOINCREMENT,
+   label TEXT NOT NULL,
+   code_hash TEXT NOT NULL UNIQUE,
+   valid_from TEXT NOT NULL,
+   valid_until TEXT NOT NULL,
+   max_uses INTEGER NOT NULL,
+   uses INTEGER NOT NULL,
+   creation_datetime TEXT NOT NULL,
+   flat_id INTEGER NOT NULL,
+   user_id INTEGER NOT NULL,
+   door_id INTEGER NOT NULL,
+   FOREIGN KEY(flat_id) REFERENCES flat(id),
+   FOREIGN KEY(user_id) REFERENCES client_user(id),
+   FOREIGN KEY(door_id) REFERENCES door(id)
+);
+
+CREATE TABLE guest_redemption (
+   id INTEGER PRIMARY KEY AUTOINCREMENT,
+   label TEXT NOT NULL,
+   redemption_datetime TEXT NOT NULL,
+   ip_address TEXT,
+   flat_id INTEGER NOT NULL,
+   FOREIGN KEY(flat_id) REFERENCES flat(id)
+);
+
+CREATE TABLE password_reset (
+   id INTEGER PRIMARY KEY AUTOINCREMENT,
+   code_hash TEXT NOT NULL UNIQUE,
+   creation_datetime TEXT NOT NULL,
+   expiry_datetime TEXT NOT NULL,
+   user_id INTEGER NOT NULL,
+   FOREIGN KEY(user_id) REFERENCES client_user(id)
+);
+
+CREATE TABLE role (
+   id INTEGER PRIMARY KEY AUTOINCREMENT,
+   name TEXT NOT NULL UNIQUE,
+   permissions TEXT NOT NULL
+);
+
+CREATE TABLE user_role (
+   user_id INTEGER NOT NULL,
+   role_id INTEGER NOT NULL,
+   PRIMARY KEY(user_id, role_id),
+   FOREIGN KEY(user_id) REFERENCES client_user(id),
+   FOREIGN KEY(role_id) REFERENCES role(id)
+);
+
+CREATE TABLE passkey (
+   id INTEGER PRIMARY KEY AUTOINCREMENT,
+   name TEXT NOT NULL,
+   credential_id TEXT NOT NULL UNIQUE,
+   credential TEXT NOT NULL,
+   creation_datetime TEXT NOT NULL,
+   last_used_datetime TEXT,
+   user_id INTEGER NOT NULL,
+   FOREIGN KEY(user_id) REFERENCES client_user(id)
+);
+
+CREATE TABLE password_history (
+   id INTEGER PRIMARY KEY AUTOINCREMENT,
+   pw_hash TEXT NOT NULL,
+   pw_salt TEXT NOT NULL,
+   pw_config TEXT NOT NULL,
+   creation_datetime TEXT NOT NULL,
+   user_id INTEGER NOT NULL,
+   FOREIGN KEY(user_id) REFERENCES client_user(id)
+);
+
+CREATE TABLE audit_log (
+   id INTEGER PRIMARY KEY AUTOINCREMENT,
+   datetime TEXT NOT NULL,
+   actor TEXT,
+   action INTEGER NOT NULL,
+   target TEXT,
+   ip TEXT
+);
+
+CREATE TRIGGER audit_log_no_update BEFORE UPDATE ON audit_log
+BEGIN
+   SELECT RAISE(ABORT, 'The audit log is append-only');
+END;
+
+CREATE TRIGGER audit_log_no_delete BEFORE DELETE ON audit_log
+BEGIN
+   SELECT RAISE(ABORT, 'The audit log is append-only');
+END;
+
+CREATE TABLE access_window (
+   id INTEGER PRIMARY KEY AUTOINCREMENT,
+   weekday INTEGER NOT NULL,
+   start_time TEXT NOT NULL,
+   end_time TEXT NOT NULL,
    user_id INTEGER NOT NULL,
    FOREIGN KEY(user_id) REFERENCES client_user(id)
 );
 
+CREATE TABLE access_exception (
+   id INTEGER PRIMARY KEY AUTOINCREMENT,
+   date TEXT NOT NULL,
+   allowed INTEGER NOT NULL,
+   user_id INTEGER NOT NULL,
+   FOREIGN KEY(user_id) REFERENCES client_user(id)
+);
+
+CREATE TABLE door (
+   id INTEGER PRIMARY KEY AUTOINCREMENT,
+   name TEXT NOT NULL UNIQUE,
+   opener_pin INTEGER NOT NULL UNIQUE,
+   opening_time INTEGER NOT NULL,
+   sensor_pin INTEGER,
+   pulse_pattern TEXT
+);
+
+CREATE TABLE flat_door (
+   flat_id INTEGER NOT NULL,
+   door_id INTEGER NOT NULL,
+   PRIMARY KEY(flat_id, door_id),
+   FOREIGN KEY(flat_id) REFERENCES flat(id),
+   FOREIGN KEY(door_id) REFERENCES door(id)
+);
+
+CREATE TABLE user_door (
+   user_id INTEGER NOT NULL,
+   door_id INTEGER NOT NULL,
+   PRIMARY KEY(user_id, door_id),
+   FOREIGN KEY(user_id) REFERENCES client_user(id),
+   FOREIGN KEY(door_id) REFERENCES door(id)
+);
+
+CREATE TABLE door_event (
+   id INTEGER PRIMARY KEY AUTOINCREMENT,
+   datetime TEXT NOT NULL,
+   door TEXT,
+   user TEXT,
+   flat_id INTEGER,
+   source INTEGER NOT NULL,
+   outcome INTEGER NOT NULL
+);
+
+CREATE TRIGGER door_event_no_update BEFORE UPDATE ON door_event
+BEGIN
+   SELECT RAISE(ABORT, 'The door history is append-only');
+END;
+
+CREATE TRIGGER door_event_no_delete BEFORE DELETE ON door_event
+BEGIN
+   SELECT RAISE(ABORT, 'The door history is append-only');
+END;
 
-INSERT INTO client_user (name, pw_hash, pw_salt, pw_config, user_type, active) VALUES ("admin", "admin", "", "plain", 2, 1);
+INSERT INTO client_user (name, pw_hash, pw_salt, pw_config, user_type, active, must_change_password) VALUES ("admin", "admin", "", "plain", 2, 1, 1);
@@ -0,0 +1,91 @@
+use super::{rusqlite, Connection, Entry, Identifier};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+#[path = "./access_exception_test.rs"]
+mod access_exception_test;
+
+/// Access-Exception entry of the corresponding "access_exception" table.
+/// It overrides the access windows of the user for a whole day, e.g. on holidays.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct AccessExceptionEntry<ID: Identifier = u32, URef: Entry = u32> {
+    pub id: ID,
+    pub date: NaiveDate,
+    /// Whether the user may open the door during the whole day or not at all
+    pub allowed: bool,
+    pub user: URef,
+}
+
+impl<URef: Entry> Entry for AccessExceptionEntry<u32, URef> {
+    #[inline(always)]
+    fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    fn delete_entry(conn: &Connection, id: u32) -> Result<(), rusqlite::Error> {
+        conn.execute("DELETE FROM access_exception WHERE ID=?1", &[&id])?;
+        Ok(())
+    }
+
+    fn update(&self, conn: &Connection) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "UPDATE access_exception SET date = ?1, allowed = ?2, user_id = ?3 WHERE id = ?4",
+            &[&self.date, &self.allowed, &self.user.get_id(), &self.id],
+        )?;
+        Ok(())
+    }
+}
+
+impl<URef: Entry> AccessExceptionEntry<(), URef> {
+    pub fn create(self, conn: &Connection) -> Result<AccessExceptionEntry, rusqlite::Error> {
+        let user_id = self.user.get_id();
+        conn.execute(
+            "INSERT INTO access_exception (date, allowed, user_id) VALUES (?1, ?2, ?3)",
+            &[&self.date, &self.allowed, &user_id],
+        )?;
+        Ok(AccessExceptionEntry {
+            id: (conn.last_insert_rowid() as u32),
+            date: self.date,
+            allowed: self.allowed,
+            user: user_id,
+        })
+    }
+}
+
+impl AccessExceptionEntry<u32, u32> {
+    /// Converts a rusqlite row to an AccessExceptionEntry
+    fn row_2_access_exception(row: &rusqlite::Row) -> Self {
+        Self {
+            id: row.get::<usize, u32>(0),
+            date: row.get::<usize, NaiveDate>(1),
+            allowed: row.get::<usize, bool>(2),
+            user: row.get::<usize, u32>(3),
+        }
+    }
+
+    pub fn get_by_id(conn: &Connection, id: u32) -> Result<Option<Self>, rusqlite::Error> {
+        let mut stmt = conn.prepare(
+            "SELECT id, date, allowed, user_id FROM access_exception WHERE id=?1 LIMIT 1",
+        )?;
+        return stmt
+            .query_map(&[&id], Self::row_2_access_exception)?
+            .next()
+            .map_or_else(|| Ok(None), |entry_result| entry_result.map(Some));
+    }
+
+    /// Returns the exceptions of the user ordered by their date
+    pub fn get_by_user(conn: &Connection, user: u32) -> Result<Vec<Self>, rusqlite::Error> {
+        let mut stmt = conn.prepare(
+            "SELECT id, date, allowed, user_id FROM access_exception WHERE user_id=?1 ORDER BY date",
+        )?;
+        return stmt
+            .query_map(&[&user], Self::row_2_access_exception)?
+            .collect();
+    }
+
+    pub fn delete_by_user(conn: &Connection, user: u32) -> Result<(), rusqlite::Error> {
+        conn.execute("DELETE FROM access_exception WHERE user_id=?1", &[&user])?;
+        Ok(())
+    }
+}
@@ -0,0 +1,71 @@
+use super::*;
+use crate::db_entry::UserEntry;
+
+impl Default for AccessExceptionEntry<(), u32> {
+    fn default() -> Self {
+        Self {
+            id: (),
+            date: NaiveDate::from_ymd_opt(2020, 12, 24).unwrap(),
+            allowed: false,
+            user: 0,
+        }
+    }
+}
+
+impl Default for AccessExceptionEntry {
+    fn default() -> Self {
+        Self {
+            id: 42,
+            date: NaiveDate::from_ymd_opt(2020, 12, 24).unwrap(),
+            allowed: false,
+            user: 42,
+        }
+    }
+}
+
+#[test]
+fn scenario_1_with_all_methods() {
+    let sql_scheme = include_str!("../../scheme.sql");
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(sql_scheme).unwrap();
+
+    let user = UserEntry::default().create(&conn).unwrap();
+
+    let mut later = AccessExceptionEntry {
+        date: NaiveDate::from_ymd_opt(2020, 12, 31).unwrap(),
+        user: user.get_id(),
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+    let earlier = AccessExceptionEntry {
+        user: user.get_id(),
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+
+    assert_eq!(
+        AccessExceptionEntry::get_by_user(&conn, user.get_id()).unwrap(),
+        vec![earlier.clone(), later.clone()]
+    );
+
+    later.allowed = true;
+    later.update(&conn).unwrap();
+    assert_eq!(
+        AccessExceptionEntry::get_by_id(&conn, later.get_id()).unwrap(),
+        Some(later.clone())
+    );
+
+    earlier.delete(&conn).unwrap();
+    assert_eq!(
+        AccessExceptionEntry::get_by_user(&conn, user.get_id()).unwrap(),
+        vec![later.clone()]
+    );
+
+    AccessExceptionEntry::delete_by_user(&conn, user.get_id()).unwrap();
+    assert_eq!(
+        AccessExceptionEntry::get_by_id(&conn, later.get_id()).unwrap(),
+        None
+    );
+}
@@ -0,0 +1,97 @@
+use super::{rusqlite, Connection, Entry, Identifier};
+use chrono::NaiveTime;
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+#[path = "./access_window_test.rs"]
+mod access_window_test;
+
+/// Access-Window entry of the corresponding "access_window" table.
+/// An user with at least one window may only open the door during his weekly windows.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct AccessWindowEntry<ID: Identifier = u32, URef: Entry = u32> {
+    pub id: ID,
+    /// The day of the week, beginning with 0 for monday
+    pub weekday: u32,
+    pub start_time: NaiveTime,
+    /// The end of the window, which is excluded
+    pub end_time: NaiveTime,
+    pub user: URef,
+}
+
+impl<URef: Entry> Entry for AccessWindowEntry<u32, URef> {
+    #[inline(always)]
+    fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    fn delete_entry(conn: &Connection, id: u32) -> Result<(), rusqlite::Error> {
+        conn.execute("DELETE FROM access_window WHERE ID=?1", &[&id])?;
+        Ok(())
+    }
+
+    fn update(&self, conn: &Connection) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "UPDATE access_window SET weekday = ?1, start_time = ?2, end_time = ?3, user_id = ?4 WHERE id = ?5",
+            &[
+                &self.weekday,
+                &self.start_time,
+                &self.end_time,
+                &self.user.get_id(),
+                &self.id,
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+impl<URef: Entry> AccessWindowEntry<(), URef> {
+    pub fn create(self, conn: &Connection) -> Result<AccessWindowEntry, rusqlite::Error> {
+        let user_id = self.user.get_id();
+        conn.execute(
+            "INSERT INTO access_window (weekday, start_time, end_time, user_id) VALUES (?1, ?2, ?3, ?4)",
+            &[&self.weekday, &self.start_time, &self.end_time, &user_id],
+        )?;
+        Ok(AccessWindowEntry {
+            id: (conn.last_insert_rowid() as u32),
+            weekday: self.weekday,
+            start_time: self.start_time,
+            end_time: self.end_time,
+            user: user_id,
+        })
+    }
+}
+
+impl AccessWindowEntry<u32, u32> {
+    /// Converts a rusqlite row to an AccessWindowEntry
+    fn row_2_access_window(row: &rusqlite::Row) -> Self {
+        Self {
+            id: row.get::<usize, u32>(0),
+            weekday: row.get::<usize, u32>(1),
+            start_time: row.get::<usize, NaiveTime>(2),
+            end_time: row.get::<usize, NaiveTime>(3),
+            user: row.get::<usize, u32>(4),
+        }
+    }
+
+    pub fn get_by_id(conn: &Connection, id: u32) -> Result<Option<Self>, rusqlite::Error> {
+        let mut stmt = conn.prepare("SELECT id, weekday, start_time, end_time, user_id FROM access_window WHERE id=?1 LIMIT 1")?;
+        return stmt
+            .query_map(&[&id], Self::row_2_access_window)?
+            .next()
+            .map_or_else(|| Ok(None), |entry_result| entry_result.map(Some));
+    }
+
+    /// Returns the windows of the user ordered by the week
+    pub fn get_by_user(conn: &Connection, user: u32) -> Result<Vec<Self>, rusqlite::Error> {
+        let mut stmt = conn.prepare("SELECT id, weekday, start_time, end_time, user_id FROM access_window WHERE user_id=?1 ORDER BY weekday, start_time")?;
+        return stmt
+            .query_map(&[&user], Self::row_2_access_window)?
+            .collect();
+    }
+
+    pub fn delete_by_user(conn: &Connection, user: u32) -> Result<(), rusqlite::Error> {
+        conn.execute("DELETE FROM access_window WHERE user_id=?1", &[&user])?;
+        Ok(())
+    }
+}
@@ -0,0 +1,73 @@
+use super::*;
+use crate::db_entry::UserEntry;
+
+impl Default for AccessWindowEntry<(), u32> {
+    fn default() -> Self {
+        Self {
+            id: (),
+            weekday: 0,
+            start_time: NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+            end_time: NaiveTime::from_hms_opt(12, 0, 0).unwrap(),
+            user: 0,
+        }
+    }
+}
+
+impl Default for AccessWindowEntry {
+    fn default() -> Self {
+        Self {
+            id: 42,
+            weekday: 0,
+            start_time: NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+            end_time: NaiveTime::from_hms_opt(12, 0, 0).unwrap(),
+            user: 42,
+        }
+    }
+}
+
+#[test]
+fn scenario_1_with_all_methods() {
+    let sql_scheme = include_str!("../../scheme.sql");
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(sql_scheme).unwrap();
+
+    let user = UserEntry::default().create(&conn).unwrap();
+
+    let mut tuesday = AccessWindowEntry {
+        weekday: 1,
+        user: user.get_id(),
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+    let monday = AccessWindowEntry {
+        user: user.get_id(),
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+
+    assert_eq!(
+        AccessWindowEntry::get_by_user(&conn, user.get_id()).unwrap(),
+        vec![monday.clone(), tuesday.clone()]
+    );
+
+    tuesday.end_time = NaiveTime::from_hms_opt(18, 30, 0).unwrap();
+    tuesday.update(&conn).unwrap();
+    assert_eq!(
+        AccessWindowEntry::get_by_id(&conn, tuesday.get_id()).unwrap(),
+        Some(tuesday.clone())
+    );
+
+    monday.delete(&conn).unwrap();
+    assert_eq!(
+        AccessWindowEntry::get_by_user(&conn, user.get_id()).unwrap(),
+        vec![tuesday.clone()]
+    );
+
+    AccessWindowEntry::delete_by_user(&conn, user.get_id()).unwrap();
+    assert_eq!(
+        AccessWindowEntry::get_by_id(&conn, tuesday.get_id()).unwrap(),
+        None
+    );
+}
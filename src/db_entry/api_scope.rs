@@ -0,0 +1,84 @@
+use super::Permission;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+#[cfg(test)]
+#[path = "./api_scope_test.rs"]
+mod api_scope_test;
+
+/// The permissions of an API token.
+/// They are stored space-separated in the scopes database field, like the scopes of OAuth.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum ApiScope {
+    /// Activates the door opener of the flat
+    #[serde(rename = "door:open")]
+    DoorOpen,
+    /// Reads the state of the door
+    #[serde(rename = "events:read")]
+    EventsRead,
+    /// Uses the administration
+    #[serde(rename = "admin")]
+    Admin,
+}
+
+impl ApiScope {
+    /// get a Vector of all scopes
+    pub fn get_list() -> Vec<Self> {
+        vec![Self::DoorOpen, Self::EventsRead, Self::Admin]
+    }
+
+    /// The permission, which the user needs to use the scope
+    pub fn permission(&self) -> Permission {
+        match *self {
+            Self::DoorOpen => Permission::DoorOpen,
+            Self::EventsRead => Permission::EventsView,
+            Self::Admin => Permission::UserManage,
+        }
+    }
+
+    /// Checks whether an user with the permissions may use the scope
+    pub fn is_allowed_for(&self, permissions: &[Permission]) -> bool {
+        permissions.contains(&self.permission())
+    }
+
+    /// Converts the scopes to the space-separated representation of the database
+    pub fn join(scopes: &[Self]) -> String {
+        scopes
+            .iter()
+            .map(Self::to_string)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Parses the space-separated representation of the database, unknown scopes are ignored
+    pub fn split(scopes: &str) -> Vec<Self> {
+        scopes
+            .split_whitespace()
+            .filter_map(|scope| scope.parse().ok())
+            .collect()
+    }
+}
+
+impl fmt::Display for ApiScope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Self::DoorOpen => write!(f, "door:open"),
+            Self::EventsRead => write!(f, "events:read"),
+            Self::Admin => write!(f, "admin"),
+        }
+    }
+}
+
+impl FromStr for ApiScope {
+    type Err = ();
+
+    fn from_str(scope: &str) -> Result<Self, Self::Err> {
+        match scope {
+            "door:open" => Ok(Self::DoorOpen),
+            "events:read" => Ok(Self::EventsRead),
+            "admin" => Ok(Self::Admin),
+            _ => Err(()),
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use super::*;
+use crate::db_entry::UserType;
+
+#[test]
+fn door_open_only_for_users() {
+    assert!(ApiScope::DoorOpen.is_allowed_for(&Permission::defaults_for(UserType::User)));
+    assert!(!ApiScope::DoorOpen.is_allowed_for(&Permission::defaults_for(UserType::Admin)));
+}
+
+#[test]
+fn admin_only_for_admins() {
+    assert!(!ApiScope::Admin.is_allowed_for(&Permission::defaults_for(UserType::User)));
+    assert!(ApiScope::Admin.is_allowed_for(&Permission::defaults_for(UserType::Admin)));
+}
+
+#[test]
+fn events_read_for_everyone() {
+    assert!(ApiScope::EventsRead.is_allowed_for(&Permission::defaults_for(UserType::User)));
+    assert!(ApiScope::EventsRead.is_allowed_for(&Permission::defaults_for(UserType::Admin)));
+}
+
+#[test]
+fn scopes_of_a_concierge_role() {
+    assert!(ApiScope::DoorOpen.is_allowed_for(&[Permission::DoorOpen, Permission::CameraView]));
+    assert!(!ApiScope::Admin.is_allowed_for(&[Permission::DoorOpen, Permission::CameraView]));
+}
+
+#[test]
+fn join_scopes() {
+    assert_eq!(
+        ApiScope::join(&[ApiScope::DoorOpen, ApiScope::EventsRead]),
+        "door:open events:read"
+    );
+    assert_eq!(ApiScope::join(&[]), "");
+}
+
+#[test]
+fn split_scopes() {
+    assert_eq!(
+        ApiScope::split(" door:open  admin unknown "),
+        vec![ApiScope::DoorOpen, ApiScope::Admin]
+    );
+    assert!(ApiScope::split("").is_empty());
+}
+
+#[test]
+fn from_str() {
+    assert_matches!("events:read".parse::<ApiScope>(), Ok(ApiScope::EventsRead));
+    assert_matches!("events".parse::<ApiScope>(), Err(()));
+}
@@ -0,0 +1,130 @@
+use super::{rusqlite, ApiScope, Connection, Entry, Identifier};
+use chrono::{offset::Utc, DateTime};
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+#[path = "./api_token_test.rs"]
+mod api_token_test;
+
+/// API-Token entry of the corresponding "api_token" table.
+/// Only the hash of the token is stored, the token itself is shown once on creation.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct ApiTokenEntry<ID: Identifier = u32, URef: Entry = u32> {
+    pub id: ID,
+    pub name: String,
+    pub token_hash: String,
+    pub scopes: Vec<ApiScope>,
+    pub creation_datetime: DateTime<Utc>,
+    pub last_used_datetime: Option<DateTime<Utc>>,
+    pub user: URef,
+}
+
+impl<URef: Entry> Entry for ApiTokenEntry<u32, URef> {
+    #[inline(always)]
+    fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    fn delete_entry(conn: &Connection, id: u32) -> Result<(), rusqlite::Error> {
+        conn.execute("DELETE FROM api_token WHERE ID=?1", &[&id])?;
+        Ok(())
+    }
+
+    fn update(&self, conn: &Connection) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "UPDATE api_token SET name = ?1, token_hash = ?2, scopes = ?3, creation_datetime = ?4, last_used_datetime = ?5, user_id = ?6 WHERE id = ?7",
+            &[
+                &self.name,
+                &self.token_hash,
+                &ApiScope::join(&self.scopes),
+                &self.creation_datetime,
+                &self.last_used_datetime,
+                &self.user.get_id(),
+                &self.id,
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+impl<URef: Entry> ApiTokenEntry<(), URef> {
+    pub fn create(self, conn: &Connection) -> Result<ApiTokenEntry, rusqlite::Error> {
+        let user_id = self.user.get_id();
+        conn.execute(
+            "INSERT INTO api_token (name, token_hash, scopes, creation_datetime, last_used_datetime, user_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            &[
+                &self.name,
+                &self.token_hash,
+                &ApiScope::join(&self.scopes),
+                &self.creation_datetime,
+                &self.last_used_datetime,
+                &user_id,
+            ],
+        )?;
+        Ok(ApiTokenEntry {
+            id: (conn.last_insert_rowid() as u32),
+            name: self.name,
+            token_hash: self.token_hash,
+            scopes: self.scopes,
+            creation_datetime: self.creation_datetime,
+            last_used_datetime: self.last_used_datetime,
+            user: user_id,
+        })
+    }
+}
+
+impl ApiTokenEntry<u32, u32> {
+    /// Converts a rusqlite row to an ApiTokenEntry
+    fn row_2_api_token(row: &rusqlite::Row) -> Self {
+        Self {
+            id: row.get::<usize, u32>(0),
+            name: row.get::<usize, String>(1),
+            token_hash: row.get::<usize, String>(2),
+            scopes: ApiScope::split(&row.get::<usize, String>(3)),
+            creation_datetime: row.get::<usize, DateTime<Utc>>(4),
+            last_used_datetime: row.get::<usize, Option<DateTime<Utc>>>(5),
+            user: row.get::<usize, u32>(6),
+        }
+    }
+
+    /// Returns all tokens with the name of their user
+    pub fn get_all_with_user_name(
+        conn: &Connection,
+    ) -> Result<Vec<(Self, String)>, rusqlite::Error> {
+        let mut stmt = conn.prepare("SELECT api_token.id, api_token.name, token_hash, scopes, creation_datetime, last_used_datetime, user_id, client_user.name FROM api_token INNER JOIN client_user ON api_token.user_id = client_user.id ORDER BY client_user.name, api_token.name")?;
+        return stmt
+            .query_map(&[], |row| {
+                (Self::row_2_api_token(row), row.get::<usize, String>(7))
+            })?
+            .collect();
+    }
+
+    pub fn get_by_user(conn: &Connection, user: u32) -> Result<Vec<Self>, rusqlite::Error> {
+        let mut stmt = conn.prepare("SELECT id, name, token_hash, scopes, creation_datetime, last_used_datetime, user_id FROM api_token WHERE user_id=?1 ORDER BY name")?;
+        return stmt.query_map(&[&user], Self::row_2_api_token)?.collect();
+    }
+
+    pub fn get_by_id(conn: &Connection, id: u32) -> Result<Option<Self>, rusqlite::Error> {
+        let mut stmt = conn.prepare("SELECT id, name, token_hash, scopes, creation_datetime, last_used_datetime, user_id FROM api_token WHERE id=?1 LIMIT 1")?;
+        return stmt
+            .query_map(&[&id], Self::row_2_api_token)?
+            .next()
+            .map_or_else(|| Ok(None), |entry_result| entry_result.map(Some));
+    }
+
+    pub fn get_by_token_hash(
+        conn: &Connection,
+        token_hash: &str,
+    ) -> Result<Option<Self>, rusqlite::Error> {
+        let mut stmt = conn.prepare("SELECT id, name, token_hash, scopes, creation_datetime, last_used_datetime, user_id FROM api_token WHERE token_hash=?1 LIMIT 1")?;
+        return stmt
+            .query_map(&[&token_hash], Self::row_2_api_token)?
+            .next()
+            .map_or_else(|| Ok(None), |entry_result| entry_result.map(Some));
+    }
+
+    pub fn delete_by_user(conn: &Connection, user: u32) -> Result<(), rusqlite::Error> {
+        conn.execute("DELETE FROM api_token WHERE user_id=?1", &[&user])?;
+        Ok(())
+    }
+}
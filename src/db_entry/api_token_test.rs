@@ -0,0 +1,92 @@
+use super::*;
+use crate::db_entry::UserEntry;
+
+impl Default for ApiTokenEntry<(), u32> {
+    fn default() -> Self {
+        Self {
+            id: (),
+            name: "Home Assistant".to_string(),
+            token_hash: "hash".to_string(),
+            scopes: vec![ApiScope::DoorOpen, ApiScope::EventsRead],
+            creation_datetime: Utc::now(),
+            last_used_datetime: None,
+            user: 0,
+        }
+    }
+}
+
+impl Default for ApiTokenEntry {
+    fn default() -> Self {
+        Self {
+            id: 1,
+            name: "Home Assistant".to_string(),
+            token_hash: "hash".to_string(),
+            scopes: vec![ApiScope::DoorOpen, ApiScope::EventsRead],
+            creation_datetime: Utc::now(),
+            last_used_datetime: Some(Utc::now()),
+            user: 0,
+        }
+    }
+}
+
+#[test]
+fn scenario_1_with_all_methods() {
+    let sql_scheme = include_str!("../../scheme.sql");
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(sql_scheme).unwrap();
+
+    let user = UserEntry::default().create(&conn).unwrap();
+
+    let mut token = ApiTokenEntry {
+        user: user.get_id(),
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+    ApiTokenEntry {
+        name: "Script".to_string(),
+        token_hash: "other_hash".to_string(),
+        scopes: vec![ApiScope::EventsRead],
+        user: user.get_id(),
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+
+    token.last_used_datetime = Some(Utc::now());
+    token.update(&conn).unwrap();
+
+    let tokens = ApiTokenEntry::get_by_user(&conn, user.get_id()).unwrap();
+    assert_eq!(tokens.len(), 2);
+    assert!(tokens.contains(&token));
+
+    assert_eq!(
+        ApiTokenEntry::get_all_with_user_name(&conn).unwrap()[0],
+        (token.clone(), user.name.clone())
+    );
+
+    assert_eq!(
+        ApiTokenEntry::get_by_token_hash(&conn, "hash")
+            .unwrap()
+            .unwrap(),
+        token
+    );
+    assert_eq!(
+        ApiTokenEntry::get_by_id(&conn, token.get_id())
+            .unwrap()
+            .unwrap(),
+        token
+    );
+
+    token.delete(&conn).unwrap();
+
+    assert!(ApiTokenEntry::get_by_token_hash(&conn, "hash")
+        .unwrap()
+        .is_none());
+
+    ApiTokenEntry::delete_by_user(&conn, user.get_id()).unwrap();
+
+    assert!(ApiTokenEntry::get_all_with_user_name(&conn)
+        .unwrap()
+        .is_empty());
+}
@@ -0,0 +1,158 @@
+use super::rusqlite::{
+    self,
+    types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef},
+};
+use derive_try_from_primitive::TryFromPrimitive;
+use rocket::http::RawStr;
+use rocket::request::FromFormValue;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::fmt;
+
+#[cfg(test)]
+#[path = "./audit_action_test.rs"]
+mod audit_action_test;
+
+/// A logical enum of the action database field of the audit log.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, TryFromPrimitive)]
+#[repr(u16)]
+pub enum AuditAction {
+    /// A session has been created, regardless of the login method
+    #[serde(rename = "login")]
+    Login = 1,
+    /// The password, the second factor or the passkey was invalid
+    #[serde(rename = "login.failed")]
+    LoginFailed = 2,
+    #[serde(rename = "user.create")]
+    UserCreate = 3,
+    #[serde(rename = "user.change")]
+    UserChange = 4,
+    #[serde(rename = "user.delete")]
+    UserDelete = 5,
+    #[serde(rename = "flat.create")]
+    FlatCreate = 6,
+    /// The MQTT broker credentials of a flat have been changed
+    #[serde(rename = "flat.credentials_change")]
+    FlatCredentialsChange = 7,
+    #[serde(rename = "flat.delete")]
+    FlatDelete = 8,
+    #[serde(rename = "door.open")]
+    DoorOpen = 9,
+    /// The validity of an user has ended
+    #[serde(rename = "user.deactivate")]
+    UserDeactivate = 10,
+    /// An admin views the index of an user
+    #[serde(rename = "impersonation.start")]
+    ImpersonationStart = 11,
+    #[serde(rename = "impersonation.end")]
+    ImpersonationEnd = 12,
+    #[serde(rename = "door.create")]
+    DoorCreate = 13,
+    #[serde(rename = "door.delete")]
+    DoorDelete = 14,
+    /// The name or the pins of a door have been changed
+    #[serde(rename = "door.change")]
+    DoorChange = 15,
+    /// A door has been granted to a flat or an user
+    #[serde(rename = "door.grant")]
+    DoorGrant = 16,
+    #[serde(rename = "door.revoke")]
+    DoorRevoke = 17,
+    /// The door is kept unlocked until a time or until cancelled
+    #[serde(rename = "door.hold_open")]
+    DoorHoldOpen = 18,
+    #[serde(rename = "door.hold_cancel")]
+    DoorHoldCancel = 19,
+}
+
+impl AuditAction {
+    /// get a Vector of touples of the value and string of all actions
+    pub fn get_list() -> Vec<(u16, String)> {
+        vec![
+            Self::Login.into(),
+            Self::LoginFailed.into(),
+            Self::UserCreate.into(),
+            Self::UserChange.into(),
+            Self::UserDelete.into(),
+            Self::FlatCreate.into(),
+            Self::FlatCredentialsChange.into(),
+            Self::FlatDelete.into(),
+            Self::DoorOpen.into(),
+            Self::UserDeactivate.into(),
+            Self::ImpersonationStart.into(),
+            Self::ImpersonationEnd.into(),
+            Self::DoorCreate.into(),
+            Self::DoorDelete.into(),
+            Self::DoorChange.into(),
+            Self::DoorGrant.into(),
+            Self::DoorRevoke.into(),
+            Self::DoorHoldOpen.into(),
+            Self::DoorHoldCancel.into(),
+        ]
+    }
+}
+
+impl fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Self::Login => write!(f, "login"),
+            Self::LoginFailed => write!(f, "login.failed"),
+            Self::UserCreate => write!(f, "user.create"),
+            Self::UserChange => write!(f, "user.change"),
+            Self::UserDelete => write!(f, "user.delete"),
+            Self::FlatCreate => write!(f, "flat.create"),
+            Self::FlatCredentialsChange => write!(f, "flat.credentials_change"),
+            Self::FlatDelete => write!(f, "flat.delete"),
+            Self::DoorOpen => write!(f, "door.open"),
+            Self::UserDeactivate => write!(f, "user.deactivate"),
+            Self::ImpersonationStart => write!(f, "impersonation.start"),
+            Self::ImpersonationEnd => write!(f, "impersonation.end"),
+            Self::DoorCreate => write!(f, "door.create"),
+            Self::DoorDelete => write!(f, "door.delete"),
+            Self::DoorChange => write!(f, "door.change"),
+            Self::DoorGrant => write!(f, "door.grant"),
+            Self::DoorRevoke => write!(f, "door.revoke"),
+            Self::DoorHoldOpen => write!(f, "door.hold_open"),
+            Self::DoorHoldCancel => write!(f, "door.hold_cancel"),
+        }
+    }
+}
+
+impl From<AuditAction> for u16 {
+    #[inline(always)]
+    fn from(action: AuditAction) -> Self {
+        action as Self
+    }
+}
+
+impl From<AuditAction> for (u16, String) {
+    fn from(action: AuditAction) -> Self {
+        (action.into(), action.to_string())
+    }
+}
+
+/// needed to convert from the raw SQL-value
+impl FromSql for AuditAction {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let numeric_value = value.as_i64()?;
+        Self::try_from(u16::column_result(value)?)
+            .map_err(|_| FromSqlError::OutOfRange(numeric_value))
+    }
+}
+
+/// needed to convert to the raw SQL-value
+impl ToSql for AuditAction {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        let num: u16 = (*self).into();
+        Ok(ToSqlOutput::from(num))
+    }
+}
+
+/// needed to convert from the raw Form-value
+impl<'v> FromFormValue<'v> for AuditAction {
+    type Error = &'v RawStr;
+
+    fn from_form_value(form_value: &'v RawStr) -> Result<Self, &'v RawStr> {
+        Self::try_from(u16::from_form_value(form_value)?).map_err(|_| form_value)
+    }
+}
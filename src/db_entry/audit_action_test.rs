@@ -0,0 +1,23 @@
+use super::*;
+
+#[test]
+fn from_1() {
+    assert_matches!(AuditAction::try_from(1), Ok(AuditAction::Login));
+}
+
+#[test]
+fn from_0() {
+    assert_matches!(AuditAction::try_from(0), Err(_));
+}
+
+#[test]
+fn all_actions_are_listed() {
+    let list = AuditAction::get_list();
+
+    assert_eq!(list.len(), 19);
+    for (value, name) in list {
+        let action = AuditAction::try_from(value).unwrap();
+        assert_eq!(action.to_string(), name);
+        assert_eq!(serde_json::to_value(action).unwrap(), name);
+    }
+}
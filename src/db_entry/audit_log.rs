@@ -0,0 +1,82 @@
+use super::{rusqlite, AuditAction, Connection, Identifier};
+use chrono::{offset::Utc, DateTime};
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+#[path = "./audit_log_test.rs"]
+mod audit_log_test;
+
+/// Audit-Log entry of the corresponding "audit_log" table.
+/// The table is append-only, so there is no `Entry` implementation to update or delete an entry.
+/// The names are stored instead of IDs, as the log outlives the users and flats.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct AuditLogEntry<ID: Identifier = u32> {
+    pub id: ID,
+    pub datetime: DateTime<Utc>,
+    /// The name of the acting user or the entered name of a failed login
+    pub actor: Option<String>,
+    pub action: AuditAction,
+    /// The name of the affected user or flat or of the API token, which has been used
+    pub target: Option<String>,
+    pub ip: Option<String>,
+}
+
+impl AuditLogEntry<()> {
+    pub fn create(self, conn: &Connection) -> Result<AuditLogEntry, rusqlite::Error> {
+        conn.execute(
+            "INSERT INTO audit_log (datetime, actor, action, target, ip) VALUES (?1, ?2, ?3, ?4, ?5)",
+            &[
+                &self.datetime,
+                &self.actor,
+                &self.action,
+                &self.target,
+                &self.ip,
+            ],
+        )?;
+        Ok(AuditLogEntry {
+            id: (conn.last_insert_rowid() as u32),
+            datetime: self.datetime,
+            actor: self.actor,
+            action: self.action,
+            target: self.target,
+            ip: self.ip,
+        })
+    }
+}
+
+impl AuditLogEntry<u32> {
+    /// Converts a rusqlite row to an AuditLogEntry
+    fn row_2_audit_log(row: &rusqlite::Row) -> Self {
+        Self {
+            id: row.get::<usize, u32>(0),
+            datetime: row.get::<usize, DateTime<Utc>>(1),
+            actor: row.get::<usize, Option<String>>(2),
+            action: row.get::<usize, AuditAction>(3),
+            target: row.get::<usize, Option<String>>(4),
+            ip: row.get::<usize, Option<String>>(5),
+        }
+    }
+
+    /// Returns the newest entries, which match all given filters
+    pub fn get_filtered(
+        conn: &Connection,
+        action: Option<AuditAction>,
+        actor: Option<&str>,
+        from: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+        limit: u32,
+    ) -> Result<Vec<Self>, rusqlite::Error> {
+        let mut stmt = conn.prepare(
+            "SELECT id, datetime, actor, action, target, ip FROM audit_log \
+             WHERE (?1 IS NULL OR action = ?1) AND (?2 IS NULL OR actor = ?2) \
+             AND (?3 IS NULL OR datetime >= ?3) AND (?4 IS NULL OR datetime < ?4) \
+             ORDER BY id DESC LIMIT ?5",
+        )?;
+        return stmt
+            .query_map(
+                &[&action, &actor, &from, &until, &limit],
+                Self::row_2_audit_log,
+            )?
+            .collect();
+    }
+}
@@ -0,0 +1,118 @@
+use super::*;
+use chrono::Duration;
+
+impl Default for AuditLogEntry<()> {
+    fn default() -> Self {
+        Self {
+            id: (),
+            datetime: Utc::now(),
+            actor: Some("Alice".to_string()),
+            action: AuditAction::Login,
+            target: None,
+            ip: Some("127.0.0.1".to_string()),
+        }
+    }
+}
+
+impl Default for AuditLogEntry {
+    fn default() -> Self {
+        Self {
+            id: 1,
+            datetime: Utc::now(),
+            actor: Some("Alice".to_string()),
+            action: AuditAction::UserCreate,
+            target: Some("Bob".to_string()),
+            ip: Some("127.0.0.1".to_string()),
+        }
+    }
+}
+
+#[test]
+fn scenario_1_with_all_methods() {
+    let sql_scheme = include_str!("../../scheme.sql");
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(sql_scheme).unwrap();
+
+    let old_login = AuditLogEntry {
+        datetime: Utc::now() - Duration::days(2),
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+    let failed_login = AuditLogEntry {
+        actor: Some("Mallory".to_string()),
+        action: AuditAction::LoginFailed,
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+    let door_open = AuditLogEntry {
+        action: AuditAction::DoorOpen,
+        ip: None,
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+
+    assert_eq!(
+        AuditLogEntry::get_filtered(&conn, None, None, None, None, 10).unwrap(),
+        vec![door_open.clone(), failed_login.clone(), old_login.clone()]
+    );
+    assert_eq!(
+        AuditLogEntry::get_filtered(&conn, None, None, None, None, 1).unwrap(),
+        vec![door_open.clone()]
+    );
+    assert_eq!(
+        AuditLogEntry::get_filtered(&conn, Some(AuditAction::LoginFailed), None, None, None, 10)
+            .unwrap(),
+        vec![failed_login]
+    );
+    assert_eq!(
+        AuditLogEntry::get_filtered(&conn, None, Some("Alice"), None, None, 10).unwrap(),
+        vec![door_open.clone(), old_login.clone()]
+    );
+    assert_eq!(
+        AuditLogEntry::get_filtered(
+            &conn,
+            Some(AuditAction::Login),
+            None,
+            None,
+            Some(Utc::now() - Duration::days(1)),
+            10
+        )
+        .unwrap(),
+        vec![old_login]
+    );
+    assert_eq!(
+        AuditLogEntry::get_filtered(
+            &conn,
+            Some(AuditAction::DoorOpen),
+            None,
+            Some(Utc::now() - Duration::days(1)),
+            None,
+            10
+        )
+        .unwrap(),
+        vec![door_open]
+    );
+}
+
+#[test]
+fn append_only() {
+    let sql_scheme = include_str!("../../scheme.sql");
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(sql_scheme).unwrap();
+
+    let entry = AuditLogEntry::<()>::default().create(&conn).unwrap();
+
+    assert!(conn
+        .execute("UPDATE audit_log SET actor = 'Mallory'", &[])
+        .is_err());
+    assert!(conn
+        .execute("DELETE FROM audit_log WHERE id = ?1", &[&entry.id])
+        .is_err());
+    assert_eq!(
+        AuditLogEntry::get_filtered(&conn, None, None, None, None, 10).unwrap(),
+        vec![entry]
+    );
+}
@@ -0,0 +1,178 @@
+use super::{rusqlite, Connection, Entry, Identifier, UserEntry};
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+#[path = "./door_test.rs"]
+mod door_test;
+
+/// Door entry of the corresponding "door" table.
+/// The flats and users, who may open the door, are stored in the "flat_door" and "user_door" tables.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct DoorEntry<ID: Identifier = u32> {
+    pub id: ID,
+    pub name: String,
+    /// The GPIO pin which controls the door-opener.
+    pub opener_pin: u8,
+    /// The seconds how long the door opener is activated.
+    pub opening_time: u32,
+    /// The optional GPIO pin of a reed contact, which is high while the door is open.
+    pub sensor_pin: Option<u8>,
+    /// The optional pulse pattern of the opener, see `PulsePattern`.
+    pub pulse_pattern: Option<String>,
+}
+
+impl Entry for DoorEntry<u32> {
+    #[inline(always)]
+    fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    fn update(&self, conn: &Connection) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "UPDATE door SET name = ?1, opener_pin = ?2, opening_time = ?3, sensor_pin = ?4, pulse_pattern = ?5 WHERE id = ?6",
+            &[&self.name, &self.opener_pin, &self.opening_time, &self.sensor_pin, &self.pulse_pattern, &self.id],
+        )?;
+        Ok(())
+    }
+
+    fn delete_entry(conn: &Connection, id: u32) -> Result<(), rusqlite::Error> {
+        conn.execute("DELETE FROM flat_door WHERE door_id=?1", &[&id])?;
+        conn.execute("DELETE FROM user_door WHERE door_id=?1", &[&id])?;
+        conn.execute("DELETE FROM guest_code WHERE door_id=?1", &[&id])?;
+        conn.execute("DELETE FROM door WHERE id=?1", &[&id])?;
+        Ok(())
+    }
+}
+
+impl DoorEntry<()> {
+    pub fn create(self, conn: &Connection) -> Result<DoorEntry, rusqlite::Error> {
+        conn.execute(
+            "INSERT INTO door (name, opener_pin, opening_time, sensor_pin, pulse_pattern) VALUES (?1, ?2, ?3, ?4, ?5)",
+            &[
+                &self.name,
+                &self.opener_pin,
+                &self.opening_time,
+                &self.sensor_pin,
+                &self.pulse_pattern,
+            ],
+        )?;
+        Ok(DoorEntry {
+            id: (conn.last_insert_rowid() as u32),
+            name: self.name,
+            opener_pin: self.opener_pin,
+            opening_time: self.opening_time,
+            sensor_pin: self.sensor_pin,
+            pulse_pattern: self.pulse_pattern,
+        })
+    }
+}
+
+impl DoorEntry<u32> {
+    /// Converts a rusqlite row to a DoorEntry
+    fn row_2_door(row: &rusqlite::Row) -> Self {
+        Self {
+            id: row.get::<usize, u32>(0),
+            name: row.get::<usize, String>(1),
+            opener_pin: row.get::<usize, u8>(2),
+            opening_time: row.get::<usize, u32>(3),
+            sensor_pin: row.get::<usize, Option<u8>>(4),
+            pulse_pattern: row.get::<usize, Option<String>>(5),
+        }
+    }
+
+    pub fn get_all(conn: &Connection) -> Result<Vec<Self>, rusqlite::Error> {
+        let mut stmt = conn.prepare(
+            "SELECT id, name, opener_pin, opening_time, sensor_pin, pulse_pattern FROM door ORDER BY name",
+        )?;
+        return stmt.query_map(&[], Self::row_2_door)?.collect();
+    }
+
+    pub fn get_by_id(conn: &Connection, id: u32) -> Result<Option<Self>, rusqlite::Error> {
+        let mut stmt = conn.prepare(
+            "SELECT id, name, opener_pin, opening_time, sensor_pin, pulse_pattern FROM door WHERE id=?1 LIMIT 1",
+        )?;
+        return stmt
+            .query_map(&[&id], Self::row_2_door)?
+            .next()
+            .map_or_else(|| Ok(None), |entry_result| entry_result.map(Some));
+    }
+
+    /// Returns the doors, which have been granted to the user or the flat of the user.
+    /// Admins may use all doors.
+    pub fn get_permitted<FRef: Entry>(
+        conn: &Connection,
+        user: &UserEntry<u32, FRef>,
+    ) -> Result<Vec<Self>, rusqlite::Error> {
+        if user.user_type.is_admin() {
+            return Self::get_all(conn);
+        }
+
+        let mut stmt = conn.prepare("SELECT id, name, opener_pin, opening_time, sensor_pin, pulse_pattern FROM door WHERE id IN (SELECT door_id FROM user_door WHERE user_id=?1) OR id IN (SELECT door_id FROM flat_door WHERE flat_id=?2) ORDER BY name")?;
+        return stmt
+            .query_map(
+                &[&user.id, &user.flat.as_ref().map(|flat| flat.get_id())],
+                Self::row_2_door,
+            )?
+            .collect();
+    }
+
+    /// Returns the IDs of all flats, which may use the door
+    pub fn get_flats(&self, conn: &Connection) -> Result<Vec<u32>, rusqlite::Error> {
+        let mut stmt = conn.prepare("SELECT flat_id FROM flat_door WHERE door_id=?1")?;
+        return stmt
+            .query_map(&[&self.id], |row| row.get::<usize, u32>(0))?
+            .collect();
+    }
+
+    /// Returns the IDs of all users, who have been granted the door individually
+    pub fn get_users(&self, conn: &Connection) -> Result<Vec<u32>, rusqlite::Error> {
+        let mut stmt = conn.prepare("SELECT user_id FROM user_door WHERE door_id=?1")?;
+        return stmt
+            .query_map(&[&self.id], |row| row.get::<usize, u32>(0))?
+            .collect();
+    }
+
+    pub fn add_flat(&self, conn: &Connection, flat: u32) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "INSERT OR IGNORE INTO flat_door (flat_id, door_id) VALUES (?1, ?2)",
+            &[&flat, &self.id],
+        )?;
+        Ok(())
+    }
+
+    pub fn remove_flat(&self, conn: &Connection, flat: u32) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "DELETE FROM flat_door WHERE flat_id=?1 AND door_id=?2",
+            &[&flat, &self.id],
+        )?;
+        Ok(())
+    }
+
+    pub fn add_user(&self, conn: &Connection, user: u32) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "INSERT OR IGNORE INTO user_door (user_id, door_id) VALUES (?1, ?2)",
+            &[&user, &self.id],
+        )?;
+        Ok(())
+    }
+
+    pub fn remove_user(&self, conn: &Connection, user: u32) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "DELETE FROM user_door WHERE user_id=?1 AND door_id=?2",
+            &[&user, &self.id],
+        )?;
+        Ok(())
+    }
+
+    /// Removes the doors of the flat
+    pub fn delete_by_flat(conn: &Connection, flat: u32) -> Result<(), rusqlite::Error> {
+        conn.execute("DELETE FROM flat_door WHERE flat_id=?1", &[&flat])?;
+        Ok(())
+    }
+
+    /// Removes the doors, which have been granted to the user individually
+    pub fn delete_by_user(conn: &Connection, user: u32) -> Result<(), rusqlite::Error> {
+        conn.execute("DELETE FROM user_door WHERE user_id=?1", &[&user])?;
+        Ok(())
+    }
+}
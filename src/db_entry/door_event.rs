@@ -0,0 +1,106 @@
+use super::{rusqlite, Connection, DoorEventOutcome, DoorEventSource, Identifier};
+use chrono::{offset::Utc, DateTime};
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+#[path = "./door_event_test.rs"]
+mod door_event_test;
+
+/// Door-History entry of the corresponding "door_event" table.
+/// The table is append-only like the audit log, so the names of the door and user are stored instead of IDs.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct DoorEventEntry<ID: Identifier = u32> {
+    pub id: ID,
+    pub datetime: DateTime<Utc>,
+    /// Only unset if a refused attempt didn't name an existing door, e.g. an invalid guest code
+    pub door: Option<String>,
+    /// The name of the user or the label of the guest code
+    pub user: Option<String>,
+    /// The flat of the user or guest code, whose residents see the event
+    pub flat: Option<u32>,
+    pub source: DoorEventSource,
+    pub outcome: DoorEventOutcome,
+}
+
+/// The filters of the door history, an unset filter matches all events.
+#[derive(Debug, Default)]
+pub struct DoorEventQuery<'a> {
+    pub flat: Option<u32>,
+    pub door: Option<&'a str>,
+    pub user: Option<&'a str>,
+    pub source: Option<DoorEventSource>,
+    pub outcome: Option<DoorEventOutcome>,
+    pub from: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl DoorEventEntry<()> {
+    pub fn create(self, conn: &Connection) -> Result<DoorEventEntry, rusqlite::Error> {
+        conn.execute(
+            "INSERT INTO door_event (datetime, door, user, flat_id, source, outcome) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            &[
+                &self.datetime,
+                &self.door,
+                &self.user,
+                &self.flat,
+                &self.source,
+                &self.outcome,
+            ],
+        )?;
+        Ok(DoorEventEntry {
+            id: (conn.last_insert_rowid() as u32),
+            datetime: self.datetime,
+            door: self.door,
+            user: self.user,
+            flat: self.flat,
+            source: self.source,
+            outcome: self.outcome,
+        })
+    }
+}
+
+impl DoorEventEntry<u32> {
+    /// Converts a rusqlite row to a DoorEventEntry
+    fn row_2_door_event(row: &rusqlite::Row) -> Self {
+        Self {
+            id: row.get::<usize, u32>(0),
+            datetime: row.get::<usize, DateTime<Utc>>(1),
+            door: row.get::<usize, Option<String>>(2),
+            user: row.get::<usize, Option<String>>(3),
+            flat: row.get::<usize, Option<u32>>(4),
+            source: row.get::<usize, DoorEventSource>(5),
+            outcome: row.get::<usize, DoorEventOutcome>(6),
+        }
+    }
+
+    /// Returns the newest events, which match all filters of the query
+    pub fn get_filtered(
+        conn: &Connection,
+        query: &DoorEventQuery,
+        limit: u32,
+    ) -> Result<Vec<Self>, rusqlite::Error> {
+        let mut stmt = conn.prepare(
+            "SELECT id, datetime, door, user, flat_id, source, outcome FROM door_event \
+             WHERE (?1 IS NULL OR flat_id = ?1) AND (?2 IS NULL OR door = ?2) \
+             AND (?3 IS NULL OR user = ?3) AND (?4 IS NULL OR source = ?4) \
+             AND (?5 IS NULL OR outcome = ?5) \
+             AND (?6 IS NULL OR datetime >= ?6) AND (?7 IS NULL OR datetime < ?7) \
+             ORDER BY id DESC LIMIT ?8",
+        )?;
+        return stmt
+            .query_map(
+                &[
+                    &query.flat,
+                    &query.door,
+                    &query.user,
+                    &query.source,
+                    &query.outcome,
+                    &query.from,
+                    &query.until,
+                    &limit,
+                ],
+                Self::row_2_door_event,
+            )?
+            .collect();
+    }
+}
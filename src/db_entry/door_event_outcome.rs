@@ -0,0 +1,88 @@
+use super::rusqlite::{
+    self,
+    types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef},
+};
+use derive_try_from_primitive::TryFromPrimitive;
+use rocket::http::RawStr;
+use rocket::request::FromFormValue;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::fmt;
+
+#[cfg(test)]
+#[path = "./door_event_outcome_test.rs"]
+mod door_event_outcome_test;
+
+/// A logical enum of the outcome database field of the door history.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, TryFromPrimitive)]
+#[repr(u16)]
+pub enum DoorEventOutcome {
+    #[serde(rename = "opened")]
+    Opened = 1,
+    /// The opener was still active from an earlier opening
+    #[serde(rename = "already_active")]
+    AlreadyActive = 2,
+    /// The attempt was rejected, e.g. by a missing grant, the access schedule or an invalid guest code
+    #[serde(rename = "refused")]
+    Refused = 3,
+}
+
+impl DoorEventOutcome {
+    /// get a Vector of touples of the value and string of all outcomes
+    pub fn get_list() -> Vec<(u16, String)> {
+        vec![
+            Self::Opened.into(),
+            Self::AlreadyActive.into(),
+            Self::Refused.into(),
+        ]
+    }
+}
+
+impl fmt::Display for DoorEventOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Self::Opened => write!(f, "opened"),
+            Self::AlreadyActive => write!(f, "already_active"),
+            Self::Refused => write!(f, "refused"),
+        }
+    }
+}
+
+impl From<DoorEventOutcome> for u16 {
+    #[inline(always)]
+    fn from(outcome: DoorEventOutcome) -> Self {
+        outcome as Self
+    }
+}
+
+impl From<DoorEventOutcome> for (u16, String) {
+    fn from(outcome: DoorEventOutcome) -> Self {
+        (outcome.into(), outcome.to_string())
+    }
+}
+
+/// needed to convert from the raw SQL-value
+impl FromSql for DoorEventOutcome {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let numeric_value = value.as_i64()?;
+        Self::try_from(u16::column_result(value)?)
+            .map_err(|_| FromSqlError::OutOfRange(numeric_value))
+    }
+}
+
+/// needed to convert to the raw SQL-value
+impl ToSql for DoorEventOutcome {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        let num: u16 = (*self).into();
+        Ok(ToSqlOutput::from(num))
+    }
+}
+
+/// needed to convert from the raw Form-value
+impl<'v> FromFormValue<'v> for DoorEventOutcome {
+    type Error = &'v RawStr;
+
+    fn from_form_value(form_value: &'v RawStr) -> Result<Self, &'v RawStr> {
+        Self::try_from(u16::from_form_value(form_value)?).map_err(|_| form_value)
+    }
+}
@@ -0,0 +1,24 @@
+use super::*;
+
+#[test]
+fn from_1() {
+    assert_matches!(DoorEventOutcome::try_from(1), Ok(DoorEventOutcome::Opened));
+}
+
+#[test]
+fn from_3() {
+    assert_matches!(DoorEventOutcome::try_from(3), Ok(DoorEventOutcome::Refused));
+}
+
+#[test]
+fn from_4() {
+    assert_matches!(DoorEventOutcome::try_from(4), Err(_));
+}
+
+#[test]
+fn already_active_to_string() {
+    assert_eq!(
+        DoorEventOutcome::AlreadyActive.to_string(),
+        "already_active"
+    );
+}
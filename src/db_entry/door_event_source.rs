@@ -0,0 +1,91 @@
+use super::rusqlite::{
+    self,
+    types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef},
+};
+use derive_try_from_primitive::TryFromPrimitive;
+use rocket::http::RawStr;
+use rocket::request::FromFormValue;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::fmt;
+
+#[cfg(test)]
+#[path = "./door_event_source_test.rs"]
+mod door_event_source_test;
+
+/// A logical enum of the source database field of the door history, which tells how the door was opened.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, TryFromPrimitive)]
+#[repr(u16)]
+pub enum DoorEventSource {
+    #[serde(rename = "web")]
+    Web = 1,
+    #[serde(rename = "api")]
+    Api = 2,
+    /// A command of the MQTT broker of a flat
+    #[serde(rename = "mqtt")]
+    Mqtt = 3,
+    #[serde(rename = "guest_code")]
+    GuestCode = 4,
+}
+
+impl DoorEventSource {
+    /// get a Vector of touples of the value and string of all sources
+    pub fn get_list() -> Vec<(u16, String)> {
+        vec![
+            Self::Web.into(),
+            Self::Api.into(),
+            Self::Mqtt.into(),
+            Self::GuestCode.into(),
+        ]
+    }
+}
+
+impl fmt::Display for DoorEventSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Self::Web => write!(f, "web"),
+            Self::Api => write!(f, "api"),
+            Self::Mqtt => write!(f, "mqtt"),
+            Self::GuestCode => write!(f, "guest_code"),
+        }
+    }
+}
+
+impl From<DoorEventSource> for u16 {
+    #[inline(always)]
+    fn from(source: DoorEventSource) -> Self {
+        source as Self
+    }
+}
+
+impl From<DoorEventSource> for (u16, String) {
+    fn from(source: DoorEventSource) -> Self {
+        (source.into(), source.to_string())
+    }
+}
+
+/// needed to convert from the raw SQL-value
+impl FromSql for DoorEventSource {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let numeric_value = value.as_i64()?;
+        Self::try_from(u16::column_result(value)?)
+            .map_err(|_| FromSqlError::OutOfRange(numeric_value))
+    }
+}
+
+/// needed to convert to the raw SQL-value
+impl ToSql for DoorEventSource {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        let num: u16 = (*self).into();
+        Ok(ToSqlOutput::from(num))
+    }
+}
+
+/// needed to convert from the raw Form-value
+impl<'v> FromFormValue<'v> for DoorEventSource {
+    type Error = &'v RawStr;
+
+    fn from_form_value(form_value: &'v RawStr) -> Result<Self, &'v RawStr> {
+        Self::try_from(u16::from_form_value(form_value)?).map_err(|_| form_value)
+    }
+}
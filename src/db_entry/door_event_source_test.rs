@@ -0,0 +1,27 @@
+use super::*;
+
+#[test]
+fn from_1() {
+    assert_matches!(DoorEventSource::try_from(1), Ok(DoorEventSource::Web));
+}
+
+#[test]
+fn from_4() {
+    assert_matches!(DoorEventSource::try_from(4), Ok(DoorEventSource::GuestCode));
+}
+
+#[test]
+fn from_5() {
+    assert_matches!(DoorEventSource::try_from(5), Err(_));
+}
+
+#[test]
+fn api_to_num() {
+    assert_eq!(Into::<u16>::into(DoorEventSource::Api), 2);
+}
+
+#[test]
+fn list_contains_all_sources() {
+    assert_eq!(DoorEventSource::get_list().len(), 4);
+    assert!(DoorEventSource::get_list().contains(&(3, "mqtt".to_string())));
+}
@@ -0,0 +1,131 @@
+use super::*;
+use chrono::Duration;
+
+impl Default for DoorEventEntry<()> {
+    fn default() -> Self {
+        Self {
+            id: (),
+            datetime: Utc::now(),
+            door: Some("House Door".to_string()),
+            user: Some("Alice".to_string()),
+            flat: Some(1),
+            source: DoorEventSource::Web,
+            outcome: DoorEventOutcome::Opened,
+        }
+    }
+}
+
+impl Default for DoorEventEntry {
+    fn default() -> Self {
+        Self {
+            id: 1,
+            datetime: Utc::now(),
+            door: Some("House Door".to_string()),
+            user: Some("Alice".to_string()),
+            flat: Some(1),
+            source: DoorEventSource::Web,
+            outcome: DoorEventOutcome::Opened,
+        }
+    }
+}
+
+#[test]
+fn scenario_1_with_all_methods() {
+    let sql_scheme = include_str!("../../scheme.sql");
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(sql_scheme).unwrap();
+
+    let old_opening = DoorEventEntry {
+        datetime: Utc::now() - Duration::days(2),
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+    let api_opening = DoorEventEntry {
+        door: Some("Gate".to_string()),
+        source: DoorEventSource::Api,
+        outcome: DoorEventOutcome::AlreadyActive,
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+    let invalid_guest_code = DoorEventEntry {
+        door: None,
+        user: None,
+        flat: None,
+        source: DoorEventSource::GuestCode,
+        outcome: DoorEventOutcome::Refused,
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+
+    assert_eq!(
+        DoorEventEntry::get_filtered(&conn, &DoorEventQuery::default(), 10).unwrap(),
+        vec![
+            invalid_guest_code.clone(),
+            api_opening.clone(),
+            old_opening.clone()
+        ]
+    );
+    assert_eq!(
+        DoorEventEntry::get_filtered(&conn, &DoorEventQuery::default(), 1).unwrap(),
+        vec![invalid_guest_code.clone()]
+    );
+    assert_eq!(
+        DoorEventEntry::get_filtered(
+            &conn,
+            &DoorEventQuery {
+                flat: Some(1),
+                ..Default::default()
+            },
+            10
+        )
+        .unwrap(),
+        vec![api_opening.clone(), old_opening.clone()]
+    );
+    assert_eq!(
+        DoorEventEntry::get_filtered(
+            &conn,
+            &DoorEventQuery {
+                door: Some("Gate"),
+                source: Some(DoorEventSource::Api),
+                ..Default::default()
+            },
+            10
+        )
+        .unwrap(),
+        vec![api_opening]
+    );
+    assert_eq!(
+        DoorEventEntry::get_filtered(
+            &conn,
+            &DoorEventQuery {
+                outcome: Some(DoorEventOutcome::Refused),
+                ..Default::default()
+            },
+            10
+        )
+        .unwrap(),
+        vec![invalid_guest_code]
+    );
+    assert_eq!(
+        DoorEventEntry::get_filtered(
+            &conn,
+            &DoorEventQuery {
+                user: Some("Alice"),
+                until: Some(Utc::now() - Duration::days(1)),
+                ..Default::default()
+            },
+            10
+        )
+        .unwrap(),
+        vec![old_opening]
+    );
+
+    // The history is append-only
+    assert!(conn.execute("DELETE FROM door_event", &[]).is_err());
+    assert!(conn
+        .execute("UPDATE door_event SET outcome = 1", &[])
+        .is_err());
+}
@@ -0,0 +1,152 @@
+use super::*;
+use crate::db_entry::{FlatEntry, GuestCodeEntry, UserType};
+
+impl Default for DoorEntry<()> {
+    fn default() -> Self {
+        Self {
+            id: (),
+            name: "House Door".to_string(),
+            opener_pin: 0,
+            opening_time: 3,
+            sensor_pin: None,
+            pulse_pattern: None,
+        }
+    }
+}
+
+impl Default for DoorEntry {
+    fn default() -> Self {
+        Self {
+            id: 1,
+            name: "House Door".to_string(),
+            opener_pin: 0,
+            opening_time: 3,
+            sensor_pin: None,
+            pulse_pattern: None,
+        }
+    }
+}
+
+#[test]
+fn scenario_1_with_all_methods() {
+    let sql_scheme = include_str!("../../scheme.sql");
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(sql_scheme).unwrap();
+
+    let mut door = DoorEntry::default().create(&conn).unwrap();
+    let gate = DoorEntry {
+        name: "Gate".to_string(),
+        opener_pin: 1,
+        sensor_pin: Some(2),
+        pulse_pattern: None,
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+
+    door.opening_time = 5;
+    door.update(&conn).unwrap();
+
+    assert_eq!(
+        DoorEntry::get_all(&conn).unwrap(),
+        vec![gate.clone(), door.clone()]
+    );
+    assert_eq!(
+        DoorEntry::get_by_id(&conn, door.get_id()).unwrap().unwrap(),
+        door
+    );
+
+    door.add_flat(&conn, 1).unwrap();
+    door.add_flat(&conn, 1).unwrap();
+    gate.add_user(&conn, 2).unwrap();
+    assert_eq!(door.get_flats(&conn).unwrap(), vec![1]);
+    assert_eq!(gate.get_users(&conn).unwrap(), vec![2]);
+
+    door.remove_flat(&conn, 1).unwrap();
+    gate.remove_user(&conn, 2).unwrap();
+    assert!(door.get_flats(&conn).unwrap().is_empty());
+    assert!(gate.get_users(&conn).unwrap().is_empty());
+
+    gate.add_user(&conn, 2).unwrap();
+    GuestCodeEntry {
+        door: door.get_id(),
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+    door.delete(&conn).unwrap();
+    gate.delete(&conn).unwrap();
+    assert!(DoorEntry::get_all(&conn).unwrap().is_empty());
+    assert!(DoorEntry::get_by_id(&conn, gate.get_id())
+        .unwrap()
+        .is_none());
+    assert_eq!(
+        conn.query_row("SELECT COUNT(*) FROM user_door", &[], |row| row
+            .get::<usize, u32>(0))
+            .unwrap(),
+        0
+    );
+    assert_eq!(
+        conn.query_row("SELECT COUNT(*) FROM guest_code", &[], |row| row
+            .get::<usize, u32>(0))
+            .unwrap(),
+        0
+    );
+}
+
+#[test]
+fn permitted_doors() {
+    let sql_scheme = include_str!("../../scheme.sql");
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(sql_scheme).unwrap();
+
+    let flat = FlatEntry::default().create(&conn).unwrap();
+    let user = UserEntry {
+        flat: Some(flat.get_id()),
+        user_type: UserType::User,
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+    let admin = UserEntry {
+        name: "Concierge".to_string(),
+        user_type: UserType::Admin,
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+
+    let door = DoorEntry::default().create(&conn).unwrap();
+    let gate = DoorEntry {
+        name: "Gate".to_string(),
+        opener_pin: 1,
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+    let cellar = DoorEntry {
+        name: "Cellar".to_string(),
+        opener_pin: 2,
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+
+    assert!(DoorEntry::get_permitted(&conn, &user).unwrap().is_empty());
+
+    door.add_flat(&conn, flat.get_id()).unwrap();
+    gate.add_user(&conn, user.get_id()).unwrap();
+    assert_eq!(
+        DoorEntry::get_permitted(&conn, &user).unwrap(),
+        vec![gate.clone(), door.clone()]
+    );
+    assert_eq!(
+        DoorEntry::get_permitted(&conn, &admin).unwrap(),
+        vec![cellar, gate.clone(), door]
+    );
+
+    DoorEntry::delete_by_flat(&conn, flat.get_id()).unwrap();
+    assert_eq!(DoorEntry::get_permitted(&conn, &user).unwrap(), vec![gate]);
+    DoorEntry::delete_by_user(&conn, user.get_id()).unwrap();
+    assert!(DoorEntry::get_permitted(&conn, &user).unwrap().is_empty());
+}
@@ -14,6 +14,8 @@ pub struct FlatEntry<ID: Identifier = u32> {
     pub bell_button_pin: u8,
     pub bell_topic: String,
     pub tamper_alarm_topic: Option<String>,
+    /// The topic of the alarms, when the door is held open too long or forced open
+    pub door_alarm_topic: Option<String>,
     pub local_address: String,
     pub broker_address: String,
     pub broker_port: u16,
@@ -30,7 +32,7 @@ impl Entry for FlatEntry<u32> {
 
     fn update(&self, conn: &Connection) -> Result<(), rusqlite::Error> {
         let mut stmt = conn.prepare(
-            "UPDATE flat SET name = ?1, active = ?2, bell_button_pin = ?3, local_address = ?4, broker_address = ?5, broker_port = ?6, bell_topic = ?7, tamper_alarm_topic = ?8, broker_user = ?9, broker_pw = ?10, broker_pw_iv = ?11 WHERE id = ?12",
+            "UPDATE flat SET name = ?1, active = ?2, bell_button_pin = ?3, local_address = ?4, broker_address = ?5, broker_port = ?6, bell_topic = ?7, tamper_alarm_topic = ?8, broker_user = ?9, broker_pw = ?10, broker_pw_iv = ?11, door_alarm_topic = ?12 WHERE id = ?13",
         )?;
         stmt.execute(&[
             &self.name,
@@ -44,6 +46,7 @@ impl Entry for FlatEntry<u32> {
             &self.broker_user,
             &self.broker_password,
             &self.broker_password_iv,
+            &self.door_alarm_topic,
             &self.id,
         ])?;
         Ok(())
@@ -58,7 +61,7 @@ impl Entry for FlatEntry<u32> {
 impl FlatEntry<()> {
     pub fn create(self, conn: &Connection) -> Result<FlatEntry, rusqlite::Error> {
         conn.execute(
-            "INSERT INTO flat (name, active, bell_button_pin, local_address, broker_address, broker_port, bell_topic, tamper_alarm_topic, broker_user, broker_pw, broker_pw_iv) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            "INSERT INTO flat (name, active, bell_button_pin, local_address, broker_address, broker_port, bell_topic, tamper_alarm_topic, broker_user, broker_pw, broker_pw_iv, door_alarm_topic) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
             &[
             &self.name,
             &self.active,
@@ -70,7 +73,8 @@ impl FlatEntry<()> {
             &self.tamper_alarm_topic,
             &self.broker_user,
             &self.broker_password,
-            &self.broker_password_iv
+            &self.broker_password_iv,
+            &self.door_alarm_topic
             ])?;
         Ok(FlatEntry {
             id: (conn.last_insert_rowid() as u32),
@@ -82,6 +86,7 @@ impl FlatEntry<()> {
             broker_port: self.broker_port,
             bell_topic: self.bell_topic,
             tamper_alarm_topic: self.tamper_alarm_topic,
+            door_alarm_topic: self.door_alarm_topic,
             broker_user: self.broker_user,
             broker_password: self.broker_password,
             broker_password_iv: self.broker_password_iv,
@@ -105,25 +110,26 @@ impl FlatEntry<u32> {
             broker_user: row.get::<usize, String>(9),
             broker_password: row.get::<usize, String>(10),
             broker_password_iv: row.get::<usize, String>(11),
+            door_alarm_topic: row.get::<usize, Option<String>>(12),
         }
     }
 
     pub fn get_all(conn: &Connection) -> Result<Vec<Self>, rusqlite::Error> {
         let mut stmt =
-            conn.prepare("SELECT id, name, active, bell_button_pin, local_address, broker_address, broker_port, bell_topic, tamper_alarm_topic, broker_user, broker_pw, broker_pw_iv FROM flat")?;
+            conn.prepare("SELECT id, name, active, bell_button_pin, local_address, broker_address, broker_port, bell_topic, tamper_alarm_topic, broker_user, broker_pw, broker_pw_iv, door_alarm_topic FROM flat")?;
         return stmt.query_map(&[], Self::row_2_flat)?.collect();
     }
 
     /// Get all active flats
     pub fn get_active(conn: &Connection) -> Result<Vec<Self>, rusqlite::Error> {
         let mut stmt =
-            conn.prepare("SELECT id, name, active, bell_button_pin, local_address, broker_address, broker_port, bell_topic, tamper_alarm_topic, broker_user, broker_pw, broker_pw_iv FROM flat WHERE active = true")?;
+            conn.prepare("SELECT id, name, active, bell_button_pin, local_address, broker_address, broker_port, bell_topic, tamper_alarm_topic, broker_user, broker_pw, broker_pw_iv, door_alarm_topic FROM flat WHERE active = true")?;
         return stmt.query_map(&[], Self::row_2_flat)?.collect();
     }
 
     pub fn get_by_id(conn: &Connection, id: u32) -> Result<Option<Self>, rusqlite::Error> {
         let mut stmt = conn.prepare(
-            "SELECT id, name, active, bell_button_pin, local_address, broker_address, broker_port, bell_topic, tamper_alarm_topic, broker_user, broker_pw, broker_pw_iv FROM flat WHERE ID=?1 LIMIT 1",
+            "SELECT id, name, active, bell_button_pin, local_address, broker_address, broker_port, bell_topic, tamper_alarm_topic, broker_user, broker_pw, broker_pw_iv, door_alarm_topic FROM flat WHERE ID=?1 LIMIT 1",
         )?;
         return stmt
             .query_map(&[&id], Self::row_2_flat)?
@@ -133,7 +139,7 @@ impl FlatEntry<u32> {
 
     pub fn update_without_password(&self, conn: &Connection) -> Result<(), rusqlite::Error> {
         let mut stmt = conn.prepare(
-            "UPDATE flat SET name = ?1, active = ?2, bell_button_pin = ?3, local_address = ?4, broker_address = ?5, broker_port = ?6, bell_topic = ?7, tamper_alarm_topic = ?8, broker_user = ?9 WHERE id = ?10",
+            "UPDATE flat SET name = ?1, active = ?2, bell_button_pin = ?3, local_address = ?4, broker_address = ?5, broker_port = ?6, bell_topic = ?7, tamper_alarm_topic = ?8, broker_user = ?9, door_alarm_topic = ?10 WHERE id = ?11",
         )?;
         stmt.execute(&[
             &self.name,
@@ -145,6 +151,7 @@ impl FlatEntry<u32> {
             &self.bell_topic,
             &self.tamper_alarm_topic,
             &self.broker_user,
+            &self.door_alarm_topic,
             &self.id,
         ])?;
         Ok(())
@@ -0,0 +1,202 @@
+use super::{rusqlite, Connection, DoorEntry, Entry, Identifier};
+use chrono::{offset::Utc, DateTime};
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+#[path = "./guest_code_test.rs"]
+mod guest_code_test;
+
+/// Guest-Code entry of the corresponding "guest_code" table.
+/// Only the hash of the code is stored, the code itself is shown once on creation.
+/// The code opens the door, which the issuer has chosen.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct GuestCodeEntry<
+    ID: Identifier = u32,
+    FRef: Entry = u32,
+    URef: Entry = u32,
+    DRef: Entry = u32,
+> {
+    pub id: ID,
+    pub label: String,
+    pub code_hash: String,
+    pub valid_from: DateTime<Utc>,
+    pub valid_until: DateTime<Utc>,
+    pub max_uses: u32,
+    pub uses: u32,
+    pub creation_datetime: DateTime<Utc>,
+    pub flat: FRef,
+    pub user: URef,
+    pub door: DRef,
+}
+
+impl<FRef: Entry, URef: Entry, DRef: Entry> Entry for GuestCodeEntry<u32, FRef, URef, DRef> {
+    #[inline(always)]
+    fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    fn delete_entry(conn: &Connection, id: u32) -> Result<(), rusqlite::Error> {
+        conn.execute("DELETE FROM guest_code WHERE ID=?1", &[&id])?;
+        Ok(())
+    }
+
+    fn update(&self, conn: &Connection) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "UPDATE guest_code SET label = ?1, code_hash = ?2, valid_from = ?3, valid_until = ?4, max_uses = ?5, uses = ?6, creation_datetime = ?7, flat_id = ?8, user_id = ?9, door_id = ?10 WHERE id = ?11",
+            &[
+                &self.label,
+                &self.code_hash,
+                &self.valid_from,
+                &self.valid_until,
+                &self.max_uses,
+                &self.uses,
+                &self.creation_datetime,
+                &self.flat.get_id(),
+                &self.user.get_id(),
+                &self.door.get_id(),
+                &self.id,
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+impl<FRef: Entry, URef: Entry, DRef: Entry> GuestCodeEntry<(), FRef, URef, DRef> {
+    pub fn create(self, conn: &Connection) -> Result<GuestCodeEntry, rusqlite::Error> {
+        let flat_id = self.flat.get_id();
+        let user_id = self.user.get_id();
+        let door_id = self.door.get_id();
+        conn.execute(
+            "INSERT INTO guest_code (label, code_hash, valid_from, valid_until, max_uses, uses, creation_datetime, flat_id, user_id, door_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            &[
+                &self.label,
+                &self.code_hash,
+                &self.valid_from,
+                &self.valid_until,
+                &self.max_uses,
+                &self.uses,
+                &self.creation_datetime,
+                &flat_id,
+                &user_id,
+                &door_id,
+            ],
+        )?;
+        Ok(GuestCodeEntry {
+            id: (conn.last_insert_rowid() as u32),
+            label: self.label,
+            code_hash: self.code_hash,
+            valid_from: self.valid_from,
+            valid_until: self.valid_until,
+            max_uses: self.max_uses,
+            uses: self.uses,
+            creation_datetime: self.creation_datetime,
+            flat: flat_id,
+            user: user_id,
+            door: door_id,
+        })
+    }
+}
+
+impl<FRef: Entry, URef: Entry, DRef: Entry> GuestCodeEntry<u32, FRef, URef, DRef> {
+    /// Whether the code can be redeemed at this moment
+    pub fn is_valid(&self, now: DateTime<Utc>) -> bool {
+        self.valid_from <= now && now < self.valid_until && self.uses < self.max_uses
+    }
+}
+
+impl<FRef: Entry, URef: Entry> GuestCodeEntry<u32, FRef, URef, u32> {
+    /// Replaces the ID of the door by the door, e.g. to show its name
+    pub fn with_door(self, door: DoorEntry) -> GuestCodeEntry<u32, FRef, URef, DoorEntry> {
+        GuestCodeEntry {
+            id: self.id,
+            label: self.label,
+            code_hash: self.code_hash,
+            valid_from: self.valid_from,
+            valid_until: self.valid_until,
+            max_uses: self.max_uses,
+            uses: self.uses,
+            creation_datetime: self.creation_datetime,
+            flat: self.flat,
+            user: self.user,
+            door,
+        }
+    }
+}
+
+impl GuestCodeEntry<u32, u32, u32, u32> {
+    /// Converts a rusqlite row to a GuestCodeEntry
+    fn row_2_guest_code(row: &rusqlite::Row) -> Self {
+        Self {
+            id: row.get::<usize, u32>(0),
+            label: row.get::<usize, String>(1),
+            code_hash: row.get::<usize, String>(2),
+            valid_from: row.get::<usize, DateTime<Utc>>(3),
+            valid_until: row.get::<usize, DateTime<Utc>>(4),
+            max_uses: row.get::<usize, u32>(5),
+            uses: row.get::<usize, u32>(6),
+            creation_datetime: row.get::<usize, DateTime<Utc>>(7),
+            flat: row.get::<usize, u32>(8),
+            user: row.get::<usize, u32>(9),
+            door: row.get::<usize, u32>(10),
+        }
+    }
+
+    pub fn get_by_flat(conn: &Connection, flat: u32) -> Result<Vec<Self>, rusqlite::Error> {
+        let mut stmt = conn.prepare("SELECT id, label, code_hash, valid_from, valid_until, max_uses, uses, creation_datetime, flat_id, user_id, door_id FROM guest_code WHERE flat_id=?1 ORDER BY valid_until DESC")?;
+        return stmt.query_map(&[&flat], Self::row_2_guest_code)?.collect();
+    }
+
+    pub fn get_by_id(conn: &Connection, id: u32) -> Result<Option<Self>, rusqlite::Error> {
+        let mut stmt = conn.prepare("SELECT id, label, code_hash, valid_from, valid_until, max_uses, uses, creation_datetime, flat_id, user_id, door_id FROM guest_code WHERE id=?1 LIMIT 1")?;
+        return stmt
+            .query_map(&[&id], Self::row_2_guest_code)?
+            .next()
+            .map_or_else(|| Ok(None), |entry_result| entry_result.map(Some));
+    }
+
+    pub fn get_by_code_hash(
+        conn: &Connection,
+        code_hash: &str,
+    ) -> Result<Option<Self>, rusqlite::Error> {
+        let mut stmt = conn.prepare("SELECT id, label, code_hash, valid_from, valid_until, max_uses, uses, creation_datetime, flat_id, user_id, door_id FROM guest_code WHERE code_hash=?1 LIMIT 1")?;
+        return stmt
+            .query_map(&[&code_hash], Self::row_2_guest_code)?
+            .next()
+            .map_or_else(|| Ok(None), |entry_result| entry_result.map(Some));
+    }
+
+    /// Counts a redemption, if uses are left.
+    /// Returns whether the use was counted, so concurrent redemptions can't exceed `max_uses`.
+    pub fn increment_uses(&mut self, conn: &Connection) -> Result<bool, rusqlite::Error> {
+        let changed_rows = conn.execute(
+            "UPDATE guest_code SET uses = uses + 1 WHERE id = ?1 AND uses < max_uses",
+            &[&self.id],
+        )?;
+        if changed_rows == 1 {
+            self.uses += 1;
+        }
+        Ok(changed_rows == 1)
+    }
+
+    /// Gives back a counted use, if the doors couldn't be opened
+    pub fn decrement_uses(&mut self, conn: &Connection) -> Result<(), rusqlite::Error> {
+        let changed_rows = conn.execute(
+            "UPDATE guest_code SET uses = uses - 1 WHERE id = ?1 AND uses > 0",
+            &[&self.id],
+        )?;
+        if changed_rows == 1 {
+            self.uses -= 1;
+        }
+        Ok(())
+    }
+
+    pub fn delete_by_flat(conn: &Connection, flat: u32) -> Result<(), rusqlite::Error> {
+        conn.execute("DELETE FROM guest_code WHERE flat_id=?1", &[&flat])?;
+        Ok(())
+    }
+
+    pub fn delete_by_user(conn: &Connection, user: u32) -> Result<(), rusqlite::Error> {
+        conn.execute("DELETE FROM guest_code WHERE user_id=?1", &[&user])?;
+        Ok(())
+    }
+}
@@ -0,0 +1,132 @@
+use super::*;
+use crate::db_entry::{FlatEntry, UserEntry};
+use chrono::Duration;
+
+impl Default for GuestCodeEntry<(), u32, u32, u32> {
+    fn default() -> Self {
+        Self {
+            id: (),
+            label: "Cleaner".to_string(),
+            code_hash: "hash".to_string(),
+            valid_from: Utc::now(),
+            valid_until: Utc::now() + Duration::days(1),
+            max_uses: 1,
+            uses: 0,
+            creation_datetime: Utc::now(),
+            flat: 0,
+            user: 0,
+            door: 0,
+        }
+    }
+}
+
+impl Default for GuestCodeEntry {
+    fn default() -> Self {
+        Self {
+            id: 1,
+            label: "Cleaner".to_string(),
+            code_hash: "hash".to_string(),
+            valid_from: Utc::now(),
+            valid_until: Utc::now() + Duration::days(1),
+            max_uses: 1,
+            uses: 0,
+            creation_datetime: Utc::now(),
+            flat: 0,
+            user: 0,
+            door: 0,
+        }
+    }
+}
+
+#[test]
+fn scenario_1_with_all_methods() {
+    let sql_scheme = include_str!("../../scheme.sql");
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(sql_scheme).unwrap();
+
+    let flat = FlatEntry::default().create(&conn).unwrap();
+    let user = UserEntry {
+        flat: Some(flat.get_id()),
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+
+    let mut code = GuestCodeEntry {
+        flat: flat.get_id(),
+        user: user.get_id(),
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+    GuestCodeEntry {
+        label: "Plumber".to_string(),
+        code_hash: "other_hash".to_string(),
+        flat: flat.get_id(),
+        user: user.get_id(),
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+
+    assert!(code.is_valid(Utc::now()));
+    assert!(code.increment_uses(&conn).unwrap());
+    assert!(!code.increment_uses(&conn).unwrap());
+    assert!(!code.is_valid(Utc::now()));
+
+    code.label = "Window Cleaner".to_string();
+    code.update(&conn).unwrap();
+
+    let codes = GuestCodeEntry::get_by_flat(&conn, flat.get_id()).unwrap();
+    assert_eq!(codes.len(), 2);
+    assert!(codes.contains(&code));
+
+    assert_eq!(
+        GuestCodeEntry::get_by_code_hash(&conn, "hash")
+            .unwrap()
+            .unwrap(),
+        code
+    );
+    assert_eq!(
+        GuestCodeEntry::get_by_id(&conn, code.get_id())
+            .unwrap()
+            .unwrap(),
+        code
+    );
+
+    code.delete(&conn).unwrap();
+
+    assert!(GuestCodeEntry::get_by_code_hash(&conn, "hash")
+        .unwrap()
+        .is_none());
+
+    GuestCodeEntry::delete_by_user(&conn, user.get_id()).unwrap();
+    assert!(GuestCodeEntry::get_by_flat(&conn, flat.get_id())
+        .unwrap()
+        .is_empty());
+
+    GuestCodeEntry {
+        flat: flat.get_id(),
+        user: user.get_id(),
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+    GuestCodeEntry::delete_by_flat(&conn, flat.get_id()).unwrap();
+    assert!(GuestCodeEntry::get_by_flat(&conn, flat.get_id())
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn validity_window() {
+    let code = GuestCodeEntry {
+        max_uses: 3,
+        uses: 2,
+        ..Default::default()
+    };
+
+    assert!(!code.is_valid(code.valid_from - Duration::seconds(1)));
+    assert!(code.is_valid(code.valid_from));
+    assert!(!code.is_valid(code.valid_until));
+}
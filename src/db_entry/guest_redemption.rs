@@ -0,0 +1,96 @@
+use super::{rusqlite, Connection, Entry, Identifier};
+use chrono::{offset::Utc, DateTime};
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+#[path = "./guest_redemption_test.rs"]
+mod guest_redemption_test;
+
+/// Guest-Redemption entry of the corresponding "guest_redemption" table.
+/// It logs every redemption of a guest code with its label, so the log is kept after a revocation.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct GuestRedemptionEntry<ID: Identifier = u32, FRef: Entry = u32> {
+    pub id: ID,
+    pub label: String,
+    pub redemption_datetime: DateTime<Utc>,
+    pub ip_address: Option<String>,
+    pub flat: FRef,
+}
+
+impl<FRef: Entry> Entry for GuestRedemptionEntry<u32, FRef> {
+    #[inline(always)]
+    fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    fn delete_entry(conn: &Connection, id: u32) -> Result<(), rusqlite::Error> {
+        conn.execute("DELETE FROM guest_redemption WHERE ID=?1", &[&id])?;
+        Ok(())
+    }
+
+    fn update(&self, conn: &Connection) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "UPDATE guest_redemption SET label = ?1, redemption_datetime = ?2, ip_address = ?3, flat_id = ?4 WHERE id = ?5",
+            &[
+                &self.label,
+                &self.redemption_datetime,
+                &self.ip_address,
+                &self.flat.get_id(),
+                &self.id,
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+impl<FRef: Entry> GuestRedemptionEntry<(), FRef> {
+    pub fn create(self, conn: &Connection) -> Result<GuestRedemptionEntry, rusqlite::Error> {
+        let flat_id = self.flat.get_id();
+        conn.execute(
+            "INSERT INTO guest_redemption (label, redemption_datetime, ip_address, flat_id) VALUES (?1, ?2, ?3, ?4)",
+            &[
+                &self.label,
+                &self.redemption_datetime,
+                &self.ip_address,
+                &flat_id,
+            ],
+        )?;
+        Ok(GuestRedemptionEntry {
+            id: (conn.last_insert_rowid() as u32),
+            label: self.label,
+            redemption_datetime: self.redemption_datetime,
+            ip_address: self.ip_address,
+            flat: flat_id,
+        })
+    }
+}
+
+impl GuestRedemptionEntry<u32, u32> {
+    /// Converts a rusqlite row to a GuestRedemptionEntry
+    fn row_2_guest_redemption(row: &rusqlite::Row) -> Self {
+        Self {
+            id: row.get::<usize, u32>(0),
+            label: row.get::<usize, String>(1),
+            redemption_datetime: row.get::<usize, DateTime<Utc>>(2),
+            ip_address: row.get::<usize, Option<String>>(3),
+            flat: row.get::<usize, u32>(4),
+        }
+    }
+
+    /// Returns the latest redemptions of a flat, the newest first
+    pub fn get_by_flat(
+        conn: &Connection,
+        flat: u32,
+        limit: u32,
+    ) -> Result<Vec<Self>, rusqlite::Error> {
+        let mut stmt = conn.prepare("SELECT id, label, redemption_datetime, ip_address, flat_id FROM guest_redemption WHERE flat_id=?1 ORDER BY redemption_datetime DESC LIMIT ?2")?;
+        return stmt
+            .query_map(&[&flat, &limit], Self::row_2_guest_redemption)?
+            .collect();
+    }
+
+    pub fn delete_by_flat(conn: &Connection, flat: u32) -> Result<(), rusqlite::Error> {
+        conn.execute("DELETE FROM guest_redemption WHERE flat_id=?1", &[&flat])?;
+        Ok(())
+    }
+}
@@ -0,0 +1,77 @@
+use super::*;
+use crate::db_entry::FlatEntry;
+use chrono::Duration;
+
+impl Default for GuestRedemptionEntry<(), u32> {
+    fn default() -> Self {
+        Self {
+            id: (),
+            label: "Cleaner".to_string(),
+            redemption_datetime: Utc::now(),
+            ip_address: Some("127.0.0.1".to_string()),
+            flat: 0,
+        }
+    }
+}
+
+impl Default for GuestRedemptionEntry {
+    fn default() -> Self {
+        Self {
+            id: 1,
+            label: "Cleaner".to_string(),
+            redemption_datetime: Utc::now(),
+            ip_address: Some("127.0.0.1".to_string()),
+            flat: 0,
+        }
+    }
+}
+
+#[test]
+fn scenario_1_with_all_methods() {
+    let sql_scheme = include_str!("../../scheme.sql");
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(sql_scheme).unwrap();
+
+    let flat = FlatEntry::default().create(&conn).unwrap();
+
+    let mut redemption = GuestRedemptionEntry {
+        redemption_datetime: Utc::now() - Duration::hours(1),
+        flat: flat.get_id(),
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+    let newest_redemption = GuestRedemptionEntry {
+        label: "Plumber".to_string(),
+        ip_address: None,
+        flat: flat.get_id(),
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+
+    redemption.label = "Window Cleaner".to_string();
+    redemption.update(&conn).unwrap();
+
+    assert_eq!(
+        GuestRedemptionEntry::get_by_flat(&conn, flat.get_id(), 10).unwrap(),
+        vec![newest_redemption.clone(), redemption.clone()]
+    );
+    assert_eq!(
+        GuestRedemptionEntry::get_by_flat(&conn, flat.get_id(), 1).unwrap(),
+        vec![newest_redemption]
+    );
+
+    redemption.delete(&conn).unwrap();
+    assert_eq!(
+        GuestRedemptionEntry::get_by_flat(&conn, flat.get_id(), 10)
+            .unwrap()
+            .len(),
+        1
+    );
+
+    GuestRedemptionEntry::delete_by_flat(&conn, flat.get_id()).unwrap();
+    assert!(GuestRedemptionEntry::get_by_flat(&conn, flat.get_id(), 10)
+        .unwrap()
+        .is_empty());
+}
@@ -0,0 +1,116 @@
+use super::{rusqlite, Connection, Entry, Identifier, SubjectType};
+use chrono::{offset::Utc, DateTime};
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+#[path = "./login_throttle_test.rs"]
+mod login_throttle_test;
+
+/// Login-Throttle entry of the corresponding "login_throttle" table.
+/// It counts the failed logins of an user name or an IP address.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct LoginThrottleEntry<ID: Identifier = u32> {
+    pub id: ID,
+    pub subject_type: SubjectType,
+    pub subject: String,
+    pub failed_attempts: u32,
+    pub last_failure_datetime: DateTime<Utc>,
+    pub locked_until: Option<DateTime<Utc>>,
+}
+
+impl Entry for LoginThrottleEntry<u32> {
+    #[inline(always)]
+    fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    fn delete_entry(conn: &Connection, id: u32) -> Result<(), rusqlite::Error> {
+        conn.execute("DELETE FROM login_throttle WHERE ID=?1", &[&id])?;
+        Ok(())
+    }
+
+    fn update(&self, conn: &Connection) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "UPDATE login_throttle SET subject_type = ?1, subject = ?2, failed_attempts = ?3, last_failure_datetime = ?4, locked_until = ?5 WHERE id = ?6",
+            &[
+                &self.subject_type,
+                &self.subject,
+                &self.failed_attempts,
+                &self.last_failure_datetime,
+                &self.locked_until,
+                &self.id,
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+impl LoginThrottleEntry<()> {
+    pub fn create(self, conn: &Connection) -> Result<LoginThrottleEntry, rusqlite::Error> {
+        conn.execute(
+            "INSERT INTO login_throttle (subject_type, subject, failed_attempts, last_failure_datetime, locked_until) VALUES (?1, ?2, ?3, ?4, ?5)",
+            &[
+                &self.subject_type,
+                &self.subject,
+                &self.failed_attempts,
+                &self.last_failure_datetime,
+                &self.locked_until,
+            ],
+        )?;
+        Ok(LoginThrottleEntry {
+            id: (conn.last_insert_rowid() as u32),
+            subject_type: self.subject_type,
+            subject: self.subject,
+            failed_attempts: self.failed_attempts,
+            last_failure_datetime: self.last_failure_datetime,
+            locked_until: self.locked_until,
+        })
+    }
+}
+
+impl LoginThrottleEntry<u32> {
+    /// Converts a rusqlite row to a LoginThrottleEntry
+    fn row_2_login_throttle(row: &rusqlite::Row) -> Self {
+        Self {
+            id: row.get::<usize, u32>(0),
+            subject_type: row.get::<usize, SubjectType>(1),
+            subject: row.get::<usize, String>(2),
+            failed_attempts: row.get::<usize, u32>(3),
+            last_failure_datetime: row.get::<usize, DateTime<Utc>>(4),
+            locked_until: row.get::<usize, Option<DateTime<Utc>>>(5),
+        }
+    }
+
+    pub fn get_all(conn: &Connection) -> Result<Vec<Self>, rusqlite::Error> {
+        let mut stmt = conn.prepare(
+            "SELECT id, subject_type, subject, failed_attempts, last_failure_datetime, locked_until FROM login_throttle ORDER BY locked_until DESC",
+        )?;
+        return stmt.query_map(&[], Self::row_2_login_throttle)?.collect();
+    }
+
+    pub fn get_by_subject(
+        conn: &Connection,
+        subject_type: SubjectType,
+        subject: &str,
+    ) -> Result<Option<Self>, rusqlite::Error> {
+        let mut stmt = conn.prepare(
+            "SELECT id, subject_type, subject, failed_attempts, last_failure_datetime, locked_until FROM login_throttle WHERE subject_type = ?1 AND subject = ?2 LIMIT 1",
+        )?;
+        return stmt
+            .query_map(&[&subject_type, &subject], Self::row_2_login_throttle)?
+            .next()
+            .map_or_else(|| Ok(None), |entry_result| entry_result.map(Some));
+    }
+
+    pub fn delete_by_subject(
+        conn: &Connection,
+        subject_type: SubjectType,
+        subject: &str,
+    ) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "DELETE FROM login_throttle WHERE subject_type = ?1 AND subject = ?2",
+            &[&subject_type, &subject],
+        )?;
+        Ok(())
+    }
+}
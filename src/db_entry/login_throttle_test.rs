@@ -0,0 +1,67 @@
+use super::*;
+
+impl Default for LoginThrottleEntry<()> {
+    fn default() -> Self {
+        Self {
+            id: (),
+            subject_type: SubjectType::UserName,
+            subject: "Alice".to_string(),
+            failed_attempts: 1,
+            last_failure_datetime: Utc::now(),
+            locked_until: None,
+        }
+    }
+}
+
+impl Default for LoginThrottleEntry {
+    fn default() -> Self {
+        Self {
+            id: 1,
+            subject_type: SubjectType::IpAddress,
+            subject: "127.0.0.1".to_string(),
+            failed_attempts: 20,
+            last_failure_datetime: Utc::now(),
+            locked_until: Some(Utc::now()),
+        }
+    }
+}
+
+#[test]
+fn scenario_1_with_all_methods() {
+    let sql_scheme = include_str!("../../scheme.sql");
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(sql_scheme).unwrap();
+
+    let mut throttle = LoginThrottleEntry::default().create(&conn).unwrap();
+    let ip_throttle = LoginThrottleEntry {
+        subject_type: SubjectType::IpAddress,
+        subject: "127.0.0.1".to_string(),
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+
+    let throttles = LoginThrottleEntry::get_all(&conn).unwrap();
+    assert_eq!(throttles.len(), 2);
+    assert!(throttles.contains(&throttle));
+
+    throttle.failed_attempts = 5;
+    throttle.locked_until = Some(Utc::now());
+    throttle.update(&conn).unwrap();
+
+    assert_eq!(
+        LoginThrottleEntry::get_by_subject(&conn, SubjectType::UserName, "Alice")
+            .unwrap()
+            .unwrap(),
+        throttle
+    );
+    assert_matches!(
+        LoginThrottleEntry::get_by_subject(&conn, SubjectType::IpAddress, "Alice"),
+        Ok(None)
+    );
+
+    LoginThrottleEntry::delete_by_subject(&conn, SubjectType::UserName, "Alice").unwrap();
+    ip_throttle.delete(&conn).unwrap();
+
+    assert!(LoginThrottleEntry::get_all(&conn).unwrap().is_empty());
+}
@@ -82,3 +82,63 @@ pub use flat::FlatEntry;
 
 mod user_session;
 pub use user_session::UserSessionEntry;
+
+mod recovery_code;
+pub use recovery_code::RecoveryCodeEntry;
+
+mod subject_type;
+pub use subject_type::SubjectType;
+
+mod login_throttle;
+pub use login_throttle::LoginThrottleEntry;
+
+mod api_scope;
+pub use api_scope::ApiScope;
+
+mod api_token;
+pub use api_token::ApiTokenEntry;
+
+mod guest_code;
+pub use guest_code::GuestCodeEntry;
+
+mod guest_redemption;
+pub use guest_redemption::GuestRedemptionEntry;
+
+mod password_reset;
+pub use password_reset::PasswordResetEntry;
+
+mod permission;
+pub use permission::Permission;
+
+mod role;
+pub use role::RoleEntry;
+
+mod passkey;
+pub use passkey::PasskeyEntry;
+
+mod password_history;
+pub use password_history::PasswordHistoryEntry;
+
+mod audit_action;
+pub use audit_action::AuditAction;
+
+mod audit_log;
+pub use audit_log::AuditLogEntry;
+
+mod access_window;
+pub use access_window::AccessWindowEntry;
+
+mod access_exception;
+pub use access_exception::AccessExceptionEntry;
+
+mod door;
+pub use door::DoorEntry;
+
+mod door_event_source;
+pub use door_event_source::DoorEventSource;
+
+mod door_event_outcome;
+pub use door_event_outcome::DoorEventOutcome;
+
+mod door_event;
+pub use door_event::{DoorEventEntry, DoorEventQuery};
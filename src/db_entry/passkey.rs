@@ -0,0 +1,119 @@
+use super::{rusqlite, Connection, Entry, Identifier};
+use chrono::{offset::Utc, DateTime};
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+#[path = "./passkey_test.rs"]
+mod passkey_test;
+
+/// Passkey entry of the corresponding "passkey" table.
+/// The credential is the serialized WebAuthn passkey with its public key and signature counter.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct PasskeyEntry<ID: Identifier = u32, URef: Entry = u32> {
+    pub id: ID,
+    pub name: String,
+    /// The base64url encoded ID of the credential
+    pub credential_id: String,
+    pub credential: String,
+    pub creation_datetime: DateTime<Utc>,
+    pub last_used_datetime: Option<DateTime<Utc>>,
+    pub user: URef,
+}
+
+impl<URef: Entry> Entry for PasskeyEntry<u32, URef> {
+    #[inline(always)]
+    fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    fn delete_entry(conn: &Connection, id: u32) -> Result<(), rusqlite::Error> {
+        conn.execute("DELETE FROM passkey WHERE ID=?1", &[&id])?;
+        Ok(())
+    }
+
+    fn update(&self, conn: &Connection) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "UPDATE passkey SET name = ?1, credential_id = ?2, credential = ?3, creation_datetime = ?4, last_used_datetime = ?5, user_id = ?6 WHERE id = ?7",
+            &[
+                &self.name,
+                &self.credential_id,
+                &self.credential,
+                &self.creation_datetime,
+                &self.last_used_datetime,
+                &self.user.get_id(),
+                &self.id,
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+impl<URef: Entry> PasskeyEntry<(), URef> {
+    pub fn create(self, conn: &Connection) -> Result<PasskeyEntry, rusqlite::Error> {
+        let user_id = self.user.get_id();
+        conn.execute(
+            "INSERT INTO passkey (name, credential_id, credential, creation_datetime, last_used_datetime, user_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            &[
+                &self.name,
+                &self.credential_id,
+                &self.credential,
+                &self.creation_datetime,
+                &self.last_used_datetime,
+                &user_id,
+            ],
+        )?;
+        Ok(PasskeyEntry {
+            id: (conn.last_insert_rowid() as u32),
+            name: self.name,
+            credential_id: self.credential_id,
+            credential: self.credential,
+            creation_datetime: self.creation_datetime,
+            last_used_datetime: self.last_used_datetime,
+            user: user_id,
+        })
+    }
+}
+
+impl PasskeyEntry<u32, u32> {
+    /// Converts a rusqlite row to a PasskeyEntry
+    fn row_2_passkey(row: &rusqlite::Row) -> Self {
+        Self {
+            id: row.get::<usize, u32>(0),
+            name: row.get::<usize, String>(1),
+            credential_id: row.get::<usize, String>(2),
+            credential: row.get::<usize, String>(3),
+            creation_datetime: row.get::<usize, DateTime<Utc>>(4),
+            last_used_datetime: row.get::<usize, Option<DateTime<Utc>>>(5),
+            user: row.get::<usize, u32>(6),
+        }
+    }
+
+    pub fn get_by_user(conn: &Connection, user: u32) -> Result<Vec<Self>, rusqlite::Error> {
+        let mut stmt = conn.prepare("SELECT id, name, credential_id, credential, creation_datetime, last_used_datetime, user_id FROM passkey WHERE user_id=?1 ORDER BY name")?;
+        return stmt.query_map(&[&user], Self::row_2_passkey)?.collect();
+    }
+
+    pub fn get_by_id(conn: &Connection, id: u32) -> Result<Option<Self>, rusqlite::Error> {
+        let mut stmt = conn.prepare("SELECT id, name, credential_id, credential, creation_datetime, last_used_datetime, user_id FROM passkey WHERE id=?1 LIMIT 1")?;
+        return stmt
+            .query_map(&[&id], Self::row_2_passkey)?
+            .next()
+            .map_or_else(|| Ok(None), |entry_result| entry_result.map(Some));
+    }
+
+    pub fn get_by_credential_id(
+        conn: &Connection,
+        credential_id: &str,
+    ) -> Result<Option<Self>, rusqlite::Error> {
+        let mut stmt = conn.prepare("SELECT id, name, credential_id, credential, creation_datetime, last_used_datetime, user_id FROM passkey WHERE credential_id=?1 LIMIT 1")?;
+        return stmt
+            .query_map(&[&credential_id], Self::row_2_passkey)?
+            .next()
+            .map_or_else(|| Ok(None), |entry_result| entry_result.map(Some));
+    }
+
+    pub fn delete_by_user(conn: &Connection, user: u32) -> Result<(), rusqlite::Error> {
+        conn.execute("DELETE FROM passkey WHERE user_id=?1", &[&user])?;
+        Ok(())
+    }
+}
@@ -0,0 +1,95 @@
+use super::*;
+use crate::db_entry::UserEntry;
+
+impl Default for PasskeyEntry<(), u32> {
+    fn default() -> Self {
+        Self {
+            id: (),
+            name: "Phone".to_string(),
+            credential_id: "Y3JlZGVudGlhbA".to_string(),
+            credential: "{}".to_string(),
+            creation_datetime: Utc::now(),
+            last_used_datetime: None,
+            user: 0,
+        }
+    }
+}
+
+impl Default for PasskeyEntry {
+    fn default() -> Self {
+        Self {
+            id: 1,
+            name: "Phone".to_string(),
+            credential_id: "Y3JlZGVudGlhbA".to_string(),
+            credential: "{}".to_string(),
+            creation_datetime: Utc::now(),
+            last_used_datetime: Some(Utc::now()),
+            user: 0,
+        }
+    }
+}
+
+#[test]
+fn scenario_1_with_all_methods() {
+    let sql_scheme = include_str!("../../scheme.sql");
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(sql_scheme).unwrap();
+
+    let user = UserEntry::default().create(&conn).unwrap();
+
+    let mut passkey = PasskeyEntry {
+        user: user.get_id(),
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+    PasskeyEntry {
+        name: "Laptop".to_string(),
+        credential_id: "b3RoZXI".to_string(),
+        user: user.get_id(),
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+
+    // The credential IDs are unique
+    assert!(PasskeyEntry {
+        user: user.get_id(),
+        ..Default::default()
+    }
+    .create(&conn)
+    .is_err());
+
+    passkey.credential = "{\"counter\":1}".to_string();
+    passkey.last_used_datetime = Some(Utc::now());
+    passkey.update(&conn).unwrap();
+
+    let passkeys = PasskeyEntry::get_by_user(&conn, user.get_id()).unwrap();
+    assert_eq!(passkeys.len(), 2);
+    assert!(passkeys.contains(&passkey));
+
+    assert_eq!(
+        PasskeyEntry::get_by_credential_id(&conn, "Y3JlZGVudGlhbA")
+            .unwrap()
+            .unwrap(),
+        passkey
+    );
+    assert_eq!(
+        PasskeyEntry::get_by_id(&conn, passkey.get_id())
+            .unwrap()
+            .unwrap(),
+        passkey
+    );
+
+    passkey.delete(&conn).unwrap();
+
+    assert!(PasskeyEntry::get_by_credential_id(&conn, "Y3JlZGVudGlhbA")
+        .unwrap()
+        .is_none());
+
+    PasskeyEntry::delete_by_user(&conn, user.get_id()).unwrap();
+
+    assert!(PasskeyEntry::get_by_user(&conn, user.get_id())
+        .unwrap()
+        .is_empty());
+}
@@ -0,0 +1,113 @@
+use super::{rusqlite, Connection, Entry, HashEntry, Identifier};
+use chrono::{offset::Utc, DateTime};
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+#[path = "./password_history_test.rs"]
+mod password_history_test;
+
+/// Password-History entry of the corresponding "password_history" table.
+/// It holds the hashes of the current and the previous passwords of an user, the newest one is the current password.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct PasswordHistoryEntry<ID: Identifier = u32, URef: Entry = u32> {
+    pub id: ID,
+    pub pw_hash: HashEntry,
+    /// When the password has been set
+    pub creation_datetime: DateTime<Utc>,
+    pub user: URef,
+}
+
+impl<URef: Entry> Entry for PasswordHistoryEntry<u32, URef> {
+    #[inline(always)]
+    fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    fn delete_entry(conn: &Connection, id: u32) -> Result<(), rusqlite::Error> {
+        conn.execute("DELETE FROM password_history WHERE ID=?1", &[&id])?;
+        Ok(())
+    }
+
+    fn update(&self, conn: &Connection) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "UPDATE password_history SET pw_hash = ?1, pw_salt = ?2, pw_config = ?3, creation_datetime = ?4, user_id = ?5 WHERE id = ?6",
+            &[
+                &self.pw_hash.hash,
+                &self.pw_hash.salt,
+                &self.pw_hash.config,
+                &self.creation_datetime,
+                &self.user.get_id(),
+                &self.id,
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+impl<URef: Entry> PasswordHistoryEntry<(), URef> {
+    pub fn create(self, conn: &Connection) -> Result<PasswordHistoryEntry, rusqlite::Error> {
+        let user_id = self.user.get_id();
+        conn.execute(
+            "INSERT INTO password_history (pw_hash, pw_salt, pw_config, creation_datetime, user_id) VALUES (?1, ?2, ?3, ?4, ?5)",
+            &[
+                &self.pw_hash.hash,
+                &self.pw_hash.salt,
+                &self.pw_hash.config,
+                &self.creation_datetime,
+                &user_id,
+            ],
+        )?;
+        Ok(PasswordHistoryEntry {
+            id: (conn.last_insert_rowid() as u32),
+            pw_hash: self.pw_hash,
+            creation_datetime: self.creation_datetime,
+            user: user_id,
+        })
+    }
+}
+
+impl PasswordHistoryEntry<u32, u32> {
+    /// Converts a rusqlite row to a PasswordHistoryEntry
+    fn row_2_password_history(row: &rusqlite::Row) -> Self {
+        Self {
+            id: row.get::<usize, u32>(0),
+            pw_hash: HashEntry {
+                hash: row.get::<usize, String>(1),
+                salt: row.get::<usize, String>(2),
+                config: row.get::<usize, String>(3),
+            },
+            creation_datetime: row.get::<usize, DateTime<Utc>>(4),
+            user: row.get::<usize, u32>(5),
+        }
+    }
+
+    /// Returns the newest entries of the user, beginning with the current password
+    pub fn get_latest_by_user(
+        conn: &Connection,
+        user: u32,
+        limit: u32,
+    ) -> Result<Vec<Self>, rusqlite::Error> {
+        let mut stmt = conn.prepare("SELECT id, pw_hash, pw_salt, pw_config, creation_datetime, user_id FROM password_history WHERE user_id=?1 ORDER BY id DESC LIMIT ?2")?;
+        return stmt
+            .query_map(&[&user, &limit], Self::row_2_password_history)?
+            .collect();
+    }
+
+    /// Deletes all entries of the user except the newest ones
+    pub fn delete_old_by_user(
+        conn: &Connection,
+        user: u32,
+        keep: u32,
+    ) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "DELETE FROM password_history WHERE user_id=?1 AND id NOT IN (SELECT id FROM password_history WHERE user_id=?1 ORDER BY id DESC LIMIT ?2)",
+            &[&user, &keep],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_by_user(conn: &Connection, user: u32) -> Result<(), rusqlite::Error> {
+        conn.execute("DELETE FROM password_history WHERE user_id=?1", &[&user])?;
+        Ok(())
+    }
+}
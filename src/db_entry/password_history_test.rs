@@ -0,0 +1,81 @@
+use super::*;
+use crate::db_entry::UserEntry;
+use chrono::Duration;
+
+impl Default for PasswordHistoryEntry<(), u32> {
+    fn default() -> Self {
+        Self {
+            id: (),
+            pw_hash: HashEntry {
+                hash: "unsecure".to_string(),
+                salt: "salt".to_string(),
+                config: "plain".to_string(),
+            },
+            creation_datetime: Utc::now(),
+            user: 0,
+        }
+    }
+}
+
+#[test]
+fn scenario_1_with_all_methods() {
+    let sql_scheme = include_str!("../../scheme.sql");
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(sql_scheme).unwrap();
+
+    let user = UserEntry::default().create(&conn).unwrap();
+
+    let mut oldest = PasswordHistoryEntry {
+        creation_datetime: Utc::now() - Duration::days(2),
+        user: user.get_id(),
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+    let older = PasswordHistoryEntry {
+        creation_datetime: Utc::now() - Duration::days(1),
+        user: user.get_id(),
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+    let current = PasswordHistoryEntry {
+        user: user.get_id(),
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+
+    oldest.pw_hash.hash = "oldest".to_string();
+    oldest.update(&conn).unwrap();
+
+    assert_eq!(
+        PasswordHistoryEntry::get_latest_by_user(&conn, user.get_id(), 10).unwrap(),
+        vec![current.clone(), older.clone(), oldest]
+    );
+    assert_eq!(
+        PasswordHistoryEntry::get_latest_by_user(&conn, user.get_id(), 1).unwrap(),
+        vec![current.clone()]
+    );
+
+    PasswordHistoryEntry::delete_old_by_user(&conn, user.get_id(), 2).unwrap();
+    assert_eq!(
+        PasswordHistoryEntry::get_latest_by_user(&conn, user.get_id(), 10).unwrap(),
+        vec![current.clone(), older]
+    );
+
+    current.delete(&conn).unwrap();
+    assert_eq!(
+        PasswordHistoryEntry::get_latest_by_user(&conn, user.get_id(), 10)
+            .unwrap()
+            .len(),
+        1
+    );
+
+    PasswordHistoryEntry::delete_by_user(&conn, user.get_id()).unwrap();
+    assert!(
+        PasswordHistoryEntry::get_latest_by_user(&conn, user.get_id(), 10)
+            .unwrap()
+            .is_empty()
+    );
+}
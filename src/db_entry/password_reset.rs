@@ -0,0 +1,103 @@
+use super::{rusqlite, Connection, Entry, Identifier};
+use chrono::{offset::Utc, DateTime};
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+#[path = "./password_reset_test.rs"]
+mod password_reset_test;
+
+/// Password-Reset entry of the corresponding "password_reset" table.
+/// Only the hash of the single-use code is stored, the code itself is shown once to the admin.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct PasswordResetEntry<ID: Identifier = u32, URef: Entry = u32> {
+    pub id: ID,
+    pub code_hash: String,
+    pub creation_datetime: DateTime<Utc>,
+    pub expiry_datetime: DateTime<Utc>,
+    pub user: URef,
+}
+
+impl<URef: Entry> Entry for PasswordResetEntry<u32, URef> {
+    #[inline(always)]
+    fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    fn delete_entry(conn: &Connection, id: u32) -> Result<(), rusqlite::Error> {
+        conn.execute("DELETE FROM password_reset WHERE ID=?1", &[&id])?;
+        Ok(())
+    }
+
+    fn update(&self, conn: &Connection) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "UPDATE password_reset SET code_hash = ?1, creation_datetime = ?2, expiry_datetime = ?3, user_id = ?4 WHERE id = ?5",
+            &[
+                &self.code_hash,
+                &self.creation_datetime,
+                &self.expiry_datetime,
+                &self.user.get_id(),
+                &self.id,
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+impl<URef: Entry> PasswordResetEntry<(), URef> {
+    pub fn create(self, conn: &Connection) -> Result<PasswordResetEntry, rusqlite::Error> {
+        let user_id = self.user.get_id();
+        conn.execute(
+            "INSERT INTO password_reset (code_hash, creation_datetime, expiry_datetime, user_id) VALUES (?1, ?2, ?3, ?4)",
+            &[
+                &self.code_hash,
+                &self.creation_datetime,
+                &self.expiry_datetime,
+                &user_id,
+            ],
+        )?;
+        Ok(PasswordResetEntry {
+            id: (conn.last_insert_rowid() as u32),
+            code_hash: self.code_hash,
+            creation_datetime: self.creation_datetime,
+            expiry_datetime: self.expiry_datetime,
+            user: user_id,
+        })
+    }
+}
+
+impl PasswordResetEntry<u32, u32> {
+    /// Converts a rusqlite row to a PasswordResetEntry
+    fn row_2_password_reset(row: &rusqlite::Row) -> Self {
+        Self {
+            id: row.get::<usize, u32>(0),
+            code_hash: row.get::<usize, String>(1),
+            creation_datetime: row.get::<usize, DateTime<Utc>>(2),
+            expiry_datetime: row.get::<usize, DateTime<Utc>>(3),
+            user: row.get::<usize, u32>(4),
+        }
+    }
+
+    pub fn get_by_code_hash(
+        conn: &Connection,
+        code_hash: &str,
+    ) -> Result<Option<Self>, rusqlite::Error> {
+        let mut stmt = conn.prepare("SELECT id, code_hash, creation_datetime, expiry_datetime, user_id FROM password_reset WHERE code_hash=?1 LIMIT 1")?;
+        return stmt
+            .query_map(&[&code_hash], Self::row_2_password_reset)?
+            .next()
+            .map_or_else(|| Ok(None), |entry_result| entry_result.map(Some));
+    }
+
+    pub fn delete_by_user(conn: &Connection, user: u32) -> Result<(), rusqlite::Error> {
+        conn.execute("DELETE FROM password_reset WHERE user_id=?1", &[&user])?;
+        Ok(())
+    }
+
+    pub fn delete_expired(conn: &Connection, now: DateTime<Utc>) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "DELETE FROM password_reset WHERE expiry_datetime <= ?1",
+            &[&now],
+        )?;
+        Ok(())
+    }
+}
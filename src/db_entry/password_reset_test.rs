@@ -0,0 +1,72 @@
+use super::*;
+use crate::db_entry::UserEntry;
+use chrono::Duration;
+
+impl Default for PasswordResetEntry<(), u32> {
+    fn default() -> Self {
+        Self {
+            id: (),
+            code_hash: "hash".to_string(),
+            creation_datetime: Utc::now(),
+            expiry_datetime: Utc::now() + Duration::hours(1),
+            user: 0,
+        }
+    }
+}
+
+#[test]
+fn scenario_1_with_all_methods() {
+    let sql_scheme = include_str!("../../scheme.sql");
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(sql_scheme).unwrap();
+
+    let user = UserEntry::default().create(&conn).unwrap();
+
+    let mut reset = PasswordResetEntry {
+        user: user.get_id(),
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+    let expired_reset = PasswordResetEntry {
+        code_hash: "expired_hash".to_string(),
+        expiry_datetime: Utc::now() - Duration::hours(1),
+        user: user.get_id(),
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+
+    reset.expiry_datetime = Utc::now() + Duration::hours(2);
+    reset.update(&conn).unwrap();
+
+    assert_eq!(
+        PasswordResetEntry::get_by_code_hash(&conn, "hash")
+            .unwrap()
+            .unwrap(),
+        reset
+    );
+
+    PasswordResetEntry::delete_expired(&conn, Utc::now()).unwrap();
+    assert!(
+        PasswordResetEntry::get_by_code_hash(&conn, &expired_reset.code_hash)
+            .unwrap()
+            .is_none()
+    );
+
+    reset.delete(&conn).unwrap();
+    assert!(PasswordResetEntry::get_by_code_hash(&conn, "hash")
+        .unwrap()
+        .is_none());
+
+    PasswordResetEntry {
+        user: user.get_id(),
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+    PasswordResetEntry::delete_by_user(&conn, user.get_id()).unwrap();
+    assert!(PasswordResetEntry::get_by_code_hash(&conn, "hash")
+        .unwrap()
+        .is_none());
+}
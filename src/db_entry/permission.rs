@@ -0,0 +1,109 @@
+use super::UserType;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+#[cfg(test)]
+#[path = "./permission_test.rs"]
+mod permission_test;
+
+/// A named permission, which is checked by the `PermissionGuard`.
+/// The user type grants the default permissions, roles may grant further ones.
+/// They are stored space-separated in the permissions database field of a role.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum Permission {
+    /// Activates the door opener
+    #[serde(rename = "door.open")]
+    DoorOpen,
+    /// Watches the camera of the own flat or of all flats, if the user has no flat
+    #[serde(rename = "camera.view")]
+    CameraView,
+    /// Administers the flats
+    #[serde(rename = "flat.manage")]
+    FlatManage,
+    /// Administers the users, their roles, sessions and tokens
+    #[serde(rename = "user.manage")]
+    UserManage,
+    /// Reads the events of the door
+    #[serde(rename = "events.view")]
+    EventsView,
+    /// Keeps the door unlocked until a chosen time or until it's cancelled, it's only granted by roles
+    #[serde(rename = "door.hold_open")]
+    DoorHoldOpen,
+}
+
+impl Permission {
+    /// get a Vector of all permissions
+    pub fn get_list() -> Vec<Self> {
+        vec![
+            Self::DoorOpen,
+            Self::CameraView,
+            Self::FlatManage,
+            Self::UserManage,
+            Self::EventsView,
+            Self::DoorHoldOpen,
+        ]
+    }
+
+    /// The permissions, which every user of the type has without any role
+    pub fn defaults_for(user_type: UserType) -> Vec<Self> {
+        match user_type {
+            UserType::User | UserType::FlatManager => {
+                vec![Self::DoorOpen, Self::CameraView, Self::EventsView]
+            }
+            UserType::Admin => vec![Self::FlatManage, Self::UserManage, Self::EventsView],
+        }
+    }
+
+    /// Whether the permission belongs to the administration
+    #[inline(always)]
+    pub fn is_administrative(&self) -> bool {
+        matches!(self, Self::FlatManage | Self::UserManage)
+    }
+
+    /// Converts the permissions to the space-separated representation of the database
+    pub fn join(permissions: &[Self]) -> String {
+        permissions
+            .iter()
+            .map(Self::to_string)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Parses the space-separated representation of the database, unknown permissions are ignored
+    pub fn split(permissions: &str) -> Vec<Self> {
+        permissions
+            .split_whitespace()
+            .filter_map(|permission| permission.parse().ok())
+            .collect()
+    }
+}
+
+impl fmt::Display for Permission {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Self::DoorOpen => write!(f, "door.open"),
+            Self::CameraView => write!(f, "camera.view"),
+            Self::FlatManage => write!(f, "flat.manage"),
+            Self::UserManage => write!(f, "user.manage"),
+            Self::EventsView => write!(f, "events.view"),
+            Self::DoorHoldOpen => write!(f, "door.hold_open"),
+        }
+    }
+}
+
+impl FromStr for Permission {
+    type Err = ();
+
+    fn from_str(permission: &str) -> Result<Self, Self::Err> {
+        match permission {
+            "door.open" => Ok(Self::DoorOpen),
+            "camera.view" => Ok(Self::CameraView),
+            "flat.manage" => Ok(Self::FlatManage),
+            "user.manage" => Ok(Self::UserManage),
+            "events.view" => Ok(Self::EventsView),
+            "door.hold_open" => Ok(Self::DoorHoldOpen),
+            _ => Err(()),
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use super::*;
+
+#[test]
+fn users_open_the_door() {
+    assert!(Permission::defaults_for(UserType::User).contains(&Permission::DoorOpen));
+    assert!(Permission::defaults_for(UserType::FlatManager).contains(&Permission::DoorOpen));
+    assert!(!Permission::defaults_for(UserType::Admin).contains(&Permission::DoorOpen));
+}
+
+#[test]
+fn hold_open_only_by_roles() {
+    for user_type in &[UserType::User, UserType::FlatManager, UserType::Admin] {
+        assert!(!Permission::defaults_for(*user_type).contains(&Permission::DoorHoldOpen));
+    }
+    assert_matches!(
+        "door.hold_open".parse::<Permission>(),
+        Ok(Permission::DoorHoldOpen)
+    );
+}
+
+#[test]
+fn only_admins_administer() {
+    assert!(!Permission::defaults_for(UserType::User)
+        .iter()
+        .any(Permission::is_administrative));
+    assert!(Permission::defaults_for(UserType::Admin).contains(&Permission::FlatManage));
+    assert!(Permission::defaults_for(UserType::Admin).contains(&Permission::UserManage));
+}
+
+#[test]
+fn administrative_permissions() {
+    assert!(Permission::FlatManage.is_administrative());
+    assert!(Permission::UserManage.is_administrative());
+    assert!(!Permission::DoorOpen.is_administrative());
+    assert!(!Permission::CameraView.is_administrative());
+    assert!(!Permission::EventsView.is_administrative());
+}
+
+#[test]
+fn join_permissions() {
+    assert_eq!(
+        Permission::join(&[Permission::DoorOpen, Permission::CameraView]),
+        "door.open camera.view"
+    );
+    assert_eq!(Permission::join(&[]), "");
+}
+
+#[test]
+fn split_permissions() {
+    assert_eq!(
+        Permission::split(" door.open  user.manage unknown "),
+        vec![Permission::DoorOpen, Permission::UserManage]
+    );
+    assert!(Permission::split("").is_empty());
+}
+
+#[test]
+fn from_str() {
+    assert_matches!(
+        "flat.manage".parse::<Permission>(),
+        Ok(Permission::FlatManage)
+    );
+    assert_matches!("flat".parse::<Permission>(), Err(()));
+}
@@ -0,0 +1,83 @@
+use super::{rusqlite, Connection, Entry, Identifier};
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+#[path = "./recovery_code_test.rs"]
+mod recovery_code_test;
+
+/// Recovery-Code entry of the corresponding "recovery_code" table.
+/// A recovery code replaces the TOTP code once, e.g. if the phone is lost.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct RecoveryCodeEntry<ID: Identifier = u32, URef: Entry = u32> {
+    pub id: ID,
+    pub code_hash: String,
+    pub user: URef,
+}
+
+impl<URef: Entry> Entry for RecoveryCodeEntry<u32, URef> {
+    #[inline(always)]
+    fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    fn delete_entry(conn: &Connection, id: u32) -> Result<(), rusqlite::Error> {
+        conn.execute("DELETE FROM recovery_code WHERE ID=?1", &[&id])?;
+        Ok(())
+    }
+
+    fn update(&self, conn: &Connection) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "UPDATE recovery_code SET code_hash = ?1, user_id = ?2 WHERE id = ?3",
+            &[&self.code_hash, &self.user.get_id(), &self.id],
+        )?;
+        Ok(())
+    }
+}
+
+impl<URef: Entry> RecoveryCodeEntry<(), URef> {
+    pub fn create(self, conn: &Connection) -> Result<RecoveryCodeEntry, rusqlite::Error> {
+        let user_id = self.user.get_id();
+        conn.execute(
+            "INSERT INTO recovery_code (code_hash, user_id) VALUES (?1, ?2)",
+            &[&self.code_hash, &user_id],
+        )?;
+        Ok(RecoveryCodeEntry {
+            id: (conn.last_insert_rowid() as u32),
+            code_hash: self.code_hash,
+            user: user_id,
+        })
+    }
+}
+
+impl RecoveryCodeEntry<u32, u32> {
+    /// Converts a rusqlite row to a RecoveryCodeEntry
+    fn row_2_recovery_code(row: &rusqlite::Row) -> Self {
+        Self {
+            id: row.get::<usize, u32>(0),
+            code_hash: row.get::<usize, String>(1),
+            user: row.get::<usize, u32>(2),
+        }
+    }
+
+    pub fn get_by_user(conn: &Connection, user: u32) -> Result<Vec<Self>, rusqlite::Error> {
+        let mut stmt =
+            conn.prepare("SELECT id, code_hash, user_id FROM recovery_code WHERE user_id=?1")?;
+        return stmt
+            .query_map(&[&user], Self::row_2_recovery_code)?
+            .collect();
+    }
+
+    /// Deletes the matching recovery code of the user and returns whether it existed.
+    pub fn consume(conn: &Connection, user: u32, code_hash: &str) -> Result<bool, rusqlite::Error> {
+        let deleted_rows = conn.execute(
+            "DELETE FROM recovery_code WHERE user_id=?1 AND code_hash=?2",
+            &[&user, &code_hash],
+        )?;
+        Ok(deleted_rows > 0)
+    }
+
+    pub fn delete_by_user(conn: &Connection, user: u32) -> Result<(), rusqlite::Error> {
+        conn.execute("DELETE FROM recovery_code WHERE user_id=?1", &[&user])?;
+        Ok(())
+    }
+}
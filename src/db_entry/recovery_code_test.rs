@@ -0,0 +1,57 @@
+use super::*;
+use crate::db_entry::UserEntry;
+
+impl Default for RecoveryCodeEntry<(), u32> {
+    fn default() -> Self {
+        Self {
+            id: (),
+            code_hash: "c2VjcmV0".to_string(),
+            user: 0,
+        }
+    }
+}
+
+#[test]
+fn scenario_1_with_all_methods() {
+    let sql_scheme = include_str!("../../scheme.sql");
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(sql_scheme).unwrap();
+
+    let user = UserEntry::default().create(&conn).unwrap();
+
+    let mut code = RecoveryCodeEntry {
+        user: user.get_id(),
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+    RecoveryCodeEntry {
+        code_hash: "b3RoZXI=".to_string(),
+        user: user.get_id(),
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+
+    code.code_hash = "bmV3".to_string();
+    code.update(&conn).unwrap();
+
+    let codes = RecoveryCodeEntry::get_by_user(&conn, user.get_id()).unwrap();
+    assert_eq!(codes.len(), 2);
+    assert!(codes.contains(&code));
+
+    assert!(RecoveryCodeEntry::consume(&conn, user.get_id(), "bmV3").unwrap());
+    assert!(!RecoveryCodeEntry::consume(&conn, user.get_id(), "bmV3").unwrap());
+    assert_eq!(
+        RecoveryCodeEntry::get_by_user(&conn, user.get_id())
+            .unwrap()
+            .len(),
+        1
+    );
+
+    RecoveryCodeEntry::delete_by_user(&conn, user.get_id()).unwrap();
+
+    assert!(RecoveryCodeEntry::get_by_user(&conn, user.get_id())
+        .unwrap()
+        .is_empty());
+}
@@ -0,0 +1,126 @@
+use super::{rusqlite, Connection, Entry, Identifier, Permission, UserEntry};
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+#[path = "./role_test.rs"]
+mod role_test;
+
+/// Role entry of the corresponding "role" table.
+/// A role groups permissions, its members are stored in the "user_role" table.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct RoleEntry<ID: Identifier = u32> {
+    pub id: ID,
+    pub name: String,
+    pub permissions: Vec<Permission>,
+}
+
+impl Entry for RoleEntry<u32> {
+    #[inline(always)]
+    fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    fn delete_entry(conn: &Connection, id: u32) -> Result<(), rusqlite::Error> {
+        conn.execute("DELETE FROM user_role WHERE role_id=?1", &[&id])?;
+        conn.execute("DELETE FROM role WHERE ID=?1", &[&id])?;
+        Ok(())
+    }
+
+    fn update(&self, conn: &Connection) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "UPDATE role SET name = ?1, permissions = ?2 WHERE id = ?3",
+            &[&self.name, &Permission::join(&self.permissions), &self.id],
+        )?;
+        Ok(())
+    }
+}
+
+impl RoleEntry<()> {
+    pub fn create(self, conn: &Connection) -> Result<RoleEntry, rusqlite::Error> {
+        conn.execute(
+            "INSERT INTO role (name, permissions) VALUES (?1, ?2)",
+            &[&self.name, &Permission::join(&self.permissions)],
+        )?;
+        Ok(RoleEntry {
+            id: (conn.last_insert_rowid() as u32),
+            name: self.name,
+            permissions: self.permissions,
+        })
+    }
+}
+
+impl RoleEntry<u32> {
+    /// Converts a rusqlite row to a RoleEntry
+    fn row_2_role(row: &rusqlite::Row) -> Self {
+        Self {
+            id: row.get::<usize, u32>(0),
+            name: row.get::<usize, String>(1),
+            permissions: Permission::split(&row.get::<usize, String>(2)),
+        }
+    }
+
+    pub fn get_all(conn: &Connection) -> Result<Vec<Self>, rusqlite::Error> {
+        let mut stmt = conn.prepare("SELECT id, name, permissions FROM role ORDER BY name")?;
+        return stmt.query_map(&[], Self::row_2_role)?.collect();
+    }
+
+    pub fn get_by_id(conn: &Connection, id: u32) -> Result<Option<Self>, rusqlite::Error> {
+        let mut stmt =
+            conn.prepare("SELECT id, name, permissions FROM role WHERE id=?1 LIMIT 1")?;
+        return stmt
+            .query_map(&[&id], Self::row_2_role)?
+            .next()
+            .map_or_else(|| Ok(None), |entry_result| entry_result.map(Some));
+    }
+
+    /// Returns all roles of which the user is a member
+    pub fn get_by_user(conn: &Connection, user: u32) -> Result<Vec<Self>, rusqlite::Error> {
+        let mut stmt = conn.prepare("SELECT role.id, role.name, role.permissions FROM role INNER JOIN user_role ON role.id = user_role.role_id WHERE user_role.user_id=?1 ORDER BY role.name")?;
+        return stmt.query_map(&[&user], Self::row_2_role)?.collect();
+    }
+
+    /// Returns the IDs of all members of the role
+    pub fn get_members(&self, conn: &Connection) -> Result<Vec<u32>, rusqlite::Error> {
+        let mut stmt = conn.prepare("SELECT user_id FROM user_role WHERE role_id=?1")?;
+        return stmt
+            .query_map(&[&self.id], |row| row.get::<usize, u32>(0))?
+            .collect();
+    }
+
+    pub fn add_member(&self, conn: &Connection, user: u32) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "INSERT OR IGNORE INTO user_role (user_id, role_id) VALUES (?1, ?2)",
+            &[&user, &self.id],
+        )?;
+        Ok(())
+    }
+
+    pub fn remove_member(&self, conn: &Connection, user: u32) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "DELETE FROM user_role WHERE user_id=?1 AND role_id=?2",
+            &[&user, &self.id],
+        )?;
+        Ok(())
+    }
+
+    /// Removes the user from all roles
+    pub fn delete_by_user(conn: &Connection, user: u32) -> Result<(), rusqlite::Error> {
+        conn.execute("DELETE FROM user_role WHERE user_id=?1", &[&user])?;
+        Ok(())
+    }
+
+    /// Returns the default permissions of the user type together with those of all roles of the user
+    pub fn get_permissions<FRef: Entry>(
+        conn: &Connection,
+        user: &UserEntry<u32, FRef>,
+    ) -> Result<Vec<Permission>, rusqlite::Error> {
+        let mut permissions = Permission::defaults_for(user.user_type);
+        for role in Self::get_by_user(conn, user.id)? {
+            permissions.extend(role.permissions);
+        }
+        Ok(Permission::get_list()
+            .into_iter()
+            .filter(|permission| permissions.contains(permission))
+            .collect())
+    }
+}
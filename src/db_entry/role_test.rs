@@ -0,0 +1,104 @@
+use super::*;
+
+impl Default for RoleEntry<()> {
+    fn default() -> Self {
+        Self {
+            id: (),
+            name: "Concierge".to_string(),
+            permissions: vec![Permission::DoorOpen, Permission::CameraView],
+        }
+    }
+}
+
+impl Default for RoleEntry {
+    fn default() -> Self {
+        Self {
+            id: 1,
+            name: "Concierge".to_string(),
+            permissions: vec![Permission::DoorOpen, Permission::CameraView],
+        }
+    }
+}
+
+#[test]
+fn scenario_1_with_all_methods() {
+    let sql_scheme = include_str!("../../scheme.sql");
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(sql_scheme).unwrap();
+
+    let user = UserEntry::default().create(&conn).unwrap();
+
+    let mut role = RoleEntry::default().create(&conn).unwrap();
+    let other_role = RoleEntry {
+        name: "Caretaker".to_string(),
+        permissions: vec![Permission::FlatManage],
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+
+    role.permissions.push(Permission::EventsView);
+    role.update(&conn).unwrap();
+
+    let roles = RoleEntry::get_all(&conn).unwrap();
+    assert_eq!(roles, vec![other_role.clone(), role.clone()]);
+    assert_eq!(
+        RoleEntry::get_by_id(&conn, role.get_id()).unwrap().unwrap(),
+        role
+    );
+
+    role.add_member(&conn, user.get_id()).unwrap();
+    role.add_member(&conn, user.get_id()).unwrap();
+    other_role.add_member(&conn, user.get_id()).unwrap();
+    assert_eq!(role.get_members(&conn).unwrap(), vec![user.get_id()]);
+    assert_eq!(
+        RoleEntry::get_by_user(&conn, user.get_id()).unwrap(),
+        vec![other_role, role.clone()]
+    );
+
+    role.remove_member(&conn, user.get_id()).unwrap();
+    assert!(role.get_members(&conn).unwrap().is_empty());
+
+    RoleEntry::delete_by_user(&conn, user.get_id()).unwrap();
+    assert!(RoleEntry::get_by_user(&conn, user.get_id())
+        .unwrap()
+        .is_empty());
+
+    role.add_member(&conn, user.get_id()).unwrap();
+    role.delete(&conn).unwrap();
+    assert!(RoleEntry::get_by_user(&conn, user.get_id())
+        .unwrap()
+        .is_empty());
+    assert_eq!(RoleEntry::get_by_id(&conn, role.get_id()).unwrap(), None);
+}
+
+#[test]
+fn permissions_of_user() {
+    let sql_scheme = include_str!("../../scheme.sql");
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(sql_scheme).unwrap();
+
+    let user = UserEntry::default().create(&conn).unwrap();
+    assert_eq!(
+        RoleEntry::get_permissions(&conn, &user).unwrap(),
+        Permission::defaults_for(user.user_type)
+    );
+
+    let role = RoleEntry {
+        permissions: vec![Permission::UserManage, Permission::DoorOpen],
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+    role.add_member(&conn, user.get_id()).unwrap();
+
+    let permissions = RoleEntry::get_permissions(&conn, &user).unwrap();
+    assert!(permissions.contains(&Permission::UserManage));
+    assert_eq!(
+        permissions
+            .iter()
+            .filter(|permission| **permission == Permission::DoorOpen)
+            .count(),
+        1
+    );
+}
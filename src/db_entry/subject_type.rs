@@ -0,0 +1,53 @@
+use super::rusqlite::{
+    self,
+    types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef},
+};
+use derive_try_from_primitive::TryFromPrimitive;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::fmt;
+
+#[cfg(test)]
+#[path = "./subject_type_test.rs"]
+mod subject_type_test;
+
+/// A logical enum of the subject_type database field, which tells what is throttled.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, TryFromPrimitive)]
+#[repr(u16)]
+pub enum SubjectType {
+    UserName = 1,
+    IpAddress = 2,
+}
+
+impl fmt::Display for SubjectType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Self::UserName => write!(f, "User Name"),
+            Self::IpAddress => write!(f, "IP Address"),
+        }
+    }
+}
+
+impl From<SubjectType> for u16 {
+    #[inline(always)]
+    fn from(subject_type: SubjectType) -> Self {
+        subject_type as Self
+    }
+}
+
+/// needed to convert from the raw SQL-value
+impl FromSql for SubjectType {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let numeric_value = value.as_i64()?;
+        Self::try_from(u16::column_result(value)?)
+            .map_err(|_| FromSqlError::OutOfRange(numeric_value))
+    }
+}
+
+/// needed to convert to the raw SQL-value
+impl ToSql for SubjectType {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        let num: u16 = (*self).into();
+        Ok(ToSqlOutput::from(num))
+    }
+}
@@ -0,0 +1,26 @@
+use super::*;
+
+#[test]
+fn from_1() {
+    assert_matches!(SubjectType::try_from(1), Ok(SubjectType::UserName));
+}
+
+#[test]
+fn from_2() {
+    assert_matches!(SubjectType::try_from(2), Ok(SubjectType::IpAddress));
+}
+
+#[test]
+fn from_3() {
+    assert_matches!(SubjectType::try_from(3), Err(_));
+}
+
+#[test]
+fn user_name_to_num() {
+    assert_eq!(Into::<u16>::into(SubjectType::UserName), 1);
+}
+
+#[test]
+fn ip_address_to_num() {
+    assert_eq!(Into::<u16>::into(SubjectType::IpAddress), 2);
+}
@@ -1,4 +1,6 @@
 use super::{rusqlite, Connection, Entry, FlatEntry, Identifier, UserType};
+use crate::utils::serde::serialize_optional_local_datetime;
+use chrono::{offset::Utc, DateTime};
 use serde::{Deserialize, Serialize};
 
 #[cfg(test)]
@@ -22,6 +24,18 @@ pub struct UserEntry<ID: Identifier = u32, FRef: Entry = FlatEntry> {
     pub user_type: UserType,
     pub active: bool,
     pub flat: Option<FRef>,
+    /// The Base32 shared secret of the TOTP second factor, if it is enabled
+    pub totp_secret: Option<String>,
+    /// Whether the user has to change the password before any other request,
+    /// e.g. after the password was set by an admin
+    pub must_change_password: bool,
+    /// The optional begin of the validity, before which the user can't log in
+    #[serde(serialize_with = "serialize_optional_local_datetime")]
+    pub valid_from: Option<DateTime<Utc>>,
+    /// The optional end of the validity, after which the user is deactivated,
+    /// e.g. when a tenant moves out
+    #[serde(serialize_with = "serialize_optional_local_datetime")]
+    pub valid_until: Option<DateTime<Utc>>,
 }
 
 impl<FRef: Entry> Entry for UserEntry<u32, FRef> {
@@ -37,8 +51,8 @@ impl<FRef: Entry> Entry for UserEntry<u32, FRef> {
 
     fn update(&self, conn: &Connection) -> Result<(), rusqlite::Error> {
         conn.execute(
-                "UPDATE client_user SET name = ?1, pw_hash = ?2, pw_salt = ?3, pw_config = ?4, user_type = ?5, active = ?6, flat_id = ?7 WHERE id = ?8",
-                &[&self.name, &self.pw_hash.hash, &self.pw_hash.salt, &self.pw_hash.config, &self.user_type, &self.active, &self.flat.as_ref().map(|flat| flat.get_id()), &self.id]
+                "UPDATE client_user SET name = ?1, pw_hash = ?2, pw_salt = ?3, pw_config = ?4, user_type = ?5, active = ?6, flat_id = ?7, must_change_password = ?8, valid_from = ?9, valid_until = ?10 WHERE id = ?11",
+                &[&self.name, &self.pw_hash.hash, &self.pw_hash.salt, &self.pw_hash.config, &self.user_type, &self.active, &self.flat.as_ref().map(|flat| flat.get_id()), &self.must_change_password, &self.valid_from, &self.valid_until, &self.id]
             )?;
         Ok(())
     }
@@ -48,8 +62,8 @@ impl<FRef: Entry> UserEntry<(), FRef> {
     pub fn create(self, conn: &Connection) -> Result<UserEntry, rusqlite::Error> {
         let flat_id = self.flat.map(|flat| flat.get_id());
         conn.execute(
-            "INSERT INTO client_user (name, pw_hash, pw_salt, pw_config, user_type, active, flat_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-            &[&self.name, &self.pw_hash.hash, &self.pw_hash.salt, &self.pw_hash.config, &self.user_type, &self.active, &flat_id]
+            "INSERT INTO client_user (name, pw_hash, pw_salt, pw_config, user_type, active, flat_id, totp_secret, must_change_password, valid_from, valid_until) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            &[&self.name, &self.pw_hash.hash, &self.pw_hash.salt, &self.pw_hash.config, &self.user_type, &self.active, &flat_id, &self.totp_secret, &self.must_change_password, &self.valid_from, &self.valid_until]
         )?;
         Ok(UserEntry {
             id: (conn.last_insert_rowid() as u32),
@@ -61,6 +75,10 @@ impl<FRef: Entry> UserEntry<(), FRef> {
                 Some(flat_id) => FlatEntry::get_by_id(conn, flat_id)?,
                 None => None,
             },
+            totp_secret: self.totp_secret,
+            must_change_password: self.must_change_password,
+            valid_from: self.valid_from,
+            valid_until: self.valid_until,
         })
     }
 }
@@ -82,12 +100,16 @@ impl UserEntry<u32, FlatEntry> {
                 Some(flat_id) => FlatEntry::get_by_id(conn, flat_id)?,
                 None => None,
             },
+            totp_secret: row.get::<usize, Option<String>>(8),
+            must_change_password: row.get::<usize, bool>(9),
+            valid_from: row.get::<usize, Option<DateTime<Utc>>>(10),
+            valid_until: row.get::<usize, Option<DateTime<Utc>>>(11),
         })
     }
 
     pub fn get_all(conn: &Connection) -> Result<Vec<Self>, rusqlite::Error> {
         let mut stmt =
-            conn.prepare("SELECT id, name, pw_hash, pw_salt, pw_config, user_type, active, flat_id FROM client_user")?;
+            conn.prepare("SELECT id, name, pw_hash, pw_salt, pw_config, user_type, active, flat_id, totp_secret, must_change_password, valid_from, valid_until FROM client_user")?;
         return stmt
             .query_map(&[], |row| Self::row_2_user(conn, row))?
             .map(|r| match r {
@@ -99,7 +121,7 @@ impl UserEntry<u32, FlatEntry> {
 
     pub fn get_by_id(conn: &Connection, id: u32) -> Result<Option<Self>, rusqlite::Error> {
         let mut stmt = conn.prepare(
-            "SELECT id, name, pw_hash, pw_salt, pw_config, user_type, active, flat_id FROM client_user WHERE id=?1 LIMIT 1",
+            "SELECT id, name, pw_hash, pw_salt, pw_config, user_type, active, flat_id, totp_secret, must_change_password, valid_from, valid_until FROM client_user WHERE id=?1 LIMIT 1",
         )?;
         return stmt
             .query_map(&[&id], |row| Self::row_2_user(conn, row))?
@@ -111,16 +133,64 @@ impl UserEntry<u32, FlatEntry> {
             .map_or_else(|| Ok(None), |entry_result| entry_result.map(Some));
     }
 
+    pub fn get_by_flat(conn: &Connection, flat: u32) -> Result<Vec<Self>, rusqlite::Error> {
+        let mut stmt = conn.prepare(
+            "SELECT id, name, pw_hash, pw_salt, pw_config, user_type, active, flat_id, totp_secret, must_change_password, valid_from, valid_until FROM client_user WHERE flat_id=?1",
+        )?;
+        return stmt
+            .query_map(&[&flat], |row| Self::row_2_user(conn, row))?
+            .map(|r| match r {
+                Ok(x) => x,
+                Err(e) => Err(e),
+            })
+            .collect();
+    }
+
+    pub fn get_by_name(conn: &Connection, name: &str) -> Result<Option<Self>, rusqlite::Error> {
+        let mut stmt = conn.prepare(
+            "SELECT id, name, pw_hash, pw_salt, pw_config, user_type, active, flat_id, totp_secret, must_change_password, valid_from, valid_until FROM client_user WHERE name = ?1 LIMIT 1",
+        )?;
+        return stmt
+            .query_map(&[&name], |row| Self::row_2_user(conn, row))?
+            .map(|r| match r {
+                Ok(x) => x,
+                Err(e) => Err(e),
+            })
+            .next()
+            .map_or_else(|| Ok(None), |entry_result| entry_result.map(Some));
+    }
+
+    /// Returns the user if it's active and valid at this moment
     #[allow(clippy::ptr_arg)]
     pub fn get_active_by_name(
         conn: &Connection,
         name: &String,
+        now: DateTime<Utc>,
+    ) -> Result<Option<Self>, rusqlite::Error> {
+        let mut stmt = conn.prepare(
+            "SELECT id, name, pw_hash, pw_salt, pw_config, user_type, active, flat_id, totp_secret, must_change_password, valid_from, valid_until FROM client_user WHERE name = ?1 AND active = 1 AND (valid_from IS NULL OR valid_from <= ?2) AND (valid_until IS NULL OR valid_until > ?2) LIMIT 1",
+        )?;
+        return stmt
+            .query_map(&[name, &now], |row| Self::row_2_user(conn, row))?
+            .map(|r| match r {
+                Ok(x) => x,
+                Err(e) => Err(e),
+            })
+            .next()
+            .map_or_else(|| Ok(None), |entry_result| entry_result.map(Some));
+    }
+
+    /// Returns the user of an OpenID Connect provider by its issuer and subject
+    pub fn get_by_oidc_subject(
+        conn: &Connection,
+        issuer: &str,
+        subject: &str,
     ) -> Result<Option<Self>, rusqlite::Error> {
         let mut stmt = conn.prepare(
-            "SELECT id, name, pw_hash, pw_salt, pw_config, user_type, active, flat_id FROM client_user WHERE name = ?1 AND active = 1 LIMIT 1",
+            "SELECT id, name, pw_hash, pw_salt, pw_config, user_type, active, flat_id, totp_secret, must_change_password, valid_from, valid_until FROM client_user WHERE oidc_issuer = ?1 AND oidc_subject = ?2 LIMIT 1",
         )?;
         return stmt
-            .query_map(&[name], |row| Self::row_2_user(conn, row))?
+            .query_map(&[&issuer, &subject], |row| Self::row_2_user(conn, row))?
             .map(|r| match r {
                 Ok(x) => x,
                 Err(e) => Err(e),
@@ -130,23 +200,137 @@ impl UserEntry<u32, FlatEntry> {
     }
 }
 
+impl UserEntry<u32, FlatEntry> {
+    /// Returns the active users, whose validity has ended
+    pub fn get_expired(
+        conn: &Connection,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<Self>, rusqlite::Error> {
+        let mut stmt = conn.prepare(
+            "SELECT id, name, pw_hash, pw_salt, pw_config, user_type, active, flat_id, totp_secret, must_change_password, valid_from, valid_until FROM client_user WHERE active = 1 AND valid_until <= ?1",
+        )?;
+        return stmt
+            .query_map(&[&now], |row| Self::row_2_user(conn, row))?
+            .map(|r| match r {
+                Ok(x) => x,
+                Err(e) => Err(e),
+            })
+            .collect();
+    }
+}
+
+impl<ID: Identifier, FRef: Entry> UserEntry<ID, FRef> {
+    /// Whether the user is active and valid at this moment
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.active
+            && self.valid_from.map_or(true, |valid_from| valid_from <= now)
+            && self
+                .valid_until
+                .map_or(true, |valid_until| now < valid_until)
+    }
+}
+
 impl<FRef: Entry> UserEntry<u32, FRef> {
     pub fn update_without_password(&self, conn: &Connection) -> Result<(), rusqlite::Error> {
         conn.execute(
-                "UPDATE client_user SET name = ?1, user_type = ?2, active = ?3, flat_id = ?4 WHERE id = ?5",
-                &[&self.name, &self.user_type, &self.active, &self.flat.as_ref().map(|flat| flat.get_id()), &self.id],
+                "UPDATE client_user SET name = ?1, user_type = ?2, active = ?3, flat_id = ?4, valid_from = ?5, valid_until = ?6 WHERE id = ?7",
+                &[&self.name, &self.user_type, &self.active, &self.flat.as_ref().map(|flat| flat.get_id()), &self.valid_from, &self.valid_until, &self.id],
             )?;
         Ok(())
     }
 
     pub fn update_unprivileged(&self, conn: &Connection) -> Result<(), rusqlite::Error> {
         conn.execute(
-                "UPDATE client_user SET name = ?1, pw_hash = ?2, pw_salt = ?3, pw_config = ?4 WHERE id = ?5",
-                &[&self.name, &self.pw_hash.hash, &self.pw_hash.salt, &self.pw_hash.config, &self.id]
+                "UPDATE client_user SET name = ?1, pw_hash = ?2, pw_salt = ?3, pw_config = ?4, must_change_password = ?5 WHERE id = ?6",
+                &[&self.name, &self.pw_hash.hash, &self.pw_hash.salt, &self.pw_hash.config, &self.must_change_password, &self.id]
             )?;
         Ok(())
     }
 
+    pub fn update_password(&self, conn: &Connection) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "UPDATE client_user SET pw_hash = ?1, pw_salt = ?2, pw_config = ?3, must_change_password = ?4 WHERE id = ?5",
+            &[
+                &self.pw_hash.hash,
+                &self.pw_hash.salt,
+                &self.pw_hash.config,
+                &self.must_change_password,
+                &self.id,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Deactivates the user without changing the other fields
+    pub fn deactivate(&mut self, conn: &Connection) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "UPDATE client_user SET active = 0 WHERE id = ?1",
+            &[&self.id],
+        )?;
+        self.active = false;
+        Ok(())
+    }
+
+    /// Links the user to its issuer and subject at an OpenID Connect provider
+    pub fn set_oidc_subject(
+        &self,
+        conn: &Connection,
+        issuer: &str,
+        subject: &str,
+    ) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "UPDATE client_user SET oidc_issuer = ?1, oidc_subject = ?2 WHERE id = ?3",
+            &[&issuer, &subject, &self.id],
+        )?;
+        Ok(())
+    }
+
+    pub fn update_totp_secret(&self, conn: &Connection) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "UPDATE client_user SET totp_secret = ?1, totp_last_step = NULL WHERE id = ?2",
+            &[&self.totp_secret, &self.id],
+        )?;
+        Ok(())
+    }
+
+    /// Stores the time step of an accepted TOTP code.
+    /// Returns false if the code of this or a later step has already been used, so a code can't be replayed.
+    pub fn accept_totp_step(&self, conn: &Connection, step: u64) -> Result<bool, rusqlite::Error> {
+        let step = step as i64;
+        let changed = conn.execute(
+            "UPDATE client_user SET totp_last_step = ?1 WHERE id = ?2 AND (totp_last_step IS NULL OR totp_last_step < ?1)",
+            &[&step, &self.id],
+        )?;
+        Ok(changed == 1)
+    }
+
+    /// Counts a failed second factor and returns the number of failures since the last accepted second factor
+    pub fn record_two_factor_failure(&self, conn: &Connection) -> Result<u32, rusqlite::Error> {
+        conn.execute(
+            "UPDATE client_user SET two_factor_failures = two_factor_failures + 1 WHERE id = ?1",
+            &[&self.id],
+        )?;
+        self.get_two_factor_failures(conn)
+    }
+
+    /// The number of failed second factors since the last accepted second factor
+    pub fn get_two_factor_failures(&self, conn: &Connection) -> Result<u32, rusqlite::Error> {
+        conn.query_row(
+            "SELECT two_factor_failures FROM client_user WHERE id = ?1",
+            &[&self.id],
+            |row| row.get::<usize, u32>(0),
+        )
+    }
+
+    /// Forgets the failed second factors, e.g. when a second factor is accepted
+    pub fn reset_two_factor_failures(&self, conn: &Connection) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "UPDATE client_user SET two_factor_failures = 0 WHERE id = ?1",
+            &[&self.id],
+        )?;
+        Ok(())
+    }
+
     pub fn update_unprivileged_without_password(
         &self,
         conn: &Connection,
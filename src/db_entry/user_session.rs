@@ -7,10 +7,16 @@ use serde::{Deserialize, Serialize};
 mod user_session_test;
 
 /// User-Session entry of the corresponding "user_session" table.
+/// The cookie of the client only holds the token, of which the hash is stored.
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct UserSessionEntry<ID: Identifier = u32, URef: Entry = u32> {
     pub id: ID,
+    pub token_hash: String,
     pub login_datetime: DateTime<Utc>,
+    pub expiry_datetime: DateTime<Utc>,
+    pub last_seen_datetime: DateTime<Utc>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
     pub user: URef,
 }
 
@@ -27,8 +33,17 @@ impl<URef: Entry> Entry for UserSessionEntry<u32, URef> {
 
     fn update(&self, conn: &Connection) -> Result<(), rusqlite::Error> {
         conn.execute(
-            "UPDATE user_session SET login_datetime = ?1, user_id = ?2 WHERE id = ?3",
-            &[&self.login_datetime, &self.user.get_id(), &self.id],
+            "UPDATE user_session SET token_hash = ?1, login_datetime = ?2, expiry_datetime = ?3, last_seen_datetime = ?4, ip_address = ?5, user_agent = ?6, user_id = ?7 WHERE id = ?8",
+            &[
+                &self.token_hash,
+                &self.login_datetime,
+                &self.expiry_datetime,
+                &self.last_seen_datetime,
+                &self.ip_address,
+                &self.user_agent,
+                &self.user.get_id(),
+                &self.id,
+            ],
         )?;
         Ok(())
     }
@@ -38,48 +53,74 @@ impl<URef: Entry> UserSessionEntry<(), URef> {
     pub fn create(self, conn: &Connection) -> Result<UserSessionEntry, rusqlite::Error> {
         let user_id = self.user.get_id();
         conn.execute(
-            "INSERT INTO user_session (login_datetime, user_id) VALUES (?1, ?2)",
-            &[&self.login_datetime, &user_id],
+            "INSERT INTO user_session (token_hash, login_datetime, expiry_datetime, last_seen_datetime, ip_address, user_agent, user_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            &[
+                &self.token_hash,
+                &self.login_datetime,
+                &self.expiry_datetime,
+                &self.last_seen_datetime,
+                &self.ip_address,
+                &self.user_agent,
+                &user_id,
+            ],
         )?;
         Ok(UserSessionEntry {
             id: (conn.last_insert_rowid() as u32),
+            token_hash: self.token_hash,
             login_datetime: self.login_datetime,
+            expiry_datetime: self.expiry_datetime,
+            last_seen_datetime: self.last_seen_datetime,
+            ip_address: self.ip_address,
+            user_agent: self.user_agent,
             user: user_id,
         })
     }
 }
 
 impl UserSessionEntry<u32, u32> {
-    /// Converts a rusqlite row to an UserEntry
-    fn row_2_user(row: &rusqlite::Row) -> Result<Self, rusqlite::Error> {
-        Ok(Self {
+    /// Converts a rusqlite row to an UserSessionEntry
+    fn row_2_user_session(row: &rusqlite::Row) -> Self {
+        Self {
             id: row.get::<usize, u32>(0),
-            login_datetime: row.get::<usize, DateTime<Utc>>(1),
-            user: row.get::<usize, u32>(2),
-        })
+            token_hash: row.get::<usize, String>(1),
+            login_datetime: row.get::<usize, DateTime<Utc>>(2),
+            expiry_datetime: row.get::<usize, DateTime<Utc>>(3),
+            last_seen_datetime: row.get::<usize, DateTime<Utc>>(4),
+            ip_address: row.get::<usize, Option<String>>(5),
+            user_agent: row.get::<usize, Option<String>>(6),
+            user: row.get::<usize, u32>(7),
+        }
     }
 
     #[allow(dead_code)]
     pub fn get_all(conn: &Connection) -> Result<Vec<Self>, rusqlite::Error> {
-        let mut stmt = conn.prepare("SELECT id, login_datetime, user_id FROM user_session")?;
+        let mut stmt = conn.prepare("SELECT id, token_hash, login_datetime, expiry_datetime, last_seen_datetime, ip_address, user_agent, user_id FROM user_session")?;
+        return stmt.query_map(&[], Self::row_2_user_session)?.collect();
+    }
+
+    pub fn get_by_id(conn: &Connection, id: u32) -> Result<Option<Self>, rusqlite::Error> {
+        let mut stmt = conn.prepare("SELECT id, token_hash, login_datetime, expiry_datetime, last_seen_datetime, ip_address, user_agent, user_id FROM user_session WHERE id=?1 LIMIT 1")?;
         return stmt
-            .query_map(&[], |row| Self::row_2_user(row))?
-            .map(|r| match r {
-                Ok(x) => x,
-                Err(e) => Err(e),
-            })
+            .query_map(&[&id], Self::row_2_user_session)?
+            .next()
+            .map_or_else(|| Ok(None), |entry_result| entry_result.map(Some));
+    }
+
+    /// Returns the sessions of an user, the most recently used first.
+    pub fn get_by_user(conn: &Connection, user: u32) -> Result<Vec<Self>, rusqlite::Error> {
+        let mut stmt = conn.prepare("SELECT id, token_hash, login_datetime, expiry_datetime, last_seen_datetime, ip_address, user_agent, user_id FROM user_session WHERE user_id=?1 ORDER BY last_seen_datetime DESC")?;
+        return stmt
+            .query_map(&[&user], Self::row_2_user_session)?
             .collect();
     }
 
-    pub fn get_by_id(conn: &Connection, id: u32) -> Result<Option<Self>, rusqlite::Error> {
-        let mut stmt = conn
-            .prepare("SELECT id, login_datetime, user_id FROM user_session WHERE id=?1 LIMIT 1")?;
+    pub fn get_by_token_hash(
+        conn: &Connection,
+        token_hash: &str,
+    ) -> Result<Option<Self>, rusqlite::Error> {
+        let mut stmt = conn.prepare("SELECT id, token_hash, login_datetime, expiry_datetime, last_seen_datetime, ip_address, user_agent, user_id FROM user_session WHERE token_hash=?1 LIMIT 1")?;
         return stmt
-            .query_map(&[&id], |row| Self::row_2_user(row))?
-            .map(|r| match r {
-                Ok(x) => x,
-                Err(e) => Err(e),
-            })
+            .query_map(&[&token_hash], Self::row_2_user_session)?
             .next()
             .map_or_else(|| Ok(None), |entry_result| entry_result.map(Some));
     }
@@ -88,4 +129,17 @@ impl UserSessionEntry<u32, u32> {
         conn.execute("DELETE FROM user_session WHERE user_id=?1", &[&user])?;
         Ok(())
     }
+
+    /// Deletes all sessions which have expired or have been idle since `idle_since`.
+    pub fn delete_expired(
+        conn: &Connection,
+        now: DateTime<Utc>,
+        idle_since: DateTime<Utc>,
+    ) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "DELETE FROM user_session WHERE expiry_datetime <= ?1 OR last_seen_datetime <= ?2",
+            &[&now, &idle_since],
+        )?;
+        Ok(())
+    }
 }
@@ -1,11 +1,17 @@
 use super::*;
 use crate::db_entry::UserEntry;
+use chrono::Duration;
 
 impl Default for UserSessionEntry<(), u32> {
     fn default() -> Self {
         Self {
             id: (),
+            token_hash: "hash".to_string(),
             login_datetime: Utc::now(),
+            expiry_datetime: Utc::now() + Duration::hours(1),
+            last_seen_datetime: Utc::now(),
+            ip_address: Some("127.0.0.1".to_string()),
+            user_agent: None,
             user: 0,
         }
     }
@@ -15,7 +21,12 @@ impl Default for UserSessionEntry {
     fn default() -> Self {
         Self {
             id: 1,
+            token_hash: "hash".to_string(),
             login_datetime: Utc::now(),
+            expiry_datetime: Utc::now() + Duration::hours(1),
+            last_seen_datetime: Utc::now(),
+            ip_address: Some("127.0.0.1".to_string()),
+            user_agent: None,
             user: 0,
         }
     }
@@ -33,18 +44,31 @@ fn scenario_1_with_all_methods() {
     assert_eq!(sessions.len(), 1);
     assert!(sessions.contains(&session));
 
-    session.login_datetime = Utc::now();
+    session.last_seen_datetime = Utc::now();
+    session.user_agent = Some("Mozilla/5.0".to_string());
     session.update(&conn).unwrap();
 
+    assert_eq!(
+        UserSessionEntry::get_by_token_hash(&conn, "hash")
+            .unwrap()
+            .unwrap(),
+        session
+    );
+    assert!(UserSessionEntry::get_by_token_hash(&conn, "other")
+        .unwrap()
+        .is_none());
+
     let other_user = UserEntry::default().create(&conn).unwrap();
 
     UserSessionEntry {
+        token_hash: "other_hash_1".to_string(),
         user: other_user.get_id(),
         ..Default::default()
     }
     .create(&conn)
     .unwrap();
     UserSessionEntry {
+        token_hash: "other_hash_2".to_string(),
         user: other_user.get_id(),
         ..Default::default()
     }
@@ -52,6 +76,12 @@ fn scenario_1_with_all_methods() {
     .unwrap();
 
     assert_eq!(UserSessionEntry::get_all(&conn).unwrap().len(), 3);
+    assert_eq!(
+        UserSessionEntry::get_by_user(&conn, other_user.get_id())
+            .unwrap()
+            .len(),
+        2
+    );
 
     UserSessionEntry::delete_by_user(&conn, other_user.get_id()).unwrap();
 
@@ -68,3 +98,32 @@ fn scenario_1_with_all_methods() {
 
     assert!(UserSessionEntry::get_all(&conn).unwrap().is_empty());
 }
+
+#[test]
+fn delete_expired_sessions() {
+    let sql_scheme = include_str!("../../scheme.sql");
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(sql_scheme).unwrap();
+
+    let now = Utc::now();
+
+    let active = UserSessionEntry::default().create(&conn).unwrap();
+    UserSessionEntry {
+        token_hash: "expired".to_string(),
+        expiry_datetime: now - Duration::seconds(1),
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+    UserSessionEntry {
+        token_hash: "idle".to_string(),
+        last_seen_datetime: now - Duration::hours(2),
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+
+    UserSessionEntry::delete_expired(&conn, now, now - Duration::hours(1)).unwrap();
+
+    assert_eq!(UserSessionEntry::get_all(&conn).unwrap(), vec![active]);
+}
@@ -1,4 +1,5 @@
 use super::*;
+use chrono::Duration;
 
 impl Default for UserEntry<(), u32> {
     fn default() -> Self {
@@ -13,6 +14,10 @@ impl Default for UserEntry<(), u32> {
             user_type: UserType::User,
             active: true,
             flat: None,
+            totp_secret: None,
+            must_change_password: false,
+            valid_from: None,
+            valid_until: None,
         }
     }
 }
@@ -30,6 +35,10 @@ impl Default for UserEntry {
             user_type: UserType::User,
             active: true,
             flat: None,
+            totp_secret: None,
+            must_change_password: false,
+            valid_from: None,
+            valid_until: None,
         }
     }
 }
@@ -58,12 +67,18 @@ fn scenario_1_with_all_methods() {
     user.update_without_password(&conn).unwrap();
 
     assert_eq!(
-        UserEntry::get_active_by_name(&conn, &user.name)
+        UserEntry::get_active_by_name(&conn, &user.name, Utc::now())
             .unwrap()
             .unwrap(),
         user
     );
 
+    assert_eq!(
+        UserEntry::get_by_name(&conn, &user.name).unwrap().unwrap(),
+        user
+    );
+    assert_eq!(UserEntry::get_by_name(&conn, "nobody").unwrap(), None);
+
     user.pw_hash.salt = "random".to_string();
     user.update_unprivileged(&conn).unwrap();
 
@@ -72,6 +87,23 @@ fn scenario_1_with_all_methods() {
         user
     );
 
+    user.pw_hash.config = "Blake2b".to_string();
+    user.must_change_password = true;
+    user.update_password(&conn).unwrap();
+
+    assert_eq!(
+        UserEntry::get_by_id(&conn, user.get_id()).unwrap().unwrap(),
+        user
+    );
+
+    user.totp_secret = Some("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ".to_string());
+    user.update_totp_secret(&conn).unwrap();
+
+    assert_eq!(
+        UserEntry::get_by_id(&conn, user.get_id()).unwrap().unwrap(),
+        user
+    );
+
     user.name = "Bob".to_string();
     user.update_unprivileged_without_password(&conn).unwrap();
 
@@ -83,3 +115,87 @@ fn scenario_1_with_all_methods() {
 
     assert_eq!(UserEntry::get_all(&conn).unwrap().len(), 1);
 }
+
+#[test]
+fn users_of_flat() {
+    let sql_scheme = include_str!("../../scheme.sql");
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(sql_scheme).unwrap();
+
+    let flat = FlatEntry::default().create(&conn).unwrap();
+    let user = UserEntry {
+        flat: Some(flat.get_id()),
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+    UserEntry {
+        name: "Bob".to_string(),
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+
+    assert_eq!(
+        UserEntry::get_by_flat(&conn, flat.get_id()).unwrap(),
+        vec![user]
+    );
+}
+
+#[test]
+fn validity() {
+    let sql_scheme = include_str!("../../scheme.sql");
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(sql_scheme).unwrap();
+    let now = Utc::now();
+
+    let user = UserEntry {
+        valid_from: Some(now - Duration::days(1)),
+        valid_until: Some(now + Duration::days(1)),
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+    assert!(user.is_active(now));
+    assert!(!user.is_active(now - Duration::days(2)));
+    assert!(!user.is_active(now + Duration::days(1)));
+
+    assert_eq!(
+        UserEntry::get_active_by_name(&conn, &user.name, now).unwrap(),
+        Some(user.clone())
+    );
+    assert_eq!(
+        UserEntry::get_active_by_name(&conn, &user.name, now - Duration::days(2)).unwrap(),
+        None
+    );
+    assert_eq!(
+        UserEntry::get_active_by_name(&conn, &user.name, now + Duration::days(1)).unwrap(),
+        None
+    );
+
+    assert!(UserEntry::get_expired(&conn, now).unwrap().is_empty());
+    assert_eq!(
+        UserEntry::get_expired(&conn, now + Duration::days(1)).unwrap(),
+        vec![user]
+    );
+}
+
+#[test]
+fn second_factor_state() {
+    let sql_scheme = include_str!("../../scheme.sql");
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(sql_scheme).unwrap();
+    let user = UserEntry::default().create(&conn).unwrap();
+
+    // A TOTP code can't be used twice, nor the code of an earlier step
+    assert!(user.accept_totp_step(&conn, 42).unwrap());
+    assert!(!user.accept_totp_step(&conn, 42).unwrap());
+    assert!(!user.accept_totp_step(&conn, 41).unwrap());
+    assert!(user.accept_totp_step(&conn, 43).unwrap());
+
+    assert_eq!(user.get_two_factor_failures(&conn).unwrap(), 0);
+    user.record_two_factor_failure(&conn).unwrap();
+    assert_eq!(user.record_two_factor_failure(&conn).unwrap(), 2);
+    user.reset_two_factor_failures(&conn).unwrap();
+    assert_eq!(user.get_two_factor_failures(&conn).unwrap(), 0);
+}
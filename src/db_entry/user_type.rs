@@ -19,12 +19,15 @@ mod user_type_test;
 pub enum UserType {
     User = 1,
     Admin = 2,
+    /// An user who administers the other users of the own flat
+    FlatManager = 3,
 }
 
 impl UserType {
+    /// Whether it is a resident who uses the door, which includes flat managers
     #[inline(always)]
     pub fn is_user(&self) -> bool {
-        matches!(self, Self::User)
+        matches!(self, Self::User | Self::FlatManager)
     }
 
     #[inline(always)]
@@ -32,9 +35,18 @@ impl UserType {
         matches!(self, Self::Admin)
     }
 
+    #[inline(always)]
+    pub fn is_flat_manager(&self) -> bool {
+        matches!(self, Self::FlatManager)
+    }
+
     /// get a Vector of touples of the value and string of all types
     pub fn get_list() -> Vec<(u16, String)> {
-        vec![Self::User.into(), Self::Admin.into()]
+        vec![
+            Self::User.into(),
+            Self::Admin.into(),
+            Self::FlatManager.into(),
+        ]
     }
 }
 
@@ -43,6 +55,7 @@ impl fmt::Display for UserType {
         match *self {
             Self::User => write!(f, "User"),
             Self::Admin => write!(f, "Admin"),
+            Self::FlatManager => write!(f, "Flat Manager"),
         }
     }
 }
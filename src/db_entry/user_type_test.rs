@@ -20,6 +20,22 @@ fn admin_is_not_user() {
     assert_eq!(UserType::Admin.is_user(), false);
 }
 
+#[test]
+fn flat_manager_is_user() {
+    assert_eq!(UserType::FlatManager.is_user(), true);
+}
+
+#[test]
+fn flat_manager_is_not_admin() {
+    assert_eq!(UserType::FlatManager.is_admin(), false);
+}
+
+#[test]
+fn only_flat_manager_is_flat_manager() {
+    assert_eq!(UserType::FlatManager.is_flat_manager(), true);
+    assert_eq!(UserType::User.is_flat_manager(), false);
+}
+
 #[test]
 fn from_1() {
     assert_matches!(UserType::try_from(1), Ok(UserType::User));
@@ -32,7 +48,12 @@ fn from_2() {
 
 #[test]
 fn from_3() {
-    assert_matches!(UserType::try_from(3), Err(_));
+    assert_matches!(UserType::try_from(3), Ok(UserType::FlatManager));
+}
+
+#[test]
+fn from_4() {
+    assert_matches!(UserType::try_from(4), Err(_));
 }
 
 #[test]
@@ -1,52 +1,42 @@
-#[cfg(feature = "iot")]
-use super::GPIO;
-use crate::db_entry::FlatEntry;
+use super::door_state::DoorAlarm;
+use super::gpio::{self, Gpio, InputPin, Trigger};
+use crate::db_entry::{DoorEntry, FlatEntry};
 use crate::utils::crypto;
-#[cfg(feature = "iot")]
 use crate::{debounce_callback, setup_debounce, CONFIG};
 use log::{error, info};
-#[cfg(feature = "iot")]
-use rppal::gpio::{InputPin, Trigger};
 use rumqttc::{Client, MqttOptions, QoS};
 use std::convert::TryInto;
-#[cfg(feature = "iot")]
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::thread;
-#[cfg(feature = "iot")]
 use std::time::Instant;
 
-#[cfg(feature = "iot")]
 #[derive(thiserror::Error, Debug)]
 pub enum BellError {
     #[error(transparent)]
     Decryption(#[from] DecryptionError),
     #[error(transparent)]
-    Gpio(#[from] rppal::gpio::Error),
+    Gpio(#[from] gpio::Error),
 }
 
 /// Checks whether the button is pushed and sends a signal to the MQTT-Broker.
 #[derive(Clone)]
 pub struct BellButton {
-    #[cfg(feature = "iot")]
-    dev: Option<Arc<InputPin>>,
+    /// The pin is kept until the last clone is dropped
+    dev: Option<Arc<Mutex<Box<dyn InputPin>>>>,
     mqtt_client: Client,
     flat: FlatEntry,
 }
 
-#[cfg(not(feature = "iot"))]
 impl BellButton {
-    pub fn new(flat: FlatEntry) -> Result<Self, DecryptionError> {
+    /// Spawns a thread with an event-loop
+    pub fn new(gpio: &dyn Gpio, flat: FlatEntry) -> Result<Self, BellError> {
         let broker_password = Self::decrypt_broker_password(&flat)?;
-
         let mut mqtt_conn_options =
             MqttOptions::new("doorcam", flat.broker_address.clone(), flat.broker_port);
         mqtt_conn_options.set_credentials(flat.broker_user.clone(), broker_password);
         let (mqtt_client, mut mqtt_conn) = Client::new(mqtt_conn_options, 5);
 
-        let mut mqtt_bell = Self { mqtt_client, flat };
-
-        mqtt_bell.send_bell_signal();
-        mqtt_bell.send_tamper_alarm();
+        let mqtt_bell = Self::with_client(gpio, flat, mqtt_client)?;
 
         thread::spawn(move || {
             mqtt_conn.iter().for_each(|notification| {
@@ -56,19 +46,14 @@ impl BellButton {
 
         Ok(mqtt_bell)
     }
-}
 
-#[cfg(feature = "iot")]
-impl BellButton {
-    /// Spawns a thread with an event-loop
-    pub fn new(flat: FlatEntry) -> Result<Self, BellError> {
-        let broker_password = Self::decrypt_broker_password(&flat)?;
-        let mut mqtt_conn_options =
-            MqttOptions::new("doorcam", flat.broker_address.clone(), flat.broker_port);
-        mqtt_conn_options.set_credentials(flat.broker_user.clone(), broker_password);
-        let (mqtt_client, mut mqtt_conn) = Client::new(mqtt_conn_options, 5);
-
-        let mut dev = GPIO.get(flat.bell_button_pin)?.into_input_pulldown();
+    /// Arms the button, whose signals are published by the client
+    pub(super) fn with_client(
+        gpio: &dyn Gpio,
+        flat: FlatEntry,
+        mqtt_client: Client,
+    ) -> Result<Self, BellError> {
+        let mut dev = gpio.input_pulldown(flat.bell_button_pin)?;
 
         let mut mqtt_bell = Self {
             mqtt_client,
@@ -80,18 +65,15 @@ impl BellButton {
 
         let mut last_action = setup_debounce!(CONFIG.iot.bell_debounce_interval);
 
-        dev.set_async_interrupt(Trigger::RisingEdge, move |_level| {
-            debounce_callback!(last_action, CONFIG.iot.bell_debounce_interval);
-            this.send_bell_signal();
-        })?;
+        dev.set_async_interrupt(
+            Trigger::RisingEdge,
+            Box::new(move |_level| {
+                debounce_callback!(last_action, CONFIG.iot.bell_debounce_interval);
+                this.send_bell_signal();
+            }),
+        )?;
 
-        mqtt_bell.dev = Some(Arc::new(dev));
-
-        thread::spawn(move || {
-            mqtt_conn.iter().for_each(|notification| {
-                info!("IoT: Received MQTT notification: {:?}", notification)
-            });
-        });
+        mqtt_bell.dev = Some(Arc::new(Mutex::new(dev)));
 
         Ok(mqtt_bell)
     }
@@ -132,6 +114,25 @@ impl BellButton {
             error!("IoT: Can't send Alarm Signal: {}", e);
         }
     }
+    /// The ID of the flat, whose broker receives the signals
+    pub fn flat_id(&self) -> u32 {
+        self.flat.id
+    }
+
+    pub fn send_door_alarm(&mut self, door: &DoorEntry, alarm: DoorAlarm) {
+        let door_alarm_topic = match &self.flat.door_alarm_topic {
+            Some(door_alarm_topic) => door_alarm_topic.clone(),
+            None => return,
+        };
+        if let Err(e) = self.mqtt_client.publish(
+            door_alarm_topic,
+            QoS::ExactlyOnce,
+            false,
+            alarm.payload(door),
+        ) {
+            error!("IoT: Can't send Door Alarm: {}", e);
+        }
+    }
 
     fn decrypt_broker_password(flat: &FlatEntry) -> Result<String, DecryptionError> {
         let broker_password_iv: [u8; 16] = base64::decode(flat.broker_password_iv.clone())?
@@ -146,3 +147,7 @@ impl BellButton {
         )?)?)
     }
 }
+
+#[cfg(test)]
+#[path = "./bell_button_test.rs"]
+mod bell_button_test;
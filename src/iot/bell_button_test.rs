@@ -0,0 +1,65 @@
+use super::*;
+use crate::iot::gpio::{Level, SimulatedGpio};
+use rumqttc::{Connection, Request};
+use std::time::Duration;
+
+/// Creates a client, whose published requests stay in the connection
+fn client() -> (Client, Connection) {
+    Client::new(MqttOptions::new("doorcam", "mqtt.local", 1883), 10)
+}
+
+/// Takes the topics of all messages, which were published until now
+fn published_topics(conn: &Connection) -> Vec<String> {
+    let mut topics = Vec::new();
+    while let Ok(request) = conn.eventloop.requests_rx.try_recv() {
+        if let Request::Publish(publish) = request {
+            topics.push(publish.topic);
+        }
+    }
+    topics
+}
+
+#[test]
+fn ring_bell() {
+    let gpio = SimulatedGpio::default();
+    let (client, conn) = client();
+    let _button = BellButton::with_client(&gpio, FlatEntry::default(), client).unwrap();
+    assert!(published_topics(&conn).is_empty());
+
+    gpio.set_level(0, Level::High);
+    assert_eq!(published_topics(&conn), vec!["/door/bell".to_string()]);
+
+    // Releasing the button doesn't ring again
+    gpio.set_level(0, Level::Low);
+    assert!(published_topics(&conn).is_empty());
+}
+
+#[test]
+fn debounce_bell() {
+    let gpio = SimulatedGpio::default();
+    let (client, conn) = client();
+    let _button = BellButton::with_client(&gpio, FlatEntry::default(), client).unwrap();
+
+    gpio.set_level(0, Level::High);
+    gpio.set_level(0, Level::Low);
+    gpio.set_level(0, Level::High);
+    assert_eq!(published_topics(&conn), vec!["/door/bell".to_string()]);
+
+    gpio.set_level(0, Level::Low);
+    std::thread::sleep(CONFIG.iot.bell_debounce_interval + Duration::from_millis(10));
+    gpio.set_level(0, Level::High);
+    assert_eq!(published_topics(&conn), vec!["/door/bell".to_string()]);
+}
+
+#[test]
+fn release_pin_with_last_clone() {
+    let gpio = SimulatedGpio::default();
+    let (client, _conn) = client();
+    let button = BellButton::with_client(&gpio, FlatEntry::default(), client).unwrap();
+    let clone = button.clone();
+
+    drop(button);
+    assert!(gpio.input_pulldown(0).is_err());
+    drop(clone);
+    assert!(gpio.input_pulldown(0).is_ok());
+}
@@ -1,101 +1,434 @@
-#[cfg(feature = "iot")]
-use super::GPIO;
+use super::door_state::{DoorAlarm, DoorEvent, DoorState, DoorStateMachine};
+use super::gpio::{self, Gpio, InputPin, Level, OutputPin, Trigger};
+use super::pulse_pattern::PulsePattern;
+use crate::db_entry::DoorEntry;
 use crate::utils::config::CONFIG;
-use log::{error, info};
-#[cfg(feature = "iot")]
-use rppal::gpio::OutputPin;
-use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
-use std::thread;
+use chrono::{DateTime, Utc};
+use log::{error, info, warn};
+use std::collections::HashMap;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
 
 #[cfg(test)]
 #[path = "./door_control_test.rs"]
 mod door_control_test;
 
+/// The callback, which sends an alarm of the door
+pub type AlarmCallback = Arc<dyn Fn(DoorAlarm) + Send + Sync>;
+
+/// The callback, which sends an alarm with the door, which has set it off
+pub type DoorAlarmCallback = Arc<dyn Fn(&DoorEntry, DoorAlarm) + Send + Sync>;
+
+/// The state machine of the door with the callback of its alarms
+#[derive(Clone)]
+struct DoorMonitor {
+    door: Arc<Mutex<DoorStateMachine>>,
+    alarm: AlarmCallback,
+}
+
+impl DoorMonitor {
+    /// Feeds the event to the state machine, sends its alarm and starts the timer of a new opening
+    fn handle(&self, event: DoorEvent) {
+        let (alarm, opening) = match self.door.lock() {
+            Ok(mut door) => {
+                let alarm = door.handle(event);
+                let opening = (matches!(event, DoorEvent::SensorOpened | DoorEvent::HoldOpenEnded)
+                    && door.state() == DoorState::Open)
+                    .then(|| door.opening());
+                (alarm, opening)
+            }
+            Err(e) => {
+                error!("IoT: Can't lock the door state: {}", e);
+                return;
+            }
+        };
+
+        if let Some(alarm) = alarm {
+            warn!("IoT: Door alarm {:?}", alarm);
+            (self.alarm)(alarm);
+        }
+
+        if let (Some(opening), Some(held_open_time)) = (opening, CONFIG.iot.door_held_open_time) {
+            let monitor = self.clone();
+            thread::spawn(move || {
+                thread::sleep(held_open_time);
+                monitor.handle(DoorEvent::HeldOpenTimeout { opening });
+            });
+        }
+    }
+}
+
+/// How long the door is kept unlocked in the hold-open mode
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum HoldOpen {
+    Until(DateTime<Utc>),
+    UntilCancelled,
+}
+
+impl HoldOpen {
+    /// Ends the hold-open mode at the latest at the `end`, if there is one
+    pub fn limit(self, end: Option<DateTime<Utc>>) -> Self {
+        match (self, end) {
+            (Self::Until(until), Some(end)) => Self::Until(until.min(end)),
+            (Self::UntilCancelled, Some(end)) => Self::Until(end),
+            (hold, None) => hold,
+        }
+    }
+}
+
+/// The thread, which drives an activation of the opener.
+/// It stops as soon as its sender is dropped.
+struct Driver {
+    stop: mpsc::Sender<()>,
+    thread: JoinHandle<()>,
+}
+
+/// The output pin of the opener with the state of its activation
+pub struct Opener {
+    /// Is taken to release the pin, when the control is shut down
+    dev: Option<Box<dyn OutputPin>>,
+    /// Counts the activations, so the driver of a previous activation stops
+    activation: u64,
+    active: bool,
+    hold: Option<HoldOpen>,
+    /// The driver of the current activation, a replaced driver stops
+    driver: Option<Driver>,
+}
+
+impl Opener {
+    fn set(&mut self, level: Level) {
+        if let Some(dev) = &mut self.dev {
+            match level {
+                Level::High => dev.set_high(),
+                Level::Low => dev.set_low(),
+            }
+        }
+    }
+
+    /// Deactivates the opener and returns whether it has been held open
+    fn stop(&mut self) -> bool {
+        self.set(Level::Low);
+        self.active = false;
+        self.hold.take().is_some()
+    }
+}
+
+/// How often the driver of an opener without a pulse pattern checks whether its activation is over
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
 ///Used to activate the door-opener.
 pub struct DoorControl {
-    #[cfg(not(feature = "iot"))]
-    is_open: Arc<Mutex<bool>>,
-
-    #[cfg(feature = "iot")]
-    dev: Arc<Mutex<OutputPin>>,
+    opener: Arc<Mutex<Opener>>,
+    /// The duration how long the door opener is activated
+    opening_time: Duration,
+    /// Only set if the opener is pulsed instead of being kept on
+    pulse_pattern: Option<PulsePattern>,
+    monitor: DoorMonitor,
+    /// Only set if a door sensor is used, the pin is kept to receive its interrupts
+    sensor: Mutex<Option<Box<dyn InputPin>>>,
 }
 
-#[cfg(not(feature = "iot"))]
 impl DoorControl {
-    #[allow(clippy::mutex_atomic)]
-    pub fn new(_pin: u8) -> Result<Self, ()> {
+    pub fn new(gpio: &dyn Gpio, pin: u8, opening_time: Duration) -> Result<Self, gpio::Error> {
         Ok(Self {
-            is_open: Arc::new(Mutex::new(false)),
+            opener: Arc::new(Mutex::new(Opener {
+                dev: Some(gpio.output(pin)?),
+                activation: 0,
+                active: false,
+                hold: None,
+                driver: None,
+            })),
+            opening_time,
+            pulse_pattern: None,
+            monitor: DoorMonitor {
+                door: Arc::new(Mutex::new(DoorStateMachine::default())),
+                alarm: Arc::new(|_alarm| ()),
+            },
+            sensor: Mutex::new(None),
         })
     }
 
-    /// Activates the opener for the `door_opening_time`
-    pub fn activate_opener(&self) -> Result<(), PoisonError<MutexGuard<bool>>> {
-        let mut state = self.is_open.lock()?;
+    /// Pulses the opener with the pattern while it is active
+    pub fn with_pulse_pattern(mut self, pattern: PulsePattern) -> Self {
+        self.pulse_pattern = Some(pattern);
+        self
+    }
+
+    /// Uses a reed contact, which is high while the door is open, to detect when the door is held open too long or forced open.
+    /// The alarms are sent with the callback.
+    pub fn with_sensor(
+        mut self,
+        gpio: &dyn Gpio,
+        pin: u8,
+        alarm: AlarmCallback,
+    ) -> Result<Self, gpio::Error> {
+        self.monitor.alarm = alarm;
+        let mut sensor = gpio.input_pulldown(pin)?;
+
+        let monitor = self.monitor.clone();
+        sensor.set_async_interrupt(
+            Trigger::Both,
+            Box::new(move |level| match level {
+                Level::High => monitor.handle(DoorEvent::SensorOpened),
+                Level::Low => monitor.handle(DoorEvent::SensorClosed),
+            }),
+        )?;
+
+        self.sensor = Mutex::new(Some(sensor));
+        Ok(self)
+    }
+
+    /// Activates the opener for the `opening_time`.
+    /// Returns false if the opener was already active, which doesn't extend the opening.
+    pub fn activate_opener(&self) -> Result<bool, PoisonError<MutexGuard<Opener>>> {
+        let mut opener = self.opener.lock()?;
         // Stop if the opener is active
-        if *state {
+        if opener.active {
             info!("IoT: Opener already active");
-            return Ok(());
+            return Ok(false);
         }
 
         info!("IoT: Activating opener");
-        *state = true;
-
-        // Spawn thread which waits the `door_opening_time` and stops the opener
-        let is_open = Arc::clone(&self.is_open);
-        thread::spawn(move || {
-            thread::sleep(CONFIG.iot.door_opening_time);
-            match is_open.lock() {
-                Ok(mut state) => *state = false,
-                Err(e) => error!("IoT: Can't deactivate opener: {}", e),
+        opener.activation += 1;
+        opener.active = true;
+        opener.set(Level::High);
+        self.monitor.handle(DoorEvent::OpenerActivated);
+        self.drive(&mut opener, Some(Instant::now() + self.opening_time));
+        Ok(true)
+    }
+
+    /// Keeps the opener active until the hold-open mode ends or is cancelled.
+    /// A running opening or hold-open mode is replaced.
+    pub fn hold_open(&self, hold: HoldOpen) -> Result<(), PoisonError<MutexGuard<Opener>>> {
+        let mut opener = self.opener.lock()?;
+        info!("IoT: Holding the door open {:?}", hold);
+        opener.activation += 1;
+        opener.set(Level::High);
+        if !opener.active {
+            opener.active = true;
+            self.monitor.handle(DoorEvent::OpenerActivated);
+        }
+        if opener.hold.replace(hold).is_none() {
+            self.monitor.handle(DoorEvent::HoldOpenStarted);
+        }
+
+        let end = match hold {
+            HoldOpen::Until(until) => {
+                let remaining = (until - Utc::now()).to_std().unwrap_or_default();
+                Some(Instant::now() + remaining)
             }
-        });
+            HoldOpen::UntilCancelled => None,
+        };
+        self.drive(&mut opener, end);
         Ok(())
     }
 
-    #[cfg(test)]
-    pub fn is_opener_active(&mut self) -> Result<bool, PoisonError<MutexGuard<bool>>> {
-        let state = self.is_open.lock()?;
-        Ok(*state)
+    /// Ends the hold-open mode and deactivates the opener.
+    /// Returns false if the door hasn't been held open.
+    pub fn cancel_hold_open(&self) -> Result<bool, PoisonError<MutexGuard<Opener>>> {
+        let mut opener = self.opener.lock()?;
+        if opener.hold.is_none() {
+            return Ok(false);
+        }
+
+        info!("IoT: Cancelling the hold-open mode");
+        opener.activation += 1;
+        opener.stop();
+        opener.driver = None;
+        self.monitor.handle(DoorEvent::HoldOpenEnded);
+        self.monitor.handle(DoorEvent::OpenerDeactivated);
+        Ok(true)
+    }
+
+    /// Ends an opening or the hold-open mode without alarms, waits for its driver and releases the pins.
+    /// The control can't activate the opener anymore.
+    fn shut_down(&self) {
+        let driver = {
+            let mut opener = self.opener.lock().unwrap_or_else(PoisonError::into_inner);
+            opener.activation += 1;
+            if opener.stop() {
+                info!("IoT: Cancelling the hold-open mode of a removed door");
+            }
+            opener.dev = None;
+            opener.driver.take()
+        };
+        // The driver may wait for the opener, so it's joined after the opener is unlocked
+        if let Some(Driver { stop, thread }) = driver {
+            drop(stop);
+            if thread.join().is_err() {
+                error!("IoT: The driver of the opener panicked");
+            }
+        }
+        self.sensor
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+    }
+
+    /// Spawns the thread, which switches the opener by its pulse pattern and deactivates it at the end.
+    /// It stops without changing the opener, if another activation has taken over.
+    fn drive(&self, opener: &mut Opener, end: Option<Instant>) {
+        let activation = opener.activation;
+        let (stop, stopped) = mpsc::channel();
+        let shared_opener = Arc::clone(&self.opener);
+        let monitor = self.monitor.clone();
+        let mut steps = self.pulse_pattern.as_ref().map(PulsePattern::steps);
+
+        let thread = thread::spawn(move || loop {
+            let (level, duration) = steps
+                .as_mut()
+                .and_then(Iterator::next)
+                .unwrap_or((Level::High, POLL_INTERVAL));
+
+            let remaining = {
+                let mut opener = match shared_opener.lock() {
+                    Ok(opener) => opener,
+                    Err(e) => {
+                        error!("IoT: Can't drive the opener: {}", e);
+                        return;
+                    }
+                };
+                if opener.activation != activation {
+                    return;
+                }
+
+                let remaining = end.map(|end| end.saturating_duration_since(Instant::now()));
+                if remaining == Some(Duration::from_secs(0)) {
+                    info!("IoT: Deactivating opener");
+                    let held_open = opener.stop();
+                    drop(opener);
+                    if held_open {
+                        monitor.handle(DoorEvent::HoldOpenEnded);
+                    }
+                    monitor.handle(DoorEvent::OpenerDeactivated);
+                    return;
+                }
+
+                opener.set(level);
+                remaining
+            };
+            let timeout = remaining.map_or(duration, |remaining| remaining.min(duration));
+            if stopped.recv_timeout(timeout) != Err(RecvTimeoutError::Timeout) {
+                return;
+            }
+        });
+        // The driver of the previous activation stops, when its sender is dropped
+        opener.driver = Some(Driver { stop, thread });
+    }
+
+    pub fn is_opener_active(&self) -> Result<bool, PoisonError<MutexGuard<Opener>>> {
+        Ok(self.opener.lock()?.active)
+    }
+
+    /// The hold-open mode of the opener, if the door is held open
+    pub fn hold(&self) -> Result<Option<HoldOpen>, PoisonError<MutexGuard<Opener>>> {
+        Ok(self.opener.lock()?.hold)
+    }
+
+    /// The current state of the door.
+    /// Without a door sensor, it only distinguishes between closed and unlocked.
+    pub fn door_state(&self) -> Result<DoorState, PoisonError<MutexGuard<DoorStateMachine>>> {
+        Ok(self.monitor.door.lock()?.state())
     }
 }
 
-#[cfg(feature = "iot")]
-impl DoorControl {
-    pub fn new(pin: u8) -> Result<Self, rppal::gpio::Error> {
-        Ok(Self {
-            dev: Arc::new(Mutex::new(GPIO.get(pin)?.into_output())),
-        })
+/// The controls of all doors, which are created from the door entries.
+pub struct DoorControls {
+    gpio: Arc<dyn Gpio>,
+    /// The callback of the alarms of all door sensors
+    alarm: DoorAlarmCallback,
+    controls: RwLock<HashMap<u32, (DoorEntry, Arc<DoorControl>)>>,
+}
+
+impl DoorControls {
+    pub fn new(gpio: Arc<dyn Gpio>, alarm: DoorAlarmCallback) -> Self {
+        Self {
+            gpio,
+            alarm,
+            controls: RwLock::new(HashMap::new()),
+        }
     }
 
-    /// Activates the opener for the `door_opening_time`
-    pub fn activate_opener(&self) -> Result<(), PoisonError<MutexGuard<OutputPin>>> {
-        let mut dev = self.dev.lock()?;
-        // Stop if the opener is active
-        if dev.is_set_high() {
-            info!("IoT: Opener already active");
-            return Ok(());
+    fn create(&self, door: &DoorEntry) -> Result<DoorControl, gpio::Error> {
+        let mut control = DoorControl::new(
+            &*self.gpio,
+            door.opener_pin,
+            Duration::from_secs(door.opening_time.into()),
+        )?;
+        if let Some(pattern) = &door.pulse_pattern {
+            match pattern.parse() {
+                Ok(pattern) => control = control.with_pulse_pattern(pattern),
+                Err(e) => warn!(
+                    "IoT: Invalid pulse pattern of the door {}, the opener isn't pulsed: {}",
+                    door.name, e
+                ),
+            }
         }
+        match door.sensor_pin {
+            Some(sensor_pin) => {
+                let alarm = Arc::clone(&self.alarm);
+                let alarm_door = door.clone();
+                control.with_sensor(
+                    &*self.gpio,
+                    sensor_pin,
+                    Arc::new(move |door_alarm| alarm(&alarm_door, door_alarm)),
+                )
+            }
+            None => Ok(control),
+        }
+    }
 
-        info!("IoT: Activating opener");
-        dev.set_high();
+    /// Shuts down the controls of changed and removed doors
+    fn shut_down(controls: HashMap<u32, (DoorEntry, Arc<DoorControl>)>) {
+        for (door, control) in controls.into_values() {
+            info!("IoT: Shutting down the control of the door {}", door.name);
+            control.shut_down();
+        }
+    }
 
-        let dev = Arc::clone(&self.dev);
+    /// Creates the controls of new and changed doors and shuts down the controls of changed and removed doors.
+    /// A door, whose pins can't be used, is skipped and the last error is returned.
+    pub fn sync(&self, doors: Vec<DoorEntry>) -> Result<(), gpio::Error> {
+        let mut controls = self
+            .controls
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        // Release the pins of the changed doors first, so they can be used by the other doors
+        let (kept, removed) = controls
+            .drain()
+            .partition(|(_, (door, _))| doors.contains(door));
+        *controls = kept;
+        Self::shut_down(removed);
 
-        // Spawn thread which waits the `door_opening_time` and stops the opener
-        thread::spawn(move || {
-            thread::sleep(CONFIG.iot.door_opening_time);
-            info!("IoT: Deactivating opener");
-            match dev.lock() {
-                Ok(mut dev) => dev.set_low(),
-                Err(e) => error!("IoT: Can't deactivate opener: {}", e),
+        let mut result = Ok(());
+        for door in doors {
+            if controls.contains_key(&door.id) {
+                continue;
             }
-        });
-        Ok(())
+            match self.create(&door) {
+                Ok(control) => {
+                    controls.insert(door.id, (door, Arc::new(control)));
+                }
+                Err(e) => {
+                    error!(
+                        "IoT: Can't create the control of the door {}: {}",
+                        door.name, e
+                    );
+                    result = Err(e);
+                }
+            }
+        }
+        result
     }
 
-    #[cfg(test)]
-    pub fn is_opener_active(&self) -> Result<bool, PoisonError<MutexGuard<OutputPin>>> {
-        Ok(self.dev.lock()?.is_set_high())
+    /// The control of the door, if its pins could be used
+    pub fn get(&self, id: u32) -> Option<Arc<DoorControl>> {
+        self.controls
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&id)
+            .map(|(_, control)| Arc::clone(control))
     }
 }
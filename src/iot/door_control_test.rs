@@ -1,22 +1,279 @@
 use super::*;
+use crate::iot::gpio::SimulatedGpio;
+use std::sync::mpsc::Receiver;
+
+const OPENING_TIME: Duration = Duration::from_millis(300);
+/// The generous time, in which an expected change has to happen even on a slow machine
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Waits until the condition holds and returns false after the timeout
+fn wait_until<F: Fn() -> bool>(condition: F) -> bool {
+    let deadline = Instant::now() + TIMEOUT;
+    while !condition() {
+        if Instant::now() > deadline {
+            return false;
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+    true
+}
 
 #[test]
 fn start_closed() {
-    let mut ctrl = DoorControl::new(0).unwrap();
+    let gpio = SimulatedGpio::default();
+    let ctrl = DoorControl::new(&gpio, 0, OPENING_TIME).unwrap();
     assert_matches!(ctrl.is_opener_active(), Ok(false));
+    assert_eq!(gpio.level(0), Level::Low);
 }
 
 #[test]
 fn open() {
-    let mut ctrl = DoorControl::new(0).unwrap();
-    assert_matches!(ctrl.activate_opener(), Ok(()));
+    let gpio = SimulatedGpio::default();
+    let ctrl = DoorControl::new(&gpio, 0, OPENING_TIME).unwrap();
+    assert_matches!(ctrl.activate_opener(), Ok(true));
     assert_matches!(ctrl.is_opener_active(), Ok(true));
+    assert_eq!(gpio.level(0), Level::High);
+    // The running opening isn't extended
+    assert_matches!(ctrl.activate_opener(), Ok(false));
 }
 
 #[test]
 fn auto_stop_opening() {
-    let mut ctrl = DoorControl::new(0).unwrap();
-    assert_matches!(ctrl.activate_opener(), Ok(()));
-    std::thread::sleep(CONFIG.iot.door_opening_time + std::time::Duration::from_millis(42));
+    let gpio = SimulatedGpio::default();
+    let ctrl = DoorControl::new(&gpio, 0, OPENING_TIME).unwrap();
+    assert_matches!(ctrl.activate_opener(), Ok(true));
+    assert!(wait_until(|| !ctrl.is_opener_active().unwrap()));
+    assert_eq!(gpio.level(0), Level::Low);
+}
+
+#[test]
+fn pin_in_use() {
+    let gpio = SimulatedGpio::default();
+    let _ctrl = DoorControl::new(&gpio, 0, OPENING_TIME).unwrap();
+    assert_matches!(
+        DoorControl::new(&gpio, 0, OPENING_TIME).err(),
+        Some(gpio::Error::PinInUse(0))
+    );
+}
+
+/// Creates a door control with a sensor on pin 1, whose alarms are received by the channel
+fn with_sensor(gpio: &SimulatedGpio) -> (DoorControl, Receiver<DoorAlarm>) {
+    let (sender, alarms) = mpsc::channel();
+    let sender = Mutex::new(sender);
+    let ctrl = DoorControl::new(gpio, 0, OPENING_TIME)
+        .unwrap()
+        .with_sensor(
+            gpio,
+            1,
+            Arc::new(move |alarm| sender.lock().unwrap().send(alarm).unwrap()),
+        )
+        .unwrap();
+    (ctrl, alarms)
+}
+
+#[test]
+fn unlocked_without_sensor() {
+    let gpio = SimulatedGpio::default();
+    let ctrl = DoorControl::new(&gpio, 0, OPENING_TIME).unwrap();
+    assert_matches!(ctrl.door_state(), Ok(DoorState::Closed));
+    ctrl.activate_opener().unwrap();
+    assert_matches!(ctrl.door_state(), Ok(DoorState::Unlocked));
+}
+
+#[test]
+fn open_with_sensor() {
+    let gpio = SimulatedGpio::default();
+    let (ctrl, alarms) = with_sensor(&gpio);
+
+    ctrl.activate_opener().unwrap();
+    gpio.set_level(1, Level::High);
+    assert_matches!(ctrl.door_state(), Ok(DoorState::Open));
+    gpio.set_level(1, Level::Low);
+    assert_matches!(ctrl.door_state(), Ok(DoorState::Unlocked));
+
+    assert!(wait_until(
+        || ctrl.door_state().unwrap() == DoorState::Closed
+    ));
+    assert!(alarms.try_recv().is_err());
+}
+
+#[test]
+fn forced_open_alarm() {
+    let gpio = SimulatedGpio::default();
+    let (ctrl, alarms) = with_sensor(&gpio);
+
+    gpio.set_level(1, Level::High);
+    assert_matches!(ctrl.door_state(), Ok(DoorState::ForcedOpen));
+    assert_eq!(alarms.try_recv(), Ok(DoorAlarm::ForcedOpen));
+}
+
+#[test]
+fn held_open_alarm() {
+    let gpio = SimulatedGpio::default();
+    let (ctrl, alarms) = with_sensor(&gpio);
+
+    ctrl.activate_opener().unwrap();
+    gpio.set_level(1, Level::High);
+    assert_eq!(alarms.recv_timeout(TIMEOUT), Ok(DoorAlarm::HeldOpen));
+    assert_matches!(ctrl.door_state(), Ok(DoorState::HeldOpen));
+}
+
+#[test]
+fn pulsed_opener() {
+    let gpio = SimulatedGpio::default();
+    let ctrl = DoorControl::new(&gpio, 0, OPENING_TIME)
+        .unwrap()
+        .with_pulse_pattern("100,100".parse().unwrap());
+    assert_matches!(ctrl.activate_opener(), Ok(true));
+    assert_eq!(gpio.level(0), Level::High);
+
+    // The opener is still active during the pause
+    assert!(wait_until(|| gpio.level(0) == Level::Low));
+    assert_matches!(ctrl.is_opener_active(), Ok(true));
+    assert!(wait_until(|| gpio.level(0) == Level::High));
+
+    assert!(wait_until(|| !ctrl.is_opener_active().unwrap()));
+    assert_eq!(gpio.level(0), Level::Low);
+}
+
+#[test]
+fn hold_open_until() {
+    let gpio = SimulatedGpio::default();
+    let ctrl = DoorControl::new(&gpio, 0, OPENING_TIME).unwrap();
+    let until = Utc::now() + chrono::Duration::seconds(2);
+    assert_matches!(ctrl.hold_open(HoldOpen::Until(until)), Ok(()));
+    assert_matches!(ctrl.hold(), Ok(Some(HoldOpen::Until(_))));
+    assert_matches!(ctrl.door_state(), Ok(DoorState::Unlocked));
+
+    // The hold-open mode outlasts the opening time
+    thread::sleep(OPENING_TIME * 2);
+    assert_eq!(gpio.level(0), Level::High);
+    assert!(wait_until(|| ctrl.hold().unwrap().is_none()));
+    assert_eq!(gpio.level(0), Level::Low);
+    assert!(wait_until(
+        || ctrl.door_state().unwrap() == DoorState::Closed
+    ));
+}
+
+#[test]
+fn cancel_hold_open() {
+    let gpio = SimulatedGpio::default();
+    let ctrl = DoorControl::new(&gpio, 0, OPENING_TIME).unwrap();
+    assert_matches!(ctrl.cancel_hold_open(), Ok(false));
+
+    // A running opening is taken over
+    ctrl.activate_opener().unwrap();
+    assert_matches!(ctrl.hold_open(HoldOpen::UntilCancelled), Ok(()));
+    thread::sleep(OPENING_TIME * 2);
+    assert_matches!(ctrl.is_opener_active(), Ok(true));
+
+    assert_matches!(ctrl.cancel_hold_open(), Ok(true));
     assert_matches!(ctrl.is_opener_active(), Ok(false));
+    assert_eq!(gpio.level(0), Level::Low);
+    assert_matches!(ctrl.door_state(), Ok(DoorState::Closed));
+}
+
+#[test]
+fn no_held_open_alarm_while_held_open() {
+    let gpio = SimulatedGpio::default();
+    let (ctrl, alarms) = with_sensor(&gpio);
+    let held_open_time = CONFIG.iot.door_held_open_time.unwrap();
+
+    ctrl.hold_open(HoldOpen::UntilCancelled).unwrap();
+    gpio.set_level(1, Level::High);
+    assert_eq!(
+        alarms.recv_timeout(held_open_time * 3),
+        Err(mpsc::RecvTimeoutError::Timeout)
+    );
+    assert_matches!(ctrl.door_state(), Ok(DoorState::Open));
+
+    // The timer starts again, when the hold-open mode ends
+    ctrl.cancel_hold_open().unwrap();
+    assert_eq!(alarms.recv_timeout(TIMEOUT), Ok(DoorAlarm::HeldOpen));
+    assert_matches!(ctrl.door_state(), Ok(DoorState::HeldOpen));
+}
+
+#[test]
+fn sync_door_controls() {
+    let gpio = SimulatedGpio::default();
+    let controls = DoorControls::new(Arc::new(gpio.clone()), Arc::new(|_door, _alarm| ()));
+    let door = DoorEntry {
+        id: 1,
+        name: "House Door".to_string(),
+        opener_pin: 0,
+        opening_time: 3,
+        sensor_pin: None,
+        pulse_pattern: None,
+    };
+    let mut gate = DoorEntry {
+        id: 2,
+        name: "Gate".to_string(),
+        opener_pin: 1,
+        opening_time: 3,
+        sensor_pin: Some(2),
+        pulse_pattern: None,
+    };
+
+    assert_matches!(controls.sync(vec![door.clone(), gate.clone()]), Ok(()));
+    assert!(controls.get(1).is_some());
+    assert!(controls.get(2).is_some());
+
+    // The changed gate uses the pin of the door, which has been removed
+    gate.opener_pin = 0;
+    assert_matches!(controls.sync(vec![gate.clone()]), Ok(()));
+    assert!(controls.get(1).is_none());
+    controls.get(2).unwrap().activate_opener().unwrap();
+    assert_eq!(gpio.level(0), Level::High);
+
+    // The pin is already used by the gate
+    assert_matches!(
+        controls.sync(vec![gate, door]),
+        Err(gpio::Error::PinInUse(0))
+    );
+    assert!(controls.get(1).is_none());
+}
+
+#[test]
+fn edit_door_held_open() {
+    let gpio = SimulatedGpio::default();
+    let controls = DoorControls::new(Arc::new(gpio.clone()), Arc::new(|_door, _alarm| ()));
+    let mut door = DoorEntry {
+        id: 1,
+        name: "House Door".to_string(),
+        opener_pin: 0,
+        opening_time: 3,
+        sensor_pin: Some(1),
+        pulse_pattern: None,
+    };
+    assert_matches!(controls.sync(vec![door.clone()]), Ok(()));
+    let held_control = controls.get(1).unwrap();
+    held_control.hold_open(HoldOpen::UntilCancelled).unwrap();
+    assert_eq!(gpio.level(0), Level::High);
+
+    // The hold-open mode ends and the pins are released, although the old control is still referenced
+    door.name = "Front Door".to_string();
+    assert_matches!(controls.sync(vec![door.clone()]), Ok(()));
+    assert_eq!(gpio.level(0), Level::Low);
+    assert_eq!(held_control.hold().unwrap(), None);
+    assert!(!controls.get(1).unwrap().is_opener_active().unwrap());
+
+    // The old control can't switch the released pin anymore
+    held_control.activate_opener().unwrap();
+    assert_eq!(gpio.level(0), Level::Low);
+
+    // The changed door can be opened and its pins can be used by another door
+    controls.get(1).unwrap().activate_opener().unwrap();
+    assert_eq!(gpio.level(0), Level::High);
+    door.opener_pin = 2;
+    door.sensor_pin = None;
+    let gate = DoorEntry {
+        id: 2,
+        name: "Gate".to_string(),
+        opener_pin: 0,
+        opening_time: 3,
+        sensor_pin: Some(1),
+        pulse_pattern: None,
+    };
+    assert_matches!(controls.sync(vec![door, gate]), Ok(()));
+    assert_eq!(gpio.level(0), Level::Low);
 }
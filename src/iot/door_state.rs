@@ -0,0 +1,161 @@
+//! The state of the door, which is fed by the opener and the optional door sensor.
+
+use crate::db_entry::DoorEntry;
+use serde::Serialize;
+use std::fmt;
+
+#[cfg(test)]
+#[path = "./door_state_test.rs"]
+mod door_state_test;
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize)]
+pub enum DoorState {
+    Closed,
+    /// The opener is active, but the door hasn't been opened yet
+    Unlocked,
+    Open,
+    /// The door has been open longer than `door_held_open_time`
+    HeldOpen,
+    /// The door has been opened without an activation of the opener
+    ForcedOpen,
+}
+
+impl DoorState {
+    #[inline(always)]
+    pub fn is_open(self) -> bool {
+        matches!(self, Self::Open | Self::HeldOpen | Self::ForcedOpen)
+    }
+}
+
+impl fmt::Display for DoorState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Closed => write!(f, "Closed"),
+            Self::Unlocked => write!(f, "Unlocked"),
+            Self::Open => write!(f, "Open"),
+            Self::HeldOpen => write!(f, "Held open too long"),
+            Self::ForcedOpen => write!(f, "Forced open"),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum DoorEvent {
+    OpenerActivated,
+    OpenerDeactivated,
+    SensorOpened,
+    SensorClosed,
+    /// The door is kept unlocked, so it may stay open without an alarm
+    HoldOpenStarted,
+    /// The hold-open mode has ended, an open door gets a new timer
+    HoldOpenEnded,
+    /// The timer of an opening has run out, it is ignored if the door has been closed meanwhile
+    HeldOpenTimeout {
+        opening: u64,
+    },
+}
+
+/// The alarms, which are sent to the brokers of the flats of the door
+#[derive(Copy, Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DoorAlarm {
+    HeldOpen,
+    ForcedOpen,
+}
+
+/// The payload of an alarm, which names its door
+#[derive(Serialize)]
+struct AlarmPayload<'a> {
+    door_id: u32,
+    door_name: &'a str,
+    alarm: DoorAlarm,
+}
+
+impl DoorAlarm {
+    /// The JSON payload of the MQTT message with the door, which has set the alarm off
+    pub fn payload(self, door: &DoorEntry) -> Vec<u8> {
+        serde_json::to_vec(&AlarmPayload {
+            door_id: door.id,
+            door_name: &door.name,
+            alarm: self,
+        })
+        .unwrap_or_default()
+    }
+}
+
+/// The state machine of the door
+#[derive(Debug)]
+pub struct DoorStateMachine {
+    state: DoorState,
+    opener_active: bool,
+    hold_open: bool,
+    /// Counts the openings, so an old timer doesn't apply to a later opening
+    openings: u64,
+}
+
+impl Default for DoorStateMachine {
+    fn default() -> Self {
+        Self {
+            state: DoorState::Closed,
+            opener_active: false,
+            hold_open: false,
+            openings: 0,
+        }
+    }
+}
+
+impl DoorStateMachine {
+    #[inline(always)]
+    pub fn state(&self) -> DoorState {
+        self.state
+    }
+
+    /// The number of the current opening, which is passed to `DoorEvent::HeldOpenTimeout`
+    #[inline(always)]
+    pub fn opening(&self) -> u64 {
+        self.openings
+    }
+
+    /// Changes the state by the event and returns the alarm, which the change sets off
+    pub fn handle(&mut self, event: DoorEvent) -> Option<DoorAlarm> {
+        let (state, alarm) = match (self.state, event) {
+            (DoorState::Closed, DoorEvent::OpenerActivated) => (DoorState::Unlocked, None),
+            (DoorState::Unlocked, DoorEvent::OpenerDeactivated) => (DoorState::Closed, None),
+            (DoorState::Unlocked, DoorEvent::SensorOpened) => {
+                self.openings += 1;
+                (DoorState::Open, None)
+            }
+            (DoorState::Closed, DoorEvent::SensorOpened) => {
+                self.openings += 1;
+                (DoorState::ForcedOpen, Some(DoorAlarm::ForcedOpen))
+            }
+            (state, DoorEvent::SensorClosed) if state.is_open() => {
+                if self.opener_active {
+                    (DoorState::Unlocked, None)
+                } else {
+                    (DoorState::Closed, None)
+                }
+            }
+            (DoorState::Open, DoorEvent::HoldOpenEnded) => {
+                self.openings += 1;
+                (DoorState::Open, None)
+            }
+            (DoorState::Open, DoorEvent::HeldOpenTimeout { opening })
+                if opening == self.openings && !self.hold_open =>
+            {
+                (DoorState::HeldOpen, Some(DoorAlarm::HeldOpen))
+            }
+            (state, _) => (state, None),
+        };
+
+        match event {
+            DoorEvent::OpenerActivated => self.opener_active = true,
+            DoorEvent::OpenerDeactivated => self.opener_active = false,
+            DoorEvent::HoldOpenStarted => self.hold_open = true,
+            DoorEvent::HoldOpenEnded => self.hold_open = false,
+            _ => (),
+        }
+        self.state = state;
+        alarm
+    }
+}
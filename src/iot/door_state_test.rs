@@ -0,0 +1,113 @@
+use super::*;
+
+#[test]
+fn regular_opening() {
+    let mut door = DoorStateMachine::default();
+    assert_eq!(door.state(), DoorState::Closed);
+
+    assert_eq!(door.handle(DoorEvent::OpenerActivated), None);
+    assert_eq!(door.state(), DoorState::Unlocked);
+    assert_eq!(door.handle(DoorEvent::SensorOpened), None);
+    assert_eq!(door.state(), DoorState::Open);
+    // The door is still open, when the opener stops
+    assert_eq!(door.handle(DoorEvent::OpenerDeactivated), None);
+    assert_eq!(door.state(), DoorState::Open);
+    assert_eq!(door.handle(DoorEvent::SensorClosed), None);
+    assert_eq!(door.state(), DoorState::Closed);
+}
+
+#[test]
+fn closed_while_unlocked() {
+    let mut door = DoorStateMachine::default();
+    door.handle(DoorEvent::OpenerActivated);
+    door.handle(DoorEvent::SensorOpened);
+    door.handle(DoorEvent::SensorClosed);
+    assert_eq!(door.state(), DoorState::Unlocked);
+
+    door.handle(DoorEvent::OpenerDeactivated);
+    assert_eq!(door.state(), DoorState::Closed);
+}
+
+#[test]
+fn forced_open() {
+    let mut door = DoorStateMachine::default();
+    assert_eq!(
+        door.handle(DoorEvent::SensorOpened),
+        Some(DoorAlarm::ForcedOpen)
+    );
+    assert_eq!(door.state(), DoorState::ForcedOpen);
+
+    // The opener doesn't legitimate an opened door
+    assert_eq!(door.handle(DoorEvent::OpenerActivated), None);
+    assert_eq!(door.state(), DoorState::ForcedOpen);
+    door.handle(DoorEvent::SensorClosed);
+    assert_eq!(door.state(), DoorState::Unlocked);
+}
+
+#[test]
+fn held_open() {
+    let mut door = DoorStateMachine::default();
+    door.handle(DoorEvent::OpenerActivated);
+    door.handle(DoorEvent::SensorOpened);
+    let opening = door.opening();
+
+    assert_eq!(
+        door.handle(DoorEvent::HeldOpenTimeout { opening }),
+        Some(DoorAlarm::HeldOpen)
+    );
+    assert_eq!(door.state(), DoorState::HeldOpen);
+    // Only one alarm per opening
+    assert_eq!(door.handle(DoorEvent::HeldOpenTimeout { opening }), None);
+
+    door.handle(DoorEvent::SensorClosed);
+    assert_eq!(door.state(), DoorState::Unlocked);
+}
+
+#[test]
+fn timeout_of_previous_opening() {
+    let mut door = DoorStateMachine::default();
+    door.handle(DoorEvent::OpenerActivated);
+    door.handle(DoorEvent::SensorOpened);
+    let opening = door.opening();
+    door.handle(DoorEvent::SensorClosed);
+    door.handle(DoorEvent::SensorOpened);
+
+    assert_eq!(door.handle(DoorEvent::HeldOpenTimeout { opening }), None);
+    assert_eq!(door.state(), DoorState::Open);
+}
+
+#[test]
+fn hold_open() {
+    let mut door = DoorStateMachine::default();
+    door.handle(DoorEvent::OpenerActivated);
+    door.handle(DoorEvent::HoldOpenStarted);
+    door.handle(DoorEvent::SensorOpened);
+    let opening = door.opening();
+
+    // No alarm while the door is held open on purpose
+    assert_eq!(door.handle(DoorEvent::HeldOpenTimeout { opening }), None);
+    assert_eq!(door.state(), DoorState::Open);
+
+    // The timer of the hold-open mode doesn't apply afterwards
+    door.handle(DoorEvent::HoldOpenEnded);
+    door.handle(DoorEvent::OpenerDeactivated);
+    assert_eq!(door.handle(DoorEvent::HeldOpenTimeout { opening }), None);
+    let opening = door.opening();
+    assert_eq!(
+        door.handle(DoorEvent::HeldOpenTimeout { opening }),
+        Some(DoorAlarm::HeldOpen)
+    );
+}
+
+#[test]
+fn alarm_payload() {
+    let door = DoorEntry::default();
+
+    assert_eq!(
+        String::from_utf8(DoorAlarm::ForcedOpen.payload(&door)).unwrap(),
+        format!(
+            r#"{{"door_id":{},"door_name":"House Door","alarm":"forced_open"}}"#,
+            door.id
+        )
+    );
+}
@@ -1,31 +1,33 @@
 //! Syncs the flats between web and IoT and manages the BellButtons.
 
-use super::BellButton;
-#[cfg(feature = "iot")]
-use super::GPIO;
-use crate::db_entry::FlatEntry;
+use super::gpio::{Gpio, InputPin, Trigger};
+use super::{BellButton, DoorAlarm, DoorAlarmCallback};
+use crate::db_entry::{DoorEntry, FlatEntry};
 use crate::utils::no_operation;
-#[cfg(feature = "iot")]
 use crate::CONFIG;
-#[cfg(feature = "iot")]
 use crate::{debounce_callback, setup_debounce};
 use log::{error, info};
 use rocket_contrib::databases::rusqlite::Connection;
-#[cfg(feature = "iot")]
-use rppal::gpio::{InputPin, Trigger};
 use rsevents::{AutoResetEvent, Awaitable};
 use std::sync::{Arc, Mutex};
 use std::thread;
-#[cfg(feature = "iot")]
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
-/// Create multi-threaded event-loops in order to sync with the web application and creates and drops BellButtons, which have their own event-loop
-pub fn event_loop(flat_sync_event: &Arc<AutoResetEvent>, conn: Connection) {
+/// Create multi-threaded event-loops in order to sync with the web application and creates and drops BellButtons, which have their own event-loop.
+/// Returns the callback, which sends the door alarms to the brokers of the flats, which have been granted the door.
+/// It looks the flats up with `alarm_conn`, as the grants can change without a sync of the flats.
+pub fn event_loop(
+    gpio: Arc<dyn Gpio>,
+    flat_sync_event: &Arc<AutoResetEvent>,
+    conn: Connection,
+    alarm_conn: Connection,
+) -> DoorAlarmCallback {
     let flat_sync_event = Arc::clone(flat_sync_event);
     let buttons: Arc<Mutex<Vec<BellButton>>> = Arc::new(Mutex::new(Vec::new()));
 
-    let tamper_sensor_pin = setup_tamper_sensor(Arc::clone(&buttons));
+    let tamper_sensor_pin = setup_tamper_sensor(&*gpio, Arc::clone(&buttons));
 
+    let alarm_buttons = Arc::clone(&buttons);
     thread::spawn(move || {
         info!("IoT: Loading flats in event_loop");
         loop {
@@ -33,17 +35,43 @@ pub fn event_loop(flat_sync_event: &Arc<AutoResetEvent>, conn: Connection) {
             no_operation(&tamper_sensor_pin);
 
             match buttons.lock() {
-                Ok(mut buttons) => fetch_flats(&mut buttons, &conn),
+                Ok(mut buttons) => fetch_flats(&*gpio, &mut buttons, &conn),
                 Err(e) => error!("IoT: Can't lock bell buttons: {}", e),
             }
             flat_sync_event.wait();
             info!("IoT: Reloading flats in event_loop");
         }
     });
+
+    let alarm_conn = Mutex::new(alarm_conn);
+    Arc::new(move |door: &DoorEntry, alarm: DoorAlarm| {
+        let flats = match alarm_conn.lock() {
+            Ok(conn) => door.get_flats(&conn),
+            Err(e) => {
+                error!("IoT: Can't lock the alarm connection: {}", e);
+                return;
+            }
+        };
+        let flats = match flats {
+            Ok(flats) => flats,
+            Err(e) => {
+                error!("IoT: Can't load the flats of the door {}: {}", door.name, e);
+                return;
+            }
+        };
+
+        match alarm_buttons.lock() {
+            Ok(mut buttons) => buttons
+                .iter_mut()
+                .filter(|button| flats.contains(&button.flat_id()))
+                .for_each(|button| button.send_door_alarm(door, alarm)),
+            Err(e) => error!("IoT: Can't lock bell buttons: {}", e),
+        }
+    })
 }
 
 /// Fetches all active flats from the database if a flat has been changed
-fn fetch_flats(buttons: &mut Vec<BellButton>, conn: &Connection) {
+fn fetch_flats(gpio: &dyn Gpio, buttons: &mut Vec<BellButton>, conn: &Connection) {
     let flats = match FlatEntry::get_active(conn) {
         Ok(f) => f,
         Err(e) => {
@@ -51,7 +79,13 @@ fn fetch_flats(buttons: &mut Vec<BellButton>, conn: &Connection) {
             return;
         }
     };
-    *buttons = match flats.into_iter().map(BellButton::new).collect() {
+    // Release the pins of the old buttons, so they can be used again
+    buttons.clear();
+    *buttons = match flats
+        .into_iter()
+        .map(|flat| BellButton::new(gpio, flat))
+        .collect()
+    {
         Ok(buttons) => buttons,
         Err(e) => {
             error!("IoT: Can't create bell button: {}", e);
@@ -60,32 +94,53 @@ fn fetch_flats(buttons: &mut Vec<BellButton>, conn: &Connection) {
     }
 }
 
-#[cfg(not(feature = "iot"))]
-fn setup_tamper_sensor(_connections: Arc<Mutex<Vec<BellButton>>>) {}
-#[cfg(feature = "iot")]
-fn setup_tamper_sensor(connections: Arc<Mutex<Vec<BellButton>>>) -> Option<InputPin> {
+fn setup_tamper_sensor(
+    gpio: &dyn Gpio,
+    connections: Arc<Mutex<Vec<BellButton>>>,
+) -> Option<Box<dyn InputPin>> {
     let tamper_sensor_pin = CONFIG.iot.tamper_sensor_pin?;
     let tamper_sensor_debounce_interval = CONFIG.iot.tamper_sensor_debounce_interval?;
-    let mut dev = GPIO
-        .get(tamper_sensor_pin)
-        .expect("Can't use the tamper sensor pin")
-        .into_input_pulldown();
+    Some(arm_tamper_sensor(
+        gpio,
+        tamper_sensor_pin,
+        tamper_sensor_debounce_interval,
+        connections,
+    ))
+}
+
+/// Sends a tamper alarm with every connection, when the sensor on the pin rises
+fn arm_tamper_sensor(
+    gpio: &dyn Gpio,
+    tamper_sensor_pin: u8,
+    tamper_sensor_debounce_interval: Duration,
+    connections: Arc<Mutex<Vec<BellButton>>>,
+) -> Box<dyn InputPin> {
+    let mut dev = gpio
+        .input_pulldown(tamper_sensor_pin)
+        .expect("Can't use the tamper sensor pin");
 
     let mut last_action = setup_debounce!(tamper_sensor_debounce_interval);
 
-    dev.set_async_interrupt(Trigger::RisingEdge, move |_level| {
-        debounce_callback!(last_action, tamper_sensor_debounce_interval);
+    dev.set_async_interrupt(
+        Trigger::RisingEdge,
+        Box::new(move |_level| {
+            debounce_callback!(last_action, tamper_sensor_debounce_interval);
 
-        info!("IoT: tamper sensor recieved alarm");
+            info!("IoT: tamper sensor recieved alarm");
 
-        match connections.lock() {
-            Ok(mut connections) => connections
-                .iter_mut()
-                .for_each(BellButton::send_tamper_alarm),
-            Err(e) => error!("IoT: Can't lock connections: {}", e),
-        }
-        info!("IoT: tamper sensor sent alarm(s)");
-    })
+            match connections.lock() {
+                Ok(mut connections) => connections
+                    .iter_mut()
+                    .for_each(BellButton::send_tamper_alarm),
+                Err(e) => error!("IoT: Can't lock connections: {}", e),
+            }
+            info!("IoT: tamper sensor sent alarm(s)");
+        }),
+    )
     .expect("IoT: Couldn't arm the tamper sensor!");
-    Some(dev)
+    dev
 }
+
+#[cfg(test)]
+#[path = "./event_handler_test.rs"]
+mod event_handler_test;
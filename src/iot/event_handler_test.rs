@@ -0,0 +1,69 @@
+use super::*;
+use crate::iot::gpio::{Level, SimulatedGpio};
+use rumqttc::{Client, Connection, MqttOptions, Request};
+
+const TAMPER_SENSOR_PIN: u8 = 5;
+
+/// Creates a bell button on its own pin, whose published requests stay in the connection
+fn button(gpio: &SimulatedGpio, flat: FlatEntry) -> (BellButton, Connection) {
+    let (client, conn) = Client::new(MqttOptions::new("doorcam", "mqtt.local", 1883), 10);
+    (BellButton::with_client(gpio, flat, client).unwrap(), conn)
+}
+
+/// Takes the topics of all messages, which were published until now
+fn published_topics(conn: &Connection) -> Vec<String> {
+    let mut topics = Vec::new();
+    while let Ok(request) = conn.eventloop.requests_rx.try_recv() {
+        if let Request::Publish(publish) = request {
+            topics.push(publish.topic);
+        }
+    }
+    topics
+}
+
+#[test]
+fn send_tamper_alarm() {
+    let gpio = SimulatedGpio::default();
+    let (with_topic, with_topic_conn) = button(
+        &gpio,
+        FlatEntry {
+            tamper_alarm_topic: Some("/door/tamper".to_string()),
+            ..FlatEntry::default()
+        },
+    );
+    let (without_topic, without_topic_conn) = button(
+        &gpio,
+        FlatEntry {
+            id: 2,
+            bell_button_pin: 1,
+            ..FlatEntry::default()
+        },
+    );
+    let buttons = Arc::new(Mutex::new(vec![with_topic, without_topic]));
+    let _sensor = arm_tamper_sensor(&gpio, TAMPER_SENSOR_PIN, Duration::from_millis(42), buttons);
+
+    gpio.set_level(TAMPER_SENSOR_PIN, Level::High);
+    assert_eq!(
+        published_topics(&with_topic_conn),
+        vec!["/door/tamper".to_string()]
+    );
+    assert!(published_topics(&without_topic_conn).is_empty());
+
+    // Bouncing of the sensor doesn't repeat the alarm
+    gpio.set_level(TAMPER_SENSOR_PIN, Level::Low);
+    gpio.set_level(TAMPER_SENSOR_PIN, Level::High);
+    assert!(published_topics(&with_topic_conn).is_empty());
+}
+
+#[test]
+fn tamper_sensor_without_buttons() {
+    let gpio = SimulatedGpio::default();
+    let _sensor = arm_tamper_sensor(
+        &gpio,
+        TAMPER_SENSOR_PIN,
+        Duration::from_millis(42),
+        Arc::new(Mutex::new(Vec::new())),
+    );
+    gpio.set_level(TAMPER_SENSOR_PIN, Level::High);
+    assert!(gpio.input_pulldown(TAMPER_SENSOR_PIN).is_err());
+}
@@ -0,0 +1,79 @@
+//! The abstraction of the GPIO pins, so the IoT components run on a Raspberry Pi and in a simulation.
+
+#[cfg(feature = "iot")]
+pub use super::rppal_gpio::RppalGpio;
+pub use super::simulated_gpio::SimulatedGpio;
+
+/// The callback of an interrupt, which receives the new level of the pin.
+pub type InterruptCallback = Box<dyn FnMut(Level) + Send>;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[cfg(feature = "iot")]
+    #[error(transparent)]
+    Rppal(#[from] rppal::gpio::Error),
+    #[error("The GPIO pin {0} is already in use")]
+    PinInUse(u8),
+}
+
+/// The logic level of a pin.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Level {
+    Low,
+    High,
+}
+
+/// The edges of an input pin, which trigger an interrupt.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Trigger {
+    RisingEdge,
+    Both,
+}
+
+impl Trigger {
+    /// Whether the change to the new level triggers an interrupt
+    pub fn matches(self, level: Level) -> bool {
+        matches!(
+            (self, level),
+            (Self::Both, _) | (Self::RisingEdge, Level::High)
+        )
+    }
+}
+
+/// A GPIO chip, which hands out the pins.
+/// A pin can only be used once at a time, it is released when it's dropped.
+pub trait Gpio: Send + Sync {
+    /// Gets an input pin with an enabled pull-down resistor
+    fn input_pulldown(&self, pin: u8) -> Result<Box<dyn InputPin>, Error>;
+    /// Gets an output pin, which starts low
+    fn output(&self, pin: u8) -> Result<Box<dyn OutputPin>, Error>;
+}
+
+pub trait InputPin: Send {
+    fn is_high(&self) -> bool;
+    /// Calls the callback on every matching edge, which may happen in another thread.
+    /// A previous interrupt is replaced.
+    fn set_async_interrupt(
+        &mut self,
+        trigger: Trigger,
+        callback: InterruptCallback,
+    ) -> Result<(), Error>;
+}
+
+pub trait OutputPin: Send {
+    fn is_set_high(&self) -> bool;
+    fn set_high(&mut self);
+    fn set_low(&mut self);
+}
+
+/// The GPIO chip of the Raspberry Pi
+#[cfg(feature = "iot")]
+pub fn default() -> RppalGpio {
+    RppalGpio::new().expect("Couldn't connect to the GPIO-chip!")
+}
+
+/// The simulated GPIO chip without the feature `iot`
+#[cfg(not(feature = "iot"))]
+pub fn default() -> SimulatedGpio {
+    SimulatedGpio::default()
+}
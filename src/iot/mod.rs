@@ -1,7 +1,13 @@
 //! All logic which is not web-based.
 
 mod door_control;
-pub use door_control::DoorControl;
+pub use door_control::{DoorAlarmCallback, DoorControl, DoorControls, HoldOpen};
+
+mod door_state;
+pub use door_state::{DoorAlarm, DoorState};
+
+mod pulse_pattern;
+pub use pulse_pattern::PulsePattern;
 
 mod bell_button;
 use bell_button::BellButton;
@@ -9,15 +15,18 @@ use bell_button::BellButton;
 mod event_handler;
 pub use event_handler::event_loop;
 
+pub mod gpio;
 #[cfg(feature = "iot")]
-use rppal::gpio::Gpio;
+mod rppal_gpio;
+mod simulated_gpio;
+
+use std::sync::Arc;
 
-#[cfg(feature = "iot")]
 lazy_static! {
-    static ref GPIO: Gpio = Gpio::new().expect("Couldn't connect to the GPIO-chip!");
+    /// The GPIO chip of the Raspberry Pi, or a simulated one without the feature `iot`
+    pub static ref GPIO: Arc<dyn gpio::Gpio> = Arc::new(gpio::default());
 }
 
-#[cfg(feature = "iot")]
 #[macro_export]
 macro_rules! setup_debounce {
     ($debounce_interval:expr) => {
@@ -27,7 +36,6 @@ macro_rules! setup_debounce {
     };
 }
 
-#[cfg(feature = "iot")]
 #[macro_export]
 macro_rules! debounce_callback {
     ($last_action:ident, $debounce_interval:expr) => {
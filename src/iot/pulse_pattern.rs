@@ -0,0 +1,74 @@
+//! The on/off sequence of an opener, since some electric strikes need a pulsed activation.
+
+use super::gpio::Level;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+#[cfg(test)]
+#[path = "./pulse_pattern_test.rs"]
+mod pulse_pattern_test;
+
+/// All errors which could happen during the parsing of a pulse pattern.
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum Error {
+    #[error("'{0}' isn't a duration in milliseconds")]
+    InvalidDuration(String),
+    #[error("The durations of a pulse pattern have to be at least 1 ms")]
+    ZeroDuration,
+    #[error("A pulse pattern needs pairs of on and off durations")]
+    Unpaired,
+}
+
+/// Comma-separated durations in milliseconds, which alternately switch the opener on and off, starting with on.
+/// The pattern is repeated as long as the opener is active, e.g. `500,250` pulses for half a second with a pause of a quarter second.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PulsePattern(Vec<Duration>);
+
+impl PulsePattern {
+    /// The levels of the opener with their durations, which are repeated endlessly
+    pub fn steps(&self) -> impl Iterator<Item = (Level, Duration)> + Clone + Send {
+        [Level::High, Level::Low]
+            .iter()
+            .copied()
+            .cycle()
+            .zip(self.0.clone())
+            .collect::<Vec<_>>()
+            .into_iter()
+            .cycle()
+    }
+}
+
+impl FromStr for PulsePattern {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let durations = s
+            .split(',')
+            .map(|duration| {
+                let duration = duration.trim();
+                match duration.parse::<u64>() {
+                    Ok(0) => Err(Error::ZeroDuration),
+                    Ok(millis) => Ok(Duration::from_millis(millis)),
+                    Err(_) => Err(Error::InvalidDuration(duration.to_string())),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if durations.len() % 2 != 0 {
+            return Err(Error::Unpaired);
+        }
+        Ok(Self(durations))
+    }
+}
+
+impl fmt::Display for PulsePattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let durations: Vec<String> = self
+            .0
+            .iter()
+            .map(|duration| duration.as_millis().to_string())
+            .collect();
+        write!(f, "{}", durations.join(","))
+    }
+}
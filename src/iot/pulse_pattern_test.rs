@@ -0,0 +1,35 @@
+use super::*;
+
+#[test]
+fn parse_pattern() {
+    let pattern: PulsePattern = "500, 250,100,50".parse().unwrap();
+    assert_eq!(pattern.to_string(), "500,250,100,50");
+}
+
+#[test]
+fn repeat_steps() {
+    let pattern: PulsePattern = "500,250".parse().unwrap();
+    let steps: Vec<_> = pattern.steps().take(3).collect();
+    assert_eq!(
+        steps,
+        vec![
+            (Level::High, Duration::from_millis(500)),
+            (Level::Low, Duration::from_millis(250)),
+            (Level::High, Duration::from_millis(500)),
+        ]
+    );
+}
+
+#[test]
+fn invalid_patterns() {
+    assert_eq!(
+        "".parse::<PulsePattern>(),
+        Err(Error::InvalidDuration(String::new()))
+    );
+    assert_eq!(
+        "500,1s".parse::<PulsePattern>(),
+        Err(Error::InvalidDuration("1s".to_string()))
+    );
+    assert_eq!("500,0".parse::<PulsePattern>(), Err(Error::ZeroDuration));
+    assert_eq!("500,250,100".parse::<PulsePattern>(), Err(Error::Unpaired));
+}
@@ -0,0 +1,68 @@
+use super::gpio::{Error, Gpio, InputPin, InterruptCallback, Level, OutputPin, Trigger};
+use rppal::gpio;
+
+/// The GPIO chip of the Raspberry Pi
+pub struct RppalGpio(gpio::Gpio);
+
+impl RppalGpio {
+    pub fn new() -> Result<Self, Error> {
+        Ok(Self(gpio::Gpio::new()?))
+    }
+}
+
+impl Gpio for RppalGpio {
+    fn input_pulldown(&self, pin: u8) -> Result<Box<dyn InputPin>, Error> {
+        Ok(Box::new(self.0.get(pin)?.into_input_pulldown()))
+    }
+
+    fn output(&self, pin: u8) -> Result<Box<dyn OutputPin>, Error> {
+        Ok(Box::new(self.0.get(pin)?.into_output()))
+    }
+}
+
+impl From<gpio::Level> for Level {
+    fn from(level: gpio::Level) -> Self {
+        match level {
+            gpio::Level::Low => Self::Low,
+            gpio::Level::High => Self::High,
+        }
+    }
+}
+
+impl From<Trigger> for gpio::Trigger {
+    fn from(trigger: Trigger) -> Self {
+        match trigger {
+            Trigger::RisingEdge => Self::RisingEdge,
+            Trigger::Both => Self::Both,
+        }
+    }
+}
+
+impl InputPin for gpio::InputPin {
+    fn is_high(&self) -> bool {
+        Self::is_high(self)
+    }
+
+    fn set_async_interrupt(
+        &mut self,
+        trigger: Trigger,
+        mut callback: InterruptCallback,
+    ) -> Result<(), Error> {
+        Self::set_async_interrupt(self, trigger.into(), move |level| callback(level.into()))?;
+        Ok(())
+    }
+}
+
+impl OutputPin for gpio::OutputPin {
+    fn is_set_high(&self) -> bool {
+        Self::is_set_high(self)
+    }
+
+    fn set_high(&mut self) {
+        Self::set_high(self)
+    }
+
+    fn set_low(&mut self) {
+        Self::set_low(self)
+    }
+}
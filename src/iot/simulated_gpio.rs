@@ -0,0 +1,169 @@
+use super::gpio::{Error, Gpio, InputPin, InterruptCallback, Level, OutputPin, Trigger};
+use log::{error, info};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+#[cfg(test)]
+#[path = "./simulated_gpio_test.rs"]
+mod simulated_gpio_test;
+
+type Interrupt = (Trigger, Arc<Mutex<InterruptCallback>>);
+
+#[derive(Default)]
+struct PinState {
+    high: bool,
+    /// Whether the pin is handed out
+    used: bool,
+    interrupt: Option<Interrupt>,
+}
+
+type Pins = Arc<Mutex<HashMap<u8, PinState>>>;
+
+/// Locks the pins, a poisoned lock still holds consistent states
+fn lock(pins: &Pins) -> MutexGuard<HashMap<u8, PinState>> {
+    pins.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// An in-memory GPIO chip, whose inputs are driven with `set_level` and whose outputs are read with `level`.
+/// The clones share the same pins.
+#[derive(Clone, Default)]
+pub struct SimulatedGpio {
+    pins: Pins,
+}
+
+impl SimulatedGpio {
+    fn take(&self, pin: u8) -> Result<(), Error> {
+        let mut pins = lock(&self.pins);
+        let state = pins.entry(pin).or_default();
+        if state.used {
+            return Err(Error::PinInUse(pin));
+        }
+        *state = PinState {
+            used: true,
+            ..Default::default()
+        };
+        Ok(())
+    }
+
+    /// Sets the level of a pin like an external signal.
+    /// A matching interrupt is called in the current thread.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn set_level(&self, pin: u8, level: Level) {
+        let callback = {
+            let mut pins = lock(&self.pins);
+            let state = pins.entry(pin).or_default();
+            let high = level == Level::High;
+            let changed = state.high != high;
+            state.high = high;
+
+            match &state.interrupt {
+                Some((trigger, callback)) if changed && trigger.matches(level) => {
+                    Some(Arc::clone(callback))
+                }
+                _ => None,
+            }
+        };
+
+        // The pins aren't locked, so the callback may use them
+        if let Some(callback) = callback {
+            match callback.lock() {
+                Ok(mut callback) => callback(level),
+                Err(e) => error!("IoT: Can't call the interrupt of pin {}: {}", pin, e),
+            }
+        }
+    }
+
+    /// The current level of a pin
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn level(&self, pin: u8) -> Level {
+        match lock(&self.pins).get(&pin) {
+            Some(state) if state.high => Level::High,
+            _ => Level::Low,
+        }
+    }
+}
+
+impl Gpio for SimulatedGpio {
+    fn input_pulldown(&self, pin: u8) -> Result<Box<dyn InputPin>, Error> {
+        self.take(pin)?;
+        Ok(Box::new(SimulatedPin {
+            pin,
+            pins: Arc::clone(&self.pins),
+        }))
+    }
+
+    fn output(&self, pin: u8) -> Result<Box<dyn OutputPin>, Error> {
+        self.take(pin)?;
+        Ok(Box::new(SimulatedPin {
+            pin,
+            pins: Arc::clone(&self.pins),
+        }))
+    }
+}
+
+/// A pin of the `SimulatedGpio`, which is released when it's dropped
+struct SimulatedPin {
+    pin: u8,
+    pins: Pins,
+}
+
+impl SimulatedPin {
+    fn is_high(&self) -> bool {
+        lock(&self.pins)
+            .get(&self.pin)
+            .map_or(false, |state| state.high)
+    }
+
+    fn set(&mut self, high: bool) {
+        info!(
+            "IoT: Simulated pin {} set {}",
+            self.pin,
+            if high { "high" } else { "low" }
+        );
+        lock(&self.pins).entry(self.pin).or_default().high = high;
+    }
+}
+
+impl InputPin for SimulatedPin {
+    fn is_high(&self) -> bool {
+        Self::is_high(self)
+    }
+
+    fn set_async_interrupt(
+        &mut self,
+        trigger: Trigger,
+        callback: InterruptCallback,
+    ) -> Result<(), Error> {
+        lock(&self.pins).entry(self.pin).or_default().interrupt =
+            Some((trigger, Arc::new(Mutex::new(callback))));
+        Ok(())
+    }
+}
+
+impl OutputPin for SimulatedPin {
+    fn is_set_high(&self) -> bool {
+        self.is_high()
+    }
+
+    fn set_high(&mut self) {
+        self.set(true);
+    }
+
+    fn set_low(&mut self) {
+        self.set(false);
+    }
+}
+
+impl Drop for SimulatedPin {
+    fn drop(&mut self) {
+        let interrupt = match lock(&self.pins).get_mut(&self.pin) {
+            Some(state) => {
+                state.used = false;
+                state.interrupt.take()
+            }
+            None => None,
+        };
+        // The callback may own other pins, which are released without the lock
+        drop(interrupt);
+    }
+}
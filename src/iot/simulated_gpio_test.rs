@@ -0,0 +1,77 @@
+use super::*;
+
+#[test]
+fn pin_in_use() {
+    let gpio = SimulatedGpio::default();
+    let pin = gpio.output(4).unwrap();
+    assert_matches!(gpio.input_pulldown(4).err(), Some(Error::PinInUse(4)));
+
+    drop(pin);
+    assert!(gpio.input_pulldown(4).is_ok());
+}
+
+#[test]
+fn output_level() {
+    let gpio = SimulatedGpio::default();
+    let mut pin = gpio.output(4).unwrap();
+    assert!(!pin.is_set_high());
+    assert_eq!(gpio.level(4), Level::Low);
+
+    pin.set_high();
+    assert!(pin.is_set_high());
+    assert_eq!(gpio.level(4), Level::High);
+}
+
+#[test]
+fn interrupt_on_edge() {
+    let gpio = SimulatedGpio::default();
+    let mut pin = gpio.input_pulldown(17).unwrap();
+    let levels = Arc::new(Mutex::new(Vec::new()));
+    let received = Arc::clone(&levels);
+    pin.set_async_interrupt(
+        Trigger::RisingEdge,
+        Box::new(move |level| received.lock().unwrap().push(level)),
+    )
+    .unwrap();
+
+    gpio.set_level(17, Level::High);
+    // No edge
+    gpio.set_level(17, Level::High);
+    gpio.set_level(17, Level::Low);
+    assert!(!pin.is_high());
+    gpio.set_level(17, Level::High);
+
+    assert_eq!(*levels.lock().unwrap(), vec![Level::High, Level::High]);
+}
+
+#[test]
+fn no_interrupt_after_drop() {
+    let gpio = SimulatedGpio::default();
+    let mut pin = gpio.input_pulldown(17).unwrap();
+    let levels = Arc::new(Mutex::new(Vec::new()));
+    let received = Arc::clone(&levels);
+    pin.set_async_interrupt(
+        Trigger::RisingEdge,
+        Box::new(move |level| received.lock().unwrap().push(level)),
+    )
+    .unwrap();
+    drop(pin);
+
+    gpio.set_level(17, Level::High);
+    assert!(levels.lock().unwrap().is_empty());
+}
+
+#[test]
+fn release_pins_of_interrupt() {
+    let gpio = SimulatedGpio::default();
+    let mut pin = gpio.input_pulldown(17).unwrap();
+    let owned_pin = gpio.input_pulldown(18).unwrap();
+    pin.set_async_interrupt(
+        Trigger::RisingEdge,
+        Box::new(move |_level| assert!(!owned_pin.is_high())),
+    )
+    .unwrap();
+
+    drop(pin);
+    assert!(gpio.input_pulldown(18).is_ok());
+}
@@ -37,6 +37,10 @@ mod iot;
 
 use utils::config::CONFIG;
 
+#[cfg(test)]
+#[path = "./main_test.rs"]
+mod main_test;
+
 fn main() {
     #[cfg(not(debug_assertions))]
     log4rs::init_file("logger.yaml", Default::default()).unwrap();
@@ -51,34 +55,157 @@ fn main() {
             return;
         }
     };
-    iot::event_loop(&flat_sync_event, db);
+    let doors = match db_entry::DoorEntry::get_all(&db) {
+        Ok(doors) => doors,
+        Err(e) => {
+            eprintln!("Can't load the doors: {}", e);
+            log::error!("Can't load the doors: {}", e);
+            return;
+        }
+    };
+    let alarm_db = match rusqlite::Connection::open("db.sqlite") {
+        Ok(conn) => conn,
+        Err(e) => {
+            eprintln!("Can't establish db connection: {}", e);
+            log::error!("Can't establish db connection: {}", e);
+            return;
+        }
+    };
+    let door_alarm = iot::event_loop(Arc::clone(&iot::GPIO), &flat_sync_event, db, alarm_db);
+
+    let door_controls = iot::DoorControls::new(Arc::clone(&iot::GPIO), door_alarm);
+    if let Err(e) = door_controls.sync(doors) {
+        log::error!("IoT: Couldn't create all door controls: {}", e);
+    }
+
+    // Deactivation of expired users
+    match rusqlite::Connection::open("db.sqlite") {
+        Ok(conn) => utils::account_validity::deactivation_job(conn),
+        Err(e) => {
+            eprintln!("Can't establish db connection: {}", e);
+            log::error!("Can't establish db connection: {}", e);
+            return;
+        }
+    };
+
+    // Load the list before the first password is checked
+    lazy_static::initialize(&utils::breached_passwords::BREACHED_PASSWORDS);
 
     // Web
+    rocket(door_controls, flat_sync_event).launch();
+}
+
+/// The web server with all routes, fairings and the managed state
+fn rocket(
+    door_controls: iot::DoorControls,
+    flat_sync_event: Arc<AutoResetEvent>,
+) -> rocket::Rocket {
     rocket::ignite()
         .mount(
             "/",
             routes![
                 requests::index_view::get_user_index_view,
+                requests::index_view::get_impersonated_index_view,
                 requests::index_view::redirect_admin_to_index,
+                requests::door::get_door_state,
                 requests::index_view::get_admin_index_view,
                 requests::index_view::get_not_found,
                 requests::user_auth::get_login,
                 requests::user_auth::post_login_data,
+                requests::user_auth::get_oidc_login,
+                requests::user_auth::get_oidc_callback,
+                requests::user_auth::post_passkey_login,
+                requests::user_auth::post_passkey_login_finish,
                 requests::user_auth::get_logout,
+                requests::two_factor::get_two_factor_login,
+                requests::two_factor::post_two_factor_login,
+                requests::two_factor::get_setup,
+                requests::two_factor::post_setup,
+                requests::two_factor::post_disable,
+                requests::two_factor::admin_reset,
+                requests::two_factor::redirect_to_setup,
+                requests::password::get_change_password,
+                requests::password::post_change_password,
+                requests::password::redirect_to_password_change,
+                requests::password_reset::admin_post_reset,
+                requests::password_reset::get_reset_form,
+                requests::password_reset::get_reset,
+                requests::password_reset::post_reset,
                 requests::user::get_users,
                 requests::user::get_create,
                 requests::user::post_create_data,
                 requests::user::get_change,
                 requests::user::user_post_change_data,
+                requests::user::flat_get_users,
+                requests::user::flat_get_create,
+                requests::user::flat_post_create_data,
+                requests::user::flat_get_change,
+                requests::user::flat_post_change_data,
                 requests::user::admin_post_change_data,
                 requests::user::delete,
+                requests::role::get_roles,
+                requests::role::get_create,
+                requests::role::post_create_data,
+                requests::role::get_change,
+                requests::role::post_change_data,
+                requests::role::delete,
+                requests::role::post_add_member,
+                requests::role::delete_member,
+                requests::door::get_doors,
+                requests::door::get_create,
+                requests::door::post_create_data,
+                requests::door::get_change,
+                requests::door::post_change_data,
+                requests::door::delete,
+                requests::door::post_add_flat,
+                requests::door::delete_flat,
+                requests::door::post_add_user,
+                requests::door::delete_user,
+                requests::user_session::get_sessions,
+                requests::user_session::delete,
+                requests::user_session::admin_get_sessions,
+                requests::user_session::admin_delete,
+                requests::user_session::admin_delete_all,
+                requests::api_token::get_tokens,
+                requests::api_token::post_create,
+                requests::api_token::delete,
+                requests::api_token::admin_get_tokens,
+                requests::api_token::admin_delete,
+                requests::passkey::get_passkeys,
+                requests::passkey::post_register,
+                requests::passkey::post_register_finish,
+                requests::passkey::delete,
+                requests::guest_code::get_guest_codes,
+                requests::guest_code::post_create,
+                requests::guest_code::delete,
+                requests::guest_code::get_guest,
+                requests::guest_code::post_guest,
+                requests::api::post_open_door,
+                requests::api::get_doors,
+                requests::api::get_users,
                 requests::flat::get_flats,
                 requests::flat::get_create,
                 requests::flat::post_create_data,
                 requests::flat::get_change,
                 requests::flat::post_change_data,
                 requests::flat::delete,
-                requests::door::get_open_door,
+                requests::lockout::get_lockouts,
+                requests::lockout::delete,
+                requests::access_schedule::post_access_window,
+                requests::access_schedule::post_access_exception,
+                requests::access_schedule::delete_access_window,
+                requests::access_schedule::delete_access_exception,
+                requests::impersonation::post_start_impersonation,
+                requests::impersonation::post_end_impersonation,
+                requests::audit_log::get_audit_log,
+                requests::audit_log::get_export_csv,
+                requests::door_history::get_flat_door_history,
+                requests::door_history::get_door_history,
+                requests::door_history::get_export_csv,
+                requests::audit_log::get_export_json,
+                requests::door::post_open_door,
+                requests::door::post_hold_open,
+                requests::door::post_cancel_hold_open,
             ],
         )
         .register(catchers![requests::index_view::not_found_catcher])
@@ -86,10 +213,6 @@ fn main() {
         .attach(Template::fairing())
         .attach(db_entry::DbConn::fairing())
         .attach(SpaceHelmet::default())
-        .manage(
-            iot::DoorControl::new(CONFIG.iot.door_opener_pin)
-                .expect("IoT: Couldn't create the Door Opener!"),
-        )
+        .manage(door_controls)
         .manage(flat_sync_event)
-        .launch();
 }
@@ -0,0 +1,17 @@
+use super::*;
+use crate::iot::gpio::SimulatedGpio;
+use rocket::local::Client;
+
+#[test]
+fn launch_with_all_routes() {
+    let door_controls = iot::DoorControls::new(
+        Arc::new(SimulatedGpio::default()),
+        Arc::new(|_door, _alarm| ()),
+    );
+    let flat_sync_event = Arc::new(AutoResetEvent::new(State::Unset));
+
+    // The launch fails on colliding routes
+    if let Err(e) = Client::new(rocket(door_controls, flat_sync_event)) {
+        panic!("The web server can't be launched: {}", e);
+    }
+}
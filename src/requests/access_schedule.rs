@@ -0,0 +1,147 @@
+use super::{user::*, ErrorIntoFlash, ResultFlash};
+use crate::db_entry::{AccessExceptionEntry, AccessWindowEntry, DbConn, Entry, UserEntry};
+use crate::utils::csrf::{CsrfHeader, CsrfToken};
+use crate::utils::guards::{require, PermissionGuard};
+use bool_ext::BoolExt;
+use chrono::{NaiveDate, NaiveTime};
+use rocket::request::Form;
+use rocket::response::{Flash, Redirect};
+
+/// Struct with the form data of a weekly access window.
+#[derive(FromForm)]
+pub struct AccessWindowForm {
+    weekday: u32,
+    /// The time in the format `HH:MM`
+    start_time: String,
+    end_time: String,
+    csrf_token: String,
+}
+
+/// Struct with the form data of an exception date.
+#[derive(FromForm)]
+pub struct AccessExceptionForm {
+    /// The date in the format `YYYY-MM-DD`
+    date: String,
+    allowed: bool,
+    csrf_token: String,
+}
+
+fn parse_time(time: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(time, "%H:%M").map_err(|_| format!("{} isn't a time", time))
+}
+
+/// Fails if the user doesn't exist
+fn check_user(conn: &DbConn, id: u32) -> Result<(), Flash<Redirect>> {
+    UserEntry::get_by_id(conn, id)
+        .map_err(|e| e.into_redirect_flash(uri!(get_users)))?
+        .map(|_| ())
+        .ok_or_else(|| "No user found".into_redirect_flash(uri!(get_users)))
+}
+
+/// Adds a weekly window to the access schedule of an user
+#[post("/admin/user/access_window/<id>", data = "<window_data>")]
+pub fn post_access_window(
+    _admin: PermissionGuard<require::UserManage>,
+    csrf_token: CsrfToken,
+    conn: DbConn,
+    id: u32,
+    window_data: Form<AccessWindowForm>,
+) -> ResultFlash<Redirect> {
+    csrf_token
+        .verify(&window_data.csrf_token)
+        .map_err(|e| e.into_redirect_flash(uri!(get_change: id)))?;
+    check_user(&conn, id)?;
+
+    (window_data.weekday < 7)
+        .err_with(|| "Invalid weekday".into_redirect_flash(uri!(get_change: id)))?;
+    let start_time = parse_time(&window_data.start_time)
+        .map_err(|e| e.into_redirect_flash(uri!(get_change: id)))?;
+    let end_time = parse_time(&window_data.end_time)
+        .map_err(|e| e.into_redirect_flash(uri!(get_change: id)))?;
+    // A window over midnight has to be split into two windows
+    (start_time < end_time).err_with(|| {
+        "The end has to be after the start".into_redirect_flash(uri!(get_change: id))
+    })?;
+
+    AccessWindowEntry {
+        id: (),
+        weekday: window_data.weekday,
+        start_time,
+        end_time,
+        user: id,
+    }
+    .create(&conn)
+    .map_err(|e| e.into_redirect_flash(uri!(get_change: id)))?;
+
+    Ok(Flash::success(
+        Redirect::to(uri!(get_change: id)),
+        "Access window added",
+    ))
+}
+
+/// Adds an exception date to the access schedule of an user
+#[post("/admin/user/access_exception/<id>", data = "<exception_data>")]
+pub fn post_access_exception(
+    _admin: PermissionGuard<require::UserManage>,
+    csrf_token: CsrfToken,
+    conn: DbConn,
+    id: u32,
+    exception_data: Form<AccessExceptionForm>,
+) -> ResultFlash<Redirect> {
+    csrf_token
+        .verify(&exception_data.csrf_token)
+        .map_err(|e| e.into_redirect_flash(uri!(get_change: id)))?;
+    check_user(&conn, id)?;
+
+    let date = NaiveDate::parse_from_str(&exception_data.date, "%Y-%m-%d").map_err(|_| {
+        format!("{} isn't a date", exception_data.date).into_redirect_flash(uri!(get_change: id))
+    })?;
+
+    AccessExceptionEntry {
+        id: (),
+        date,
+        allowed: exception_data.allowed,
+        user: id,
+    }
+    .create(&conn)
+    .map_err(|e| e.into_redirect_flash(uri!(get_change: id)))?;
+
+    Ok(Flash::success(
+        Redirect::to(uri!(get_change: id)),
+        "Exception date added",
+    ))
+}
+
+/// Removes a weekly window of an access schedule
+#[delete("/admin/user/access_window/delete/<id>")]
+pub fn delete_access_window(
+    _admin: PermissionGuard<require::UserManage>,
+    _csrf: CsrfHeader,
+    conn: DbConn,
+    id: u32,
+) -> ResultFlash<()> {
+    AccessWindowEntry::get_by_id(&conn, id)
+        .map_err(|e| e.into_flash())?
+        .ok_or_else(|| "No access window found".into_flash())?
+        .delete(&conn)
+        .map_err(|e| e.into_flash())?;
+
+    Ok(Flash::success((), "Access window removed"))
+}
+
+/// Removes an exception date of an access schedule
+#[delete("/admin/user/access_exception/delete/<id>")]
+pub fn delete_access_exception(
+    _admin: PermissionGuard<require::UserManage>,
+    _csrf: CsrfHeader,
+    conn: DbConn,
+    id: u32,
+) -> ResultFlash<()> {
+    AccessExceptionEntry::get_by_id(&conn, id)
+        .map_err(|e| e.into_flash())?
+        .ok_or_else(|| "No exception date found".into_flash())?
+        .delete(&conn)
+        .map_err(|e| e.into_flash())?;
+
+    Ok(Flash::success((), "Exception date removed"))
+}
@@ -0,0 +1,122 @@
+use crate::db_entry::{
+    ApiScope, AuditAction, DbConn, DoorEntry, DoorEventSource, UserEntry, UserType,
+};
+use crate::iot::DoorControls;
+use crate::utils::guards::{ApiGuard, ClientInfo};
+use crate::utils::{access_schedule, audit, door_opening};
+use chrono::offset::Utc;
+use rocket::http::Status;
+use rocket::State;
+use rocket_contrib::json::Json;
+use serde::Serialize;
+
+/// The state of a door, which is returned by the API.
+#[derive(Serialize)]
+pub struct ApiDoor {
+    id: u32,
+    name: String,
+    opener_active: bool,
+}
+
+/// An user without any credentials, which is returned by the API.
+#[derive(Serialize)]
+pub struct ApiUser {
+    id: u32,
+    name: String,
+    user_type: UserType,
+    active: bool,
+    flat: Option<String>,
+}
+
+impl From<UserEntry> for ApiUser {
+    fn from(user: UserEntry) -> Self {
+        Self {
+            id: user.id,
+            name: user.name,
+            user_type: user.user_type,
+            active: user.active,
+            flat: user.flat.map(|flat| flat.name),
+        }
+    }
+}
+
+/// Post to activate the opener of a door, needs the `door:open` scope
+#[post("/api/v1/door/<id>/activate")]
+pub fn post_open_door(
+    api_guard: ApiGuard,
+    conn: DbConn,
+    client: ClientInfo,
+    door_controls: State<DoorControls>,
+    id: u32,
+) -> Result<Json<ApiDoor>, Status> {
+    api_guard.require(ApiScope::DoorOpen)?;
+    let (door, _) = door_opening::open(
+        &conn,
+        &door_controls,
+        DoorEventSource::Api,
+        &api_guard.user,
+        id,
+        Utc::now(),
+    )
+    .map_err(|e| match e {
+        door_opening::Error::NotPermitted => Status::NotFound,
+        door_opening::Error::Schedule(access_schedule::Error::OutsideSchedule) => Status::Forbidden,
+        door_opening::Error::Unavailable => Status::ServiceUnavailable,
+        _ => Status::InternalServerError,
+    })?;
+    // The target is the API token, which has been used
+    audit::record(
+        &conn,
+        Some(&api_guard.user.name),
+        AuditAction::DoorOpen,
+        Some(&format!("{} ({})", api_guard.token.name, door.name)),
+        client.ip,
+    );
+
+    Ok(Json(ApiDoor {
+        id,
+        name: door.name,
+        opener_active: true,
+    }))
+}
+
+/// Get the states of the doors, which the user of the token may open, needs the `events:read` scope
+#[get("/api/v1/door")]
+pub fn get_doors(
+    api_guard: ApiGuard,
+    conn: DbConn,
+    door_controls: State<DoorControls>,
+) -> Result<Json<Vec<ApiDoor>>, Status> {
+    api_guard.require(ApiScope::EventsRead)?;
+
+    let doors = DoorEntry::get_permitted(&conn, &api_guard.user)
+        .map_err(|_| Status::InternalServerError)?;
+
+    doors
+        .into_iter()
+        .map(|door| {
+            let opener_active = match door_controls.get(door.id) {
+                Some(control) => control
+                    .is_opener_active()
+                    .map_err(|_| Status::InternalServerError)?,
+                None => false,
+            };
+            Ok(ApiDoor {
+                id: door.id,
+                name: door.name,
+                opener_active,
+            })
+        })
+        .collect::<Result<_, _>>()
+        .map(Json)
+}
+
+/// Get all users, needs the `admin` scope
+#[get("/api/v1/admin/user")]
+pub fn get_users(api_guard: ApiGuard, conn: DbConn) -> Result<Json<Vec<ApiUser>>, Status> {
+    api_guard.require(ApiScope::Admin)?;
+
+    let users = UserEntry::get_all(&conn).map_err(|_| Status::InternalServerError)?;
+
+    Ok(Json(users.into_iter().map(ApiUser::from).collect()))
+}
@@ -0,0 +1,153 @@
+use super::{ErrorIntoFlash, ResultFlash};
+use crate::db_entry::{ApiScope, ApiTokenEntry, DbConn, Entry};
+use crate::template_contexts::{ApiTokenOverviewContext, Message};
+use crate::utils::csrf::{CsrfHeader, CsrfToken};
+use crate::utils::guards::{require, ApiGuard, PermissionGuard, UserGuard};
+use bool_ext::BoolExt;
+use chrono::offset::Utc;
+use rocket::request::{FlashMessage, Form};
+use rocket::response::{Flash, Redirect};
+use rocket_contrib::templates::Template;
+use std::ops::Not;
+
+/// Struct with all form data to create an API token.
+#[derive(FromForm)]
+pub struct ApiTokenForm {
+    name: String,
+    door_open: bool,
+    events_read: bool,
+    admin: bool,
+    csrf_token: String,
+}
+
+impl ApiTokenForm {
+    fn scopes(&self) -> Vec<ApiScope> {
+        vec![
+            (self.door_open, ApiScope::DoorOpen),
+            (self.events_read, ApiScope::EventsRead),
+            (self.admin, ApiScope::Admin),
+        ]
+        .into_iter()
+        .filter(|(selected, _)| *selected)
+        .map(|(_, scope)| scope)
+        .collect()
+    }
+}
+
+/// Shows the API tokens of the user and the form to create one
+#[get("/user/api_token")]
+pub fn get_tokens(
+    user_guard: UserGuard,
+    csrf_token: CsrfToken,
+    flash: Option<FlashMessage>,
+    conn: DbConn,
+) -> Template {
+    let context = match ApiTokenEntry::get_by_user(&conn, user_guard.user.id) {
+        Ok(tokens) => ApiTokenOverviewContext::view(
+            flash.map(Message::from),
+            &user_guard.user,
+            &user_guard.permissions,
+            tokens,
+            None,
+        ),
+        Err(e) => ApiTokenOverviewContext::error(
+            Message::error(format!("DB Error: {}", e)),
+            user_guard.is_admin(),
+        ),
+    };
+    Template::render("api_token_overview", &csrf_token.context(context))
+}
+
+/// Post the form to create an API token, which is shown once
+#[post("/user/api_token", data = "<token_data>")]
+pub fn post_create(
+    user_guard: UserGuard,
+    csrf_token: CsrfToken,
+    conn: DbConn,
+    token_data: Form<ApiTokenForm>,
+) -> Result<Template, Flash<Redirect>> {
+    csrf_token
+        .verify(&token_data.csrf_token)
+        .map_err(|e| e.into_redirect_flash(uri!(get_tokens)))?;
+
+    token_data
+        .name
+        .is_empty()
+        .not()
+        .err_with(|| "Name is empty".into_redirect_flash(uri!(get_tokens)))?;
+
+    let scopes = token_data.scopes();
+    (!scopes.is_empty()
+        && scopes
+            .iter()
+            .all(|scope| scope.is_allowed_for(&user_guard.permissions)))
+    .err_with(|| "The selected scopes are invalid".into_redirect_flash(uri!(get_tokens)))?;
+
+    let (token, token_hash) = ApiGuard::generate_token();
+
+    ApiTokenEntry {
+        id: (),
+        name: token_data.into_inner().name,
+        token_hash,
+        scopes,
+        creation_datetime: Utc::now(),
+        last_used_datetime: None,
+        user: user_guard.user.id,
+    }
+    .create(&conn)
+    .and_then(|_| ApiTokenEntry::get_by_user(&conn, user_guard.user.id))
+    .map(|tokens| {
+        let context = ApiTokenOverviewContext::view(
+            None,
+            &user_guard.user,
+            &user_guard.permissions,
+            tokens,
+            Some(token),
+        );
+        Template::render("api_token_overview", &csrf_token.context(context))
+    })
+    .map_err(|e| e.into_redirect_flash(uri!(get_tokens)))
+}
+
+/// Revokes an own API token
+#[delete("/user/api_token/delete/<id>")]
+pub fn delete(user_guard: UserGuard, _csrf: CsrfHeader, conn: DbConn, id: u32) -> ResultFlash<()> {
+    let token = ApiTokenEntry::get_by_id(&conn, id)
+        .map_err(|e| e.into_flash())?
+        .ok_or_else(|| "No API token found".into_flash())?;
+
+    // An ordinary user is only allowed to revoke his own tokens
+    (token.user == user_guard.user.id).err_with(|| "No API token found".into_flash())?;
+
+    token.delete(&conn).map_err(|e| e.into_flash())?;
+
+    Ok(Flash::success((), "API token revoked"))
+}
+
+/// Shows the API tokens of all users
+#[get("/admin/api_token")]
+pub fn admin_get_tokens(
+    _admin: PermissionGuard<require::UserManage>,
+    csrf_token: CsrfToken,
+    flash: Option<FlashMessage>,
+    conn: DbConn,
+) -> Template {
+    let context = match ApiTokenEntry::get_all_with_user_name(&conn) {
+        Ok(tokens) => ApiTokenOverviewContext::admin_view(flash.map(Message::from), tokens),
+        Err(e) => ApiTokenOverviewContext::error(Message::error(format!("DB Error: {}", e)), true),
+    };
+    Template::render("api_token_overview", &csrf_token.context(context))
+}
+
+/// Revokes an API token of any user
+#[delete("/admin/api_token/delete/<id>")]
+pub fn admin_delete(
+    _admin: PermissionGuard<require::UserManage>,
+    _csrf: CsrfHeader,
+    conn: DbConn,
+    id: u32,
+) -> ResultFlash<()> {
+    ApiTokenEntry::<_>::delete_entry(&conn, id).map_err(|e| e.into_flash())?;
+
+    Ok(Flash::success((), "API token revoked"))
+}
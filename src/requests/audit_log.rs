@@ -0,0 +1,107 @@
+use super::{parse_filter_day, ErrorIntoFlash, FileExport};
+use crate::db_entry::{AuditAction, AuditLogEntry, DbConn};
+use crate::template_contexts::{AuditLogContext, Message};
+use crate::utils::audit;
+use crate::utils::guards::{require, PermissionGuard};
+use chrono::Duration;
+use rocket::http::uri::Origin;
+use rocket::http::ContentType;
+use rocket::request::{FlashMessage, Form};
+use rocket::response::{Flash, Redirect};
+use rocket_contrib::templates::Template;
+
+/// The maximal number of entries, which are shown in the view. The export contains all matching entries.
+pub const AUDIT_LOG_VIEW_LIMIT: u32 = 500;
+
+/// The filters of the audit log, which are passed as query parameters.
+#[derive(FromForm, Default)]
+pub struct AuditLogFilter {
+    pub action: Option<AuditAction>,
+    pub actor: Option<String>,
+    /// The first day in the format `YYYY-MM-DD`
+    pub from: Option<String>,
+    /// The last day in the format `YYYY-MM-DD`
+    pub until: Option<String>,
+}
+
+impl AuditLogFilter {
+    fn actor(&self) -> Option<&str> {
+        self.actor.as_deref().filter(|actor| !actor.is_empty())
+    }
+
+    /// Loads the newest matching entries
+    fn load(&self, conn: &DbConn, limit: u32) -> Result<Vec<AuditLogEntry>, String> {
+        let from = parse_filter_day(&self.from)?;
+        // The last day is included
+        let until = parse_filter_day(&self.until)?.map(|until| until + Duration::days(1));
+
+        AuditLogEntry::get_filtered(conn, self.action, self.actor(), from, until, limit)
+            .map_err(|e| format!("DB Error: {}", e))
+    }
+}
+
+impl From<Option<Form<Self>>> for AuditLogFilter {
+    /// Without any query parameters there are no filters
+    fn from(filter: Option<Form<Self>>) -> Self {
+        filter.map(Form::into_inner).unwrap_or_default()
+    }
+}
+
+/// Shows the newest entries of the audit log, which match the filters
+#[get("/admin/audit_log?<filter..>")]
+pub fn get_audit_log(
+    _admin: PermissionGuard<require::UserManage>,
+    flash: Option<FlashMessage>,
+    conn: DbConn,
+    origin: &Origin,
+    filter: Option<Form<AuditLogFilter>>,
+) -> Template {
+    let filter = AuditLogFilter::from(filter);
+    let context = match filter.load(&conn, AUDIT_LOG_VIEW_LIMIT) {
+        Ok(entries) => {
+            AuditLogContext::view(flash.map(Message::from), &filter, origin.query(), entries)
+        }
+        Err(e) => AuditLogContext::error(Message::error(e), &filter, origin.query()),
+    };
+    Template::render("audit_log", &context)
+}
+
+/// Exports all entries of the audit log, which match the filters, as CSV
+#[get("/admin/audit_log/export/csv?<filter..>")]
+pub fn get_export_csv(
+    _admin: PermissionGuard<require::UserManage>,
+    conn: DbConn,
+    filter: Option<Form<AuditLogFilter>>,
+) -> Result<FileExport, Flash<Redirect>> {
+    let entries = AuditLogFilter::from(filter)
+        .load(&conn, u32::MAX)
+        .map_err(|e| e.into_redirect_flash(uri!(get_audit_log: _)))?;
+
+    Ok(FileExport::new(
+        "audit_log",
+        audit::to_csv(&entries),
+        ContentType::CSV,
+        "csv",
+    ))
+}
+
+/// Exports all entries of the audit log, which match the filters, as JSON
+#[get("/admin/audit_log/export/json?<filter..>")]
+pub fn get_export_json(
+    _admin: PermissionGuard<require::UserManage>,
+    conn: DbConn,
+    filter: Option<Form<AuditLogFilter>>,
+) -> Result<FileExport, Flash<Redirect>> {
+    let entries = AuditLogFilter::from(filter)
+        .load(&conn, u32::MAX)
+        .map_err(|e| e.into_redirect_flash(uri!(get_audit_log: _)))?;
+    let content = serde_json::to_string(&entries)
+        .map_err(|e| e.into_redirect_flash(uri!(get_audit_log: _)))?;
+
+    Ok(FileExport::new(
+        "audit_log",
+        content,
+        ContentType::JSON,
+        "json",
+    ))
+}
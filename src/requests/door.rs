@@ -1,20 +1,633 @@
-use super::{index_view::*, ErrorIntoFlash, ResultFlash};
-use crate::utils::guards::OnlyUserGuard;
+use super::user::LocalDateTime;
+use super::{index_view::*, ErrorIntoFlash, FormIntoEntry, ResultFlash};
+use crate::db_entry::{
+    rusqlite, AuditAction, DbConn, DoorEntry, DoorEventSource, Entry, FlatEntry, UserEntry,
+};
+use crate::iot::{DoorControls, HoldOpen, PulsePattern};
+use crate::template_contexts::{DoorContext, DoorDetailsContext, DoorOverviewContext, Message};
+use crate::utils::config::CONFIG;
+use crate::utils::csrf::{CsrfForm, CsrfHeader, CsrfToken};
+use crate::utils::guards::{require, ClientInfo, PermissionGuard, UserGuard};
+use crate::utils::{audit, door_opening};
+use bool_ext::BoolExt;
+use chrono::{offset::Utc, DateTime};
+use rocket::http::Status;
+use rocket::request::{FlashMessage, Form};
 use rocket::response::{Flash, Redirect};
 use rocket::State;
+use rocket_contrib::json::Json;
+use rocket_contrib::templates::Template;
+use std::collections::HashMap;
+use std::ops::Not;
 
-/// Get to activate the door opener
-#[get("/api/door/activate")]
-pub fn get_open_door(
-    _user: OnlyUserGuard,
-    door_ctrl: State<crate::iot::DoorControl>,
+/// The highest GPIO pin of the Raspberry Pi
+const MAX_GPIO_PIN: u8 = 27;
+
+/// Struct which retrieves all form data from the door details.
+#[derive(FromForm)]
+pub struct DoorForm {
+    name: String,
+    opener_pin: u8,
+    /// The seconds how long the door opener is activated
+    opening_time: u32,
+    /// Is empty without a door sensor
+    sensor_pin: Option<u8>,
+    /// Is empty if the opener isn't pulsed
+    pulse_pattern: Option<String>,
+    csrf_token: String,
+}
+
+impl DoorForm {
+    /// The pulse pattern, an empty field is no pattern
+    fn pulse_pattern(&self) -> Option<String> {
+        self.pulse_pattern
+            .as_ref()
+            .map(|pattern| pattern.trim().to_string())
+            .filter(|pattern| !pattern.is_empty())
+    }
+
+    /// Returns the first invalid field
+    fn validate(&self) -> Result<(), &'static str> {
+        self.name.is_empty().not().err("Name is empty")?;
+        (self.opener_pin <= MAX_GPIO_PIN).err("The opener pin has to be between 0 and 27")?;
+        self.sensor_pin
+            .map_or(true, |pin| pin <= MAX_GPIO_PIN)
+            .err("The sensor pin has to be between 0 and 27")?;
+        (self.sensor_pin != Some(self.opener_pin))
+            .err("The sensor and the opener need different pins")?;
+        (self.opening_time > 0).err("The opening time has to be at least one second")?;
+        self.pulse_pattern()
+            .map_or(true, |pattern| pattern.parse::<PulsePattern>().is_ok())
+            .err("The pulse pattern needs pairs of on and off durations in milliseconds")
+    }
+}
+
+impl FormIntoEntry<DoorEntry<()>, DoorEntry> for DoorForm {
+    fn into_insertable(self) -> DoorEntry<()> {
+        let pulse_pattern = self.pulse_pattern();
+        DoorEntry {
+            id: (),
+            name: self.name,
+            opener_pin: self.opener_pin,
+            opening_time: self.opening_time,
+            sensor_pin: self.sensor_pin,
+            pulse_pattern,
+        }
+    }
+
+    fn into_entry(self, id: u32) -> DoorEntry {
+        let pulse_pattern = self.pulse_pattern();
+        DoorEntry {
+            id,
+            name: self.name,
+            opener_pin: self.opener_pin,
+            opening_time: self.opening_time,
+            sensor_pin: self.sensor_pin,
+            pulse_pattern,
+        }
+    }
+}
+
+/// Struct with the form data to grant a door to a flat.
+#[derive(FromForm)]
+pub struct DoorFlatForm {
+    flat_id: u32,
+    csrf_token: String,
+}
+
+/// Struct with the form data to grant a door to an user.
+#[derive(FromForm)]
+pub struct DoorUserForm {
+    user_id: u32,
+    csrf_token: String,
+}
+
+/// Recreates the controls of the doors after a door has been changed
+fn sync_door_controls(conn: &DbConn, door_controls: &DoorControls) -> Result<(), String> {
+    let doors = DoorEntry::get_all(conn).map_err(|e| e.to_string())?;
+    door_controls
+        .sync(doors)
+        .map_err(|e| format!("The door control can't be created: {}", e))
+}
+
+/// The live state of the door, or why it's unknown
+fn door_state(door_controls: &DoorControls, id: u32) -> String {
+    match door_controls.get(id) {
+        Some(control) => match control.door_state() {
+            Ok(state) => state.to_string(),
+            Err(_) => "Unknown".to_string(),
+        },
+        None => "Unavailable".to_string(),
+    }
+}
+
+/// The hold-open mode of the door, if it's held open
+fn door_hold(door_controls: &DoorControls, id: u32) -> Option<HoldOpen> {
+    door_controls
+        .get(id)
+        .and_then(|control| control.hold().ok().flatten())
+}
+
+/// The doors of the user for the index views.
+/// The doors can only be opened with the permission `door.open` and held open with `door.hold_open`.
+pub fn door_contexts<FRef: Entry>(
+    conn: &DbConn,
+    door_controls: &DoorControls,
+    user: &UserEntry<u32, FRef>,
+    may_open: bool,
+    may_hold: bool,
+) -> Result<Vec<DoorContext>, rusqlite::Error> {
+    Ok(DoorEntry::get_permitted(conn, user)?
+        .into_iter()
+        .map(|door| {
+            let hold = door_hold(door_controls, door.id);
+            DoorContext {
+                id: door.id,
+                state: door_state(door_controls, door.id),
+                may_open,
+                activate_url: may_open.then(|| uri!(post_open_door: door.id).to_string()),
+                held_open: hold.is_some(),
+                held_until: match hold {
+                    Some(HoldOpen::Until(until)) => Some(CONFIG.building.local_datetime(until)),
+                    _ => None,
+                },
+                hold_url: may_hold.then(|| uri!(post_hold_open: door.id).to_string()),
+                cancel_hold_url: (may_hold && hold.is_some())
+                    .then(|| uri!(post_cancel_hold_open: door.id).to_string()),
+                name: door.name,
+            }
+        })
+        .collect())
+}
+
+/// Post to activate the opener of a door
+#[post("/api/door/<id>/activate", data = "<csrf_data>")]
+pub fn post_open_door(
+    user: PermissionGuard<require::DoorOpen>,
+    csrf_token: CsrfToken,
+    conn: DbConn,
+    client: ClientInfo,
+    door_controls: State<DoorControls>,
+    id: u32,
+    csrf_data: Form<CsrfForm>,
 ) -> ResultFlash<Redirect> {
-    door_ctrl
-        .activate_opener()
+    csrf_token
+        .verify(&csrf_data.csrf_token)
         .map_err(|e| e.into_redirect_flash(uri!(get_user_index_view)))?;
 
+    let (door, _) = door_opening::open(
+        &conn,
+        &door_controls,
+        DoorEventSource::Web,
+        &user.user,
+        id,
+        Utc::now(),
+    )
+    .map_err(|e| e.into_redirect_flash(uri!(get_user_index_view)))?;
+    audit::record(
+        &conn,
+        Some(&user.user.name),
+        AuditAction::DoorOpen,
+        Some(&door.name),
+        client.ip,
+    );
+
     Ok(Flash::success(
         Redirect::to(uri!(get_user_index_view)),
-        "Door opened",
+        format!("{} opened", door.name),
     ))
 }
+
+/// Struct with the form data to hold a door open.
+#[derive(FromForm)]
+pub struct HoldOpenForm {
+    /// Is empty to hold the door open until cancelled
+    until: Option<LocalDateTime>,
+    csrf_token: String,
+}
+
+impl HoldOpenForm {
+    /// The hold-open mode, whose end has to be in the future
+    fn hold(&self, now: DateTime<Utc>) -> Result<HoldOpen, &'static str> {
+        match &self.until {
+            Some(until) => (until.0 > now)
+                .err("The end of the hold-open mode has to be in the future")
+                .map(|_| HoldOpen::Until(until.0)),
+            None => Ok(HoldOpen::UntilCancelled),
+        }
+    }
+}
+
+/// Post to keep a door unlocked until a time or until cancelled
+#[post("/api/door/<id>/hold", data = "<hold_data>")]
+pub fn post_hold_open(
+    user: PermissionGuard<require::DoorHoldOpen>,
+    csrf_token: CsrfToken,
+    conn: DbConn,
+    client: ClientInfo,
+    door_controls: State<DoorControls>,
+    id: u32,
+    hold_data: Form<HoldOpenForm>,
+) -> ResultFlash<Redirect> {
+    csrf_token
+        .verify(&hold_data.csrf_token)
+        .map_err(|e| e.into_redirect_flash(uri!(get_user_index_view)))?;
+    let now = Utc::now();
+    let hold = hold_data
+        .hold(now)
+        .map_err(|e| e.into_redirect_flash(uri!(get_user_index_view)))?;
+
+    let (door, hold) = door_opening::hold_open(
+        &conn,
+        &door_controls,
+        DoorEventSource::Web,
+        &user.user,
+        id,
+        hold,
+        now,
+    )
+    .map_err(|e| e.into_redirect_flash(uri!(get_user_index_view)))?;
+
+    let until = match hold {
+        HoldOpen::Until(until) => CONFIG
+            .building
+            .local_datetime(until)
+            .format("%Y-%m-%d %H:%M")
+            .to_string(),
+        HoldOpen::UntilCancelled => "cancelled".to_string(),
+    };
+    audit::record(
+        &conn,
+        Some(&user.user.name),
+        AuditAction::DoorHoldOpen,
+        Some(&format!("{} until {}", door.name, until)),
+        client.ip,
+    );
+
+    Ok(Flash::success(
+        Redirect::to(uri!(get_user_index_view)),
+        format!("{} is held open until {}", door.name, until),
+    ))
+}
+
+/// Post to end the hold-open mode of a door, which locks the door again
+#[post("/api/door/<id>/hold/cancel", data = "<csrf_data>")]
+pub fn post_cancel_hold_open(
+    user: PermissionGuard<require::DoorHoldOpen>,
+    csrf_token: CsrfToken,
+    conn: DbConn,
+    client: ClientInfo,
+    door_controls: State<DoorControls>,
+    id: u32,
+    csrf_data: Form<CsrfForm>,
+) -> ResultFlash<Redirect> {
+    csrf_token
+        .verify(&csrf_data.csrf_token)
+        .map_err(|e| e.into_redirect_flash(uri!(get_user_index_view)))?;
+
+    let door = DoorEntry::get_permitted(&conn, &user.user)
+        .map_err(|e| e.into_redirect_flash(uri!(get_user_index_view)))?
+        .into_iter()
+        .find(|door| door.id == id)
+        .ok_or_else(|| {
+            "You may not open this door".into_redirect_flash(uri!(get_user_index_view))
+        })?;
+    let cancelled = door_controls
+        .get(id)
+        .ok_or_else(|| "The door isn't available".into_redirect_flash(uri!(get_user_index_view)))?
+        .cancel_hold_open()
+        .map_err(|_| {
+            "The door opener can't be deactivated".into_redirect_flash(uri!(get_user_index_view))
+        })?;
+    cancelled
+        .err("The door isn't held open")
+        .map_err(|e| e.into_redirect_flash(uri!(get_user_index_view)))?;
+
+    audit::record(
+        &conn,
+        Some(&user.user.name),
+        AuditAction::DoorHoldCancel,
+        Some(&door.name),
+        client.ip,
+    );
+
+    Ok(Flash::success(
+        Redirect::to(uri!(get_user_index_view)),
+        format!("{} is locked again", door.name),
+    ))
+}
+
+/// Get the current states of the doors of the user, which the index views poll
+#[get("/door/state")]
+pub fn get_door_state(
+    user: UserGuard,
+    conn: DbConn,
+    door_controls: State<DoorControls>,
+) -> Result<Json<HashMap<u32, String>>, Status> {
+    let doors =
+        DoorEntry::get_permitted(&conn, &user.user).map_err(|_| Status::InternalServerError)?;
+
+    Ok(Json(
+        doors
+            .into_iter()
+            .map(|door| (door.id, door_state(&door_controls, door.id)))
+            .collect(),
+    ))
+}
+
+/// get all doors
+#[get("/admin/door")]
+pub fn get_doors(
+    _admin: PermissionGuard<require::FlatManage>,
+    csrf_token: CsrfToken,
+    flash: Option<FlashMessage>,
+    conn: DbConn,
+) -> Template {
+    let context = match DoorEntry::get_all(&conn) {
+        Ok(doors) => DoorOverviewContext::view(doors, flash.map(Message::from)),
+        Err(e) => DoorOverviewContext::error(Message::error(format!("DB Error: {}", e))),
+    };
+    Template::render("door_overview", &csrf_token.context(context))
+}
+
+/// get form to create a door
+#[get("/admin/door/create")]
+pub fn get_create(
+    _admin: PermissionGuard<require::FlatManage>,
+    csrf_token: CsrfToken,
+    flash: Option<FlashMessage>,
+) -> Template {
+    let context = DoorDetailsContext::create(flash.map(Message::from));
+    Template::render("door_details", &csrf_token.context(context))
+}
+
+/// post door-data to create a door
+#[post("/admin/door/create", data = "<door_data>")]
+pub fn post_create_data(
+    door_data: Form<DoorForm>,
+    admin: PermissionGuard<require::FlatManage>,
+    csrf_token: CsrfToken,
+    conn: DbConn,
+    client: ClientInfo,
+    door_controls: State<DoorControls>,
+) -> Result<Redirect, Flash<Redirect>> {
+    csrf_token
+        .verify(&door_data.csrf_token)
+        .map_err(|e| e.into_redirect_flash(uri!(get_create)))?;
+    door_data
+        .validate()
+        .map_err(|e| e.into_redirect_flash(uri!(get_create)))?;
+
+    let door = door_data
+        .into_inner()
+        .into_insertable()
+        .create(&conn)
+        .map_err(|e| e.into_redirect_flash(uri!(get_create)))?;
+    audit::record(
+        &conn,
+        Some(&admin.user.name),
+        AuditAction::DoorCreate,
+        Some(&door.name),
+        client.ip,
+    );
+
+    sync_door_controls(&conn, &door_controls)
+        .map_err(|e| e.into_redirect_flash(uri!(get_change: door.get_id())))?;
+
+    Ok(Redirect::to(uri!(get_change: door.get_id())))
+}
+
+/// delete a door by id, its flats and users lose the access
+#[delete("/admin/door/delete/<id>")]
+pub fn delete(
+    admin: PermissionGuard<require::FlatManage>,
+    _csrf: CsrfHeader,
+    conn: DbConn,
+    client: ClientInfo,
+    door_controls: State<DoorControls>,
+    id: u32,
+) -> ResultFlash<()> {
+    let door = DoorEntry::get_by_id(&conn, id)
+        .map_err(|e| e.into_flash())?
+        .ok_or_else(|| "No door found".into_flash())?;
+
+    door.delete(&conn).map_err(|e| e.into_flash())?;
+    audit::record(
+        &conn,
+        Some(&admin.user.name),
+        AuditAction::DoorDelete,
+        Some(&door.name),
+        client.ip,
+    );
+
+    sync_door_controls(&conn, &door_controls).map_err(|e| e.into_flash())?;
+
+    Ok(Flash::success((), "Door deleted"))
+}
+
+/// get the form for modifying a door and its flats and users
+#[get("/admin/door/change/<id>")]
+pub fn get_change(
+    _admin: PermissionGuard<require::FlatManage>,
+    csrf_token: CsrfToken,
+    conn: DbConn,
+    flash: Option<FlashMessage>,
+    id: u32,
+) -> Result<Template, Status> {
+    let door = DoorEntry::get_by_id(&conn, id)
+        .map_err(|_| Status::InternalServerError)?
+        .ok_or(Status::NotFound)?;
+
+    let grants = door
+        .get_flats(&conn)
+        .and_then(|flat_ids| Ok((flat_ids, FlatEntry::get_all(&conn)?)))
+        .and_then(|flats| Ok((flats, door.get_users(&conn)?, UserEntry::get_all(&conn)?)));
+    let context = match grants {
+        Ok(((flat_ids, flats), user_ids, users)) => {
+            let (granted_flats, flats) = flats
+                .into_iter()
+                .partition(|flat| flat_ids.contains(&flat.get_id()));
+            let (granted_users, users) = users
+                .into_iter()
+                .partition(|user| user_ids.contains(&user.get_id()));
+            DoorDetailsContext::change(
+                flash.map(Message::from),
+                door,
+                (granted_flats, flats),
+                (granted_users, users),
+            )
+        }
+        Err(e) => DoorDetailsContext::error(Message::error(e.to_string())),
+    };
+    Ok(Template::render(
+        "door_details",
+        &csrf_token.context(context),
+    ))
+}
+
+/// post the form-data to modify the door
+#[post("/admin/door/change/<id>", data = "<door_data>")]
+pub fn post_change_data(
+    admin: PermissionGuard<require::FlatManage>,
+    conn: DbConn,
+    csrf_token: CsrfToken,
+    client: ClientInfo,
+    door_controls: State<DoorControls>,
+    id: u32,
+    door_data: Form<DoorForm>,
+) -> Result<Redirect, Flash<Redirect>> {
+    csrf_token
+        .verify(&door_data.csrf_token)
+        .map_err(|e| e.into_redirect_flash(uri!(get_change: id)))?;
+    door_data
+        .validate()
+        .map_err(|e| e.into_redirect_flash(uri!(get_change: id)))?;
+
+    let door = door_data.into_inner().into_entry(id);
+    door.update(&conn)
+        .map_err(|e| e.into_redirect_flash(uri!(get_change: id)))?;
+    audit::record(
+        &conn,
+        Some(&admin.user.name),
+        AuditAction::DoorChange,
+        Some(&door.name),
+        client.ip,
+    );
+
+    sync_door_controls(&conn, &door_controls)
+        .map_err(|e| e.into_redirect_flash(uri!(get_change: id)))?;
+
+    Ok(Redirect::to(uri!(get_doors)))
+}
+
+/// post the form-data to grant the door to a flat
+#[post("/admin/door/flat/<id>", data = "<flat_data>")]
+pub fn post_add_flat(
+    admin: PermissionGuard<require::FlatManage>,
+    conn: DbConn,
+    csrf_token: CsrfToken,
+    client: ClientInfo,
+    id: u32,
+    flat_data: Form<DoorFlatForm>,
+) -> Result<Redirect, Flash<Redirect>> {
+    csrf_token
+        .verify(&flat_data.csrf_token)
+        .map_err(|e| e.into_redirect_flash(uri!(get_change: id)))?;
+
+    let door = DoorEntry::get_by_id(&conn, id)
+        .map_err(|e| e.into_redirect_flash(uri!(get_doors)))?
+        .ok_or_else(|| "No door found".into_redirect_flash(uri!(get_doors)))?;
+
+    let flat = FlatEntry::get_by_id(&conn, flat_data.flat_id)
+        .map_err(|e| e.into_redirect_flash(uri!(get_change: id)))?
+        .ok_or_else(|| "No flat found".into_redirect_flash(uri!(get_change: id)))?;
+
+    door.add_flat(&conn, flat.id)
+        .map_err(|e| e.into_redirect_flash(uri!(get_change: id)))?;
+    audit::record(
+        &conn,
+        Some(&admin.user.name),
+        AuditAction::DoorGrant,
+        Some(&format!("{} for flat {}", door.name, flat.name)),
+        client.ip,
+    );
+
+    Ok(Redirect::to(uri!(get_change: id)))
+}
+
+/// revoke the door from a flat
+#[delete("/admin/door/flat/<id>/<flat_id>")]
+pub fn delete_flat(
+    admin: PermissionGuard<require::FlatManage>,
+    _csrf: CsrfHeader,
+    conn: DbConn,
+    client: ClientInfo,
+    id: u32,
+    flat_id: u32,
+) -> ResultFlash<()> {
+    let door = DoorEntry::get_by_id(&conn, id)
+        .map_err(|e| e.into_flash())?
+        .ok_or_else(|| "No door found".into_flash())?;
+
+    let flat = FlatEntry::get_by_id(&conn, flat_id)
+        .map_err(|e| e.into_flash())?
+        .ok_or_else(|| "No flat found".into_flash())?;
+
+    door.remove_flat(&conn, flat.id)
+        .map_err(|e| e.into_flash())?;
+    audit::record(
+        &conn,
+        Some(&admin.user.name),
+        AuditAction::DoorRevoke,
+        Some(&format!("{} for flat {}", door.name, flat.name)),
+        client.ip,
+    );
+
+    Ok(Flash::success((), "Flat removed"))
+}
+
+/// post the form-data to grant the door to an user
+#[post("/admin/door/user/<id>", data = "<user_data>")]
+pub fn post_add_user(
+    admin: PermissionGuard<require::FlatManage>,
+    conn: DbConn,
+    csrf_token: CsrfToken,
+    client: ClientInfo,
+    id: u32,
+    user_data: Form<DoorUserForm>,
+) -> Result<Redirect, Flash<Redirect>> {
+    csrf_token
+        .verify(&user_data.csrf_token)
+        .map_err(|e| e.into_redirect_flash(uri!(get_change: id)))?;
+
+    let door = DoorEntry::get_by_id(&conn, id)
+        .map_err(|e| e.into_redirect_flash(uri!(get_doors)))?
+        .ok_or_else(|| "No door found".into_redirect_flash(uri!(get_doors)))?;
+
+    let user = UserEntry::get_by_id(&conn, user_data.user_id)
+        .map_err(|e| e.into_redirect_flash(uri!(get_change: id)))?
+        .ok_or_else(|| "No user found".into_redirect_flash(uri!(get_change: id)))?;
+
+    door.add_user(&conn, user.id)
+        .map_err(|e| e.into_redirect_flash(uri!(get_change: id)))?;
+    audit::record(
+        &conn,
+        Some(&admin.user.name),
+        AuditAction::DoorGrant,
+        Some(&format!("{} for user {}", door.name, user.name)),
+        client.ip,
+    );
+
+    Ok(Redirect::to(uri!(get_change: id)))
+}
+
+/// revoke the door from an user
+#[delete("/admin/door/user/<id>/<user_id>")]
+pub fn delete_user(
+    admin: PermissionGuard<require::FlatManage>,
+    _csrf: CsrfHeader,
+    conn: DbConn,
+    client: ClientInfo,
+    id: u32,
+    user_id: u32,
+) -> ResultFlash<()> {
+    let door = DoorEntry::get_by_id(&conn, id)
+        .map_err(|e| e.into_flash())?
+        .ok_or_else(|| "No door found".into_flash())?;
+
+    let user = UserEntry::get_by_id(&conn, user_id)
+        .map_err(|e| e.into_flash())?
+        .ok_or_else(|| "No user found".into_flash())?;
+
+    door.remove_user(&conn, user.id)
+        .map_err(|e| e.into_flash())?;
+    audit::record(
+        &conn,
+        Some(&admin.user.name),
+        AuditAction::DoorRevoke,
+        Some(&format!("{} for user {}", door.name, user.name)),
+        client.ip,
+    );
+
+    Ok(Flash::success((), "User removed"))
+}
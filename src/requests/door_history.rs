@@ -0,0 +1,131 @@
+use super::{parse_filter_day, ErrorIntoFlash, FileExport};
+use crate::db_entry::{DbConn, DoorEventEntry, DoorEventOutcome, DoorEventQuery, DoorEventSource};
+use crate::template_contexts::{DoorHistoryContext, Message};
+use crate::utils::door_history;
+use crate::utils::guards::{require, OnlyUserGuard, PermissionGuard};
+use chrono::Duration;
+use rocket::http::uri::Origin;
+use rocket::http::ContentType;
+use rocket::request::{FlashMessage, Form};
+use rocket::response::{Flash, Redirect};
+use rocket_contrib::templates::Template;
+
+/// The maximal number of events, which are shown in the view. The export contains all matching events.
+pub const DOOR_HISTORY_VIEW_LIMIT: u32 = 500;
+
+/// The filters of the door history, which are passed as query parameters.
+#[derive(FromForm, Default)]
+pub struct DoorHistoryFilter {
+    pub door: Option<String>,
+    pub user: Option<String>,
+    pub source: Option<DoorEventSource>,
+    pub outcome: Option<DoorEventOutcome>,
+    /// The first day in the format `YYYY-MM-DD`
+    pub from: Option<String>,
+    /// The last day in the format `YYYY-MM-DD`
+    pub until: Option<String>,
+}
+
+impl DoorHistoryFilter {
+    /// An empty text field is no filter
+    fn text(value: &Option<String>) -> Option<&str> {
+        value.as_deref().filter(|value| !value.is_empty())
+    }
+
+    /// Loads the newest matching events, which are restricted to the flat if it's set
+    fn load(
+        &self,
+        conn: &DbConn,
+        flat: Option<u32>,
+        limit: u32,
+    ) -> Result<Vec<DoorEventEntry>, String> {
+        let query = DoorEventQuery {
+            flat,
+            door: Self::text(&self.door),
+            user: Self::text(&self.user),
+            source: self.source,
+            outcome: self.outcome,
+            from: parse_filter_day(&self.from)?,
+            // The last day is included
+            until: parse_filter_day(&self.until)?.map(|until| until + Duration::days(1)),
+        };
+
+        DoorEventEntry::get_filtered(conn, &query, limit).map_err(|e| format!("DB Error: {}", e))
+    }
+}
+
+impl From<Option<Form<Self>>> for DoorHistoryFilter {
+    /// Without any query parameters there are no filters
+    fn from(filter: Option<Form<Self>>) -> Self {
+        filter.map(Form::into_inner).unwrap_or_default()
+    }
+}
+
+/// Shows the newest door events of the user's flat, which match the filters
+#[get("/door/history?<filter..>")]
+pub fn get_flat_door_history(
+    user: OnlyUserGuard,
+    flash: Option<FlashMessage>,
+    conn: DbConn,
+    origin: &Origin,
+    filter: Option<Form<DoorHistoryFilter>>,
+) -> Template {
+    let filter = DoorHistoryFilter::from(filter);
+    let events = match &user.user.flat {
+        Some(flat) => filter.load(&conn, Some(flat.id), DOOR_HISTORY_VIEW_LIMIT),
+        None => Err("You don't belong to a flat".to_string()),
+    };
+    let context = match events {
+        Ok(events) => DoorHistoryContext::view(
+            flash.map(Message::from),
+            false,
+            &filter,
+            origin.query(),
+            events,
+        ),
+        Err(e) => DoorHistoryContext::error(Message::error(e), false, &filter, origin.query()),
+    };
+    Template::render("door_history", &context)
+}
+
+/// Shows the newest door events of all flats, which match the filters
+#[get("/admin/door_history?<filter..>")]
+pub fn get_door_history(
+    _admin: PermissionGuard<require::FlatManage>,
+    flash: Option<FlashMessage>,
+    conn: DbConn,
+    origin: &Origin,
+    filter: Option<Form<DoorHistoryFilter>>,
+) -> Template {
+    let filter = DoorHistoryFilter::from(filter);
+    let context = match filter.load(&conn, None, DOOR_HISTORY_VIEW_LIMIT) {
+        Ok(events) => DoorHistoryContext::view(
+            flash.map(Message::from),
+            true,
+            &filter,
+            origin.query(),
+            events,
+        ),
+        Err(e) => DoorHistoryContext::error(Message::error(e), true, &filter, origin.query()),
+    };
+    Template::render("door_history", &context)
+}
+
+/// Exports all door events, which match the filters, as CSV
+#[get("/admin/door_history/export/csv?<filter..>")]
+pub fn get_export_csv(
+    _admin: PermissionGuard<require::FlatManage>,
+    conn: DbConn,
+    filter: Option<Form<DoorHistoryFilter>>,
+) -> Result<FileExport, Flash<Redirect>> {
+    let events = DoorHistoryFilter::from(filter)
+        .load(&conn, None, u32::MAX)
+        .map_err(|e| e.into_redirect_flash(uri!(get_door_history: _)))?;
+
+    Ok(FileExport::new(
+        "door_history",
+        door_history::to_csv(&events),
+        ContentType::CSV,
+        "csv",
+    ))
+}
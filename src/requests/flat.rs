@@ -1,8 +1,11 @@
 use super::{ErrorIntoFlash, FormIntoEntry, ResultFlash};
-use crate::db_entry::{DbConn, Entry, FlatEntry};
+use crate::db_entry::{
+    AuditAction, DbConn, DoorEntry, Entry, FlatEntry, GuestCodeEntry, GuestRedemptionEntry,
+};
 use crate::template_contexts::{FlatDetailsContext, FlatOverviewContext, Message};
-use crate::utils::crypto;
-use crate::utils::guards::AdminGuard;
+use crate::utils::csrf::{CsrfHeader, CsrfToken};
+use crate::utils::guards::{require, ClientInfo, PermissionGuard};
+use crate::utils::{audit, crypto};
 use bool_ext::BoolExt;
 use rocket::http::Status;
 use rocket::request::{FlashMessage, Form};
@@ -24,8 +27,10 @@ pub struct FlatForm {
     broker_port: u16,
     bell_topic: String,
     tamper_alarm_topic: String,
+    door_alarm_topic: String,
     broker_user: String,
     broker_password: String,
+    csrf_token: String,
 }
 
 impl FlatForm {
@@ -60,6 +65,11 @@ impl FormIntoEntry<FlatEntry<()>, FlatEntry> for FlatForm {
                 .is_empty()
                 .not()
                 .some(self.tamper_alarm_topic),
+            door_alarm_topic: self
+                .door_alarm_topic
+                .is_empty()
+                .not()
+                .some(self.door_alarm_topic),
             broker_user: self.broker_user,
             broker_password: encrypted_broker_password,
             broker_password_iv: broker_pw_iv,
@@ -83,6 +93,11 @@ impl FormIntoEntry<FlatEntry<()>, FlatEntry> for FlatForm {
                 .is_empty()
                 .not()
                 .some(self.tamper_alarm_topic),
+            door_alarm_topic: self
+                .door_alarm_topic
+                .is_empty()
+                .not()
+                .some(self.door_alarm_topic),
             broker_user: self.broker_user,
             broker_password: encrypted_broker_password,
             broker_password_iv: broker_pw_iv,
@@ -92,19 +107,29 @@ impl FormIntoEntry<FlatEntry<()>, FlatEntry> for FlatForm {
 
 /// get form to create a flat
 #[get("/admin/flat/create")]
-pub fn get_create(_admin: AdminGuard, flash: Option<FlashMessage>) -> Template {
+pub fn get_create(
+    _admin: PermissionGuard<require::FlatManage>,
+    csrf_token: CsrfToken,
+    flash: Option<FlashMessage>,
+) -> Template {
     let context = FlatDetailsContext::create(flash.map(Message::from));
-    Template::render("flat_details", &context)
+    Template::render("flat_details", &csrf_token.context(context))
 }
 
 /// post flat-data to create a flat
 #[post("/admin/flat/create", data = "<flat_data>")]
 pub fn post_create_data(
     flat_data: Form<FlatForm>,
-    _admin: AdminGuard,
+    admin: PermissionGuard<require::FlatManage>,
+    csrf_token: CsrfToken,
     conn: DbConn,
+    client: ClientInfo,
     flat_sync_event: State<Arc<AutoResetEvent>>,
 ) -> Result<Redirect, Flash<Redirect>> {
+    csrf_token
+        .verify(&flat_data.csrf_token)
+        .map_err(|e| e.into_redirect_flash(uri!(get_create)))?;
+
     (flat_data.name.is_empty()
         || flat_data.local_address.is_empty()
         || flat_data.broker_address.is_empty()
@@ -114,11 +139,18 @@ pub fn post_create_data(
     .not()
     .err_with(|| "Mandatory field is empty".into_redirect_flash(uri!(get_create)))?;
 
-    flat_data
+    let flat = flat_data
         .into_inner()
         .into_insertable()
         .create(&conn)
         .map_err(|e| e.into_redirect_flash(uri!(get_create)))?;
+    audit::record(
+        &conn,
+        Some(&admin.user.name),
+        AuditAction::FlatCreate,
+        Some(&flat.name),
+        client.ip,
+    );
 
     // sync iot::EventHandler
     flat_sync_event.set();
@@ -128,23 +160,45 @@ pub fn post_create_data(
 
 /// get all flats
 #[get("/admin/flat")]
-pub fn get_flats(_admin: AdminGuard, flash: Option<FlashMessage>, conn: DbConn) -> Template {
+pub fn get_flats(
+    _admin: PermissionGuard<require::FlatManage>,
+    csrf_token: CsrfToken,
+    flash: Option<FlashMessage>,
+    conn: DbConn,
+) -> Template {
     let context = match FlatEntry::get_all(&conn) {
         Ok(flats) => FlatOverviewContext::view(flats, flash.map(Message::from)),
         Err(e) => FlatOverviewContext::error(Message::error(format!("DB Error: {}", e))),
     };
-    Template::render("flat_overview", &context)
+    Template::render("flat_overview", &csrf_token.context(context))
 }
 
 /// delete a flat by id
 #[delete("/admin/flat/delete/<id>")]
 pub fn delete(
-    _admin: AdminGuard,
+    admin: PermissionGuard<require::FlatManage>,
+    _csrf: CsrfHeader,
     conn: DbConn,
+    client: ClientInfo,
     flat_sync_event: State<Arc<AutoResetEvent>>,
     id: u32,
 ) -> ResultFlash<()> {
-    FlatEntry::delete_entry(&conn, id).map_err(|e| e.into_flash())?;
+    let flat = FlatEntry::get_by_id(&conn, id)
+        .map_err(|e| e.into_flash())?
+        .ok_or_else(|| "No flat found".into_flash())?;
+
+    GuestCodeEntry::delete_by_flat(&conn, id)
+        .and_then(|_| GuestRedemptionEntry::delete_by_flat(&conn, id))
+        .and_then(|_| DoorEntry::delete_by_flat(&conn, id))
+        .and_then(|_| FlatEntry::delete_entry(&conn, id))
+        .map_err(|e| e.into_flash())?;
+    audit::record(
+        &conn,
+        Some(&admin.user.name),
+        AuditAction::FlatDelete,
+        Some(&flat.name),
+        client.ip,
+    );
 
     // sync iot::EventHandler
     flat_sync_event.set();
@@ -155,7 +209,8 @@ pub fn delete(
 /// get the form for modifying a flat
 #[get("/admin/flat/change/<id>")]
 pub fn get_change(
-    _admin: AdminGuard,
+    _admin: PermissionGuard<require::FlatManage>,
+    csrf_token: CsrfToken,
     conn: DbConn,
     flash: Option<FlashMessage>,
     id: u32,
@@ -166,18 +221,27 @@ pub fn get_change(
         Ok(None) => FlatDetailsContext::error(Message::error("No flat found".to_string())),
         Err(e) => FlatDetailsContext::error(Message::error(e.to_string())),
     };
-    Ok(Template::render("flat_details", &context))
+    Ok(Template::render(
+        "flat_details",
+        &csrf_token.context(context),
+    ))
 }
 
 /// post the form-data to modify the flat
 #[post("/admin/flat/change/<id>", data = "<flat_data>")]
 pub fn post_change_data(
-    _admin: AdminGuard,
+    admin: PermissionGuard<require::FlatManage>,
     conn: DbConn,
+    client: ClientInfo,
     flat_sync_event: State<Arc<AutoResetEvent>>,
+    csrf_token: CsrfToken,
     id: u32,
     flat_data: Form<FlatForm>,
 ) -> Result<Redirect, Flash<Redirect>> {
+    csrf_token
+        .verify(&flat_data.csrf_token)
+        .map_err(|e| e.into_redirect_flash(uri!(get_change: id)))?;
+
     (flat_data.name.is_empty()
         || flat_data.local_address.is_empty()
         || flat_data.broker_address.is_empty()
@@ -186,6 +250,10 @@ pub fn post_change_data(
     .not()
     .err_with(|| "Mandatory field is empty".into_redirect_flash(uri!(get_create)))?;
 
+    let previous_flat = FlatEntry::get_by_id(&conn, id)
+        .map_err(|e| e.into_redirect_flash(uri!(get_change: id)))?
+        .ok_or_else(|| "No flat found".into_redirect_flash(uri!(get_flats)))?;
+
     let update_password = !flat_data.broker_password.is_empty();
     let flat = flat_data.into_inner().into_entry(id);
 
@@ -196,6 +264,16 @@ pub fn post_change_data(
 
     update_result.map_err(|e| e.into_redirect_flash(uri!(get_change: id)))?;
 
+    if update_password || flat.broker_user != previous_flat.broker_user {
+        audit::record(
+            &conn,
+            Some(&admin.user.name),
+            AuditAction::FlatCredentialsChange,
+            Some(&flat.name),
+            client.ip,
+        );
+    }
+
     // sync iot::EventHandler
     flat_sync_event.set();
 
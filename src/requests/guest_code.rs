@@ -0,0 +1,253 @@
+use super::user::LocalDateTime;
+use super::{ErrorIntoFlash, ResultFlash};
+use crate::db_entry::{
+    rusqlite, Connection, DbConn, DoorEntry, Entry, GuestCodeEntry, GuestRedemptionEntry,
+    Permission, UserEntry,
+};
+use crate::iot::DoorControls;
+use crate::template_contexts::{GuestCodeOverviewContext, GuestContext, Message};
+use crate::utils::csrf::{CsrfHeader, CsrfToken};
+use crate::utils::guards::{require, ClientInfo, PermissionGuard, UserGuard};
+use crate::utils::{door_opening, guest_access};
+use bool_ext::BoolExt;
+use chrono::offset::Utc;
+use rocket::request::{FlashMessage, Form};
+use rocket::response::{Flash, Redirect};
+use rocket::State;
+use rocket_contrib::templates::Template;
+use std::ops::Not;
+
+/// The number of redemptions, which are shown to the residents.
+const REDEMPTION_LOG_LENGTH: u32 = 20;
+
+/// Struct with all form data to create a guest code.
+#[derive(FromForm)]
+pub struct GuestCodeForm {
+    label: String,
+    /// The start of the time window, it starts immediately if it is empty.
+    valid_from: Option<LocalDateTime>,
+    /// The end of the time window, which is required.
+    valid_until: Option<LocalDateTime>,
+    /// The code can be used once if it is 1.
+    max_uses: u32,
+    /// The door, which the code opens
+    door: u32,
+    csrf_token: String,
+}
+
+/// Struct with the form data to redeem a guest code.
+#[derive(FromForm)]
+pub struct GuestForm {
+    code: String,
+    csrf_token: String,
+}
+
+/// A guest code with its door, whose name is shown
+type GuestCodeWithDoor = GuestCodeEntry<u32, u32, u32, DoorEntry>;
+
+/// Loads the guest codes of the flat with their doors and the latest redemptions
+fn load_flat_overview(
+    conn: &Connection,
+    flat: u32,
+) -> Result<(Vec<GuestCodeWithDoor>, Vec<GuestRedemptionEntry>), rusqlite::Error> {
+    let doors = DoorEntry::get_all(conn)?;
+    let codes = GuestCodeEntry::get_by_flat(conn, flat)?
+        .into_iter()
+        .filter_map(|code| {
+            let door = doors.iter().find(|door| door.id == code.door)?.clone();
+            Some(code.with_door(door))
+        })
+        .collect();
+    let redemptions = GuestRedemptionEntry::get_by_flat(conn, flat, REDEMPTION_LOG_LENGTH)?;
+    Ok((codes, redemptions))
+}
+
+/// The doors, which the user may choose for a guest code, if the user may open doors
+fn selectable_doors(
+    conn: &Connection,
+    user: &UserEntry,
+    may_open: bool,
+) -> Result<Vec<DoorEntry>, rusqlite::Error> {
+    match may_open {
+        true => DoorEntry::get_permitted(conn, user),
+        false => Ok(Vec::new()),
+    }
+}
+
+/// Shows the guest codes and the redemptions of the user's flat and the form to create a code
+#[get("/user/guest_code")]
+pub fn get_guest_codes(
+    user_guard: UserGuard,
+    csrf_token: CsrfToken,
+    flash: Option<FlashMessage>,
+    conn: DbConn,
+) -> Template {
+    let context = match user_guard.user.flat.as_ref().map(Entry::get_id) {
+        Some(flat) => selectable_doors(
+            &conn,
+            &user_guard.user,
+            user_guard.has_permission(Permission::DoorOpen),
+        )
+        .and_then(|doors| load_flat_overview(&conn, flat).map(|overview| (doors, overview)))
+        .map(|(doors, (codes, redemptions))| {
+            GuestCodeOverviewContext::view(
+                flash.map(Message::from),
+                user_guard.is_admin(),
+                doors,
+                codes,
+                redemptions,
+                None,
+            )
+        })
+        .unwrap_or_else(|e| {
+            GuestCodeOverviewContext::error(
+                Message::error(format!("DB Error: {}", e)),
+                user_guard.is_admin(),
+            )
+        }),
+        None => GuestCodeOverviewContext::error(
+            Message::error("You don't belong to a flat".to_string()),
+            user_guard.is_admin(),
+        ),
+    };
+    Template::render("guest_code_overview", &csrf_token.context(context))
+}
+
+/// Post the form to create a guest code for the user's flat, which is shown once.
+/// Only users, who may open the door themselves, may let in guests.
+#[post("/user/guest_code", data = "<code_data>")]
+pub fn post_create(
+    user_guard: PermissionGuard<require::DoorOpen>,
+    csrf_token: CsrfToken,
+    conn: DbConn,
+    code_data: Form<GuestCodeForm>,
+) -> Result<Template, Flash<Redirect>> {
+    csrf_token
+        .verify(&code_data.csrf_token)
+        .map_err(|e| e.into_redirect_flash(uri!(get_guest_codes)))?;
+
+    let flat = user_guard
+        .user
+        .flat
+        .as_ref()
+        .map(Entry::get_id)
+        .ok_or_else(|| "You don't belong to a flat".into_redirect_flash(uri!(get_guest_codes)))?;
+
+    code_data
+        .label
+        .is_empty()
+        .not()
+        .err_with(|| "Label is empty".into_redirect_flash(uri!(get_guest_codes)))?;
+
+    (code_data.max_uses > 0).err_with(|| {
+        "The maximal number of uses has to be greater than 0"
+            .into_redirect_flash(uri!(get_guest_codes))
+    })?;
+
+    let doors = DoorEntry::get_permitted(&conn, &user_guard.user)
+        .map_err(|e| e.into_redirect_flash(uri!(get_guest_codes)))?;
+    doors
+        .iter()
+        .any(|door| door.id == code_data.door)
+        .err_with(|| "You may not open this door".into_redirect_flash(uri!(get_guest_codes)))?;
+
+    let now = Utc::now();
+    let valid_from = code_data
+        .valid_from
+        .as_ref()
+        .map_or(now, |valid_from| valid_from.0);
+    let (valid_from, valid_until) = code_data
+        .valid_until
+        .as_ref()
+        .map(|valid_until| (valid_from, valid_until.0))
+        .filter(|(valid_from, valid_until)| valid_from < valid_until && now < *valid_until)
+        .ok_or_else(|| "The time window is invalid".into_redirect_flash(uri!(get_guest_codes)))?;
+
+    let (code, code_hash) = guest_access::generate_code();
+
+    let code_data = code_data.into_inner();
+    GuestCodeEntry {
+        id: (),
+        label: code_data.label,
+        code_hash,
+        valid_from,
+        valid_until,
+        max_uses: code_data.max_uses,
+        uses: 0,
+        creation_datetime: now,
+        flat,
+        user: user_guard.user.id,
+        door: code_data.door,
+    }
+    .create(&conn)
+    .and_then(|_| load_flat_overview(&conn, flat))
+    .map(|(codes, redemptions)| {
+        let context = GuestCodeOverviewContext::view(
+            None,
+            user_guard
+                .permissions
+                .iter()
+                .any(Permission::is_administrative),
+            doors,
+            codes,
+            redemptions,
+            Some(code),
+        );
+        Template::render("guest_code_overview", &csrf_token.context(context))
+    })
+    .map_err(|e| e.into_redirect_flash(uri!(get_guest_codes)))
+}
+
+/// Revokes a guest code of the user's flat
+#[delete("/user/guest_code/delete/<id>")]
+pub fn delete(user_guard: UserGuard, _csrf: CsrfHeader, conn: DbConn, id: u32) -> ResultFlash<()> {
+    let guest_code = GuestCodeEntry::get_by_id(&conn, id)
+        .map_err(|e| e.into_flash())?
+        .ok_or_else(|| "No guest code found".into_flash())?;
+
+    // Every resident of the flat is allowed to revoke its codes
+    (user_guard.user.flat.as_ref().map(Entry::get_id) == Some(guest_code.flat))
+        .err_with(|| "No guest code found".into_flash())?;
+
+    guest_code.delete(&conn).map_err(|e| e.into_flash())?;
+
+    Ok(Flash::success((), "Guest code revoked"))
+}
+
+/// Get the public form to redeem a guest code
+#[get("/guest")]
+pub fn get_guest(csrf_token: CsrfToken, flash: Option<FlashMessage>) -> Template {
+    let context = GuestContext::new(flash.map(Message::from));
+    Template::render("guest", &csrf_token.context(context))
+}
+
+/// Post a guest code to activate the opener of its door
+#[post("/guest", data = "<guest_data>")]
+pub fn post_guest(
+    csrf_token: CsrfToken,
+    conn: DbConn,
+    client: ClientInfo,
+    door_controls: State<DoorControls>,
+    guest_data: Form<GuestForm>,
+) -> ResultFlash<Redirect> {
+    csrf_token
+        .verify(&guest_data.csrf_token)
+        .map_err(|e| e.into_redirect_flash(uri!(get_guest)))?;
+
+    guest_access::redeem(
+        &conn,
+        &guest_data.code,
+        client.ip,
+        Utc::now(),
+        |guest_code| door_opening::open_for_guest(&conn, &door_controls, guest_code).map(|_| ()),
+    )
+    .map_err(|e| {
+        // The attempt to open the door is already recorded
+        if !matches!(e, guest_access::Error::Door(_)) {
+            door_opening::record_refused_guest(&conn);
+        }
+        e.into_redirect_flash(uri!(get_guest))
+    })?;
+
+    Ok(Flash::success(Redirect::to(uri!(get_guest)), "Door opened"))
+}
@@ -0,0 +1,54 @@
+use super::{index_view::*, user::*, ErrorIntoFlash, ResultFlash};
+use crate::db_entry::DbConn;
+use crate::utils::csrf::{CsrfForm, CsrfToken};
+use crate::utils::guards::{require, ClientInfo, ImpersonationGuard, PermissionGuard};
+use rocket::http::Cookies;
+use rocket::request::Form;
+use rocket::response::{Flash, Redirect};
+
+/// Starts to view the index of an user as admin
+#[post("/admin/user/impersonate/<id>", data = "<csrf_data>")]
+pub fn post_start_impersonation(
+    admin: PermissionGuard<require::UserManage>,
+    csrf_token: CsrfToken,
+    conn: DbConn,
+    client: ClientInfo,
+    cookies: Cookies,
+    id: u32,
+    csrf_data: Form<CsrfForm>,
+) -> ResultFlash<Redirect> {
+    csrf_token
+        .verify(&csrf_data.csrf_token)
+        .map_err(|e| e.into_redirect_flash(uri!(get_change: id)))?;
+
+    let user = ImpersonationGuard::start(&conn, &admin.user, cookies, &client, id)
+        .map_err(|e| e.into_redirect_flash(uri!(get_change: id)))?;
+
+    Ok(Flash::success(
+        Redirect::to(uri!(get_user_index_view)),
+        format!("Viewing as {}", user.name),
+    ))
+}
+
+/// Ends the impersonation and returns to the admin view
+#[post("/impersonate/end", data = "<csrf_data>")]
+pub fn post_end_impersonation(
+    impersonation: ImpersonationGuard,
+    csrf_token: CsrfToken,
+    conn: DbConn,
+    client: ClientInfo,
+    cookies: Cookies,
+    csrf_data: Form<CsrfForm>,
+) -> ResultFlash<Redirect> {
+    csrf_token
+        .verify(&csrf_data.csrf_token)
+        .map_err(|e| e.into_redirect_flash(uri!(get_user_index_view)))?;
+
+    let id = impersonation.user.id;
+    impersonation.end(&conn, cookies, &client);
+
+    Ok(Flash::success(
+        Redirect::to(uri!(get_change: id)),
+        "Returned to admin",
+    ))
+}
@@ -1,46 +1,183 @@
-use super::door::*;
+use super::door::{door_contexts, rocket_uri_macro_get_door_state};
+use super::door_history::rocket_uri_macro_get_flat_door_history;
+use super::impersonation::*;
+use super::password::*;
 use super::user::*;
 use super::user_auth::*;
-use crate::template_contexts::{AdminNav, AdminViewContext, MainViewContext, Message};
+use crate::db_entry::{rusqlite, DbConn, FlatEntry, Permission, UserEntry};
+use crate::iot::DoorControls;
+use crate::template_contexts::{
+    AdminNav, AdminViewContext, CameraContext, DoorContext, ImpersonationContext, MainViewContext,
+    Message,
+};
 use crate::utils::config::CONFIG;
-use crate::utils::guards::{AdminGuard, OnlyUserGuard, UserGuard};
+use crate::utils::csrf::CsrfToken;
+use crate::utils::guards::{
+    AdminGuard, ImpersonationGuard, OnlyUserGuard, PasswordChangeGuard, UserGuard,
+};
 use rocket::request::{FlashMessage, FromRequest, Request};
 use rocket::response::Redirect;
-use rocket::Outcome;
+use rocket::{Outcome, State};
 use rocket_contrib::templates::{tera::Context, Template};
 
+/// Returns the cameras, which the user may watch.
+/// Users without a flat, e.g. a concierge, watch the cameras of all active flats.
+fn get_cameras(
+    conn: &DbConn,
+    user: &UserEntry,
+    permissions: &[Permission],
+) -> Result<Vec<CameraContext>, rusqlite::Error> {
+    if !permissions.contains(&Permission::CameraView) {
+        return Ok(Vec::new());
+    }
+
+    let flats = match &user.flat {
+        Some(flat) => vec![flat.clone()],
+        None => FlatEntry::get_all(conn)?
+            .into_iter()
+            .filter(|flat| flat.active)
+            .collect(),
+    };
+    Ok(flats
+        .into_iter()
+        .map(|flat| CameraContext {
+            name: flat.name,
+            url: format!(
+                "http://{}:{}/",
+                flat.local_address, CONFIG.web.mjpeg_stream_port
+            ),
+        })
+        .collect())
+}
+
 /// Get the index-view of an user
 #[get("/", rank = 1)]
-pub fn get_user_index_view(user: OnlyUserGuard, flash: Option<FlashMessage>) -> Template {
-    let context = MainViewContext {
-        message: flash.map(Message::from),
-        cam_url: format!(
-            "http://{}:{}/",
-            user.user
-                .flat
-                .map_or_else(String::new, |flat| flat.local_address),
-            CONFIG.web.mjpeg_stream_port,
+pub fn get_user_index_view(
+    user: OnlyUserGuard,
+    csrf_token: CsrfToken,
+    flash: Option<FlashMessage>,
+    conn: DbConn,
+    door_controls: State<DoorControls>,
+) -> Template {
+    let may_open = user.permissions.contains(&Permission::DoorOpen);
+    let may_hold = user.permissions.contains(&Permission::DoorHoldOpen);
+    let views = get_cameras(&conn, &user.user, &user.permissions).and_then(|cameras| {
+        Ok((
+            cameras,
+            door_contexts(&conn, &door_controls, &user.user, may_open, may_hold)?,
+        ))
+    });
+    let (cameras, doors, message) = match views {
+        Ok((cameras, doors)) => (cameras, doors, flash.map(Message::from)),
+        Err(e) => (
+            Vec::new(),
+            Vec::new(),
+            Some(Message::error(format!("DB Error: {}", e))),
         ),
-        activate_door_url: uri!(get_open_door).to_string(),
+    };
+    let manage_users_url = user
+        .user
+        .user_type
+        .is_flat_manager()
+        .then(|| uri!(flat_get_users).to_string());
+    let context = MainViewContext {
+        message,
+        cameras,
+        doors,
+        door_state_url: uri!(get_door_state).to_string(),
         change_user_url: uri!(get_change: user.user.id).to_string(),
+        manage_users_url,
+        door_history_url: user
+            .user
+            .flat
+            .as_ref()
+            .map(|_| uri!(get_flat_door_history: _).to_string()),
         logout_url: uri!(get_logout).to_string(),
+        impersonation: None,
     };
-    Template::render("main_view", &context)
+    Template::render("main_view", &csrf_token.context(context))
 }
 
+/// Get the index-view of an user, which is impersonated by an admin.
+/// The view is read-only, so the doors can't be opened and the flat can't be managed.
 #[get("/", rank = 2)]
+pub fn get_impersonated_index_view(
+    impersonation: ImpersonationGuard,
+    csrf_token: CsrfToken,
+    flash: Option<FlashMessage>,
+    conn: DbConn,
+    door_controls: State<DoorControls>,
+) -> Template {
+    let may_open = impersonation.permissions.contains(&Permission::DoorOpen);
+    let views =
+        get_cameras(&conn, &impersonation.user, &impersonation.permissions).and_then(|cameras| {
+            let doors = door_contexts(&conn, &door_controls, &impersonation.user, may_open, false)?;
+            Ok((cameras, doors))
+        });
+    let (cameras, doors, message) = match views {
+        Ok((cameras, doors)) => (cameras, doors, flash.map(Message::from)),
+        Err(e) => (
+            Vec::new(),
+            Vec::new(),
+            Some(Message::error(format!("DB Error: {}", e))),
+        ),
+    };
+    // the doors are only shown, they can't be opened or held open during the impersonation
+    let doors = doors
+        .into_iter()
+        .map(|door| DoorContext {
+            activate_url: None,
+            hold_url: None,
+            cancel_hold_url: None,
+            ..door
+        })
+        .collect();
+    let context = MainViewContext {
+        message,
+        cameras,
+        doors,
+        door_state_url: uri!(get_door_state).to_string(),
+        change_user_url: uri!(get_change: impersonation.user.id).to_string(),
+        manage_users_url: None,
+        door_history_url: None,
+        logout_url: uri!(get_logout).to_string(),
+        impersonation: Some(ImpersonationContext {
+            user_name: impersonation.user.name,
+            expiry: CONFIG.building.local_datetime(impersonation.expiry),
+            end_url: uri!(post_end_impersonation).to_string(),
+        }),
+    };
+    Template::render("main_view", &csrf_token.context(context))
+}
+
+#[get("/", rank = 3)]
 pub fn redirect_admin_to_index(_admin: AdminGuard) -> Redirect {
     Redirect::to(uri!(get_admin_index_view))
 }
 
 /// Get the index-view of an admin
 #[get("/admin")]
-pub fn get_admin_index_view(_admin: AdminGuard, flash: Option<FlashMessage>) -> Template {
+pub fn get_admin_index_view(
+    admin: AdminGuard,
+    csrf_token: CsrfToken,
+    flash: Option<FlashMessage>,
+    conn: DbConn,
+    door_controls: State<DoorControls>,
+) -> Template {
+    let may_open = admin.permissions.contains(&Permission::DoorOpen);
+    let may_hold = admin.permissions.contains(&Permission::DoorHoldOpen);
+    let (doors, message) =
+        match door_contexts(&conn, &door_controls, &admin.user, may_open, may_hold) {
+            Ok(doors) => (doors, flash.map(Message::from)),
+            Err(e) => (Vec::new(), Some(Message::error(format!("DB Error: {}", e)))),
+        };
     let context = AdminViewContext {
-        message: flash.map(Message::from),
+        message,
         nav: AdminNav::new(),
+        doors,
+        door_state_url: uri!(get_door_state).to_string(),
     };
-    Template::render("admin_view", &context)
+    Template::render("admin_view", &csrf_token.context(context))
 }
 
 #[get("/404")]
@@ -51,7 +188,11 @@ pub fn get_not_found(_user: UserGuard) -> Template {
 #[catch(404)]
 pub fn not_found_catcher(req: &Request) -> Redirect {
     match UserGuard::from_request(req) {
-        Outcome::Failure(_) | Outcome::Forward(_) => Redirect::to(uri!(get_login)),
         Outcome::Success(_) => Redirect::to(uri!(get_not_found)),
+        // Users, who have to change their password, aren't allowed to use any other route
+        _ => match PasswordChangeGuard::from_request(req) {
+            Outcome::Success(_) => Redirect::to(uri!(get_change_password)),
+            Outcome::Failure(_) | Outcome::Forward(_) => Redirect::to(uri!(get_login)),
+        },
     }
 }
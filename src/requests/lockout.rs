@@ -0,0 +1,36 @@
+use super::{ErrorIntoFlash, ResultFlash};
+use crate::db_entry::{DbConn, Entry, LoginThrottleEntry};
+use crate::template_contexts::{LockoutOverviewContext, Message};
+use crate::utils::csrf::{CsrfHeader, CsrfToken};
+use crate::utils::guards::{require, PermissionGuard};
+use rocket::request::FlashMessage;
+use rocket::response::Flash;
+use rocket_contrib::templates::Template;
+
+/// get all user names and IP addresses with failed logins
+#[get("/admin/lockout")]
+pub fn get_lockouts(
+    _admin: PermissionGuard<require::UserManage>,
+    csrf_token: CsrfToken,
+    flash: Option<FlashMessage>,
+    conn: DbConn,
+) -> Template {
+    let context = match LoginThrottleEntry::get_all(&conn) {
+        Ok(throttles) => LockoutOverviewContext::view(throttles, flash.map(Message::from)),
+        Err(e) => LockoutOverviewContext::error(Message::error(format!("DB Error: {}", e))),
+    };
+    Template::render("lockout_overview", &csrf_token.context(context))
+}
+
+/// unlock a user name or an IP address by deleting its failed logins
+#[delete("/admin/lockout/delete/<id>")]
+pub fn delete(
+    _admin: PermissionGuard<require::UserManage>,
+    _csrf: CsrfHeader,
+    conn: DbConn,
+    id: u32,
+) -> ResultFlash<()> {
+    LoginThrottleEntry::delete_entry(&conn, id).map_err(|e| e.into_flash())?;
+
+    Ok(Flash::success((), "Unlocked"))
+}
@@ -1,20 +1,41 @@
 //! All user-facing logic.
 
 use crate::db_entry::Entry;
+use chrono::{offset::Utc, DateTime, NaiveDate};
 use rocket::http::uri::Origin;
+use rocket::http::{ContentType, Header};
+use rocket::response::status::BadRequest;
 use rocket::response::{Flash, Redirect};
+use rocket_contrib::json::Json;
 
+pub mod access_schedule;
+pub mod api;
+pub mod api_token;
+pub mod audit_log;
 pub mod door;
+pub mod door_history;
 pub mod flat;
+pub mod guest_code;
+pub mod impersonation;
 pub mod index_view;
+pub mod lockout;
+pub mod passkey;
+pub mod password;
+pub mod password_reset;
+pub mod role;
+pub mod two_factor;
 pub mod user;
 pub mod user_auth;
+pub mod user_session;
 
 type ResultFlash<T> = Result<Flash<T>, Flash<T>>;
+/// The result of a JS request, whose error is shown as flash after reloading the page
+type ResultJson<T> = Result<Json<T>, Flash<BadRequest<()>>>;
 
 trait ErrorIntoFlash {
     fn into_redirect_flash(self, uri: Origin<'static>) -> Flash<Redirect>;
     fn into_flash(self) -> Flash<()>;
+    fn into_bad_request_flash(self) -> Flash<BadRequest<()>>;
 }
 
 impl<T: ToString> ErrorIntoFlash for T {
@@ -24,6 +45,9 @@ impl<T: ToString> ErrorIntoFlash for T {
     fn into_flash(self) -> Flash<()> {
         Flash::error((), self.to_string())
     }
+    fn into_bad_request_flash(self) -> Flash<BadRequest<()>> {
+        Flash::error(BadRequest(None), self.to_string())
+    }
 }
 
 /// Trait which transforms form-data to entry-data using an additional id-field
@@ -31,3 +55,42 @@ trait FormIntoEntry<I, E: Entry> {
     fn into_insertable(self) -> I;
     fn into_entry(self, id: u32) -> E;
 }
+
+/// A download, e.g. the export of the audit log or the door history.
+#[derive(Responder)]
+pub struct FileExport {
+    content: String,
+    content_type: ContentType,
+    disposition: Header<'static>,
+}
+
+impl FileExport {
+    /// The file is named after the export and the current day
+    fn new(name: &str, content: String, content_type: ContentType, extension: &str) -> Self {
+        Self {
+            content,
+            content_type,
+            disposition: Header::new(
+                "Content-Disposition",
+                format!(
+                    "attachment; filename=\"{}_{}.{}\"",
+                    name,
+                    Utc::now().format("%Y-%m-%d"),
+                    extension
+                ),
+            ),
+        }
+    }
+}
+
+/// Parses a day of a filter in the format `YYYY-MM-DD`, an empty field is no filter
+fn parse_filter_day(day: &Option<String>) -> Result<Option<DateTime<Utc>>, String> {
+    match day.as_deref().filter(|day| !day.is_empty()) {
+        Some(day) => NaiveDate::parse_from_str(day, "%Y-%m-%d")
+            .ok()
+            .and_then(|day| day.and_hms_opt(0, 0, 0))
+            .map(|day| Some(DateTime::from_utc(day, Utc)))
+            .ok_or_else(|| format!("{} isn't a date", day)),
+        None => Ok(None),
+    }
+}
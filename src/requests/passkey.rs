@@ -0,0 +1,124 @@
+use super::{ErrorIntoFlash, ResultFlash, ResultJson};
+use crate::db_entry::{DbConn, Entry, PasskeyEntry};
+use crate::template_contexts::{Message, PasskeyOverviewContext};
+use crate::utils::config::{Webauthn, CONFIG};
+use crate::utils::csrf::{CsrfHeader, CsrfToken};
+use crate::utils::guards::UserGuard;
+use crate::utils::passkey::{self, PendingRegistration};
+use bool_ext::BoolExt;
+use rocket::http::{Cookie, Cookies};
+use rocket::request::FlashMessage;
+use rocket::response::Flash;
+use rocket_contrib::json::Json;
+use rocket_contrib::templates::Template;
+use serde::Deserialize;
+use std::ops::Not;
+use webauthn_rs::prelude::{CreationChallengeResponse, RegisterPublicKeyCredential};
+
+/// The name of the private cookie, which holds the registration until the authenticator has created the passkey.
+const REGISTRATION_COOKIE: &str = "passkey_registration";
+
+/// The new credential of the authenticator and its name.
+#[derive(Deserialize)]
+pub struct PasskeyRegistration {
+    name: String,
+    credential: RegisterPublicKeyCredential,
+}
+
+fn config() -> Result<&'static Webauthn, &'static str> {
+    CONFIG.webauthn.as_ref().ok_or("The passkeys are disabled")
+}
+
+/// Shows the passkeys of the user and the button to register one
+#[get("/user/passkey")]
+pub fn get_passkeys(
+    user_guard: UserGuard,
+    csrf_token: CsrfToken,
+    flash: Option<FlashMessage>,
+    conn: DbConn,
+) -> Template {
+    let context = match config().map_err(str::to_string).and_then(|_| {
+        PasskeyEntry::get_by_user(&conn, user_guard.user.id).map_err(|e| format!("DB Error: {}", e))
+    }) {
+        Ok(passkeys) => {
+            PasskeyOverviewContext::view(flash.map(Message::from), user_guard.is_admin(), passkeys)
+        }
+        Err(e) => PasskeyOverviewContext::error(Message::error(e), user_guard.is_admin()),
+    };
+    Template::render("passkey_overview", &csrf_token.context(context))
+}
+
+/// Returns the options for the authenticator to create a new passkey
+#[post("/user/passkey/register")]
+pub fn post_register(
+    user_guard: UserGuard,
+    _csrf: CsrfHeader,
+    conn: DbConn,
+    mut cookies: Cookies,
+) -> ResultJson<CreationChallengeResponse> {
+    let config = config().map_err(|e| e.into_bad_request_flash())?;
+
+    let (options, pending_registration) =
+        passkey::begin_registration(config, &conn, &user_guard.user)
+            .map_err(|e| e.into_bad_request_flash())?;
+
+    cookies.add_private(Cookie::new(
+        REGISTRATION_COOKIE,
+        serde_json::to_string(&pending_registration).map_err(|e| e.into_bad_request_flash())?,
+    ));
+    Ok(Json(options))
+}
+
+/// Post the new credential of the authenticator to store the passkey
+#[post("/user/passkey/register/finish", data = "<registration>")]
+pub fn post_register_finish(
+    user_guard: UserGuard,
+    _csrf: CsrfHeader,
+    conn: DbConn,
+    mut cookies: Cookies,
+    registration: Json<PasskeyRegistration>,
+) -> ResultFlash<()> {
+    let config = config().map_err(|e| e.into_flash())?;
+
+    registration
+        .name
+        .is_empty()
+        .not()
+        .err_with(|| "Name is empty".into_flash())?;
+
+    let pending_registration = cookies
+        .get_private(REGISTRATION_COOKIE)
+        .map(|cookie| serde_json::from_str::<PendingRegistration>(cookie.value()))
+        .transpose()
+        .map_err(|e| e.into_flash())?
+        .ok_or_else(|| passkey::Error::Expired.into_flash())?;
+    cookies.remove_private(Cookie::named(REGISTRATION_COOKIE));
+
+    let registration = registration.into_inner();
+    passkey::finish_registration(
+        config,
+        &conn,
+        pending_registration,
+        user_guard.user.id,
+        registration.name,
+        &registration.credential,
+    )
+    .map_err(|e| e.into_flash())?;
+
+    Ok(Flash::success((), "Passkey registered"))
+}
+
+/// Removes an own passkey
+#[delete("/user/passkey/delete/<id>")]
+pub fn delete(user_guard: UserGuard, _csrf: CsrfHeader, conn: DbConn, id: u32) -> ResultFlash<()> {
+    let passkey = PasskeyEntry::get_by_id(&conn, id)
+        .map_err(|e| e.into_flash())?
+        .ok_or_else(|| "No passkey found".into_flash())?;
+
+    // An user is only allowed to remove his own passkeys
+    (passkey.user == user_guard.user.id).err_with(|| "No passkey found".into_flash())?;
+
+    passkey.delete(&conn).map_err(|e| e.into_flash())?;
+
+    Ok(Flash::success((), "Passkey removed"))
+}
@@ -0,0 +1,93 @@
+use super::index_view::*;
+use super::{ErrorIntoFlash, ResultFlash};
+use crate::db_entry::DbConn;
+use crate::template_contexts::{Message, PasswordChangeContext};
+use crate::utils::csrf::CsrfToken;
+use crate::utils::guards::{PasswordChangeGuard, UserGuard};
+use crate::utils::{crypto, provisioning};
+use bool_ext::BoolExt;
+use rocket::request::{FlashMessage, Form};
+use rocket::response::{Flash, Redirect};
+use rocket_contrib::templates::Template;
+use std::ops::Not;
+
+/// Struct with the form data to change the own password.
+#[derive(FromForm)]
+pub struct PasswordForm {
+    pw: String,
+    pw_repeat: String,
+    csrf_token: String,
+}
+
+/// Get the form to change the own password, which is mandatory for flagged users
+#[get("/user/password")]
+pub fn get_change_password(
+    password_change_guard: PasswordChangeGuard,
+    csrf_token: CsrfToken,
+    flash: Option<FlashMessage>,
+) -> Template {
+    let context = PasswordChangeContext::new(
+        flash.map(Message::from),
+        password_change_guard.user_guard.user.must_change_password,
+    );
+    Template::render("password_change", &csrf_token.context(context))
+}
+
+/// Post the new password, which has to differ from the current one
+#[post("/user/password", data = "<password_data>")]
+pub fn post_change_password(
+    password_change_guard: PasswordChangeGuard,
+    csrf_token: CsrfToken,
+    conn: DbConn,
+    password_data: Form<PasswordForm>,
+) -> ResultFlash<Redirect> {
+    csrf_token
+        .verify(&password_data.csrf_token)
+        .map_err(|e| e.into_redirect_flash(uri!(get_change_password)))?;
+
+    password_data
+        .pw
+        .is_empty()
+        .not()
+        .err_with(|| "Password is empty".into_redirect_flash(uri!(get_change_password)))?;
+
+    (password_data.pw == password_data.pw_repeat)
+        .err_with(|| "Passwords are not the same".into_redirect_flash(uri!(get_change_password)))?;
+
+    let mut user = password_change_guard.user_guard.user;
+    UserGuard::check_new_password(&conn, user.id, &password_data.pw)
+        .map_err(|e| e.into_redirect_flash(uri!(get_change_password)))?;
+
+    provisioning::is_external_user(&user).not().err_with(|| {
+        "The password is managed by an external login provider"
+            .into_redirect_flash(uri!(get_change_password))
+    })?;
+
+    let unchanged_password =
+        crypto::hash_with_config(&password_data.pw, &user.pw_hash.salt, &user.pw_hash.config)
+            .map_or(false, |pw_hash| pw_hash == user.pw_hash.hash);
+    unchanged_password.not().err_with(|| {
+        "The new password has to differ from the current one"
+            .into_redirect_flash(uri!(get_change_password))
+    })?;
+
+    user.pw_hash = crypto::hash(&password_data.pw);
+    user.must_change_password = false;
+    user.update_password(&conn)
+        .and_then(|_| UserGuard::record_password(&conn, user.id, &user.pw_hash))
+        .map_err(|e| e.into_redirect_flash(uri!(get_change_password)))?;
+
+    Ok(Flash::success(
+        Redirect::to(uri!(get_user_index_view)),
+        "Your password has been changed",
+    ))
+}
+
+/// Redirects users to the password change, who have to change their password first
+#[get("/", rank = 5)]
+pub fn redirect_to_password_change(_user: PasswordChangeGuard) -> Flash<Redirect> {
+    Flash::error(
+        Redirect::to(uri!(get_change_password)),
+        "You have to change your password first",
+    )
+}
@@ -0,0 +1,93 @@
+use super::user::*;
+use super::user_auth::*;
+use super::{ErrorIntoFlash, ResultFlash};
+use crate::db_entry::{DbConn, UserEntry};
+use crate::template_contexts::{Message, PasswordResetContext, PasswordResetIssuedContext};
+use crate::utils::csrf::{CsrfForm, CsrfToken};
+use crate::utils::guards::{require, ClientInfo, PermissionGuard};
+use crate::utils::password_reset;
+use bool_ext::BoolExt;
+use chrono::offset::Utc;
+use rocket::request::{FlashMessage, Form};
+use rocket::response::{Flash, Redirect};
+use rocket_contrib::templates::Template;
+
+/// Struct with all form data to redeem a reset code.
+#[derive(FromForm)]
+pub struct PasswordResetForm {
+    code: String,
+    pw: String,
+    pw_repeat: String,
+    csrf_token: String,
+}
+
+/// Post to issue a new reset code for an user, which is shown once
+#[post("/admin/user/password_reset/<id>", data = "<csrf_data>")]
+pub fn admin_post_reset(
+    _admin: PermissionGuard<require::UserManage>,
+    csrf_token: CsrfToken,
+    conn: DbConn,
+    id: u32,
+    csrf_data: Form<CsrfForm>,
+) -> Result<Template, Flash<Redirect>> {
+    csrf_token
+        .verify(&csrf_data.csrf_token)
+        .map_err(|e| e.into_redirect_flash(uri!(get_change: id)))?;
+
+    let user = UserEntry::get_by_id(&conn, id)
+        .map_err(|e| e.into_redirect_flash(uri!(get_users)))?
+        .ok_or_else(|| "No user found".into_redirect_flash(uri!(get_users)))?;
+
+    let code = password_reset::issue(&conn, user.id, Utc::now())
+        .map_err(|e| e.into_redirect_flash(uri!(get_change: id)))?;
+
+    let context = PasswordResetIssuedContext::new(&user, code);
+    Ok(Template::render(
+        "password_reset_issued",
+        &csrf_token.context(context),
+    ))
+}
+
+/// Get the public form to enter a printed reset code and the new password
+#[get("/password_reset")]
+pub fn get_reset_form(csrf_token: CsrfToken, flash: Option<FlashMessage>) -> Template {
+    let context = PasswordResetContext::new(flash.map(Message::from), None);
+    Template::render("password_reset", &csrf_token.context(context))
+}
+
+/// Get the public form of a reset link, which only asks for the new password
+#[get("/password_reset/<code>")]
+pub fn get_reset(csrf_token: CsrfToken, flash: Option<FlashMessage>, code: String) -> Template {
+    let context = PasswordResetContext::new(flash.map(Message::from), Some(code));
+    Template::render("password_reset", &csrf_token.context(context))
+}
+
+/// Post the reset code with the new password, which logs the user out everywhere
+#[post("/password_reset", data = "<reset_data>")]
+pub fn post_reset(
+    csrf_token: CsrfToken,
+    conn: DbConn,
+    client: ClientInfo,
+    reset_data: Form<PasswordResetForm>,
+) -> ResultFlash<Redirect> {
+    let code = password_reset::normalize(&reset_data.code);
+    let retry_uri = match code.is_empty() {
+        true => uri!(get_reset_form),
+        false => uri!(get_reset: &code),
+    };
+
+    csrf_token
+        .verify(&reset_data.csrf_token)
+        .map_err(|e| e.into_redirect_flash(retry_uri.clone()))?;
+
+    (reset_data.pw == reset_data.pw_repeat)
+        .err_with(|| "Passwords are not the same".into_redirect_flash(retry_uri.clone()))?;
+
+    password_reset::redeem(&conn, &code, &reset_data.pw, client.ip, Utc::now())
+        .map_err(|e| e.into_redirect_flash(retry_uri))?;
+
+    Ok(Flash::success(
+        Redirect::to(uri!(get_login)),
+        "Your password has been reset, please log in",
+    ))
+}
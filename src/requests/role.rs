@@ -0,0 +1,237 @@
+use super::{ErrorIntoFlash, FormIntoEntry, ResultFlash};
+use crate::db_entry::{DbConn, Entry, Permission, RoleEntry, UserEntry};
+use crate::template_contexts::{Message, RoleDetailsContext, RoleOverviewContext};
+use crate::utils::csrf::{CsrfHeader, CsrfToken};
+use crate::utils::guards::{require, PermissionGuard};
+use bool_ext::BoolExt;
+use rocket::http::Status;
+use rocket::request::{FlashMessage, Form};
+use rocket::response::{Flash, Redirect};
+use rocket_contrib::templates::Template;
+use std::ops::Not;
+
+/// Struct which retrieves all form data from the role details.
+#[derive(FromForm)]
+pub struct RoleForm {
+    name: String,
+    door_open: bool,
+    camera_view: bool,
+    flat_manage: bool,
+    user_manage: bool,
+    events_view: bool,
+    door_hold_open: bool,
+    csrf_token: String,
+}
+
+impl RoleForm {
+    fn permissions(&self) -> Vec<Permission> {
+        vec![
+            (self.door_open, Permission::DoorOpen),
+            (self.camera_view, Permission::CameraView),
+            (self.flat_manage, Permission::FlatManage),
+            (self.user_manage, Permission::UserManage),
+            (self.events_view, Permission::EventsView),
+            (self.door_hold_open, Permission::DoorHoldOpen),
+        ]
+        .into_iter()
+        .filter(|(selected, _)| *selected)
+        .map(|(_, permission)| permission)
+        .collect()
+    }
+}
+
+impl FormIntoEntry<RoleEntry<()>, RoleEntry> for RoleForm {
+    fn into_insertable(self) -> RoleEntry<()> {
+        RoleEntry {
+            id: (),
+            permissions: self.permissions(),
+            name: self.name,
+        }
+    }
+
+    fn into_entry(self, id: u32) -> RoleEntry {
+        RoleEntry {
+            id,
+            permissions: self.permissions(),
+            name: self.name,
+        }
+    }
+}
+
+/// Struct with the form data to add a member to a role.
+#[derive(FromForm)]
+pub struct RoleMemberForm {
+    user_id: u32,
+    csrf_token: String,
+}
+
+/// get all roles
+#[get("/admin/role")]
+pub fn get_roles(
+    _admin: PermissionGuard<require::UserManage>,
+    csrf_token: CsrfToken,
+    flash: Option<FlashMessage>,
+    conn: DbConn,
+) -> Template {
+    let context = match RoleEntry::get_all(&conn) {
+        Ok(roles) => RoleOverviewContext::view(roles, flash.map(Message::from)),
+        Err(e) => RoleOverviewContext::error(Message::error(format!("DB Error: {}", e))),
+    };
+    Template::render("role_overview", &csrf_token.context(context))
+}
+
+/// get form to create a role
+#[get("/admin/role/create")]
+pub fn get_create(
+    _admin: PermissionGuard<require::UserManage>,
+    csrf_token: CsrfToken,
+    flash: Option<FlashMessage>,
+) -> Template {
+    let context = RoleDetailsContext::create(flash.map(Message::from));
+    Template::render("role_details", &csrf_token.context(context))
+}
+
+/// post role-data to create a role
+#[post("/admin/role/create", data = "<role_data>")]
+pub fn post_create_data(
+    role_data: Form<RoleForm>,
+    _admin: PermissionGuard<require::UserManage>,
+    csrf_token: CsrfToken,
+    conn: DbConn,
+) -> Result<Redirect, Flash<Redirect>> {
+    csrf_token
+        .verify(&role_data.csrf_token)
+        .map_err(|e| e.into_redirect_flash(uri!(get_create)))?;
+
+    role_data
+        .name
+        .is_empty()
+        .not()
+        .err_with(|| "Name is empty".into_redirect_flash(uri!(get_create)))?;
+
+    let role = role_data
+        .into_inner()
+        .into_insertable()
+        .create(&conn)
+        .map_err(|e| e.into_redirect_flash(uri!(get_create)))?;
+
+    Ok(Redirect::to(uri!(get_change: role.get_id())))
+}
+
+/// delete a role by id, its members lose the permissions of the role
+#[delete("/admin/role/delete/<id>")]
+pub fn delete(
+    _admin: PermissionGuard<require::UserManage>,
+    _csrf: CsrfHeader,
+    conn: DbConn,
+    id: u32,
+) -> ResultFlash<()> {
+    RoleEntry::delete_entry(&conn, id).map_err(|e| e.into_flash())?;
+
+    Ok(Flash::success((), "Role deleted"))
+}
+
+/// get the form for modifying a role and its members
+#[get("/admin/role/change/<id>")]
+pub fn get_change(
+    _admin: PermissionGuard<require::UserManage>,
+    csrf_token: CsrfToken,
+    conn: DbConn,
+    flash: Option<FlashMessage>,
+    id: u32,
+) -> Result<Template, Status> {
+    let role = RoleEntry::get_by_id(&conn, id)
+        .map_err(|_| Status::InternalServerError)?
+        .ok_or(Status::NotFound)?;
+
+    let context = match role
+        .get_members(&conn)
+        .and_then(|members| Ok((members, UserEntry::get_all(&conn)?)))
+    {
+        Ok((members, users)) => {
+            let (members, users) = users
+                .into_iter()
+                .partition(|user| members.contains(&user.get_id()));
+            RoleDetailsContext::change(flash.map(Message::from), role, members, users)
+        }
+        Err(e) => RoleDetailsContext::error(Message::error(e.to_string())),
+    };
+    Ok(Template::render(
+        "role_details",
+        &csrf_token.context(context),
+    ))
+}
+
+/// post the form-data to modify the role
+#[post("/admin/role/change/<id>", data = "<role_data>")]
+pub fn post_change_data(
+    _admin: PermissionGuard<require::UserManage>,
+    conn: DbConn,
+    csrf_token: CsrfToken,
+    id: u32,
+    role_data: Form<RoleForm>,
+) -> Result<Redirect, Flash<Redirect>> {
+    csrf_token
+        .verify(&role_data.csrf_token)
+        .map_err(|e| e.into_redirect_flash(uri!(get_change: id)))?;
+
+    role_data
+        .name
+        .is_empty()
+        .not()
+        .err_with(|| "Name is empty".into_redirect_flash(uri!(get_change: id)))?;
+
+    role_data
+        .into_inner()
+        .into_entry(id)
+        .update(&conn)
+        .map_err(|e| e.into_redirect_flash(uri!(get_change: id)))?;
+
+    Ok(Redirect::to(uri!(get_roles)))
+}
+
+/// post the form-data to add an user to the role
+#[post("/admin/role/member/<id>", data = "<member_data>")]
+pub fn post_add_member(
+    _admin: PermissionGuard<require::UserManage>,
+    conn: DbConn,
+    csrf_token: CsrfToken,
+    id: u32,
+    member_data: Form<RoleMemberForm>,
+) -> Result<Redirect, Flash<Redirect>> {
+    csrf_token
+        .verify(&member_data.csrf_token)
+        .map_err(|e| e.into_redirect_flash(uri!(get_change: id)))?;
+
+    let role = RoleEntry::get_by_id(&conn, id)
+        .map_err(|e| e.into_redirect_flash(uri!(get_roles)))?
+        .ok_or_else(|| "No role found".into_redirect_flash(uri!(get_roles)))?;
+
+    UserEntry::get_by_id(&conn, member_data.user_id)
+        .map_err(|e| e.into_redirect_flash(uri!(get_change: id)))?
+        .ok_or_else(|| "No user found".into_redirect_flash(uri!(get_change: id)))?;
+
+    role.add_member(&conn, member_data.user_id)
+        .map_err(|e| e.into_redirect_flash(uri!(get_change: id)))?;
+
+    Ok(Redirect::to(uri!(get_change: id)))
+}
+
+/// remove an user from the role
+#[delete("/admin/role/member/<id>/<user_id>")]
+pub fn delete_member(
+    _admin: PermissionGuard<require::UserManage>,
+    _csrf: CsrfHeader,
+    conn: DbConn,
+    id: u32,
+    user_id: u32,
+) -> ResultFlash<()> {
+    let role = RoleEntry::get_by_id(&conn, id)
+        .map_err(|e| e.into_flash())?
+        .ok_or_else(|| "No role found".into_flash())?;
+
+    role.remove_member(&conn, user_id)
+        .map_err(|e| e.into_flash())?;
+
+    Ok(Flash::success((), "Member removed"))
+}
@@ -0,0 +1,229 @@
+use super::index_view::*;
+use super::user_auth::*;
+use super::{ErrorIntoFlash, ResultFlash};
+use crate::db_entry::{
+    rusqlite, Connection, DbConn, RecoveryCodeEntry, UserEntry, UserSessionEntry,
+};
+use crate::template_contexts::{LoginContext, Message, TwoFactorContext};
+use crate::utils::config::CONFIG;
+use crate::utils::csrf::{CsrfHeader, CsrfToken};
+use crate::utils::guards::{
+    self, require, ClientInfo, PermissionGuard, TwoFactorSetupGuard, UserGuard,
+};
+use crate::utils::{crypto, totp};
+use bool_ext::BoolExt;
+use rocket::http::{Cookie, Cookies};
+use rocket::request::{FlashMessage, Form};
+use rocket::response::{Flash, Redirect};
+use rocket_contrib::templates::Template;
+use std::ops::Not;
+
+/// The name of the private cookie, which holds the secret until the first code is confirmed.
+const SETUP_COOKIE: &str = "two_factor_setup";
+
+/// Struct with the TOTP or recovery code of a form.
+#[derive(FromForm)]
+pub struct CodeForm {
+    code: String,
+    csrf_token: String,
+}
+
+/// Disables the second factor of an user and deletes the recovery codes and sessions.
+fn reset_two_factor(conn: &Connection, mut user: UserEntry) -> Result<(), rusqlite::Error> {
+    user.totp_secret = None;
+    user.update_totp_secret(conn)?;
+    user.reset_two_factor_failures(conn)?;
+    RecoveryCodeEntry::delete_by_user(conn, user.id)?;
+    UserSessionEntry::delete_by_user(conn, user.id)
+}
+
+/// Get the form for the second factor of the login
+#[get("/login/two_factor")]
+pub fn get_two_factor_login(csrf_token: CsrfToken, flash: Option<FlashMessage>) -> Template {
+    let context = LoginContext {
+        message: flash.map(Message::from),
+        oidc_login_url: None,
+        passkey_login_url: None,
+    };
+    Template::render("two_factor_login", &csrf_token.context(context))
+}
+
+/// Post the TOTP or recovery code to finish the login
+#[post("/login/two_factor", data = "<code_data>")]
+pub fn post_two_factor_login(
+    code_data: Form<CodeForm>,
+    conn: DbConn,
+    csrf_token: CsrfToken,
+    cookies: Cookies,
+    client: ClientInfo,
+) -> Result<Redirect, Flash<Redirect>> {
+    csrf_token
+        .verify(&code_data.csrf_token)
+        .map_err(|e| e.into_redirect_flash(uri!(get_two_factor_login)))?;
+
+    UserGuard::authenticate_two_factor(&conn, cookies, &client, &code_data.code).map_err(|e| {
+        // Only an invalid code can be retried without the password
+        let uri = match e {
+            guards::Error::InvalidTwoFactorCode => uri!(get_two_factor_login),
+            _ => uri!(get_login),
+        };
+        e.into_redirect_flash(uri)
+    })?;
+
+    Ok(Redirect::to(uri!(get_user_index_view)))
+}
+
+/// Get the enrollment of the second factor or the form to disable it
+#[get("/user/two_factor")]
+pub fn get_setup(
+    user_guard: UserGuard,
+    conn: DbConn,
+    csrf_token: CsrfToken,
+    mut cookies: Cookies,
+    flash: Option<FlashMessage>,
+) -> Template {
+    let message = flash.map(Message::from);
+    let is_admin = user_guard.is_admin();
+
+    if user_guard.user.totp_secret.is_some() {
+        let context = match RecoveryCodeEntry::get_by_user(&conn, user_guard.user.id) {
+            Ok(recovery_codes) => {
+                TwoFactorContext::enabled(message, is_admin, recovery_codes.len())
+            }
+            Err(e) => TwoFactorContext::error(Message::error(e.to_string())),
+        };
+        return Template::render("two_factor", &csrf_token.context(context));
+    }
+
+    // Keep the secret of a failed confirmation, so it hasn't to be scanned again
+    let secret = cookies
+        .get_private(SETUP_COOKIE)
+        .map(|cookie| cookie.value().to_string())
+        .unwrap_or_else(totp::generate_secret);
+    cookies.add_private(Cookie::new(SETUP_COOKIE, secret.clone()));
+
+    let context = match totp::qr_code_svg(&totp::provisioning_uri(&secret, &user_guard.user.name)) {
+        Ok(qr_code) => TwoFactorContext::setup(message, is_admin, secret, qr_code),
+        Err(e) => TwoFactorContext::error(Message::error(e.to_string())),
+    };
+    Template::render("two_factor", &csrf_token.context(context))
+}
+
+/// Post the first code to enable the second factor and show the recovery codes
+#[post("/user/two_factor", data = "<code_data>")]
+pub fn post_setup(
+    user_guard: UserGuard,
+    conn: DbConn,
+    csrf_token: CsrfToken,
+    mut cookies: Cookies,
+    code_data: Form<CodeForm>,
+) -> Result<Template, Flash<Redirect>> {
+    csrf_token
+        .verify(&code_data.csrf_token)
+        .map_err(|e| e.into_redirect_flash(uri!(get_setup)))?;
+
+    let secret = cookies
+        .get_private(SETUP_COOKIE)
+        .map(|cookie| cookie.value().to_string())
+        .ok_or_else(|| "The setup has expired".into_redirect_flash(uri!(get_setup)))?;
+
+    let step = totp::step(&secret, &code_data.code)
+        .ok_or_else(|| "The code is invalid".into_redirect_flash(uri!(get_setup)))?;
+
+    let mut user = user_guard.user.clone();
+    user.totp_secret = Some(secret);
+    let recovery_codes = totp::generate_recovery_codes();
+
+    // The confirmation code can't be used for the next login
+    user.update_totp_secret(&conn)
+        .and_then(|_| user.accept_totp_step(&conn, step))
+        .and_then(|_| RecoveryCodeEntry::delete_by_user(&conn, user.id))
+        .and_then(|_| {
+            recovery_codes.iter().try_for_each(|code| {
+                RecoveryCodeEntry {
+                    id: (),
+                    code_hash: crypto::hash_token(&totp::normalize_recovery_code(code)),
+                    user: user.id,
+                }
+                .create(&conn)
+                .map(|_| ())
+            })
+        })
+        .and_then(|_| UserSessionEntry::delete_by_user(&conn, user.id))
+        .map_err(|e| e.into_redirect_flash(uri!(get_setup)))?;
+
+    cookies.remove_private(Cookie::named(SETUP_COOKIE));
+    user_guard
+        .destroy_user_session(&conn, cookies)
+        .map_err(|e| e.into_redirect_flash(uri!(get_setup)))?;
+
+    Ok(Template::render(
+        "two_factor",
+        &csrf_token.context(TwoFactorContext::recovery_codes(recovery_codes)),
+    ))
+}
+
+/// Post a current code to disable the second factor
+#[post("/user/two_factor/disable", data = "<code_data>")]
+pub fn post_disable(
+    user_guard: UserGuard,
+    conn: DbConn,
+    csrf_token: CsrfToken,
+    cookies: Cookies,
+    code_data: Form<CodeForm>,
+) -> ResultFlash<Redirect> {
+    csrf_token
+        .verify(&code_data.csrf_token)
+        .map_err(|e| e.into_redirect_flash(uri!(get_setup)))?;
+
+    (user_guard.is_admin() && CONFIG.security.admin_two_factor_required)
+        .not()
+        .err_with(|| {
+            "Two-factor authentication is mandatory for admins".into_redirect_flash(uri!(get_setup))
+        })?;
+
+    let secret = user_guard.user.totp_secret.clone().ok_or_else(|| {
+        "Two-factor authentication isn't enabled".into_redirect_flash(uri!(get_setup))
+    })?;
+
+    totp::verify(&secret, &code_data.code)
+        .err_with(|| "The code is invalid".into_redirect_flash(uri!(get_setup)))?;
+
+    reset_two_factor(&conn, user_guard.user.clone())
+        .map_err(|e| e.into_redirect_flash(uri!(get_setup)))?;
+
+    user_guard
+        .destroy_user_session(&conn, cookies)
+        .map_err(|e| e.into_redirect_flash(uri!(get_login)))?;
+
+    Ok(Flash::success(
+        Redirect::to(uri!(get_login)),
+        "Two-factor authentication has been disabled. Please log in again.",
+    ))
+}
+
+/// Disables the second factor of an user, e.g. if the phone is lost
+#[delete("/admin/user/two_factor/<id>")]
+pub fn admin_reset(
+    _admin: PermissionGuard<require::UserManage>,
+    _csrf: CsrfHeader,
+    conn: DbConn,
+    id: u32,
+) -> ResultFlash<()> {
+    let user = UserEntry::get_by_id(&conn, id)
+        .map_err(|e| e.into_flash())?
+        .ok_or_else(|| "No user found".into_flash())?;
+
+    reset_two_factor(&conn, user).map_err(|e| e.into_flash())?;
+
+    Ok(Flash::success((), "Two-factor authentication reset"))
+}
+
+/// Redirects admins to the enrollment, who have to enable the second factor
+#[get("/", rank = 4)]
+pub fn redirect_to_setup(_admin: TwoFactorSetupGuard) -> Flash<Redirect> {
+    Flash::error(
+        Redirect::to(uri!(get_setup)),
+        "Two-factor authentication is mandatory for admins",
+    )
+}
@@ -1,15 +1,43 @@
 use super::{user_auth::rocket_uri_macro_get_login, ErrorIntoFlash, FormIntoEntry, ResultFlash};
-use crate::db_entry::{DbConn, Entry, FlatEntry, UserEntry, UserSessionEntry, UserType};
+use crate::db_entry::{
+    rusqlite, AccessExceptionEntry, AccessWindowEntry, ApiTokenEntry, AuditAction, DbConn,
+    DoorEntry, Entry, FlatEntry, GuestCodeEntry, PasskeyEntry, PasswordHistoryEntry,
+    PasswordResetEntry, Permission, RecoveryCodeEntry, RoleEntry, UserEntry, UserSessionEntry,
+    UserType,
+};
 use crate::template_contexts::{Message, UserDetailsContext, UserOverviewContext};
-use crate::utils::crypto;
-use crate::utils::guards::{AdminGuard, OnlyUserGuard, UserGuard};
+use crate::utils::config::CONFIG;
+use crate::utils::csrf::{CsrfHeader, CsrfToken};
+use crate::utils::guards::{
+    require, ClientInfo, FlatManagerGuard, OnlyUserGuard, PermissionGuard, UserGuard,
+};
+use crate::utils::{audit, crypto, provisioning};
 use bool_ext::BoolExt;
+use chrono::{offset::Utc, DateTime, NaiveDateTime};
+use rocket::http::RawStr;
 use rocket::http::Status;
-use rocket::request::{FlashMessage, Form};
+use rocket::request::{FlashMessage, Form, FromFormValue};
 use rocket::response::{Flash, Redirect};
 use rocket_contrib::templates::Template;
 use std::ops::Not;
 
+/// The value of a `datetime-local` input, which is the wall-clock time of the building.
+/// An empty input is no value.
+pub struct LocalDateTime(pub DateTime<Utc>);
+
+impl<'v> FromFormValue<'v> for LocalDateTime {
+    type Error = &'v RawStr;
+
+    fn from_form_value(form_value: &'v RawStr) -> Result<Self, &'v RawStr> {
+        let value = form_value.url_decode().map_err(|_| form_value)?;
+        NaiveDateTime::parse_from_str(&value, "%Y-%m-%dT%H:%M")
+            .ok()
+            .and_then(|local| CONFIG.building.to_utc(&local))
+            .map(Self)
+            .ok_or(form_value)
+    }
+}
+
 /// Struct with all user-details form data.
 /// The optional values have to be considered as non-admins editing themselves aren't allowed to change these values.
 #[derive(FromForm)]
@@ -20,6 +48,19 @@ pub struct UserForm {
     user_type: Option<UserType>,
     active: Option<bool>,
     flat_id: Option<u32>,
+    valid_from: Option<LocalDateTime>,
+    valid_until: Option<LocalDateTime>,
+    csrf_token: String,
+}
+
+impl UserForm {
+    /// Whether the validity doesn't end before it begins
+    fn has_valid_period(&self) -> bool {
+        match (&self.valid_from, &self.valid_until) {
+            (Some(valid_from), Some(valid_until)) => valid_from.0 < valid_until.0,
+            _ => true,
+        }
+    }
 }
 
 impl FormIntoEntry<UserEntry<(), u32>, UserEntry<u32, u32>> for UserForm {
@@ -33,6 +74,11 @@ impl FormIntoEntry<UserEntry<(), u32>, UserEntry<u32, u32>> for UserForm {
             user_type: self.user_type.unwrap_or(UserType::User),
             active: self.active.unwrap_or(false),
             flat: self.flat_id,
+            totp_secret: None,
+            // The user has to replace the password, which is known by the admin
+            must_change_password: true,
+            valid_from: self.valid_from.map(|valid_from| valid_from.0),
+            valid_until: self.valid_until.map(|valid_until| valid_until.0),
         }
     }
 
@@ -46,33 +92,52 @@ impl FormIntoEntry<UserEntry<(), u32>, UserEntry<u32, u32>> for UserForm {
             user_type: self.user_type.unwrap_or(UserType::User),
             active: self.active.unwrap_or(false),
             flat: self.flat_id,
+            totp_secret: None,
+            must_change_password: false,
+            valid_from: self.valid_from.map(|valid_from| valid_from.0),
+            valid_until: self.valid_until.map(|valid_until| valid_until.0),
         }
     }
 }
 
 /// Get the form to create an user
 #[get("/admin/user/create")]
-pub fn get_create(_admin: AdminGuard, conn: DbConn, flash: Option<FlashMessage>) -> Template {
+pub fn get_create(
+    _admin: PermissionGuard<require::UserManage>,
+    csrf_token: CsrfToken,
+    conn: DbConn,
+    flash: Option<FlashMessage>,
+) -> Template {
     let context = match FlatEntry::get_all(&conn) {
         Err(e) => UserDetailsContext::error(Message::error(e.to_string())),
         Ok(flats) => UserDetailsContext::create(flash.map(Message::from), flats),
     };
-    Template::render("user_details", &context)
+    Template::render("user_details", &csrf_token.context(context))
 }
 
 /// Post the user-data in order to create an user
 #[post("/admin/user/create", data = "<user_data>")]
 pub fn post_create_data(
     user_data: Form<UserForm>,
-    _admin: AdminGuard,
+    admin: PermissionGuard<require::UserManage>,
+    csrf_token: CsrfToken,
     conn: DbConn,
+    client: ClientInfo,
 ) -> Result<Redirect, Flash<Redirect>> {
+    csrf_token
+        .verify(&user_data.csrf_token)
+        .map_err(|e| e.into_redirect_flash(uri!(get_create)))?;
+
     user_data
         .name
         .is_empty()
         .not()
         .err_with(|| "Name is empty".into_redirect_flash(uri!(get_create)))?;
 
+    user_data.has_valid_period().err_with(|| {
+        "The validity has to end after its begin".into_redirect_flash(uri!(get_create))
+    })?;
+
     user_data
         .pw
         .is_empty()
@@ -85,33 +150,73 @@ pub fn post_create_data(
     UserGuard::check_password(&user_data.pw)
         .map_err(|e| e.into_redirect_flash(uri!(get_create)))?;
 
-    user_data
+    let user = user_data
         .into_inner()
         .into_insertable()
         .create(&conn)
+        .and_then(|user| UserGuard::record_password(&conn, user.id, &user.pw_hash).map(|_| user))
         .map_err(|e| e.into_redirect_flash(uri!(get_create)))?;
+    audit::record(
+        &conn,
+        Some(&admin.user.name),
+        AuditAction::UserCreate,
+        Some(&user.name),
+        client.ip,
+    );
 
     return Ok(Redirect::to(uri!(get_users)));
 }
 
 /// Shows all users
 #[get("/admin/user")]
-pub fn get_users(_admin: AdminGuard, flash: Option<FlashMessage>, conn: DbConn) -> Template {
+pub fn get_users(
+    _admin: PermissionGuard<require::UserManage>,
+    csrf_token: CsrfToken,
+    flash: Option<FlashMessage>,
+    conn: DbConn,
+) -> Template {
     let context = match UserEntry::get_all(&conn) {
         Ok(users) => UserOverviewContext::view(users, flash.map(Message::from)),
         Err(e) => UserOverviewContext::error(Message::error(format!("DB Error: {}", e))),
     };
-    Template::render("user_overview", &context)
+    Template::render("user_overview", &csrf_token.context(context))
 }
 
 /// Deletes an user
 #[delete("/admin/user/delete/<id>")]
-pub fn delete(admin: AdminGuard, conn: DbConn, id: u32) -> ResultFlash<()> {
+pub fn delete(
+    admin: PermissionGuard<require::UserManage>,
+    _csrf: CsrfHeader,
+    conn: DbConn,
+    client: ClientInfo,
+    id: u32,
+) -> ResultFlash<()> {
     (admin.user.id != id).err_with(|| "Can't delete yourself".into_flash())?;
 
+    let user = UserEntry::get_by_id(&conn, id)
+        .map_err(|e| e.into_flash())?
+        .ok_or_else(|| "No user found".into_flash())?;
+
     UserSessionEntry::delete_by_user(&conn, id)
+        .and_then(|_| RecoveryCodeEntry::delete_by_user(&conn, id))
+        .and_then(|_| ApiTokenEntry::delete_by_user(&conn, id))
+        .and_then(|_| GuestCodeEntry::delete_by_user(&conn, id))
+        .and_then(|_| PasswordResetEntry::delete_by_user(&conn, id))
+        .and_then(|_| RoleEntry::delete_by_user(&conn, id))
+        .and_then(|_| PasskeyEntry::delete_by_user(&conn, id))
+        .and_then(|_| PasswordHistoryEntry::delete_by_user(&conn, id))
+        .and_then(|_| AccessWindowEntry::delete_by_user(&conn, id))
+        .and_then(|_| AccessExceptionEntry::delete_by_user(&conn, id))
+        .and_then(|_| DoorEntry::delete_by_user(&conn, id))
         .and_then(|_| UserEntry::<_>::delete_entry(&conn, id))
         .map_err(|e| e.into_flash())?;
+    audit::record(
+        &conn,
+        Some(&admin.user.name),
+        AuditAction::UserDelete,
+        Some(&user.name),
+        client.ip,
+    );
 
     Err(Flash::success((), "User deleted"))
 }
@@ -120,46 +225,73 @@ pub fn delete(admin: AdminGuard, conn: DbConn, id: u32) -> ResultFlash<()> {
 #[get("/admin/user/change/<id>")]
 pub fn get_change(
     user_guard: UserGuard,
+    csrf_token: CsrfToken,
     conn: DbConn,
     flash: Option<FlashMessage>,
     id: u32,
 ) -> Result<Template, Status> {
-    // An ordinary user is only allowed to modify himself
-    (user_guard.user.user_type.is_admin() || user_guard.user.id == id).err(Status::Forbidden)?;
+    // An user without the permission to administer users is only allowed to modify himself
+    (user_guard.has_permission(Permission::UserManage) || user_guard.user.id == id)
+        .err(Status::Forbidden)?;
 
     // Get all FlatEntrys to display them in a select-box
     let flats = match FlatEntry::get_all(&conn) {
         Err(e) => {
             return Ok(Template::render(
                 "user_details",
-                &UserDetailsContext::error(Message::error(e.to_string())),
+                &csrf_token.context(UserDetailsContext::error(Message::error(e.to_string()))),
             ))
         }
         Ok(flats) => flats,
     };
 
+    let is_admin = user_guard.has_permission(Permission::UserManage);
     // Get the UserEntry in order to know the old values
     let context = match UserEntry::get_by_id(&conn, id) {
+        // Only admins see and edit the access schedule
+        Ok(Some(user)) if is_admin => match AccessWindowEntry::get_by_user(&conn, id)
+            .and_then(|windows| Ok((windows, AccessExceptionEntry::get_by_user(&conn, id)?)))
+        {
+            Ok((windows, exceptions)) => UserDetailsContext::change(
+                flash.map(Message::from),
+                is_admin,
+                user_guard.user.id == id,
+                user,
+                flats,
+            )
+            .with_access_schedule(windows, exceptions),
+            Err(e) => UserDetailsContext::error(Message::error(e.to_string())),
+        },
         Ok(Some(user)) => UserDetailsContext::change(
             flash.map(Message::from),
-            user_guard.user.user_type.is_admin(),
+            is_admin,
+            user_guard.user.id == id,
             user,
             flats,
         ),
         Ok(None) => UserDetailsContext::error(Message::error("No user found".to_string())),
         Err(e) => UserDetailsContext::error(Message::error(e.to_string())),
     };
-    Ok(Template::render("user_details", &context))
+    Ok(Template::render(
+        "user_details",
+        &csrf_token.context(context),
+    ))
 }
 
 /// Post user data to modify the user
 #[post("/admin/user/change/<id>", data = "<user_data>", rank = 2)]
 pub fn admin_post_change_data(
-    _admin_guard: AdminGuard,
+    admin_guard: PermissionGuard<require::UserManage>,
+    csrf_token: CsrfToken,
     conn: DbConn,
+    client: ClientInfo,
     id: u32,
     user_data: Form<UserForm>,
 ) -> Result<Redirect, Flash<Redirect>> {
+    csrf_token
+        .verify(&user_data.csrf_token)
+        .map_err(|e| e.into_redirect_flash(uri!(get_change: id)))?;
+
     let unchanged_password = user_data.pw.is_empty();
     let changed_password = !unchanged_password;
 
@@ -169,23 +301,38 @@ pub fn admin_post_change_data(
         .not()
         .err_with(|| "Name is empty".into_redirect_flash(uri!(get_change: id)))?;
 
+    user_data.has_valid_period().err_with(|| {
+        "The validity has to end after its begin".into_redirect_flash(uri!(get_change: id))
+    })?;
+
     // If the password is updated, the two fields must be the same
     (unchanged_password || user_data.pw == user_data.pw_repeat)
         .err_with(|| "Passwords are not the same".into_redirect_flash(uri!(get_change: id)))?;
 
     if changed_password {
-        UserGuard::check_password(&user_data.pw)
+        UserGuard::check_new_password(&conn, id, &user_data.pw)
             .map_err(|e| e.into_redirect_flash(uri!(get_change: id)))?;
     }
 
-    let entry = user_data.into_inner().into_entry(id);
+    let mut entry = user_data.into_inner().into_entry(id);
+    // A password, which is reset by an admin, has to be changed by its user
+    entry.must_change_password = admin_guard.user.id != id;
 
     match changed_password {
-        true => entry.update(&conn),
+        true => entry
+            .update(&conn)
+            .and_then(|_| UserGuard::record_password(&conn, id, &entry.pw_hash)),
         false => entry.update_without_password(&conn),
     }
     .and_then(|_| UserSessionEntry::delete_by_user(&conn, entry.get_id()))
     .map_err(|e| e.into_redirect_flash(uri!(get_change: id)))?;
+    audit::record(
+        &conn,
+        Some(&admin_guard.user.name),
+        AuditAction::UserChange,
+        Some(&entry.name),
+        client.ip,
+    );
 
     return Ok(Redirect::to(uri!(get_users)));
 }
@@ -194,10 +341,16 @@ pub fn admin_post_change_data(
 #[post("/admin/user/change/<id>", data = "<user_data>")]
 pub fn user_post_change_data(
     user_guard: OnlyUserGuard,
+    csrf_token: CsrfToken,
     conn: DbConn,
+    client: ClientInfo,
     id: u32,
     user_data: Form<UserForm>,
 ) -> Result<Flash<Redirect>, Flash<Redirect>> {
+    csrf_token
+        .verify(&user_data.csrf_token)
+        .map_err(|e| e.into_redirect_flash(uri!(get_change: id)))?;
+
     // An ordinary user is only allowed to modify himself
     (user_guard.user.get_id() == id)
         .err_with(|| "Forbidden".into_redirect_flash(uri!(get_change: id)))?;
@@ -206,11 +359,15 @@ pub fn user_post_change_data(
     let changed_password = !unchanged_password;
 
     // A non-admin isn't allowed to change these fields
-    (user_data.user_type.is_none() && user_data.active.is_none() && user_data.flat_id.is_none())
-        .err_with(|| {
-            "Don't manipulate the user-type, active-Flag or flat-ID"
-                .into_redirect_flash(uri!(get_change: id))
-        })?;
+    (user_data.user_type.is_none()
+        && user_data.active.is_none()
+        && user_data.flat_id.is_none()
+        && user_data.valid_from.is_none()
+        && user_data.valid_until.is_none())
+    .err_with(|| {
+        "Don't manipulate the user-type, active-Flag, flat-ID or validity"
+            .into_redirect_flash(uri!(get_change: id))
+    })?;
 
     user_data
         .name
@@ -223,21 +380,224 @@ pub fn user_post_change_data(
         .err_with(|| "Passwords are not the same".into_redirect_flash(uri!(get_change: id)))?;
 
     if changed_password {
-        UserGuard::check_password(&user_data.pw)
+        UserGuard::check_new_password(&conn, id, &user_data.pw)
             .map_err(|e| e.into_redirect_flash(uri!(get_change: id)))?;
     }
 
+    // The name and password of an user of an external login provider are managed there
+    (!provisioning::is_external_user(&user_guard.user)
+        || (unchanged_password && user_data.name == user_guard.user.name))
+        .err_with(|| {
+            "The name and password are managed by an external login provider"
+                .into_redirect_flash(uri!(get_change: id))
+        })?;
+
     let entry = user_data.into_inner().into_entry(id);
 
     match changed_password {
-        true => entry.update_unprivileged(&conn),
+        true => entry
+            .update_unprivileged(&conn)
+            .and_then(|_| UserGuard::record_password(&conn, id, &entry.pw_hash)),
         false => entry.update_unprivileged_without_password(&conn),
     }
     .and_then(|_| UserSessionEntry::delete_by_user(&conn, user_guard.user.get_id()))
     .map_err(|e| e.into_redirect_flash(uri!(get_change: id)))?;
+    audit::record(
+        &conn,
+        Some(&user_guard.user.name),
+        AuditAction::UserChange,
+        Some(&entry.name),
+        client.ip,
+    );
 
     Ok(Flash::success(
         Redirect::to(uri!(get_login)),
         "Your account has been successfully updated. Please log in again.".to_string(),
     ))
 }
+
+/// Loads an user of the own flat, which the flat manager is allowed to administer
+fn get_flat_user(
+    conn: &DbConn,
+    flat_manager: &FlatManagerGuard,
+    id: u32,
+) -> Result<Option<UserEntry>, rusqlite::Error> {
+    Ok(UserEntry::get_by_id(conn, id)?.filter(|user| {
+        user.user_type == UserType::User
+            && user.flat.as_ref().map(Entry::get_id) == Some(flat_manager.flat.id)
+    }))
+}
+
+/// Shows all users of the flat manager's flat
+#[get("/flat/user")]
+pub fn flat_get_users(
+    flat_manager: FlatManagerGuard,
+    csrf_token: CsrfToken,
+    flash: Option<FlashMessage>,
+    conn: DbConn,
+) -> Template {
+    let context = match UserEntry::get_by_flat(&conn, flat_manager.flat.id) {
+        Ok(users) => UserOverviewContext::flat_view(users, flash.map(Message::from)),
+        Err(e) => UserOverviewContext::flat_error(Message::error(format!("DB Error: {}", e))),
+    };
+    Template::render("user_overview", &csrf_token.context(context))
+}
+
+/// Get the form to create an user of the flat manager's flat
+#[get("/flat/user/create")]
+pub fn flat_get_create(
+    _flat_manager: FlatManagerGuard,
+    csrf_token: CsrfToken,
+    flash: Option<FlashMessage>,
+) -> Template {
+    let context = UserDetailsContext::flat_create(flash.map(Message::from));
+    Template::render("user_details", &csrf_token.context(context))
+}
+
+/// Post the user-data in order to create an user of the flat manager's flat
+#[post("/flat/user/create", data = "<user_data>")]
+pub fn flat_post_create_data(
+    user_data: Form<UserForm>,
+    flat_manager: FlatManagerGuard,
+    csrf_token: CsrfToken,
+    conn: DbConn,
+    client: ClientInfo,
+) -> Result<Redirect, Flash<Redirect>> {
+    csrf_token
+        .verify(&user_data.csrf_token)
+        .map_err(|e| e.into_redirect_flash(uri!(flat_get_create)))?;
+
+    // A flat manager isn't allowed to choose these fields
+    (user_data.user_type.is_none() && user_data.flat_id.is_none()).err_with(|| {
+        "Don't manipulate the user-type or flat-ID".into_redirect_flash(uri!(flat_get_create))
+    })?;
+
+    user_data
+        .name
+        .is_empty()
+        .not()
+        .err_with(|| "Name is empty".into_redirect_flash(uri!(flat_get_create)))?;
+
+    user_data.has_valid_period().err_with(|| {
+        "The validity has to end after its begin".into_redirect_flash(uri!(flat_get_create))
+    })?;
+
+    user_data
+        .pw
+        .is_empty()
+        .not()
+        .err_with(|| "Password is empty".into_redirect_flash(uri!(flat_get_create)))?;
+
+    (user_data.pw == user_data.pw_repeat)
+        .err_with(|| "Passwords are not the same".into_redirect_flash(uri!(flat_get_create)))?;
+
+    UserGuard::check_password(&user_data.pw)
+        .map_err(|e| e.into_redirect_flash(uri!(flat_get_create)))?;
+
+    let mut entry = user_data.into_inner().into_insertable();
+    entry.user_type = UserType::User;
+    entry.flat = Some(flat_manager.flat.id);
+    let user = entry
+        .create(&conn)
+        .and_then(|user| UserGuard::record_password(&conn, user.id, &user.pw_hash).map(|_| user))
+        .map_err(|e| e.into_redirect_flash(uri!(flat_get_create)))?;
+    audit::record(
+        &conn,
+        Some(&flat_manager.user.name),
+        AuditAction::UserCreate,
+        Some(&user.name),
+        client.ip,
+    );
+
+    Ok(Redirect::to(uri!(flat_get_users)))
+}
+
+/// Get the form to modify an user of the flat manager's flat
+#[get("/flat/user/change/<id>")]
+pub fn flat_get_change(
+    flat_manager: FlatManagerGuard,
+    csrf_token: CsrfToken,
+    conn: DbConn,
+    flash: Option<FlashMessage>,
+    id: u32,
+) -> Result<Template, Status> {
+    let user = get_flat_user(&conn, &flat_manager, id)
+        .map_err(|_| Status::InternalServerError)?
+        .ok_or(Status::Forbidden)?;
+
+    let context = UserDetailsContext::flat_change(flash.map(Message::from), user);
+    Ok(Template::render(
+        "user_details",
+        &csrf_token.context(context),
+    ))
+}
+
+/// Post user data to modify an user of the flat manager's flat
+#[post("/flat/user/change/<id>", data = "<user_data>")]
+pub fn flat_post_change_data(
+    flat_manager: FlatManagerGuard,
+    csrf_token: CsrfToken,
+    conn: DbConn,
+    client: ClientInfo,
+    id: u32,
+    user_data: Form<UserForm>,
+) -> Result<Redirect, Flash<Redirect>> {
+    csrf_token
+        .verify(&user_data.csrf_token)
+        .map_err(|e| e.into_redirect_flash(uri!(flat_get_change: id)))?;
+
+    get_flat_user(&conn, &flat_manager, id)
+        .map_err(|e| e.into_redirect_flash(uri!(flat_get_users)))?
+        .ok_or_else(|| "No user found".into_redirect_flash(uri!(flat_get_users)))?;
+
+    // A flat manager isn't allowed to change these fields
+    (user_data.user_type.is_none() && user_data.flat_id.is_none()).err_with(|| {
+        "Don't manipulate the user-type or flat-ID".into_redirect_flash(uri!(flat_get_change: id))
+    })?;
+
+    let unchanged_password = user_data.pw.is_empty();
+    let changed_password = !unchanged_password;
+
+    user_data
+        .name
+        .is_empty()
+        .not()
+        .err_with(|| "Name is empty".into_redirect_flash(uri!(flat_get_change: id)))?;
+
+    user_data.has_valid_period().err_with(|| {
+        "The validity has to end after its begin".into_redirect_flash(uri!(flat_get_change: id))
+    })?;
+
+    // If the password is updated, the two fields must be the same
+    (unchanged_password || user_data.pw == user_data.pw_repeat)
+        .err_with(|| "Passwords are not the same".into_redirect_flash(uri!(flat_get_change: id)))?;
+
+    if changed_password {
+        UserGuard::check_new_password(&conn, id, &user_data.pw)
+            .map_err(|e| e.into_redirect_flash(uri!(flat_get_change: id)))?;
+    }
+
+    let mut entry = user_data.into_inner().into_entry(id);
+    entry.user_type = UserType::User;
+    entry.flat = Some(flat_manager.flat.id);
+    // A password, which is reset by the flat manager, has to be changed by its user
+    entry.must_change_password = changed_password;
+
+    match changed_password {
+        true => entry
+            .update(&conn)
+            .and_then(|_| UserGuard::record_password(&conn, id, &entry.pw_hash)),
+        false => entry.update_without_password(&conn),
+    }
+    .and_then(|_| UserSessionEntry::delete_by_user(&conn, entry.get_id()))
+    .map_err(|e| e.into_redirect_flash(uri!(flat_get_change: id)))?;
+    audit::record(
+        &conn,
+        Some(&flat_manager.user.name),
+        AuditAction::UserChange,
+        Some(&entry.name),
+        client.ip,
+    );
+
+    Ok(Redirect::to(uri!(flat_get_users)))
+}
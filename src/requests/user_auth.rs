@@ -1,27 +1,48 @@
 use super::index_view::*;
-use super::{ErrorIntoFlash, ResultFlash};
+use super::two_factor::*;
+use super::{ErrorIntoFlash, ResultFlash, ResultJson};
 use crate::db_entry::DbConn;
-use crate::template_contexts::{LoginContext, Message};
-use crate::utils::guards::UserGuard;
+use crate::template_contexts::{LoginContext, LoginRedirectContext, Message};
+use crate::utils::config::CONFIG;
+use crate::utils::csrf::{CsrfHeader, CsrfToken};
+use crate::utils::guards::{Authentication, ClientInfo, PasswordChangeGuard, UserGuard};
 use rocket::http::Cookies;
 use rocket::request::{FlashMessage, Form};
 use rocket::response::{Flash, Redirect};
+use rocket_contrib::json::Json;
 use rocket_contrib::templates::Template;
+use serde::Deserialize;
+use webauthn_rs::prelude::{PublicKeyCredential, RequestChallengeResponse};
 
 /// Struct with all login form data.
 #[derive(FromForm)]
 pub struct LoginForm {
     name: String,
     pw: String,
+    csrf_token: String,
+}
+
+/// The user, who wants to log in with a passkey.
+#[derive(Deserialize)]
+pub struct PasskeyLoginData {
+    name: String,
 }
 
 /// Get the login form
 #[get("/login")]
-pub fn get_login(flash: Option<FlashMessage>) -> Template {
+pub fn get_login(csrf_token: CsrfToken, flash: Option<FlashMessage>) -> Template {
     let context = LoginContext {
         message: flash.map(Message::from),
+        oidc_login_url: CONFIG
+            .oidc
+            .as_ref()
+            .map(|_| uri!(get_oidc_login).to_string()),
+        passkey_login_url: CONFIG
+            .webauthn
+            .as_ref()
+            .map(|_| uri!(post_passkey_login).to_string()),
     };
-    Template::render("login", &context)
+    Template::render("login", &csrf_token.context(context))
 }
 
 /// Post the user-data to do the login logic
@@ -29,18 +50,125 @@ pub fn get_login(flash: Option<FlashMessage>) -> Template {
 pub fn post_login_data(
     user_data: Form<LoginForm>,
     conn: DbConn,
+    csrf_token: CsrfToken,
     cookies: Cookies,
+    client: ClientInfo,
 ) -> Result<Redirect, Flash<Redirect>> {
-    UserGuard::authenticate(&conn, cookies, &user_data.name, &user_data.pw)
+    csrf_token
+        .verify(&user_data.csrf_token)
+        .map_err(|e| e.into_redirect_flash(uri!(get_login)))?;
+
+    let authentication =
+        UserGuard::authenticate(&conn, cookies, &client, &user_data.name, &user_data.pw)
+            .map_err(|e| e.into_redirect_flash(uri!(get_login)))?;
+
+    Ok(match authentication {
+        Authentication::LoggedIn => Redirect::to(uri!(get_user_index_view)),
+        Authentication::TwoFactorPending => Redirect::to(uri!(get_two_factor_login)),
+    })
+}
+
+/// Redirect to the identity provider for the OpenID Connect login
+#[get("/login/oidc")]
+pub fn get_oidc_login(cookies: Cookies) -> Result<Redirect, Flash<Redirect>> {
+    let config = CONFIG.oidc.as_ref().ok_or_else(|| {
+        "The OpenID Connect login is disabled".into_redirect_flash(uri!(get_login))
+    })?;
+
+    let url = UserGuard::begin_oidc_login(config, cookies)
         .map_err(|e| e.into_redirect_flash(uri!(get_login)))?;
 
-    Ok(Redirect::to(uri!(get_user_index_view)))
+    Ok(Redirect::to(url.to_string()))
+}
+
+/// The callback of the identity provider with the authorization code
+#[get("/login/oidc/callback?<code>&<state>&<error>")]
+pub fn get_oidc_callback(
+    code: Option<String>,
+    state: Option<String>,
+    error: Option<String>,
+    conn: DbConn,
+    cookies: Cookies,
+    client: ClientInfo,
+) -> Result<Template, Flash<Redirect>> {
+    let config = CONFIG.oidc.as_ref().ok_or_else(|| {
+        "The OpenID Connect login is disabled".into_redirect_flash(uri!(get_login))
+    })?;
+
+    if let Some(error) = error {
+        return Err(format!("The identity provider denied the login: {}", error)
+            .into_redirect_flash(uri!(get_login)));
+    }
+    let (code, state) = code
+        .zip(state)
+        .ok_or_else(|| "The callback has no code".into_redirect_flash(uri!(get_login)))?;
+
+    let authentication =
+        UserGuard::authenticate_oidc(&conn, config, cookies, &client, code, &state)
+            .map_err(|e| e.into_redirect_flash(uri!(get_login)))?;
+
+    let redirect_url = match authentication {
+        Authentication::LoggedIn => uri!(get_user_index_view),
+        Authentication::TwoFactorPending => uri!(get_two_factor_login),
+    };
+    Ok(Template::render(
+        "login_redirect",
+        &LoginRedirectContext {
+            redirect_url: redirect_url.to_string(),
+        },
+    ))
+}
+
+/// Returns the challenge, which has to be signed with a passkey of the user
+#[post("/login/passkey", data = "<login_data>")]
+pub fn post_passkey_login(
+    login_data: Json<PasskeyLoginData>,
+    _csrf: CsrfHeader,
+    conn: DbConn,
+    cookies: Cookies,
+    client: ClientInfo,
+) -> ResultJson<RequestChallengeResponse> {
+    let config = CONFIG
+        .webauthn
+        .as_ref()
+        .ok_or_else(|| "The passkeys are disabled".into_bad_request_flash())?;
+
+    UserGuard::begin_passkey_login(&conn, config, cookies, &client, &login_data.name)
+        .map(Json)
+        .map_err(|e| e.into_bad_request_flash())
+}
+
+/// Post the signed challenge to do the login logic, returns the URL of the next page
+#[post("/login/passkey/finish", data = "<credential>")]
+pub fn post_passkey_login_finish(
+    credential: Json<PublicKeyCredential>,
+    _csrf: CsrfHeader,
+    conn: DbConn,
+    cookies: Cookies,
+    client: ClientInfo,
+) -> ResultJson<LoginRedirectContext> {
+    let config = CONFIG
+        .webauthn
+        .as_ref()
+        .ok_or_else(|| "The passkeys are disabled".into_bad_request_flash())?;
+
+    UserGuard::authenticate_passkey(&conn, config, cookies, &client, &credential)
+        .map_err(|e| e.into_bad_request_flash())?;
+
+    Ok(Json(LoginRedirectContext {
+        redirect_url: uri!(get_user_index_view).to_string(),
+    }))
 }
 
 /// Get logout to destroy the user-cookie
 #[get("/logout")]
-pub fn get_logout(user_guard: UserGuard, conn: DbConn, cookies: Cookies) -> ResultFlash<Redirect> {
-    user_guard
+pub fn get_logout(
+    password_change_guard: PasswordChangeGuard,
+    conn: DbConn,
+    cookies: Cookies,
+) -> ResultFlash<Redirect> {
+    password_change_guard
+        .user_guard
         .destroy_user_session(&conn, cookies)
         .map_err(|e| e.into_redirect_flash(uri!(get_user_index_view)))?;
 
@@ -0,0 +1,104 @@
+use super::{ErrorIntoFlash, ResultFlash};
+use crate::db_entry::{DbConn, Entry, UserEntry, UserSessionEntry};
+use crate::template_contexts::{Message, SessionOverviewContext};
+use crate::utils::csrf::{CsrfHeader, CsrfToken};
+use crate::utils::guards::{require, PermissionGuard, UserGuard};
+use bool_ext::BoolExt;
+use rocket::request::FlashMessage;
+use rocket::response::Flash;
+use rocket_contrib::templates::Template;
+
+/// Shows the active sessions of the user
+#[get("/user/session")]
+pub fn get_sessions(
+    user_guard: UserGuard,
+    csrf_token: CsrfToken,
+    flash: Option<FlashMessage>,
+    conn: DbConn,
+) -> Template {
+    let context = match UserSessionEntry::get_by_user(&conn, user_guard.user.id) {
+        Ok(sessions) => SessionOverviewContext::view(
+            flash.map(Message::from),
+            user_guard.is_admin(),
+            false,
+            &user_guard.user,
+            Some(user_guard.session_id()),
+            sessions,
+        ),
+        Err(e) => SessionOverviewContext::error(
+            Message::error(format!("DB Error: {}", e)),
+            user_guard.is_admin(),
+        ),
+    };
+    Template::render("session_overview", &csrf_token.context(context))
+}
+
+/// Revokes an own session, e.g. of a lost phone
+#[delete("/user/session/delete/<id>")]
+pub fn delete(user_guard: UserGuard, _csrf: CsrfHeader, conn: DbConn, id: u32) -> ResultFlash<()> {
+    let session = UserSessionEntry::get_by_id(&conn, id)
+        .map_err(|e| e.into_flash())?
+        .ok_or_else(|| "No session found".into_flash())?;
+
+    // An ordinary user is only allowed to revoke his own sessions
+    (session.user == user_guard.user.id).err_with(|| "No session found".into_flash())?;
+
+    session.delete(&conn).map_err(|e| e.into_flash())?;
+
+    Ok(Flash::success((), "Session revoked"))
+}
+
+/// Shows the active sessions of an user
+#[get("/admin/user/session/<id>")]
+pub fn admin_get_sessions(
+    _admin: PermissionGuard<require::UserManage>,
+    csrf_token: CsrfToken,
+    flash: Option<FlashMessage>,
+    conn: DbConn,
+    id: u32,
+) -> Template {
+    let context = match UserEntry::get_by_id(&conn, id).and_then(|user| {
+        user.map(|user| UserSessionEntry::get_by_user(&conn, id).map(|sessions| (user, sessions)))
+            .transpose()
+    }) {
+        Ok(Some((user, sessions))) => SessionOverviewContext::view(
+            flash.map(Message::from),
+            true,
+            true,
+            &user,
+            None,
+            sessions,
+        ),
+        Ok(None) => {
+            SessionOverviewContext::error(Message::error("No user found".to_string()), true)
+        }
+        Err(e) => SessionOverviewContext::error(Message::error(format!("DB Error: {}", e)), true),
+    };
+    Template::render("session_overview", &csrf_token.context(context))
+}
+
+/// Revokes a session of any user
+#[delete("/admin/session/delete/<id>")]
+pub fn admin_delete(
+    _admin: PermissionGuard<require::UserManage>,
+    _csrf: CsrfHeader,
+    conn: DbConn,
+    id: u32,
+) -> ResultFlash<()> {
+    UserSessionEntry::<_>::delete_entry(&conn, id).map_err(|e| e.into_flash())?;
+
+    Ok(Flash::success((), "Session revoked"))
+}
+
+/// Logs an user out on every device
+#[delete("/admin/user/session/<id>")]
+pub fn admin_delete_all(
+    _admin: PermissionGuard<require::UserManage>,
+    _csrf: CsrfHeader,
+    conn: DbConn,
+    id: u32,
+) -> ResultFlash<()> {
+    UserSessionEntry::delete_by_user(&conn, id).map_err(|e| e.into_flash())?;
+
+    Ok(Flash::success((), "User logged out everywhere"))
+}
@@ -0,0 +1,76 @@
+use super::{AdminNav, Message};
+use crate::db_entry::{ApiScope, ApiTokenEntry, Permission, UserEntry};
+use crate::requests::api_token::*;
+use serde::Serialize;
+
+#[cfg(test)]
+#[path = "./api_token_contexts_test.rs"]
+mod api_token_contexts_test;
+
+#[derive(Serialize)]
+pub struct ApiTokenOverviewContext {
+    pub message: Option<Message>,
+    pub nav: Option<AdminNav>,
+    pub admin_view: bool,
+    pub create_token_url: String,
+    /// The scopes, which the user may select
+    pub scopes: Vec<ApiScope>,
+    /// The plain token, which is only shown after the creation
+    pub new_token: Option<String>,
+    pub tokens: Option<Vec<(ApiTokenEntry, String)>>,
+}
+
+impl ApiTokenOverviewContext {
+    pub fn view(
+        message: Option<Message>,
+        user: &UserEntry,
+        permissions: &[Permission],
+        tokens: Vec<ApiTokenEntry>,
+        new_token: Option<String>,
+    ) -> Self {
+        Self {
+            message,
+            nav: permissions
+                .iter()
+                .any(Permission::is_administrative)
+                .then(AdminNav::new),
+            admin_view: false,
+            create_token_url: uri!(post_create).to_string(),
+            scopes: ApiScope::get_list()
+                .into_iter()
+                .filter(|scope| scope.is_allowed_for(permissions))
+                .collect(),
+            new_token,
+            tokens: Some(
+                tokens
+                    .into_iter()
+                    .map(|token| (token, user.name.clone()))
+                    .collect(),
+            ),
+        }
+    }
+
+    pub fn admin_view(message: Option<Message>, tokens: Vec<(ApiTokenEntry, String)>) -> Self {
+        Self {
+            message,
+            nav: Some(AdminNav::new()),
+            admin_view: true,
+            create_token_url: uri!(post_create).to_string(),
+            scopes: Vec::new(),
+            new_token: None,
+            tokens: Some(tokens),
+        }
+    }
+
+    pub fn error(message: Message, is_admin: bool) -> Self {
+        Self {
+            message: Some(message),
+            nav: is_admin.then(AdminNav::new),
+            admin_view: false,
+            create_token_url: uri!(post_create).to_string(),
+            scopes: Vec::new(),
+            new_token: None,
+            tokens: None,
+        }
+    }
+}
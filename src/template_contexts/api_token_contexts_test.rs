@@ -0,0 +1,75 @@
+use super::*;
+use crate::db_entry::UserType;
+use crate::utils::csrf::CsrfToken;
+use rocket::local::Client;
+use rocket_contrib::templates::Template;
+
+#[test]
+fn api_token_overview_with_empty_view() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "api_token_overview",
+        &CsrfToken("token".to_string()).context(ApiTokenOverviewContext::view(
+            None,
+            &UserEntry::default(),
+            &Permission::defaults_for(UserType::User),
+            Vec::new(),
+            None,
+        )),
+    )
+    .unwrap();
+}
+
+#[test]
+fn api_token_overview_with_new_token() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "api_token_overview",
+        &CsrfToken("token".to_string()).context(ApiTokenOverviewContext::view(
+            None,
+            &UserEntry::default(),
+            &Permission::defaults_for(UserType::User),
+            vec![ApiTokenEntry::default()],
+            Some("secret".to_string()),
+        )),
+    )
+    .unwrap();
+}
+
+#[test]
+fn api_token_overview_with_admin_view() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "api_token_overview",
+        &CsrfToken("token".to_string()).context(ApiTokenOverviewContext::admin_view(
+            Some(Message::error("21".to_string())),
+            vec![(ApiTokenEntry::default(), "Alice".to_string())],
+        )),
+    )
+    .unwrap();
+}
+
+#[test]
+fn api_token_overview_with_error() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "api_token_overview",
+        &CsrfToken("token".to_string()).context(ApiTokenOverviewContext::error(
+            Message::error("21".to_string()),
+            false,
+        )),
+    )
+    .unwrap();
+}
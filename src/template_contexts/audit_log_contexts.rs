@@ -0,0 +1,65 @@
+use super::{AdminNav, Message};
+use crate::db_entry::{AuditAction, AuditLogEntry};
+use crate::requests::audit_log::*;
+use serde::Serialize;
+
+#[cfg(test)]
+#[path = "./audit_log_contexts_test.rs"]
+mod audit_log_contexts_test;
+
+#[derive(Serialize)]
+pub struct AuditLogContext {
+    pub message: Option<Message>,
+    pub nav: AdminNav,
+    pub actions: Vec<(u16, String)>,
+    /// The current filters to fill the form
+    pub action: Option<u16>,
+    pub actor: String,
+    pub from: String,
+    pub until: String,
+    /// The export URLs with the current filters
+    pub export_csv_url: String,
+    pub export_json_url: String,
+    pub limit: u32,
+    pub entries: Option<Vec<AuditLogEntry>>,
+}
+
+impl AuditLogContext {
+    /// The query is the one of the view, which is passed to the export
+    pub fn view(
+        message: Option<Message>,
+        filter: &AuditLogFilter,
+        query: Option<&str>,
+        entries: Vec<AuditLogEntry>,
+    ) -> Self {
+        Self {
+            entries: Some(entries),
+            ..Self::error_or_empty(message, filter, query)
+        }
+    }
+
+    pub fn error(message: Message, filter: &AuditLogFilter, query: Option<&str>) -> Self {
+        Self::error_or_empty(Some(message), filter, query)
+    }
+
+    fn error_or_empty(
+        message: Option<Message>,
+        filter: &AuditLogFilter,
+        query: Option<&str>,
+    ) -> Self {
+        let query = query.map(|query| format!("?{}", query)).unwrap_or_default();
+        Self {
+            message,
+            nav: AdminNav::new(),
+            actions: AuditAction::get_list(),
+            action: filter.action.map(u16::from),
+            actor: filter.actor.clone().unwrap_or_default(),
+            from: filter.from.clone().unwrap_or_default(),
+            until: filter.until.clone().unwrap_or_default(),
+            export_csv_url: format!("{}{}", uri!(get_export_csv: _), query),
+            export_json_url: format!("{}{}", uri!(get_export_json: _), query),
+            limit: AUDIT_LOG_VIEW_LIMIT,
+            entries: None,
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use super::*;
+use rocket::local::Client;
+use rocket_contrib::templates::Template;
+
+fn filter() -> AuditLogFilter {
+    AuditLogFilter {
+        action: Some(AuditAction::Login),
+        actor: Some("admin".to_string()),
+        from: Some("2020-01-01".to_string()),
+        until: None,
+    }
+}
+
+#[test]
+fn audit_log_with_empty_view() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "audit_log",
+        &AuditLogContext::view(None, &filter(), None, Vec::new()),
+    )
+    .unwrap();
+}
+
+#[test]
+fn audit_log_with_view() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+    let context = AuditLogContext::view(
+        None,
+        &filter(),
+        Some("action=1&actor=admin&from=2020-01-01"),
+        vec![AuditLogEntry::default()],
+    );
+    assert_eq!(
+        "/admin/audit_log/export/csv?action=1&actor=admin&from=2020-01-01",
+        context.export_csv_url
+    );
+
+    Template::show(client.rocket(), "audit_log", &context).unwrap();
+}
+
+#[test]
+fn audit_log_with_error() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "audit_log",
+        &AuditLogContext::error(Message::error("21".to_string()), &filter(), None),
+    )
+    .unwrap();
+}
@@ -0,0 +1,107 @@
+use super::{AdminNav, Message};
+use crate::db_entry::{DoorEntry, FlatEntry, UserEntry};
+use crate::requests::door::*;
+use serde::Serialize;
+
+#[cfg(test)]
+#[path = "./door_contexts_test.rs"]
+mod door_contexts_test;
+
+#[derive(Serialize)]
+pub struct DoorOverviewContext {
+    pub message: Option<Message>,
+    pub nav: AdminNav,
+    pub create_door_url: String,
+    pub doors: Option<Vec<DoorEntry>>,
+}
+
+impl DoorOverviewContext {
+    pub fn view(doors: Vec<DoorEntry>, message: Option<Message>) -> Self {
+        Self {
+            message,
+            nav: AdminNav::new(),
+            create_door_url: uri!(get_create).to_string(),
+            doors: Some(doors),
+        }
+    }
+
+    pub fn error(message: Message) -> Self {
+        Self {
+            message: Some(message),
+            nav: AdminNav::new(),
+            create_door_url: uri!(get_create).to_string(),
+            doors: None,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct DoorDetailsContext {
+    pub message: Option<Message>,
+    pub nav: AdminNav,
+    pub title: String,
+    pub door: Option<DoorEntry>,
+    /// The flats, whose users may open the door
+    pub granted_flats: Vec<FlatEntry>,
+    /// The flats, which may be granted the door
+    pub flats: Vec<FlatEntry>,
+    /// The users, who may open the door regardless of their flat
+    pub granted_users: Vec<UserEntry>,
+    /// The users, who may be granted the door
+    pub users: Vec<UserEntry>,
+    pub add_flat_url: Option<String>,
+    pub add_user_url: Option<String>,
+}
+
+impl DoorDetailsContext {
+    pub fn error(error: Message) -> Self {
+        Self {
+            message: Some(error),
+            nav: AdminNav::new(),
+            title: String::new(),
+            door: None,
+            granted_flats: Vec::new(),
+            flats: Vec::new(),
+            granted_users: Vec::new(),
+            users: Vec::new(),
+            add_flat_url: None,
+            add_user_url: None,
+        }
+    }
+
+    pub fn create(error: Option<Message>) -> Self {
+        Self {
+            message: error,
+            nav: AdminNav::new(),
+            title: "Create".to_string(),
+            door: None,
+            granted_flats: Vec::new(),
+            flats: Vec::new(),
+            granted_users: Vec::new(),
+            users: Vec::new(),
+            add_flat_url: None,
+            add_user_url: None,
+        }
+    }
+
+    /// The flats and users are split into the granted and the remaining ones
+    pub fn change(
+        error: Option<Message>,
+        door: DoorEntry,
+        (granted_flats, flats): (Vec<FlatEntry>, Vec<FlatEntry>),
+        (granted_users, users): (Vec<UserEntry>, Vec<UserEntry>),
+    ) -> Self {
+        Self {
+            message: error,
+            nav: AdminNav::new(),
+            title: "Change".to_string(),
+            add_flat_url: Some(uri!(post_add_flat: door.id).to_string()),
+            add_user_url: Some(uri!(post_add_user: door.id).to_string()),
+            door: Some(door),
+            granted_flats,
+            flats,
+            granted_users,
+            users,
+        }
+    }
+}
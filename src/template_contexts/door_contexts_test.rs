@@ -0,0 +1,81 @@
+use super::*;
+use crate::utils::csrf::CsrfToken;
+use rocket::local::Client;
+use rocket_contrib::templates::Template;
+
+#[test]
+fn door_overview_with_view() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "door_overview",
+        &CsrfToken("token".to_string())
+            .context(DoorOverviewContext::view(vec![DoorEntry::default()], None)),
+    )
+    .unwrap();
+}
+
+#[test]
+fn door_overview_with_error() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "door_overview",
+        &CsrfToken("token".to_string())
+            .context(DoorOverviewContext::error(Message::error("21".to_string()))),
+    )
+    .unwrap();
+}
+
+#[test]
+fn door_details_with_error() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "door_details",
+        &CsrfToken("token".to_string())
+            .context(DoorDetailsContext::error(Message::error("21".to_string()))),
+    )
+    .unwrap();
+}
+
+#[test]
+fn door_create() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "door_details",
+        &CsrfToken("token".to_string()).context(DoorDetailsContext::create(None)),
+    )
+    .unwrap();
+}
+
+#[test]
+fn door_change() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "door_details",
+        &CsrfToken("token".to_string()).context(DoorDetailsContext::change(
+            Some(Message::error("21".to_string())),
+            DoorEntry {
+                sensor_pin: Some(1),
+                pulse_pattern: None,
+                ..DoorEntry::default()
+            },
+            (vec![FlatEntry::default()], vec![FlatEntry::default()]),
+            (vec![UserEntry::default()], vec![UserEntry::default()]),
+        )),
+    )
+    .unwrap();
+}
@@ -0,0 +1,77 @@
+use super::{AdminNav, Message};
+use crate::db_entry::{DoorEventEntry, DoorEventOutcome, DoorEventSource};
+use crate::requests::door_history::*;
+use serde::Serialize;
+
+#[cfg(test)]
+#[path = "./door_history_contexts_test.rs"]
+mod door_history_contexts_test;
+
+#[derive(Serialize)]
+pub struct DoorHistoryContext {
+    pub message: Option<Message>,
+    /// Only set for admins, who see the history of all flats
+    pub nav: Option<AdminNav>,
+    pub sources: Vec<(u16, String)>,
+    pub outcomes: Vec<(u16, String)>,
+    /// The current filters to fill the form
+    pub door: String,
+    pub user: String,
+    pub source: Option<u16>,
+    pub outcome: Option<u16>,
+    pub from: String,
+    pub until: String,
+    /// The export URL with the current filters, only set for admins
+    pub export_csv_url: Option<String>,
+    pub limit: u32,
+    pub events: Option<Vec<DoorEventEntry>>,
+}
+
+impl DoorHistoryContext {
+    /// The query is the one of the view, which is passed to the export
+    pub fn view(
+        message: Option<Message>,
+        is_admin: bool,
+        filter: &DoorHistoryFilter,
+        query: Option<&str>,
+        events: Vec<DoorEventEntry>,
+    ) -> Self {
+        Self {
+            events: Some(events),
+            ..Self::error_or_empty(message, is_admin, filter, query)
+        }
+    }
+
+    pub fn error(
+        message: Message,
+        is_admin: bool,
+        filter: &DoorHistoryFilter,
+        query: Option<&str>,
+    ) -> Self {
+        Self::error_or_empty(Some(message), is_admin, filter, query)
+    }
+
+    fn error_or_empty(
+        message: Option<Message>,
+        is_admin: bool,
+        filter: &DoorHistoryFilter,
+        query: Option<&str>,
+    ) -> Self {
+        let query = query.map(|query| format!("?{}", query)).unwrap_or_default();
+        Self {
+            message,
+            nav: is_admin.then(AdminNav::new),
+            sources: DoorEventSource::get_list(),
+            outcomes: DoorEventOutcome::get_list(),
+            door: filter.door.clone().unwrap_or_default(),
+            user: filter.user.clone().unwrap_or_default(),
+            source: filter.source.map(u16::from),
+            outcome: filter.outcome.map(u16::from),
+            from: filter.from.clone().unwrap_or_default(),
+            until: filter.until.clone().unwrap_or_default(),
+            export_csv_url: is_admin.then(|| format!("{}{}", uri!(get_export_csv: _), query)),
+            limit: DOOR_HISTORY_VIEW_LIMIT,
+            events: None,
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use super::*;
+use rocket::local::Client;
+use rocket_contrib::templates::Template;
+
+fn filter() -> DoorHistoryFilter {
+    DoorHistoryFilter {
+        door: Some("Gate".to_string()),
+        user: None,
+        source: Some(DoorEventSource::Api),
+        outcome: Some(DoorEventOutcome::Refused),
+        from: Some("2020-01-01".to_string()),
+        until: None,
+    }
+}
+
+#[test]
+fn door_history_with_admin_view() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+    let context = DoorHistoryContext::view(
+        None,
+        true,
+        &filter(),
+        Some("door=Gate&source=2&outcome=3&from=2020-01-01"),
+        vec![DoorEventEntry::default()],
+    );
+    assert_eq!(
+        Some("/admin/door_history/export/csv?door=Gate&source=2&outcome=3&from=2020-01-01"),
+        context.export_csv_url.as_deref()
+    );
+
+    Template::show(client.rocket(), "door_history", &context).unwrap();
+}
+
+#[test]
+fn door_history_with_flat_view() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+    let context = DoorHistoryContext::view(
+        None,
+        false,
+        &filter(),
+        None,
+        vec![DoorEventEntry {
+            door: None,
+            user: None,
+            ..Default::default()
+        }],
+    );
+    assert!(context.nav.is_none());
+    assert!(context.export_csv_url.is_none());
+
+    Template::show(client.rocket(), "door_history", &context).unwrap();
+}
+
+#[test]
+fn door_history_with_error() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "door_history",
+        &DoorHistoryContext::error(Message::error("21".to_string()), false, &filter(), None),
+    )
+    .unwrap();
+}
@@ -1,4 +1,5 @@
 use super::*;
+use crate::utils::csrf::CsrfToken;
 use rocket::local::Client;
 use rocket_contrib::templates::Template;
 
@@ -65,7 +66,8 @@ fn user_details_with_error() {
     Template::show(
         client.rocket(),
         "flat_details",
-        &FlatDetailsContext::error(Message::error("21".to_string())),
+        &CsrfToken("token".to_string())
+            .context(FlatDetailsContext::error(Message::error("21".to_string()))),
     )
     .unwrap();
 }
@@ -78,7 +80,7 @@ fn user_create() {
     Template::show(
         client.rocket(),
         "flat_details",
-        &FlatDetailsContext::create(None),
+        &CsrfToken("token".to_string()).context(FlatDetailsContext::create(None)),
     )
     .unwrap();
 }
@@ -91,7 +93,9 @@ fn user_create_with_error() {
     Template::show(
         client.rocket(),
         "flat_details",
-        &FlatDetailsContext::create(Some(Message::error("21".to_string()))),
+        &CsrfToken("token".to_string()).context(FlatDetailsContext::create(Some(Message::error(
+            "21".to_string(),
+        )))),
     )
     .unwrap();
 }
@@ -104,7 +108,8 @@ fn user_change() {
     Template::show(
         client.rocket(),
         "flat_details",
-        &FlatDetailsContext::change(None, FlatEntry::default()),
+        &CsrfToken("token".to_string())
+            .context(FlatDetailsContext::change(None, FlatEntry::default())),
     )
     .unwrap();
 }
@@ -117,7 +122,10 @@ fn user_change_with_error() {
     Template::show(
         client.rocket(),
         "flat_details",
-        &FlatDetailsContext::change(Some(Message::error("21".to_string())), FlatEntry::default()),
+        &CsrfToken("token".to_string()).context(FlatDetailsContext::change(
+            Some(Message::error("21".to_string())),
+            FlatEntry::default(),
+        )),
     )
     .unwrap();
 }
@@ -0,0 +1,73 @@
+use super::{AdminNav, Message};
+use crate::db_entry::{DoorEntry, GuestCodeEntry, GuestRedemptionEntry};
+use crate::requests::guest_code::*;
+use serde::Serialize;
+
+#[cfg(test)]
+#[path = "./guest_code_contexts_test.rs"]
+mod guest_code_contexts_test;
+
+#[derive(Serialize)]
+pub struct GuestCodeOverviewContext {
+    pub message: Option<Message>,
+    pub nav: Option<AdminNav>,
+    /// Whether the user belongs to a flat and may open one of the doors, which is required to create guest codes
+    pub can_create: bool,
+    /// The doors, which the user may choose for a new guest code
+    pub doors: Vec<DoorEntry>,
+    pub create_guest_code_url: String,
+    /// The plain code, which is only shown after the creation
+    pub new_code: Option<String>,
+    pub guest_codes: Option<Vec<GuestCodeEntry<u32, u32, u32, DoorEntry>>>,
+    pub redemptions: Option<Vec<GuestRedemptionEntry>>,
+}
+
+impl GuestCodeOverviewContext {
+    pub fn view(
+        message: Option<Message>,
+        is_admin: bool,
+        doors: Vec<DoorEntry>,
+        guest_codes: Vec<GuestCodeEntry<u32, u32, u32, DoorEntry>>,
+        redemptions: Vec<GuestRedemptionEntry>,
+        new_code: Option<String>,
+    ) -> Self {
+        Self {
+            message,
+            nav: is_admin.then(AdminNav::new),
+            can_create: !doors.is_empty(),
+            doors,
+            create_guest_code_url: uri!(post_create).to_string(),
+            new_code,
+            guest_codes: Some(guest_codes),
+            redemptions: Some(redemptions),
+        }
+    }
+
+    pub fn error(message: Message, is_admin: bool) -> Self {
+        Self {
+            message: Some(message),
+            nav: is_admin.then(AdminNav::new),
+            can_create: false,
+            doors: Vec::new(),
+            create_guest_code_url: uri!(post_create).to_string(),
+            new_code: None,
+            guest_codes: None,
+            redemptions: None,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct GuestContext {
+    pub message: Option<Message>,
+    pub redeem_url: String,
+}
+
+impl GuestContext {
+    pub fn new(message: Option<Message>) -> Self {
+        Self {
+            message,
+            redeem_url: uri!(post_guest).to_string(),
+        }
+    }
+}
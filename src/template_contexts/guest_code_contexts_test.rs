@@ -0,0 +1,74 @@
+use super::*;
+use crate::utils::csrf::CsrfToken;
+use rocket::local::Client;
+use rocket_contrib::templates::Template;
+
+#[test]
+fn guest_code_overview_with_empty_view() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "guest_code_overview",
+        &CsrfToken("token".to_string()).context(GuestCodeOverviewContext::view(
+            None,
+            false,
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            None,
+        )),
+    )
+    .unwrap();
+}
+
+#[test]
+fn guest_code_overview_with_new_code() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "guest_code_overview",
+        &CsrfToken("token".to_string()).context(GuestCodeOverviewContext::view(
+            Some(Message::error("21".to_string())),
+            true,
+            vec![DoorEntry::default()],
+            vec![GuestCodeEntry::default().with_door(DoorEntry::default())],
+            vec![GuestRedemptionEntry::default()],
+            Some("ABCDEFGHJKLM".to_string()),
+        )),
+    )
+    .unwrap();
+}
+
+#[test]
+fn guest_code_overview_with_error() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "guest_code_overview",
+        &CsrfToken("token".to_string()).context(GuestCodeOverviewContext::error(
+            Message::error("21".to_string()),
+            false,
+        )),
+    )
+    .unwrap();
+}
+
+#[test]
+fn guest() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "guest",
+        &CsrfToken("token".to_string())
+            .context(GuestContext::new(Some(Message::error("21".to_string())))),
+    )
+    .unwrap();
+}
@@ -0,0 +1,43 @@
+use super::{AdminNav, Message};
+use crate::db_entry::LoginThrottleEntry;
+use chrono::offset::Utc;
+use serde::Serialize;
+
+#[cfg(test)]
+#[path = "./lockout_contexts_test.rs"]
+mod lockout_contexts_test;
+
+#[derive(Serialize)]
+pub struct LockoutOverviewContext {
+    pub message: Option<Message>,
+    pub nav: AdminNav,
+    pub throttles: Option<Vec<LoginThrottleEntry>>,
+}
+
+impl LockoutOverviewContext {
+    pub fn view(throttles: Vec<LoginThrottleEntry>, message: Option<Message>) -> Self {
+        let now = Utc::now();
+        Self {
+            message,
+            nav: AdminNav::new(),
+            throttles: Some(
+                throttles
+                    .into_iter()
+                    .map(|mut throttle| {
+                        // Only active lockouts are shown
+                        throttle.locked_until = throttle.locked_until.filter(|until| *until > now);
+                        throttle
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    pub fn error(message: Message) -> Self {
+        Self {
+            message: Some(message),
+            nav: AdminNav::new(),
+            throttles: None,
+        }
+    }
+}
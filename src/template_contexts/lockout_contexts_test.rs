@@ -0,0 +1,42 @@
+use super::*;
+use rocket::local::Client;
+use rocket_contrib::templates::Template;
+
+#[test]
+fn lockout_overview_with_empty_view() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "lockout_overview",
+        &LockoutOverviewContext::view(Vec::new(), None),
+    )
+    .unwrap();
+}
+
+#[test]
+fn lockout_overview_with_view() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "lockout_overview",
+        &LockoutOverviewContext::view(vec![LoginThrottleEntry::default()], None),
+    )
+    .unwrap();
+}
+
+#[test]
+fn lockout_overview_with_error() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "lockout_overview",
+        &LockoutOverviewContext::error(Message::error("21".to_string())),
+    )
+    .unwrap();
+}
@@ -1,6 +1,11 @@
 //! Here are all contexts which are needed to generate the HTML from the templates.
 
-use crate::requests::{flat::*, index_view::*, user::*, user_auth::*};
+use crate::requests::{
+    api_token::*, audit_log::*, door::rocket_uri_macro_get_doors,
+    door_history::rocket_uri_macro_get_door_history, flat::*, index_view::*, lockout::*, role::*,
+    user::*, user_auth::*,
+};
+use chrono::{DateTime, FixedOffset};
 use rocket::request::FlashMessage;
 use serde::Serialize;
 
@@ -36,21 +41,83 @@ impl From<FlashMessage<'_, '_>> for Message {
 #[derive(Serialize)]
 pub struct MainViewContext {
     pub message: Option<Message>,
-    pub cam_url: String,
-    pub activate_door_url: String,
+    /// The cameras, which the user may watch
+    pub cameras: Vec<CameraContext>,
+    /// The doors, which the user may open
+    pub doors: Vec<DoorContext>,
+    /// The URL, which is polled for changes of the door states
+    pub door_state_url: String,
     pub change_user_url: String,
+    /// Only set for flat managers, which administer the users of their flat
+    pub manage_users_url: Option<String>,
+    /// Only set for users of a flat, who see its door history
+    pub door_history_url: Option<String>,
     pub logout_url: String,
+    /// Only set if an admin views the index of an user
+    pub impersonation: Option<ImpersonationContext>,
+}
+
+/// A door with its live state
+#[derive(Serialize)]
+pub struct DoorContext {
+    pub id: u32,
+    pub name: String,
+    pub state: String,
+    /// Whether the user has the permission to open the doors
+    pub may_open: bool,
+    /// Only set if the door can be opened in this view
+    pub activate_url: Option<String>,
+    /// Whether the door is kept unlocked by the hold-open mode
+    pub held_open: bool,
+    /// The end of the hold-open mode in the local time of the building, if it isn't held open until cancelled
+    pub held_until: Option<DateTime<FixedOffset>>,
+    /// Only set if the user may hold the door open in this view
+    pub hold_url: Option<String>,
+    /// Only set if the user may cancel the hold-open mode in this view
+    pub cancel_hold_url: Option<String>,
+}
+
+/// The impersonation of an user by an admin
+#[derive(Serialize)]
+pub struct ImpersonationContext {
+    pub user_name: String,
+    /// The end of the impersonation in the local time of the building
+    pub expiry: DateTime<FixedOffset>,
+    pub end_url: String,
+}
+
+/// The MJPEG stream of the camera of a flat
+#[derive(Serialize)]
+pub struct CameraContext {
+    pub name: String,
+    pub url: String,
 }
 
 #[derive(Serialize)]
 pub struct AdminViewContext {
     pub message: Option<Message>,
     pub nav: AdminNav,
+    /// All doors, which can only be opened by admins with the permission
+    pub doors: Vec<DoorContext>,
+    /// The URL, which is polled for changes of the door states
+    pub door_state_url: String,
 }
 
 #[derive(Serialize)]
 pub struct LoginContext {
     pub message: Option<Message>,
+    /// Only set if the OpenID Connect login is enabled
+    pub oidc_login_url: Option<String>,
+    /// Only set if the passkeys are enabled
+    pub passkey_login_url: Option<String>,
+}
+
+/// The page after the callback of the identity provider,
+/// which loads the next page as the session cookie isn't sent with the redirect of another site.
+/// It's also the answer of the passkey login.
+#[derive(Serialize)]
+pub struct LoginRedirectContext {
+    pub redirect_url: String,
 }
 
 /// A struct which is used in all other admin contextes to show the nav-bar
@@ -59,6 +126,12 @@ pub struct AdminNav {
     admin_view_url: String,
     flat_overview_url: String,
     user_overview_url: String,
+    role_overview_url: String,
+    door_overview_url: String,
+    door_history_url: String,
+    lockout_overview_url: String,
+    api_token_overview_url: String,
+    audit_log_url: String,
     logout_url: String,
 }
 
@@ -68,13 +141,52 @@ impl AdminNav {
             admin_view_url: uri!(get_admin_index_view).to_string(),
             flat_overview_url: uri!(get_flats).to_string(),
             user_overview_url: uri!(get_users).to_string(),
+            role_overview_url: uri!(get_roles).to_string(),
+            door_overview_url: uri!(get_doors).to_string(),
+            door_history_url: uri!(get_door_history: _).to_string(),
+            lockout_overview_url: uri!(get_lockouts).to_string(),
+            api_token_overview_url: uri!(admin_get_tokens).to_string(),
+            audit_log_url: uri!(get_audit_log: _).to_string(),
             logout_url: uri!(get_logout).to_string(),
         }
     }
 }
 
 pub mod user_contexts;
-pub use user_contexts::{UserDetailsContext, UserOverviewContext};
+pub use user_contexts::{PasswordChangeContext, UserDetailsContext, UserOverviewContext};
 
 pub mod flat_contexts;
 pub use flat_contexts::{FlatDetailsContext, FlatOverviewContext};
+
+pub mod two_factor_contexts;
+pub use two_factor_contexts::TwoFactorContext;
+
+pub mod lockout_contexts;
+pub use lockout_contexts::LockoutOverviewContext;
+
+pub mod session_contexts;
+pub use session_contexts::SessionOverviewContext;
+
+pub mod api_token_contexts;
+pub use api_token_contexts::ApiTokenOverviewContext;
+
+pub mod passkey_contexts;
+pub use passkey_contexts::PasskeyOverviewContext;
+
+pub mod guest_code_contexts;
+pub use guest_code_contexts::{GuestCodeOverviewContext, GuestContext};
+
+pub mod password_reset_contexts;
+pub use password_reset_contexts::{PasswordResetContext, PasswordResetIssuedContext};
+
+pub mod audit_log_contexts;
+pub use audit_log_contexts::AuditLogContext;
+
+pub mod role_contexts;
+pub use role_contexts::{RoleDetailsContext, RoleOverviewContext};
+
+pub mod door_contexts;
+pub use door_contexts::{DoorDetailsContext, DoorOverviewContext};
+
+pub mod door_history_contexts;
+pub use door_history_contexts::DoorHistoryContext;
@@ -0,0 +1,37 @@
+use super::{AdminNav, Message};
+use crate::db_entry::PasskeyEntry;
+use crate::requests::passkey::*;
+use serde::Serialize;
+
+#[cfg(test)]
+#[path = "./passkey_contexts_test.rs"]
+mod passkey_contexts_test;
+
+#[derive(Serialize)]
+pub struct PasskeyOverviewContext {
+    pub message: Option<Message>,
+    pub nav: Option<AdminNav>,
+    /// The URL which returns the options for the authenticator, the credential is posted to `<URL>/finish`
+    pub register_url: String,
+    pub passkeys: Option<Vec<PasskeyEntry>>,
+}
+
+impl PasskeyOverviewContext {
+    pub fn view(message: Option<Message>, is_admin: bool, passkeys: Vec<PasskeyEntry>) -> Self {
+        Self {
+            message,
+            nav: is_admin.then(AdminNav::new),
+            register_url: uri!(post_register).to_string(),
+            passkeys: Some(passkeys),
+        }
+    }
+
+    pub fn error(message: Message, is_admin: bool) -> Self {
+        Self {
+            message: Some(message),
+            nav: is_admin.then(AdminNav::new),
+            register_url: uri!(post_register).to_string(),
+            passkeys: None,
+        }
+    }
+}
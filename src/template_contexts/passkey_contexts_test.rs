@@ -0,0 +1,46 @@
+use super::*;
+use rocket::local::Client;
+use rocket_contrib::templates::Template;
+
+#[test]
+fn passkey_overview_with_empty_view() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "passkey_overview",
+        &PasskeyOverviewContext::view(None, false, Vec::new()),
+    )
+    .unwrap();
+}
+
+#[test]
+fn passkey_overview_with_view() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "passkey_overview",
+        &PasskeyOverviewContext::view(
+            Some(Message::error("21".to_string())),
+            true,
+            vec![PasskeyEntry::default()],
+        ),
+    )
+    .unwrap();
+}
+
+#[test]
+fn passkey_overview_with_error() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "passkey_overview",
+        &PasskeyOverviewContext::error(Message::error("21".to_string()), false),
+    )
+    .unwrap();
+}
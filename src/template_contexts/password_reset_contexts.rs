@@ -0,0 +1,52 @@
+use super::{AdminNav, Message};
+use crate::db_entry::UserEntry;
+use crate::requests::{password_reset::*, user::*};
+use crate::utils::password_reset;
+use serde::Serialize;
+
+#[cfg(test)]
+#[path = "./password_reset_contexts_test.rs"]
+mod password_reset_contexts_test;
+
+#[derive(Serialize)]
+pub struct PasswordResetContext {
+    pub message: Option<Message>,
+    /// The code of the reset link, otherwise the printed code has to be entered
+    pub code: Option<String>,
+    pub reset_url: String,
+}
+
+impl PasswordResetContext {
+    pub fn new(message: Option<Message>, code: Option<String>) -> Self {
+        Self {
+            message,
+            code,
+            reset_url: uri!(post_reset).to_string(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct PasswordResetIssuedContext {
+    pub message: Option<Message>,
+    pub nav: AdminNav,
+    pub user_name: String,
+    pub user_url: String,
+    /// The printable code, which is only shown once
+    pub code: String,
+    /// The path of the reset link, the template prepends the origin
+    pub reset_link: String,
+}
+
+impl PasswordResetIssuedContext {
+    pub fn new(user: &UserEntry, code: String) -> Self {
+        Self {
+            message: None,
+            nav: AdminNav::new(),
+            user_name: user.name.clone(),
+            user_url: uri!(get_change: user.id).to_string(),
+            reset_link: uri!(get_reset: password_reset::normalize(&code)).to_string(),
+            code,
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use super::*;
+use crate::utils::csrf::CsrfToken;
+use rocket::local::Client;
+use rocket_contrib::templates::Template;
+
+#[test]
+fn password_reset_with_code() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "password_reset",
+        &CsrfToken("token".to_string()).context(PasswordResetContext::new(
+            None,
+            Some("abcdabcdabcdabcd".to_string()),
+        )),
+    )
+    .unwrap();
+}
+
+#[test]
+fn password_reset_without_code() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "password_reset",
+        &CsrfToken("token".to_string()).context(PasswordResetContext::new(
+            Some(Message::error("21".to_string())),
+            None,
+        )),
+    )
+    .unwrap();
+}
+
+#[test]
+fn password_reset_issued() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "password_reset_issued",
+        &CsrfToken("token".to_string()).context(PasswordResetIssuedContext::new(
+            &UserEntry::default(),
+            "abcd-abcd-abcd-abcd".to_string(),
+        )),
+    )
+    .unwrap();
+}
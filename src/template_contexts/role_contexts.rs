@@ -0,0 +1,117 @@
+use super::{AdminNav, Message};
+use crate::db_entry::{Permission, RoleEntry, UserEntry};
+use crate::requests::role::*;
+use serde::Serialize;
+
+#[cfg(test)]
+#[path = "./role_contexts_test.rs"]
+mod role_contexts_test;
+
+#[derive(Serialize)]
+pub struct RoleOverviewContext {
+    pub message: Option<Message>,
+    pub nav: AdminNav,
+    pub create_role_url: String,
+    pub roles: Option<Vec<RoleEntry>>,
+}
+
+impl RoleOverviewContext {
+    pub fn view(roles: Vec<RoleEntry>, message: Option<Message>) -> Self {
+        Self {
+            message,
+            nav: AdminNav::new(),
+            create_role_url: uri!(get_create).to_string(),
+            roles: Some(roles),
+        }
+    }
+
+    pub fn error(message: Message) -> Self {
+        Self {
+            message: Some(message),
+            nav: AdminNav::new(),
+            create_role_url: uri!(get_create).to_string(),
+            roles: None,
+        }
+    }
+}
+
+/// A checkbox of a permission in the role details
+#[derive(Serialize)]
+pub struct PermissionField {
+    pub field: String,
+    pub permission: Permission,
+    pub selected: bool,
+}
+
+impl PermissionField {
+    fn get_list(role: Option<&RoleEntry>) -> Vec<Self> {
+        Permission::get_list()
+            .into_iter()
+            .map(|permission| Self {
+                field: permission.to_string().replace('.', "_"),
+                permission,
+                selected: role.map_or(false, |role| role.permissions.contains(&permission)),
+            })
+            .collect()
+    }
+}
+
+#[derive(Serialize)]
+pub struct RoleDetailsContext {
+    pub message: Option<Message>,
+    pub nav: AdminNav,
+    pub title: String,
+    pub role: Option<RoleEntry>,
+    pub permissions: Vec<PermissionField>,
+    /// The users, who are members of the role
+    pub members: Vec<UserEntry>,
+    /// The users, who may be added to the role
+    pub users: Vec<UserEntry>,
+    pub add_member_url: Option<String>,
+}
+
+impl RoleDetailsContext {
+    pub fn error(error: Message) -> Self {
+        Self {
+            message: Some(error),
+            nav: AdminNav::new(),
+            title: String::new(),
+            role: None,
+            permissions: Vec::new(),
+            members: Vec::new(),
+            users: Vec::new(),
+            add_member_url: None,
+        }
+    }
+
+    pub fn create(error: Option<Message>) -> Self {
+        Self {
+            message: error,
+            nav: AdminNav::new(),
+            title: "Create".to_string(),
+            role: None,
+            permissions: PermissionField::get_list(None),
+            members: Vec::new(),
+            users: Vec::new(),
+            add_member_url: None,
+        }
+    }
+
+    pub fn change(
+        error: Option<Message>,
+        role: RoleEntry,
+        members: Vec<UserEntry>,
+        users: Vec<UserEntry>,
+    ) -> Self {
+        Self {
+            message: error,
+            nav: AdminNav::new(),
+            title: "Change".to_string(),
+            permissions: PermissionField::get_list(Some(&role)),
+            add_member_url: Some(uri!(post_add_member: role.id).to_string()),
+            role: Some(role),
+            members,
+            users,
+        }
+    }
+}
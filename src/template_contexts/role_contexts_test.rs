@@ -0,0 +1,89 @@
+use super::*;
+use crate::utils::csrf::CsrfToken;
+use rocket::local::Client;
+use rocket_contrib::templates::Template;
+
+#[test]
+fn role_overview_with_view() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "role_overview",
+        &CsrfToken("token".to_string())
+            .context(RoleOverviewContext::view(vec![RoleEntry::default()], None)),
+    )
+    .unwrap();
+}
+
+#[test]
+fn role_overview_with_error() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "role_overview",
+        &CsrfToken("token".to_string())
+            .context(RoleOverviewContext::error(Message::error("21".to_string()))),
+    )
+    .unwrap();
+}
+
+#[test]
+fn role_details_with_error() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "role_details",
+        &CsrfToken("token".to_string())
+            .context(RoleDetailsContext::error(Message::error("21".to_string()))),
+    )
+    .unwrap();
+}
+
+#[test]
+fn role_create() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "role_details",
+        &CsrfToken("token".to_string()).context(RoleDetailsContext::create(None)),
+    )
+    .unwrap();
+}
+
+#[test]
+fn role_change() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "role_details",
+        &CsrfToken("token".to_string()).context(RoleDetailsContext::change(
+            Some(Message::error("21".to_string())),
+            RoleEntry::default(),
+            vec![UserEntry::default()],
+            vec![UserEntry::default()],
+        )),
+    )
+    .unwrap();
+}
+
+#[test]
+fn role_permissions_are_selected() {
+    let fields = PermissionField::get_list(Some(&RoleEntry::default()));
+    assert_eq!(fields.len(), Permission::get_list().len());
+    assert!(fields
+        .iter()
+        .any(|field| field.field == "door_open" && field.selected));
+    assert!(fields
+        .iter()
+        .any(|field| field.field == "user_manage" && !field.selected));
+}
@@ -0,0 +1,52 @@
+use super::{AdminNav, Message};
+use crate::db_entry::{UserEntry, UserSessionEntry};
+use serde::Serialize;
+
+#[cfg(test)]
+#[path = "./session_contexts_test.rs"]
+mod session_contexts_test;
+
+#[derive(Serialize)]
+pub struct SessionOverviewContext {
+    pub message: Option<Message>,
+    pub nav: Option<AdminNav>,
+    pub admin_view: bool,
+    pub user_id: Option<u32>,
+    pub user_name: String,
+    pub current_session: Option<u32>,
+    pub sessions: Option<Vec<UserSessionEntry>>,
+}
+
+impl SessionOverviewContext {
+    /// `admin_view` tells whether an admin manages the sessions of any user
+    pub fn view(
+        message: Option<Message>,
+        is_admin: bool,
+        admin_view: bool,
+        user: &UserEntry,
+        current_session: Option<u32>,
+        sessions: Vec<UserSessionEntry>,
+    ) -> Self {
+        Self {
+            message,
+            nav: is_admin.then(AdminNav::new),
+            admin_view,
+            user_id: Some(user.id),
+            user_name: user.name.clone(),
+            current_session,
+            sessions: Some(sessions),
+        }
+    }
+
+    pub fn error(message: Message, is_admin: bool) -> Self {
+        Self {
+            message: Some(message),
+            nav: is_admin.then(AdminNav::new),
+            admin_view: is_admin,
+            user_id: None,
+            user_name: String::new(),
+            current_session: None,
+            sessions: None,
+        }
+    }
+}
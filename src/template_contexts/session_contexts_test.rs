@@ -0,0 +1,76 @@
+use super::*;
+use rocket::local::Client;
+use rocket_contrib::templates::Template;
+
+#[test]
+fn session_overview_with_empty_view() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "session_overview",
+        &SessionOverviewContext::view(
+            None,
+            false,
+            false,
+            &UserEntry::default(),
+            Some(1),
+            Vec::new(),
+        ),
+    )
+    .unwrap();
+}
+
+#[test]
+fn session_overview_with_view() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "session_overview",
+        &SessionOverviewContext::view(
+            None,
+            false,
+            false,
+            &UserEntry::default(),
+            Some(1),
+            vec![UserSessionEntry::default()],
+        ),
+    )
+    .unwrap();
+}
+
+#[test]
+fn session_overview_with_admin_view() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "session_overview",
+        &SessionOverviewContext::view(
+            Some(Message::error("21".to_string())),
+            true,
+            true,
+            &UserEntry::default(),
+            None,
+            vec![UserSessionEntry::default()],
+        ),
+    )
+    .unwrap();
+}
+
+#[test]
+fn session_overview_with_error() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "session_overview",
+        &SessionOverviewContext::error(Message::error("21".to_string()), true),
+    )
+    .unwrap();
+}
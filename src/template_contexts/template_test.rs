@@ -1,4 +1,5 @@
 use super::*;
+use crate::utils::csrf::CsrfToken;
 use rocket::local::Client;
 use rocket_contrib::templates::{tera::Context, Template};
 
@@ -10,13 +11,17 @@ fn main_view_without_message() {
     Template::show(
         client.rocket(),
         "main_view",
-        &MainViewContext {
+        &CsrfToken("token".to_string()).context(MainViewContext {
             message: None,
-            cam_url: String::new(),
+            cameras: Vec::new(),
             change_user_url: String::new(),
-            activate_door_url: String::new(),
+            manage_users_url: None,
+            door_history_url: None,
             logout_url: String::new(),
-        },
+            doors: Vec::new(),
+            door_state_url: String::new(),
+            impersonation: None,
+        }),
     )
     .unwrap();
 }
@@ -29,13 +34,67 @@ fn main_view_with_message() {
     Template::show(
         client.rocket(),
         "main_view",
-        &MainViewContext {
+        &CsrfToken("token".to_string()).context(MainViewContext {
             message: Some(Message::error("21".to_string())),
-            cam_url: String::new(),
+            cameras: vec![CameraContext {
+                name: "Home".to_string(),
+                url: String::new(),
+            }],
             change_user_url: String::new(),
-            activate_door_url: String::new(),
+            manage_users_url: Some(String::new()),
+            door_history_url: Some(String::new()),
             logout_url: String::new(),
-        },
+            doors: vec![DoorContext {
+                id: 1,
+                name: "House Door".to_string(),
+                state: "Unlocked".to_string(),
+                may_open: true,
+                activate_url: Some(String::new()),
+                held_open: true,
+                held_until: Some(chrono::Utc::now().into()),
+                hold_url: Some(String::new()),
+                cancel_hold_url: Some(String::new()),
+            }],
+            door_state_url: String::new(),
+            impersonation: None,
+        }),
+    )
+    .unwrap();
+}
+
+#[test]
+fn main_view_with_impersonation() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "main_view",
+        &CsrfToken("token".to_string()).context(MainViewContext {
+            message: None,
+            cameras: Vec::new(),
+            change_user_url: String::new(),
+            manage_users_url: None,
+            door_history_url: None,
+            logout_url: String::new(),
+            doors: vec![DoorContext {
+                id: 1,
+                name: "House Door".to_string(),
+                state: "Closed".to_string(),
+                may_open: true,
+                activate_url: None,
+                held_open: true,
+                held_until: None,
+                hold_url: None,
+                cancel_hold_url: None,
+            }],
+            door_state_url: String::new(),
+            impersonation: Some(ImpersonationContext {
+                user_name: "resident".to_string(),
+                expiry: chrono::Utc::now().into(),
+                end_url: String::new(),
+            }),
+        }),
     )
     .unwrap();
 }
@@ -48,10 +107,12 @@ fn admin_view_without_message() {
     Template::show(
         client.rocket(),
         "admin_view",
-        &AdminViewContext {
+        &CsrfToken("token".to_string()).context(AdminViewContext {
             message: None,
             nav: AdminNav::new(),
-        },
+            doors: Vec::new(),
+            door_state_url: String::new(),
+        }),
     )
     .unwrap();
 }
@@ -64,10 +125,22 @@ fn admin_view_with_message() {
     Template::show(
         client.rocket(),
         "admin_view",
-        &AdminViewContext {
+        &CsrfToken("token".to_string()).context(AdminViewContext {
             message: Some(Message::error("21".to_string())),
             nav: AdminNav::new(),
-        },
+            doors: vec![DoorContext {
+                id: 1,
+                name: "House Door".to_string(),
+                state: "Forced open".to_string(),
+                may_open: true,
+                activate_url: Some(String::new()),
+                held_open: false,
+                held_until: None,
+                hold_url: Some(String::new()),
+                cancel_hold_url: None,
+            }],
+            door_state_url: String::new(),
+        }),
     )
     .unwrap();
 }
@@ -77,7 +150,16 @@ fn login_without_message() {
     let rocket = rocket::ignite().attach(Template::fairing());
     let client = Client::new(rocket).expect("valid rocket");
 
-    Template::show(client.rocket(), "login", &LoginContext { message: None }).unwrap();
+    Template::show(
+        client.rocket(),
+        "login",
+        &CsrfToken("token".to_string()).context(LoginContext {
+            message: None,
+            oidc_login_url: None,
+            passkey_login_url: None,
+        }),
+    )
+    .unwrap();
 }
 
 #[test]
@@ -88,8 +170,42 @@ fn login_with_message() {
     Template::show(
         client.rocket(),
         "login",
-        &LoginContext {
+        &CsrfToken("token".to_string()).context(LoginContext {
+            message: Some(Message::error("21".to_string())),
+            oidc_login_url: Some(String::new()),
+            passkey_login_url: Some(String::new()),
+        }),
+    )
+    .unwrap();
+}
+
+#[test]
+fn two_factor_login_with_message() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "two_factor_login",
+        &CsrfToken("token".to_string()).context(LoginContext {
             message: Some(Message::error("21".to_string())),
+            oidc_login_url: None,
+            passkey_login_url: None,
+        }),
+    )
+    .unwrap();
+}
+
+#[test]
+fn login_redirect() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "login_redirect",
+        &LoginRedirectContext {
+            redirect_url: "/".to_string(),
         },
     )
     .unwrap();
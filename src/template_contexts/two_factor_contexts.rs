@@ -0,0 +1,89 @@
+use super::{AdminNav, Message};
+use crate::requests::{two_factor::*, user_auth::*};
+use serde::Serialize;
+
+#[cfg(test)]
+#[path = "./two_factor_contexts_test.rs"]
+mod two_factor_contexts_test;
+
+/// Context of the enrollment and deactivation of the second factor
+#[derive(Serialize)]
+pub struct TwoFactorContext {
+    pub message: Option<Message>,
+    pub nav: Option<AdminNav>,
+    pub enabled: bool,
+    pub secret: Option<String>,
+    pub qr_code: Option<String>,
+    pub recovery_codes: Option<Vec<String>>,
+    pub remaining_recovery_codes: usize,
+    pub setup_url: String,
+    pub disable_url: String,
+    pub login_url: String,
+}
+
+impl TwoFactorContext {
+    pub fn error(error: Message) -> Self {
+        Self {
+            message: Some(error),
+            nav: None,
+            enabled: false,
+            secret: None,
+            qr_code: None,
+            recovery_codes: None,
+            remaining_recovery_codes: 0,
+            setup_url: uri!(post_setup).to_string(),
+            disable_url: uri!(post_disable).to_string(),
+            login_url: uri!(get_login).to_string(),
+        }
+    }
+
+    pub fn setup(error: Option<Message>, is_admin: bool, secret: String, qr_code: String) -> Self {
+        Self {
+            message: error,
+            nav: is_admin.then(AdminNav::new),
+            enabled: false,
+            secret: Some(secret),
+            qr_code: Some(qr_code),
+            recovery_codes: None,
+            remaining_recovery_codes: 0,
+            setup_url: uri!(post_setup).to_string(),
+            disable_url: uri!(post_disable).to_string(),
+            login_url: uri!(get_login).to_string(),
+        }
+    }
+
+    pub fn enabled(
+        error: Option<Message>,
+        is_admin: bool,
+        remaining_recovery_codes: usize,
+    ) -> Self {
+        Self {
+            message: error,
+            nav: is_admin.then(AdminNav::new),
+            enabled: true,
+            secret: None,
+            qr_code: None,
+            recovery_codes: None,
+            remaining_recovery_codes,
+            setup_url: uri!(post_setup).to_string(),
+            disable_url: uri!(post_disable).to_string(),
+            login_url: uri!(get_login).to_string(),
+        }
+    }
+
+    /// Shows the recovery codes once after the enrollment
+    pub fn recovery_codes(recovery_codes: Vec<String>) -> Self {
+        Self {
+            message: None,
+            nav: None,
+            enabled: true,
+            secret: None,
+            qr_code: None,
+            remaining_recovery_codes: recovery_codes.len(),
+            recovery_codes: Some(recovery_codes),
+            setup_url: uri!(post_setup).to_string(),
+            disable_url: uri!(post_disable).to_string(),
+            login_url: uri!(get_login).to_string(),
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use super::*;
+use crate::utils::csrf::CsrfToken;
+use rocket::local::Client;
+use rocket_contrib::templates::Template;
+
+#[test]
+fn two_factor_with_error() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "two_factor",
+        &CsrfToken("token".to_string())
+            .context(TwoFactorContext::error(Message::error("21".to_string()))),
+    )
+    .unwrap();
+}
+
+#[test]
+fn two_factor_setup() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "two_factor",
+        &CsrfToken("token".to_string()).context(TwoFactorContext::setup(
+            None,
+            false,
+            "ABCDEF".to_string(),
+            "<svg></svg>".to_string(),
+        )),
+    )
+    .unwrap();
+}
+
+#[test]
+fn two_factor_setup_by_admin_with_error() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "two_factor",
+        &CsrfToken("token".to_string()).context(TwoFactorContext::setup(
+            Some(Message::error("21".to_string())),
+            true,
+            "ABCDEF".to_string(),
+            "<svg></svg>".to_string(),
+        )),
+    )
+    .unwrap();
+}
+
+#[test]
+fn two_factor_enabled() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "two_factor",
+        &CsrfToken("token".to_string()).context(TwoFactorContext::enabled(None, true, 3)),
+    )
+    .unwrap();
+}
+
+#[test]
+fn two_factor_recovery_codes() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "two_factor",
+        &CsrfToken("token".to_string()).context(TwoFactorContext::recovery_codes(vec![
+            "abcde-fghij".to_string(),
+        ])),
+    )
+    .unwrap();
+}
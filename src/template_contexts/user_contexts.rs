@@ -1,6 +1,11 @@
 use super::{AdminNav, Message};
-use crate::db_entry::{FlatEntry, UserEntry, UserType};
-use crate::requests::user::*;
+use crate::db_entry::{AccessExceptionEntry, AccessWindowEntry, FlatEntry, UserEntry, UserType};
+use crate::requests::{
+    access_schedule::*, api_token::*, guest_code::*, passkey::*, password::*, two_factor::*,
+    user::*, user_auth::*, user_session::*,
+};
+use crate::utils::access_schedule;
+use crate::utils::config::CONFIG;
 use serde::Serialize;
 
 #[cfg(test)]
@@ -10,7 +15,9 @@ mod user_contexts_test;
 #[derive(Serialize)]
 pub struct UserOverviewContext {
     pub message: Option<Message>,
-    pub nav: AdminNav,
+    pub nav: Option<AdminNav>,
+    /// Whether a flat manager administers the users of the own flat
+    pub flat_view: bool,
     pub create_user_url: String,
     pub users: Option<Vec<UserEntry>>,
 }
@@ -19,7 +26,8 @@ impl UserOverviewContext {
     pub fn view(users: Vec<UserEntry>, message: Option<Message>) -> Self {
         Self {
             message,
-            nav: AdminNav::new(),
+            nav: Some(AdminNav::new()),
+            flat_view: false,
             create_user_url: uri!(get_create).to_string(),
             users: Some(users),
         }
@@ -28,11 +36,32 @@ impl UserOverviewContext {
     pub fn error(message: Message) -> Self {
         Self {
             message: Some(message),
-            nav: AdminNav::new(),
+            nav: Some(AdminNav::new()),
+            flat_view: false,
             create_user_url: uri!(get_create).to_string(),
             users: None,
         }
     }
+
+    pub fn flat_view(users: Vec<UserEntry>, message: Option<Message>) -> Self {
+        Self {
+            message,
+            nav: None,
+            flat_view: true,
+            create_user_url: uri!(flat_get_create).to_string(),
+            users: Some(users),
+        }
+    }
+
+    pub fn flat_error(message: Message) -> Self {
+        Self {
+            message: Some(message),
+            nav: None,
+            flat_view: true,
+            create_user_url: uri!(flat_get_create).to_string(),
+            users: None,
+        }
+    }
 }
 #[derive(Serialize)]
 pub struct UserDetailsContext {
@@ -40,11 +69,42 @@ pub struct UserDetailsContext {
     pub nav: Option<AdminNav>,
     pub title: String,
     pub is_admin: bool,
+    pub is_self: bool,
+    /// Whether a flat manager administers an user of the own flat
+    pub is_flat_manager: bool,
+    pub two_factor_url: String,
+    pub sessions_url: String,
+    pub api_tokens_url: String,
+    pub guest_codes_url: String,
+    /// Only set if the passkeys are enabled
+    pub passkeys_url: Option<String>,
+    /// Only set if an admin changes an user
+    pub access_schedule: Option<AccessScheduleContext>,
     pub user: Option<UserEntry>,
     pub types: Vec<(u16, String)>,
     pub flats: Vec<FlatEntry>,
 }
 
+/// The weekly windows and exception dates, in which an user may open the door
+#[derive(Serialize)]
+pub struct AccessScheduleContext {
+    pub windows: Vec<AccessWindowEntry>,
+    pub exceptions: Vec<AccessExceptionEntry>,
+    pub weekdays: Vec<(u32, String)>,
+    /// The timezone of the building, in which the times are evaluated
+    pub timezone: String,
+    pub add_window_url: String,
+    pub add_exception_url: String,
+}
+
+/// The URL of the passkey overview if the passkeys are enabled
+fn passkeys_url() -> Option<String> {
+    CONFIG
+        .webauthn
+        .as_ref()
+        .map(|_| uri!(get_passkeys).to_string())
+}
+
 impl UserDetailsContext {
     pub fn error(error: Message) -> Self {
         Self {
@@ -52,6 +112,14 @@ impl UserDetailsContext {
             nav: Some(AdminNav::new()),
             title: String::new(),
             is_admin: false,
+            is_self: false,
+            is_flat_manager: false,
+            two_factor_url: uri!(get_setup).to_string(),
+            sessions_url: uri!(get_sessions).to_string(),
+            api_tokens_url: uri!(get_tokens).to_string(),
+            guest_codes_url: uri!(get_guest_codes).to_string(),
+            passkeys_url: passkeys_url(),
+            access_schedule: None,
             user: None,
             types: UserType::get_list(),
             flats: Vec::new(),
@@ -64,6 +132,14 @@ impl UserDetailsContext {
             nav: Some(AdminNav::new()),
             title: "Create".to_string(),
             is_admin: true,
+            is_self: false,
+            is_flat_manager: false,
+            two_factor_url: uri!(get_setup).to_string(),
+            sessions_url: uri!(get_sessions).to_string(),
+            api_tokens_url: uri!(get_tokens).to_string(),
+            guest_codes_url: uri!(get_guest_codes).to_string(),
+            passkeys_url: passkeys_url(),
+            access_schedule: None,
             user: None,
             types: UserType::get_list(),
             flats,
@@ -73,6 +149,7 @@ impl UserDetailsContext {
     pub fn change(
         error: Option<Message>,
         is_admin: bool,
+        is_self: bool,
         user: UserEntry,
         flats: Vec<FlatEntry>,
     ) -> Self {
@@ -81,9 +158,98 @@ impl UserDetailsContext {
             nav: is_admin.then(AdminNav::new),
             title: "Change".to_string(),
             is_admin,
+            is_self,
+            is_flat_manager: false,
+            two_factor_url: uri!(get_setup).to_string(),
+            sessions_url: uri!(get_sessions).to_string(),
+            api_tokens_url: uri!(get_tokens).to_string(),
+            guest_codes_url: uri!(get_guest_codes).to_string(),
+            passkeys_url: passkeys_url(),
+            access_schedule: None,
             user: Some(user),
             types: UserType::get_list(),
             flats,
         }
     }
 }
+
+impl UserDetailsContext {
+    /// Adds the access schedule of the user, which is only editable by admins
+    pub fn with_access_schedule(
+        mut self,
+        windows: Vec<AccessWindowEntry>,
+        exceptions: Vec<AccessExceptionEntry>,
+    ) -> Self {
+        self.access_schedule = self.user.as_ref().map(|user| AccessScheduleContext {
+            windows,
+            exceptions,
+            weekdays: access_schedule::weekdays(),
+            timezone: CONFIG.building.timezone.name().to_string(),
+            add_window_url: uri!(post_access_window: user.id).to_string(),
+            add_exception_url: uri!(post_access_exception: user.id).to_string(),
+        });
+        self
+    }
+}
+
+impl UserDetailsContext {
+    pub fn flat_create(error: Option<Message>) -> Self {
+        Self {
+            message: error,
+            nav: None,
+            title: "Create".to_string(),
+            is_admin: false,
+            is_self: false,
+            is_flat_manager: true,
+            two_factor_url: uri!(get_setup).to_string(),
+            sessions_url: uri!(get_sessions).to_string(),
+            api_tokens_url: uri!(get_tokens).to_string(),
+            guest_codes_url: uri!(get_guest_codes).to_string(),
+            passkeys_url: passkeys_url(),
+            access_schedule: None,
+            user: None,
+            types: Vec::new(),
+            flats: Vec::new(),
+        }
+    }
+
+    pub fn flat_change(error: Option<Message>, user: UserEntry) -> Self {
+        Self {
+            message: error,
+            nav: None,
+            title: "Change".to_string(),
+            is_admin: false,
+            is_self: false,
+            is_flat_manager: true,
+            two_factor_url: uri!(get_setup).to_string(),
+            sessions_url: uri!(get_sessions).to_string(),
+            api_tokens_url: uri!(get_tokens).to_string(),
+            guest_codes_url: uri!(get_guest_codes).to_string(),
+            passkeys_url: passkeys_url(),
+            access_schedule: None,
+            user: Some(user),
+            types: Vec::new(),
+            flats: Vec::new(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct PasswordChangeContext {
+    pub message: Option<Message>,
+    /// Whether the user has to change the password before any other request
+    pub mandatory: bool,
+    pub change_password_url: String,
+    pub logout_url: String,
+}
+
+impl PasswordChangeContext {
+    pub fn new(message: Option<Message>, mandatory: bool) -> Self {
+        Self {
+            message,
+            mandatory,
+            change_password_url: uri!(post_change_password).to_string(),
+            logout_url: uri!(get_logout).to_string(),
+        }
+    }
+}
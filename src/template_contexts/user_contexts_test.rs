@@ -1,4 +1,6 @@
 use super::*;
+use crate::utils::csrf::CsrfToken;
+use chrono::offset::Utc;
 use rocket::local::Client;
 use rocket_contrib::templates::Template;
 
@@ -23,7 +25,16 @@ fn user_overview_with_view() {
     Template::show(
         client.rocket(),
         "user_overview",
-        &UserOverviewContext::view(vec![UserEntry::default()], None),
+        &UserOverviewContext::view(
+            vec![
+                UserEntry::default(),
+                UserEntry {
+                    valid_until: Some(Utc::now()),
+                    ..Default::default()
+                },
+            ],
+            None,
+        ),
     )
     .unwrap();
 }
@@ -65,7 +76,8 @@ fn user_details_with_error() {
     Template::show(
         client.rocket(),
         "user_details",
-        &UserDetailsContext::error(Message::error("21".to_string())),
+        &CsrfToken("token".to_string())
+            .context(UserDetailsContext::error(Message::error("21".to_string()))),
     )
     .unwrap();
 }
@@ -78,7 +90,8 @@ fn user_create() {
     Template::show(
         client.rocket(),
         "user_details",
-        &UserDetailsContext::create(None, vec![FlatEntry::default()]),
+        &CsrfToken("token".to_string())
+            .context(UserDetailsContext::create(None, vec![FlatEntry::default()])),
     )
     .unwrap();
 }
@@ -91,7 +104,10 @@ fn user_create_with_error() {
     Template::show(
         client.rocket(),
         "user_details",
-        &UserDetailsContext::create(Some(Message::error("21".to_string())), Vec::new()),
+        &CsrfToken("token".to_string()).context(UserDetailsContext::create(
+            Some(Message::error("21".to_string())),
+            Vec::new(),
+        )),
     )
     .unwrap();
 }
@@ -104,12 +120,13 @@ fn user_change_by_user() {
     Template::show(
         client.rocket(),
         "user_details",
-        &UserDetailsContext::change(
+        &CsrfToken("token".to_string()).context(UserDetailsContext::change(
             None,
             false,
+            true,
             UserEntry::default(),
             vec![FlatEntry::default()],
-        ),
+        )),
     )
     .unwrap();
 }
@@ -122,12 +139,13 @@ fn user_change_by_user_with_error() {
     Template::show(
         client.rocket(),
         "user_details",
-        &UserDetailsContext::change(
+        &CsrfToken("token".to_string()).context(UserDetailsContext::change(
             Some(Message::error("21".to_string())),
             false,
+            true,
             UserEntry::default(),
             Vec::new(),
-        ),
+        )),
     )
     .unwrap();
 }
@@ -140,7 +158,19 @@ fn user_change_by_admin() {
     Template::show(
         client.rocket(),
         "user_details",
-        &UserDetailsContext::change(None, true, UserEntry::default(), vec![FlatEntry::default()]),
+        &CsrfToken("token".to_string()).context(UserDetailsContext::change(
+            None,
+            true,
+            false,
+            UserEntry {
+                totp_secret: Some("ABCDEF".to_string()),
+                must_change_password: false,
+                valid_from: Some(Utc::now()),
+                valid_until: Some(Utc::now()),
+                ..Default::default()
+            },
+            vec![FlatEntry::default()],
+        )),
     )
     .unwrap();
 }
@@ -153,11 +183,107 @@ fn user_change_by_admin_with_error() {
     Template::show(
         client.rocket(),
         "user_details",
-        &UserDetailsContext::change(
+        &CsrfToken("token".to_string()).context(UserDetailsContext::change(
             Some(Message::error("21".to_string())),
             true,
+            true,
             UserEntry::default(),
             Vec::new(),
+        )),
+    )
+    .unwrap();
+}
+
+#[test]
+fn password_change() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "password_change",
+        &CsrfToken("token".to_string()).context(PasswordChangeContext::new(
+            Some(Message::error("21".to_string())),
+            true,
+        )),
+    )
+    .unwrap();
+}
+
+#[test]
+fn user_overview_with_flat_view() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "user_overview",
+        &CsrfToken("token".to_string()).context(UserOverviewContext::flat_view(
+            vec![UserEntry::default()],
+            None,
+        )),
+    )
+    .unwrap();
+}
+
+#[test]
+fn user_overview_with_flat_error() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "user_overview",
+        &CsrfToken("token".to_string()).context(UserOverviewContext::flat_error(Message::error(
+            "21".to_string(),
+        ))),
+    )
+    .unwrap();
+}
+
+#[test]
+fn user_create_by_flat_manager() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "user_details",
+        &CsrfToken("token".to_string()).context(UserDetailsContext::flat_create(None)),
+    )
+    .unwrap();
+}
+
+#[test]
+fn user_change_by_flat_manager() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "user_details",
+        &CsrfToken("token".to_string()).context(UserDetailsContext::flat_change(
+            Some(Message::error("21".to_string())),
+            UserEntry::default(),
+        )),
+    )
+    .unwrap();
+}
+
+#[test]
+fn user_change_by_admin_with_access_schedule() {
+    let rocket = rocket::ignite().attach(Template::fairing());
+    let client = Client::new(rocket).expect("valid rocket");
+
+    Template::show(
+        client.rocket(),
+        "user_details",
+        &CsrfToken("token".to_string()).context(
+            UserDetailsContext::change(None, true, false, UserEntry::default(), Vec::new())
+                .with_access_schedule(
+                    vec![AccessWindowEntry::default()],
+                    vec![AccessExceptionEntry::default()],
+                ),
         ),
     )
     .unwrap();
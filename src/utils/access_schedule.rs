@@ -0,0 +1,114 @@
+//! Weekly access windows and exception dates, which restrict when an user may open the door.
+//! An user without any window may open the door at any time, except on his denied exception dates.
+
+use super::config::CONFIG;
+use crate::db_entry::{rusqlite, AccessExceptionEntry, AccessWindowEntry, Connection};
+use bool_ext::BoolExt;
+use chrono::{offset::Utc, DateTime, Datelike, NaiveDateTime, Weekday};
+
+#[cfg(test)]
+#[path = "./access_schedule_test.rs"]
+mod access_schedule_test;
+
+/// All errors which could happen during the check of an access schedule.
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("You aren't allowed to open the door at this time")]
+    OutsideSchedule,
+    #[error(transparent)]
+    Database(#[from] rusqlite::Error),
+}
+
+/// The weekdays with their number of the access windows, which are shown in the form.
+pub fn weekdays() -> Vec<(u32, String)> {
+    [
+        Weekday::Mon,
+        Weekday::Tue,
+        Weekday::Wed,
+        Weekday::Thu,
+        Weekday::Fri,
+        Weekday::Sat,
+        Weekday::Sun,
+    ]
+    .iter()
+    .map(|day| (day.num_days_from_monday(), format!("{:?}", day)))
+    .collect()
+}
+
+/// Whether the schedule allows the door to be opened at the local time.
+/// An exception of the day overrides the windows.
+pub fn is_allowed(
+    windows: &[AccessWindowEntry],
+    exceptions: &[AccessExceptionEntry],
+    local: NaiveDateTime,
+) -> bool {
+    if let Some(exception) = exceptions
+        .iter()
+        .find(|exception| exception.date == local.date())
+    {
+        return exception.allowed;
+    }
+
+    let weekday = local.weekday().num_days_from_monday();
+    let time = local.time();
+    windows.is_empty()
+        || windows.iter().any(|window| {
+            window.weekday == weekday && window.start_time <= time && time < window.end_time
+        })
+}
+
+/// The local end of the period, in which the schedule allows the door to be opened at the local time.
+/// It is the end of the current window or of the day of an exception,
+/// without any windows it is the next denied exception date and there is no end without one.
+pub fn period_end(
+    windows: &[AccessWindowEntry],
+    exceptions: &[AccessExceptionEntry],
+    local: NaiveDateTime,
+) -> Option<NaiveDateTime> {
+    let date = local.date();
+    if exceptions.iter().any(|exception| exception.date == date) {
+        return date.succ_opt()?.and_hms_opt(0, 0, 0);
+    }
+
+    if windows.is_empty() {
+        return exceptions
+            .iter()
+            .filter(|exception| !exception.allowed && exception.date > date)
+            .map(|exception| exception.date.and_hms_opt(0, 0, 0))
+            .min()
+            .flatten();
+    }
+    let weekday = date.weekday().num_days_from_monday();
+    let time = local.time();
+    windows
+        .iter()
+        .filter(|window| {
+            window.weekday == weekday && window.start_time <= time && time < window.end_time
+        })
+        .map(|window| date.and_time(window.end_time))
+        .max()
+}
+
+/// The end of the period, in which the user may open the door at this moment, if it ends
+pub fn allowed_until(
+    conn: &Connection,
+    user: u32,
+    now: DateTime<Utc>,
+) -> Result<Option<DateTime<Utc>>, Error> {
+    let windows = AccessWindowEntry::get_by_user(conn, user)?;
+    let exceptions = AccessExceptionEntry::get_by_user(conn, user)?;
+
+    Ok(
+        period_end(&windows, &exceptions, CONFIG.building.local_time(now))
+            // An end, which is skipped by the daylight saving time, has already passed
+            .map(|end| CONFIG.building.to_utc(&end).unwrap_or(now)),
+    )
+}
+
+/// Checks whether the user may open the door at this moment
+pub fn check(conn: &Connection, user: u32, now: DateTime<Utc>) -> Result<(), Error> {
+    let windows = AccessWindowEntry::get_by_user(conn, user)?;
+    let exceptions = AccessExceptionEntry::get_by_user(conn, user)?;
+
+    is_allowed(&windows, &exceptions, CONFIG.building.local_time(now)).err(Error::OutsideSchedule)
+}
@@ -0,0 +1,145 @@
+use super::*;
+use crate::db_entry::{Entry, UserEntry};
+use chrono::{NaiveDate, NaiveTime, TimeZone};
+
+/// A monday
+fn monday_at(hour: u32, min: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2021, 3, 1)
+        .unwrap()
+        .and_hms_opt(hour, min, 0)
+        .unwrap()
+}
+
+fn window(weekday: u32, start: u32, end: u32) -> AccessWindowEntry {
+    AccessWindowEntry {
+        id: 0,
+        weekday,
+        start_time: NaiveTime::from_hms_opt(start, 0, 0).unwrap(),
+        end_time: NaiveTime::from_hms_opt(end, 0, 0).unwrap(),
+        user: 0,
+    }
+}
+
+fn exception(date: NaiveDate, allowed: bool) -> AccessExceptionEntry {
+    AccessExceptionEntry {
+        id: 0,
+        date,
+        allowed,
+        user: 0,
+    }
+}
+
+#[test]
+fn without_schedule() {
+    assert!(is_allowed(&[], &[], monday_at(3, 0)));
+}
+
+#[test]
+fn windows() {
+    let windows = [window(0, 8, 12), window(0, 14, 16), window(1, 0, 23)];
+
+    assert!(is_allowed(&windows, &[], monday_at(8, 0)));
+    assert!(is_allowed(&windows, &[], monday_at(11, 59)));
+    assert!(!is_allowed(&windows, &[], monday_at(12, 0)));
+    assert!(is_allowed(&windows, &[], monday_at(15, 0)));
+    assert!(!is_allowed(&windows, &[], monday_at(7, 59)));
+    // The window of tuesday doesn't apply on monday
+    assert!(!is_allowed(&windows, &[], monday_at(20, 0)));
+}
+
+#[test]
+fn exceptions() {
+    let monday = monday_at(0, 0).date();
+    let windows = [window(0, 8, 12)];
+
+    assert!(!is_allowed(
+        &windows,
+        &[exception(monday, false)],
+        monday_at(9, 0)
+    ));
+    assert!(is_allowed(
+        &windows,
+        &[exception(monday, true)],
+        monday_at(20, 0)
+    ));
+    // An exception of another day doesn't apply
+    assert!(!is_allowed(
+        &windows,
+        &[exception(monday.succ_opt().unwrap(), true)],
+        monday_at(20, 0)
+    ));
+    // A denied day without any windows
+    assert!(!is_allowed(
+        &[],
+        &[exception(monday, false)],
+        monday_at(9, 0)
+    ));
+}
+
+#[test]
+fn end_of_period() {
+    let monday = monday_at(0, 0).date();
+    let windows = [window(0, 8, 12), window(0, 10, 14), window(1, 0, 23)];
+
+    assert_eq!(
+        period_end(&windows, &[], monday_at(11, 0)),
+        Some(monday_at(14, 0))
+    );
+    assert_eq!(
+        period_end(&windows, &[], monday_at(9, 0)),
+        Some(monday_at(12, 0))
+    );
+    // An exception allows the whole day
+    assert_eq!(
+        period_end(&windows, &[exception(monday, true)], monday_at(20, 0)),
+        monday.succ_opt().unwrap().and_hms_opt(0, 0, 0)
+    );
+    // Without windows only a denied day ends the period
+    assert_eq!(period_end(&[], &[], monday_at(3, 0)), None);
+    let wednesday = monday + chrono::Duration::days(2);
+    assert_eq!(
+        period_end(
+            &[],
+            &[
+                exception(monday - chrono::Duration::days(7), false),
+                exception(wednesday + chrono::Duration::days(7), false),
+                exception(wednesday, false),
+            ],
+            monday_at(3, 0)
+        ),
+        wednesday.and_hms_opt(0, 0, 0)
+    );
+}
+
+#[test]
+fn building_timezone() {
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(include_str!("../../scheme.sql"))
+        .unwrap();
+    let user = UserEntry::default().create(&conn).unwrap();
+    AccessWindowEntry {
+        weekday: 0,
+        start_time: NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+        end_time: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+        user: user.get_id(),
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+
+    // 8:30 in Berlin during the winter time
+    let now = Utc.with_ymd_and_hms(2021, 3, 1, 7, 30, 0).unwrap();
+    assert_eq!(CONFIG.building.local_time(now), monday_at(8, 30));
+    check(&conn, user.get_id(), now).unwrap();
+
+    assert_eq!(
+        allowed_until(&conn, user.get_id(), now).unwrap(),
+        Some(Utc.with_ymd_and_hms(2021, 3, 1, 8, 0, 0).unwrap())
+    );
+
+    let now = Utc.with_ymd_and_hms(2021, 3, 1, 8, 30, 0).unwrap();
+    assert_matches!(
+        check(&conn, user.get_id(), now),
+        Err(Error::OutsideSchedule)
+    );
+}
@@ -0,0 +1,48 @@
+//! Deactivates the users, whose validity has ended, e.g. tenants who moved out or temporary helpers.
+
+use super::audit;
+use crate::db_entry::{rusqlite, AuditAction, Connection, UserEntry, UserSessionEntry};
+use chrono::{offset::Utc, DateTime};
+use log::{error, info};
+use std::thread;
+use std::time::Duration;
+
+#[cfg(test)]
+#[path = "./account_validity_test.rs"]
+mod account_validity_test;
+
+/// The interval in which the expired users are deactivated.
+const DEACTIVATION_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Deactivates the expired users, ends their sessions and returns them.
+pub fn deactivate_expired(
+    conn: &Connection,
+    now: DateTime<Utc>,
+) -> Result<Vec<UserEntry>, rusqlite::Error> {
+    UserEntry::get_expired(conn, now)?
+        .into_iter()
+        .map(|mut user| {
+            user.deactivate(conn)?;
+            UserSessionEntry::delete_by_user(conn, user.id)?;
+            info!("Deactivated the expired user {}", user.name);
+            audit::record(
+                conn,
+                None,
+                AuditAction::UserDeactivate,
+                Some(&user.name),
+                None,
+            );
+            Ok(user)
+        })
+        .collect()
+}
+
+/// Creates a thread, which deactivates the expired users periodically
+pub fn deactivation_job(conn: Connection) {
+    thread::spawn(move || loop {
+        if let Err(e) = deactivate_expired(&conn, Utc::now()) {
+            error!("Can't deactivate the expired users: {}", e);
+        }
+        thread::sleep(DEACTIVATION_INTERVAL);
+    });
+}
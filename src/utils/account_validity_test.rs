@@ -0,0 +1,73 @@
+use super::*;
+use crate::db_entry::{AuditLogEntry, Entry};
+use chrono::Duration;
+
+fn create_conn() -> Connection {
+    let sql_scheme = include_str!("../../scheme.sql");
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(sql_scheme).unwrap();
+    conn
+}
+
+#[test]
+fn expired_user_is_deactivated() {
+    let conn = create_conn();
+    let now = Utc::now();
+
+    let expired = UserEntry {
+        name: "expired".to_string(),
+        valid_until: Some(now - Duration::days(1)),
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+    let valid = UserEntry {
+        name: "valid".to_string(),
+        valid_until: Some(now + Duration::days(1)),
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+    UserSessionEntry {
+        user: expired.get_id(),
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+
+    let deactivated = deactivate_expired(&conn, now).unwrap();
+    assert_eq!(deactivated.len(), 1);
+    assert_eq!(deactivated[0].id, expired.id);
+
+    assert!(
+        !UserEntry::get_by_id(&conn, expired.get_id())
+            .unwrap()
+            .unwrap()
+            .active
+    );
+    assert!(
+        UserEntry::get_by_id(&conn, valid.get_id())
+            .unwrap()
+            .unwrap()
+            .active
+    );
+    assert!(UserSessionEntry::get_by_user(&conn, expired.get_id())
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        AuditLogEntry::get_filtered(
+            &conn,
+            Some(AuditAction::UserDeactivate),
+            None,
+            None,
+            None,
+            10
+        )
+        .unwrap()
+        .len(),
+        1
+    );
+
+    // A deactivated user isn't deactivated again
+    assert!(deactivate_expired(&conn, now).unwrap().is_empty());
+}
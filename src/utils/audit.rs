@@ -0,0 +1,74 @@
+//! Writes the security relevant actions to the append-only audit log and exports it.
+
+use crate::db_entry::{AuditAction, AuditLogEntry, Connection};
+use chrono::offset::Utc;
+use log::error;
+use std::net::IpAddr;
+
+#[cfg(test)]
+#[path = "./audit_test.rs"]
+mod audit_test;
+
+/// The header of the CSV export.
+const CSV_HEADER: &str = "id,datetime,actor,action,target,ip";
+
+/// Appends an entry to the audit log.
+/// A failed write is only logged, as it must not prevent e.g. the opening of the door.
+pub fn record(
+    conn: &Connection,
+    actor: Option<&str>,
+    action: AuditAction,
+    target: Option<&str>,
+    ip: Option<IpAddr>,
+) {
+    let entry = AuditLogEntry {
+        id: (),
+        datetime: Utc::now(),
+        actor: actor.map(str::to_string),
+        action,
+        target: target.map(str::to_string),
+        ip: ip.map(|ip| ip.to_string()),
+    };
+    if let Err(e) = entry.create(conn) {
+        error!(
+            "Can't write the audit log entry {} of {:?}: {}",
+            action, actor, e
+        );
+    }
+}
+
+/// Quotes a CSV field if necessary.
+/// Fields which a spreadsheet would evaluate as formula are prefixed with an apostrophe,
+/// as the names are chosen by the users.
+pub(super) fn csv_field(value: &str) -> String {
+    let value = match value.chars().next() {
+        Some('=') | Some('+') | Some('-') | Some('@') | Some('\t') | Some('\r') => {
+            format!("'{}", value)
+        }
+        _ => value.to_string(),
+    };
+
+    if value.contains(|c| matches!(c, ',' | '"' | '\n' | '\r')) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+/// Converts the entries to CSV with a header line.
+pub fn to_csv(entries: &[AuditLogEntry]) -> String {
+    let mut csv = format!("{}\n", CSV_HEADER);
+    for entry in entries {
+        let fields = [
+            entry.id.to_string(),
+            entry.datetime.to_rfc3339(),
+            entry.actor.as_deref().map(csv_field).unwrap_or_default(),
+            entry.action.to_string(),
+            entry.target.as_deref().map(csv_field).unwrap_or_default(),
+            entry.ip.as_deref().map(csv_field).unwrap_or_default(),
+        ];
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    csv
+}
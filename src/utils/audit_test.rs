@@ -0,0 +1,75 @@
+use super::*;
+
+fn get_entry() -> AuditLogEntry {
+    AuditLogEntry {
+        id: 1,
+        datetime: "2024-05-01T12:00:00Z".parse().unwrap(),
+        actor: Some("Alice".to_string()),
+        action: AuditAction::UserCreate,
+        target: Some("Bob".to_string()),
+        ip: Some("127.0.0.1".to_string()),
+    }
+}
+
+#[test]
+fn record_entry() {
+    let sql_scheme = include_str!("../../scheme.sql");
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(sql_scheme).unwrap();
+
+    record(
+        &conn,
+        Some("Alice"),
+        AuditAction::DoorOpen,
+        None,
+        Some("127.0.0.1".parse().unwrap()),
+    );
+
+    let entries = AuditLogEntry::get_filtered(&conn, None, None, None, None, 10).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].actor.as_deref(), Some("Alice"));
+    assert_eq!(entries[0].action, AuditAction::DoorOpen);
+    assert_eq!(entries[0].target, None);
+    assert_eq!(entries[0].ip.as_deref(), Some("127.0.0.1"));
+}
+
+#[test]
+fn csv_export() {
+    let entries = vec![
+        get_entry(),
+        AuditLogEntry {
+            id: 2,
+            actor: None,
+            target: None,
+            ip: None,
+            ..get_entry()
+        },
+    ];
+
+    assert_eq!(
+        to_csv(&entries),
+        "id,datetime,actor,action,target,ip\n\
+         1,2024-05-01T12:00:00+00:00,Alice,user.create,Bob,127.0.0.1\n\
+         2,2024-05-01T12:00:00+00:00,,user.create,,\n"
+    );
+}
+
+#[test]
+fn csv_export_of_empty_log() {
+    assert_eq!(to_csv(&[]), "id,datetime,actor,action,target,ip\n");
+}
+
+#[test]
+fn csv_quotes_fields() {
+    assert_eq!(csv_field("Alice"), "Alice");
+    assert_eq!(csv_field("Doe, Jane"), "\"Doe, Jane\"");
+    assert_eq!(csv_field("\"Bob\""), "\"\"\"Bob\"\"\"");
+    assert_eq!(csv_field("a\nb"), "\"a\nb\"");
+}
+
+#[test]
+fn csv_escapes_formulas() {
+    assert_eq!(csv_field("=1+1"), "'=1+1");
+    assert_eq!(csv_field("@SUM(A1)"), "'@SUM(A1)");
+    assert_eq!(csv_field("-2,3"), "\"'-2,3\"");
+}
@@ -0,0 +1,93 @@
+//! The offline check of passwords against a list of breached passwords, e.g. of [Have I Been Pwned](https://haveibeenpwned.com/Passwords).
+//! The list contains the upper case SHA-1 hashes of the passwords, one per line and optionally followed by `:<count>`.
+//! A downloaded range file contains only the suffixes of the hashes, their prefix is the name of the file, e.g. `5BAA6.txt`.
+
+use super::config::CONFIG;
+use sha1::{Digest, Sha1};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+#[cfg(test)]
+#[path = "./breached_passwords_test.rs"]
+mod breached_passwords_test;
+
+/// The length of a SHA-1 hash in hexadecimal representation.
+const HASH_LENGTH: usize = 40;
+/// The length of the prefix of a range file.
+const PREFIX_LENGTH: usize = 5;
+
+type Sha1Hash = [u8; 20];
+
+lazy_static! {
+    /// The hashes of `security.breached_passwords_file`, which are loaded at startup
+    pub static ref BREACHED_PASSWORDS: HashSet<Sha1Hash> = match &CONFIG.security.breached_passwords_file {
+        Some(path) => match load(path) {
+            Ok(hashes) => hashes,
+            Err(error) => panic!("Breached Passwords Error: {}", error),
+        },
+        None => HashSet::new(),
+    };
+}
+
+/// All errors which could happen during the loading of the list.
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("Line {0} isn't a SHA-1 hash")]
+    InvalidLine(usize),
+}
+
+/// Decodes a hexadecimal SHA-1 hash.
+fn decode(hex: &str) -> Option<Sha1Hash> {
+    if hex.len() != HASH_LENGTH || !hex.is_ascii() {
+        return None;
+    }
+    let mut hash = Sha1Hash::default();
+    for (i, byte) in hash.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).ok()?;
+    }
+    Some(hash)
+}
+
+/// Reads the hashes of a list. The prefix is prepended to every line of a range file.
+pub fn parse<R: BufRead>(reader: R, prefix: &str) -> Result<HashSet<Sha1Hash>, Error> {
+    let mut hashes = HashSet::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let hash = line.split(':').next().unwrap_or_default().trim();
+        if hash.is_empty() {
+            continue;
+        }
+        let hash = match hash.len() {
+            HASH_LENGTH => decode(hash),
+            _ => decode(&format!("{}{}", prefix, hash)),
+        };
+        hashes.insert(hash.ok_or(Error::InvalidLine(index + 1))?);
+    }
+    Ok(hashes)
+}
+
+/// Loads the list of the file, whose name is the prefix if it is a range file.
+pub fn load(path: &Path) -> Result<HashSet<Sha1Hash>, Error> {
+    let prefix = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .filter(|stem| stem.len() == PREFIX_LENGTH)
+        .unwrap_or_default();
+
+    parse(BufReader::new(File::open(path)?), prefix)
+}
+
+/// Checks whether the password is in the list.
+pub fn is_breached_in(hashes: &HashSet<Sha1Hash>, pw: &str) -> bool {
+    let hash: Sha1Hash = Sha1::digest(pw.as_bytes()).into();
+    hashes.contains(&hash)
+}
+
+/// Checks whether the password is in the list of `security.breached_passwords_file`.
+pub fn is_breached(pw: &str) -> bool {
+    is_breached_in(&BREACHED_PASSWORDS, pw)
+}
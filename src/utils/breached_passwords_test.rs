@@ -0,0 +1,48 @@
+use super::*;
+
+/// The SHA-1 hash of "password"
+const PASSWORD_HASH: &str = "5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8";
+
+#[test]
+fn parse_full_hashes() {
+    let list = format!(
+        "{}:9545824\n\n7C4A8D09CA3762AF61E59520943DC26494F8941B:4636\n",
+        PASSWORD_HASH
+    );
+    let hashes = parse(list.as_bytes(), "").unwrap();
+
+    assert_eq!(hashes.len(), 2);
+    assert!(is_breached_in(&hashes, "password"));
+    assert!(is_breached_in(&hashes, "123456"));
+    assert!(!is_breached_in(&hashes, "correct horse battery staple"));
+}
+
+#[test]
+fn parse_range_file() {
+    let list = format!("{}:9545824\r\n", &PASSWORD_HASH[PREFIX_LENGTH..]);
+    let hashes = parse(list.as_bytes(), &PASSWORD_HASH[..PREFIX_LENGTH]).unwrap();
+
+    assert!(is_breached_in(&hashes, "password"));
+}
+
+#[test]
+fn parse_lower_case_hash() {
+    let hashes = parse(PASSWORD_HASH.to_lowercase().as_bytes(), "").unwrap();
+
+    assert!(is_breached_in(&hashes, "password"));
+}
+
+#[test]
+fn parse_invalid_line() {
+    let list = format!("{}\nno hash:1\n", PASSWORD_HASH);
+
+    assert!(matches!(
+        parse(list.as_bytes(), ""),
+        Err(Error::InvalidLine(2))
+    ));
+}
+
+#[test]
+fn empty_list() {
+    assert!(!is_breached("password"));
+}
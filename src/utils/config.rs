@@ -1,12 +1,18 @@
 //! Data structures for configuration
 
 use super::serde::deserialize_optional_duration;
+use crate::db_entry::UserType;
 use bool_ext::BoolExt;
+use chrono::{offset::Utc, DateTime, FixedOffset, NaiveDateTime, Offset, TimeZone};
+use chrono_tz::Tz;
 use duration_str::deserialize_duration;
+use openidconnect::url::Url;
 use serde::Deserialize;
 use serde_with::{hex::Hex, serde_as};
 use std::collections::HashSet;
+use std::net::IpAddr;
 use std::ops::Not;
+use std::path::PathBuf;
 use std::time::Duration;
 
 #[cfg(not(test))]
@@ -21,14 +27,18 @@ lazy_static! {
 lazy_static! {
     pub static ref CONFIG: Config = Config {
         iot: IoT {
-            door_opener_pin: 0,
             tamper_sensor_pin: None,
-            door_opening_time: Duration::from_secs(3),
             bell_debounce_interval: Duration::from_millis(42),
             tamper_sensor_debounce_interval: None,
+            door_held_open_time: Some(Duration::from_millis(100)),
+            max_hold_open_time: Some(Duration::from_secs(12 * 3600)),
         },
         web: Web {
             mjpeg_stream_port: 8081,
+            trusted_proxies: vec!["127.0.0.1".parse().unwrap()],
+        },
+        building: Building {
+            timezone: Tz::Europe__Berlin,
         },
         security: Security {
             minimal_password_strength_score: 80.0,
@@ -40,8 +50,25 @@ lazy_static! {
                 0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef, 0x01, 0x23, 0x45, 0x67, 0x89, 0xab,
                 0xcd, 0xef,
             ],
-            allowed_hash_configs: hashset!["Blake2b".to_string()],
+            hash_memory_cost: 64,
+            hash_time_cost: 1,
+            hash_parallelism: 1,
+            allowed_hash_configs: hashset!["Argon2id".to_string(), "Blake2b".to_string()],
+            admin_two_factor_required: false,
+            login_lockout_threshold: 3,
+            ip_lockout_threshold: 5,
+            login_lockout_time: Duration::from_secs(60),
+            max_login_lockout_time: Duration::from_secs(3600),
+            session_lifetime: Duration::from_secs(3600),
+            session_idle_timeout: Duration::from_secs(600),
+            password_reset_lifetime: Duration::from_secs(3600),
+            password_history_length: 3,
+            max_password_age: Some(Duration::from_secs(90 * 24 * 3600)),
+            breached_passwords_file: None,
         },
+        ldap: None,
+        oidc: None,
+        webauthn: None,
     };
 }
 
@@ -59,6 +86,38 @@ pub enum Error {
     SecretDefaultValue(String),
     #[error("The `security.allowed_hash_configs` entry is empty.")]
     EmptyHashConfigs,
+    #[error("The `{0}` entry has to be greater than 0.")]
+    InvalidLockoutThreshold(String),
+    #[error("The `security.login_lockout_time` entry has to be between 0s and `security.max_login_lockout_time`.")]
+    InvalidLockoutTime,
+    #[error("The `security.session_idle_timeout` entry has to be between 0s and `security.session_lifetime`.")]
+    InvalidSessionTimeout,
+    #[error("The `security.password_reset_lifetime` entry has to be greater than 0s.")]
+    InvalidPasswordResetLifetime,
+    #[error("The `{0}` entry has to be at most 100 years.")]
+    DurationTooLong(String),
+    #[error("The `ldap.bind_dn_template` entry has to contain `{{username}}`.")]
+    InvalidLdapBindDnTemplate,
+    #[error("The `ldap.groups` entry is empty.")]
+    EmptyLdapGroups,
+    #[error("The `{0}` entry isn't a valid URL.")]
+    InvalidOidcUrl(String),
+    #[error("The `oidc.groups` entry is empty.")]
+    EmptyOidcGroups,
+    #[error("The `webauthn.rp_origin` entry has to be an URL, whose host is `webauthn.rp_id` or one of its subdomains.")]
+    InvalidWebauthnOrigin,
+    #[error("The `security.hash_*_cost` or `security.hash_parallelism` entries are invalid: {0}")]
+    InvalidHashParams(argon2::Error),
+}
+
+/// The maximal duration of a config entry, which is added to or subtracted from the current time.
+const MAX_DURATION_DAYS: i64 = 100 * 365;
+
+/// Converts a duration of the config to a chrono duration.
+/// Durations longer than the maximum are clamped, so the current time plus the duration doesn't overflow.
+pub fn to_chrono_duration(duration: Duration) -> chrono::Duration {
+    let max = chrono::Duration::days(MAX_DURATION_DAYS);
+    chrono::Duration::from_std(duration).map_or(max, |duration| duration.min(max))
 }
 
 /// A trait for validating the configuration recursively.
@@ -69,18 +128,9 @@ trait ConfigValidator {
 /// All configuration options regarding the `iot` module
 #[derive(Debug, Deserialize, Clone)]
 pub struct IoT {
-    /// The GPIO pin which controls the door-opener.
-    /// [Pinout Diagram](https://pinout.xyz).
-    pub door_opener_pin: u8,
-
     /// The optional GPIO pin for a tamper sensor which sets an alarm off when there is no connection.
     pub tamper_sensor_pin: Option<u8>,
 
-    /// The duration how long the door opener is activated.
-    /// The format is documented [here](https://docs.rs/duration-str/latest/duration_str/).
-    #[serde(deserialize_with = "deserialize_duration")]
-    pub door_opening_time: Duration,
-
     /// The minimal duration between two signals.
     /// The timer of the last signal is resetted on every signal.
     /// The format is documented [here](https://docs.rs/duration-str/latest/duration_str/).
@@ -92,6 +142,17 @@ pub struct IoT {
     /// The format is documented [here](https://docs.rs/duration-str/latest/duration_str/).
     #[serde(default, deserialize_with = "deserialize_optional_duration")]
     pub tamper_sensor_debounce_interval: Option<Duration>,
+
+    /// The duration after which an open door sets an alarm off. It requires the sensor pin of the door.
+    /// The format is documented [here](https://docs.rs/duration-str/latest/duration_str/).
+    #[serde(default, deserialize_with = "deserialize_optional_duration")]
+    pub door_held_open_time: Option<Duration>,
+
+    /// The optional maximal duration of the hold-open mode, a longer one is shortened.
+    /// Without it a door can be held open until the hold-open mode is cancelled.
+    /// The format is documented [here](https://docs.rs/duration-str/latest/duration_str/).
+    #[serde(default, deserialize_with = "deserialize_optional_duration")]
+    pub max_hold_open_time: Option<Duration>,
 }
 
 impl IoT {
@@ -104,7 +165,6 @@ impl IoT {
 
 impl ConfigValidator for IoT {
     fn validate(&self) -> Result<(), Error> {
-        Self::validate_gpio(self.door_opener_pin, "iot.door_opener_pin".to_string())?;
         if let Some(tamper_sensor_pin) = self.tamper_sensor_pin {
             Self::validate_gpio(tamper_sensor_pin, "iot.tamper_sensor_pin".to_string())?;
         }
@@ -117,6 +177,46 @@ impl ConfigValidator for IoT {
 pub struct Web {
     /// The TCP port of the MJPEG streamer
     pub mjpeg_stream_port: u16,
+    /// The addresses of the reverse proxies, whose `X-Real-IP` header is used as client IP.
+    /// The header of any other client is ignored, as it could be forged.
+    #[serde(default)]
+    pub trusted_proxies: Vec<IpAddr>,
+}
+
+/// Configuration options regarding the building
+#[derive(Debug, Deserialize, Clone)]
+pub struct Building {
+    /// The timezone of the building, in which the access schedules of the users are evaluated,
+    /// e.g. `Europe/Berlin`. The names are listed [here](https://en.wikipedia.org/wiki/List_of_tz_database_time_zones).
+    pub timezone: Tz,
+}
+
+impl Default for Building {
+    fn default() -> Self {
+        Self { timezone: Tz::UTC }
+    }
+}
+
+impl Building {
+    /// The point in time with the current offset of the building's timezone
+    pub fn local_datetime(&self, datetime: DateTime<Utc>) -> DateTime<FixedOffset> {
+        let local = datetime.with_timezone(&self.timezone);
+        local.with_timezone(&local.offset().fix())
+    }
+
+    /// The wall-clock time of the building
+    pub fn local_time(&self, datetime: DateTime<Utc>) -> NaiveDateTime {
+        datetime.with_timezone(&self.timezone).naive_local()
+    }
+
+    /// The point in time of a wall-clock time of the building.
+    /// An ambiguous time is the earlier one, a skipped time doesn't exist.
+    pub fn to_utc(&self, local: &NaiveDateTime) -> Option<DateTime<Utc>> {
+        self.timezone
+            .from_local_datetime(local)
+            .earliest()
+            .map(|datetime| datetime.with_timezone(&Utc))
+    }
 }
 
 /// You can generate such a value in hexadecimal representation with OpenSSL.
@@ -146,12 +246,67 @@ pub struct Security {
     /// ```
     #[serde_as(as = "Hex")]
     pub encryption_key: Secret128Bit,
-    /// A set of the hash configurations, which are allowed for authentication.
+    /// The memory cost in KiB of the Argon2id password hashing.
+    /// The parameters are documented [here](https://datatracker.ietf.org/doc/html/rfc9106#section-4).
+    pub hash_memory_cost: u32,
+    /// The number of iterations of the Argon2id password hashing.
+    pub hash_time_cost: u32,
+    /// The degree of parallelism of the Argon2id password hashing.
+    pub hash_parallelism: u32,
+    /// A set of the hashing algorithms, which are allowed for authentication.
+    /// Outdated hashes are replaced by an Argon2id hash with the current parameters on login.
     /// "plain" should be removed after the first setup.
     pub allowed_hash_configs: HashSet<String>,
+    /// Whether administrators have to enable the TOTP second factor before they can use the admin views.
+    #[serde(default)]
+    pub admin_two_factor_required: bool,
+    /// The number of failed logins of an user name after which it is locked.
+    pub login_lockout_threshold: u32,
+    /// The number of failed logins of an IP address after which it is locked.
+    pub ip_lockout_threshold: u32,
+    /// The duration of the first lockout, which is doubled on every further failed login.
+    /// The format is documented [here](https://docs.rs/duration-str/latest/duration_str/).
+    #[serde(deserialize_with = "deserialize_duration")]
+    pub login_lockout_time: Duration,
+    /// The maximal duration of a lockout. Failed logins which are older are forgotten.
+    /// The format is documented [here](https://docs.rs/duration-str/latest/duration_str/).
+    #[serde(deserialize_with = "deserialize_duration")]
+    pub max_login_lockout_time: Duration,
+    /// The duration after which a session expires and the user has to log in again.
+    /// The format is documented [here](https://docs.rs/duration-str/latest/duration_str/).
+    #[serde(deserialize_with = "deserialize_duration")]
+    pub session_lifetime: Duration,
+    /// The duration without any request after which a session expires.
+    /// The format is documented [here](https://docs.rs/duration-str/latest/duration_str/).
+    #[serde(deserialize_with = "deserialize_duration")]
+    pub session_idle_timeout: Duration,
+    /// The duration in which a password reset link of an admin can be used.
+    /// The format is documented [here](https://docs.rs/duration-str/latest/duration_str/).
+    #[serde(deserialize_with = "deserialize_duration")]
+    pub password_reset_lifetime: Duration,
+    /// The number of the last passwords of an user, including the current one, which can't be reused.
+    #[serde(default)]
+    pub password_history_length: u32,
+    /// The optional age after which a password has to be changed on the next login.
+    /// The format is documented [here](https://docs.rs/duration-str/latest/duration_str/).
+    #[serde(default, deserialize_with = "deserialize_optional_duration")]
+    pub max_password_age: Option<Duration>,
+    /// The optional list of breached passwords, e.g. of Have I Been Pwned, which is loaded at startup.
+    /// It contains an upper case SHA-1 hash per line, optionally followed by `:<count>`.
+    #[serde(default)]
+    pub breached_passwords_file: Option<PathBuf>,
 }
 
 impl Security {
+    fn validate_lockout_threshold(threshold: u32, name: String) -> Result<(), Error> {
+        (threshold > 0).err(Error::InvalidLockoutThreshold(name))
+    }
+
+    fn validate_duration(duration: Duration, name: &str) -> Result<(), Error> {
+        (duration.as_secs() <= MAX_DURATION_DAYS as u64 * 24 * 3600)
+            .err(Error::DurationTooLong(name.to_string()))
+    }
+
     #[cfg(not(debug_assertions))]
     fn validate_secret(secret: Secret128Bit, name: String) -> Result<(), Error> {
         const DEFAULT_SECRET: Secret128Bit = [
@@ -177,6 +332,44 @@ impl ConfigValidator for Security {
         Self::validate_secret(self.hash_pepper, "security.hash_pepper".to_string())?;
         Self::validate_secret(self.hash_pepper, "security.hash_pepper".to_string())?;
 
+        argon2::Params::new(
+            self.hash_memory_cost,
+            self.hash_time_cost,
+            self.hash_parallelism,
+            None,
+        )
+        .map_err(Error::InvalidHashParams)?;
+
+        Self::validate_lockout_threshold(
+            self.login_lockout_threshold,
+            "security.login_lockout_threshold".to_string(),
+        )?;
+        Self::validate_lockout_threshold(
+            self.ip_lockout_threshold,
+            "security.ip_lockout_threshold".to_string(),
+        )?;
+        (Duration::from_secs(0) < self.login_lockout_time
+            && self.login_lockout_time <= self.max_login_lockout_time)
+            .err(Error::InvalidLockoutTime)?;
+        (Duration::from_secs(0) < self.session_idle_timeout
+            && self.session_idle_timeout <= self.session_lifetime)
+            .err(Error::InvalidSessionTimeout)?;
+        (Duration::from_secs(0) < self.password_reset_lifetime)
+            .err(Error::InvalidPasswordResetLifetime)?;
+
+        Self::validate_duration(
+            self.max_login_lockout_time,
+            "security.max_login_lockout_time",
+        )?;
+        Self::validate_duration(self.session_lifetime, "security.session_lifetime")?;
+        Self::validate_duration(
+            self.password_reset_lifetime,
+            "security.password_reset_lifetime",
+        )?;
+        if let Some(max_password_age) = self.max_password_age {
+            Self::validate_duration(max_password_age, "security.max_password_age")?;
+        }
+
         self.allowed_hash_configs
             .is_empty()
             .not()
@@ -184,12 +377,154 @@ impl ConfigValidator for Security {
     }
 }
 
+/// The user type of the members of a directory group
+#[derive(Debug, Deserialize, Clone)]
+pub struct LdapGroup {
+    /// The DN of the group
+    pub dn: String,
+    pub user_type: UserType,
+}
+
+/// Configuration options regarding the login against an LDAP directory, e.g. Active Directory.
+/// Users of the directory are created or updated on their login, local accounts keep working.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Ldap {
+    /// The URL of the directory server, e.g. `ldap://localhost:389` or `ldaps://ldap.example.org`.
+    pub server_url: String,
+    /// The DN of the user to bind with, `{username}` is replaced by the escaped user name,
+    /// e.g. `uid={username},ou=people,dc=example,dc=org`.
+    pub bind_dn_template: String,
+    /// The attribute of the user, which contains the DNs of its groups.
+    #[serde(default = "Ldap::default_group_attribute")]
+    pub group_attribute: String,
+    /// The user types of the groups. A member of several groups gets the most privileged type,
+    /// users who aren't member of any group can't log in with the directory.
+    pub groups: Vec<LdapGroup>,
+    /// The optional attribute of the user, which holds the ID of its flat.
+    pub flat_attribute: Option<String>,
+    /// The timeout of the connection to the directory server.
+    /// The format is documented [here](https://docs.rs/duration-str/latest/duration_str/).
+    #[serde(deserialize_with = "deserialize_duration")]
+    pub timeout: Duration,
+}
+
+impl Ldap {
+    fn default_group_attribute() -> String {
+        "memberOf".to_string()
+    }
+}
+
+impl ConfigValidator for Ldap {
+    fn validate(&self) -> Result<(), Error> {
+        self.bind_dn_template
+            .contains("{username}")
+            .err(Error::InvalidLdapBindDnTemplate)?;
+
+        self.groups.is_empty().not().err(Error::EmptyLdapGroups)
+    }
+}
+
+/// The user type of the members of a group of the identity provider
+#[derive(Debug, Deserialize, Clone)]
+pub struct OidcGroup {
+    /// The name of the group in the groups claim
+    pub name: String,
+    pub user_type: UserType,
+}
+
+/// Configuration options regarding the single sign-on with an OpenID Connect provider, e.g. Keycloak.
+/// Its users are created or updated on their login with the `preferred_username` claim as name,
+/// local accounts keep working.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Oidc {
+    /// The URL of the issuer, which serves `/.well-known/openid-configuration`,
+    /// e.g. `https://keycloak.example.org/realms/doorcam`.
+    pub issuer_url: String,
+    pub client_id: String,
+    /// The secret of a confidential client, public clients are only protected by PKCE.
+    pub client_secret: Option<String>,
+    /// The absolute URL of the callback route, which has to be registered at the provider,
+    /// e.g. `https://doorcam.example.org/login/oidc/callback`.
+    pub redirect_url: String,
+    /// The claim, which contains the names of the groups of the user.
+    #[serde(default = "Oidc::default_groups_claim")]
+    pub groups_claim: String,
+    /// The user types of the groups. A member of several groups gets the most privileged type,
+    /// users who aren't member of any group can't log in with the provider.
+    pub groups: Vec<OidcGroup>,
+    /// The optional claim, which holds the ID of the flat of the user.
+    pub flat_claim: Option<String>,
+}
+
+impl Oidc {
+    fn default_groups_claim() -> String {
+        "groups".to_string()
+    }
+
+    fn validate_url(url: &str, name: String) -> Result<(), Error> {
+        Url::parse(url)
+            .map(|_| ())
+            .map_err(|_| Error::InvalidOidcUrl(name))
+    }
+}
+
+impl ConfigValidator for Oidc {
+    fn validate(&self) -> Result<(), Error> {
+        Self::validate_url(&self.issuer_url, "oidc.issuer_url".to_string())?;
+        Self::validate_url(&self.redirect_url, "oidc.redirect_url".to_string())?;
+
+        self.groups.is_empty().not().err(Error::EmptyOidcGroups)
+    }
+}
+
+/// Configuration options regarding the passwordless login with passkeys.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Webauthn {
+    /// The domain of the relying party, to which the passkeys are bound, e.g. `doorcam.example.org`.
+    pub rp_id: String,
+    /// The origin of the web interface, e.g. `https://doorcam.example.org`.
+    pub rp_origin: String,
+    /// The name of the relying party, which is shown by the authenticator.
+    #[serde(default = "Webauthn::default_rp_name")]
+    pub rp_name: String,
+}
+
+impl Webauthn {
+    fn default_rp_name() -> String {
+        "DoorCam".to_string()
+    }
+}
+
+impl ConfigValidator for Webauthn {
+    fn validate(&self) -> Result<(), Error> {
+        let host = Url::parse(&self.rp_origin)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .ok_or(Error::InvalidWebauthnOrigin)?;
+
+        (host == self.rp_id || host.ends_with(&format!(".{}", self.rp_id)))
+            .err(Error::InvalidWebauthnOrigin)
+    }
+}
+
 /// All configuration options
 #[derive(Debug, Deserialize, Clone)]
 pub struct Config {
     pub iot: IoT,
     pub web: Web,
+    /// The timezone is UTC without this section.
+    #[serde(default)]
+    pub building: Building,
     pub security: Security,
+    /// The LDAP login is disabled without this section.
+    #[serde(default)]
+    pub ldap: Option<Ldap>,
+    /// The OpenID Connect login is disabled without this section.
+    #[serde(default)]
+    pub oidc: Option<Oidc>,
+    /// The passkey login is disabled without this section.
+    #[serde(default)]
+    pub webauthn: Option<Webauthn>,
 }
 
 impl Config {
@@ -210,6 +545,16 @@ impl ConfigValidator for Config {
     fn validate(&self) -> Result<(), Error> {
         self.iot.validate()?;
 
+        if let Some(ldap) = &self.ldap {
+            ldap.validate()?;
+        }
+        if let Some(oidc) = &self.oidc {
+            oidc.validate()?;
+        }
+        if let Some(webauthn) = &self.webauthn {
+            webauthn.validate()?;
+        }
+
         self.security.validate()
     }
 }
@@ -2,6 +2,7 @@
 use super::config::CONFIG;
 use crate::db_entry::HashEntry;
 use aes::Aes128;
+use argon2::{Algorithm, Argon2, Params, Version};
 use blake2::{Blake2b, Digest};
 use block_modes::block_padding::Iso7816;
 use block_modes::{BlockMode, BlockModeError, Pcbc};
@@ -23,32 +24,141 @@ where
     }
 }
 
+/// The name of the currently most secure Hashing algorithm.
+const ARGON2ID: &str = "Argon2id";
+
+/// All errors which could happen during the hashing of a password.
+#[derive(thiserror::Error, Debug)]
+pub enum HashError {
+    #[error(transparent)]
+    Decode(#[from] base64::DecodeError),
+    #[error("Argon2: {0}")]
+    Argon2(argon2::Error),
+    #[error("The hash-config is unknown")]
+    UnknownHashConfig,
+}
+
+impl From<argon2::Error> for HashError {
+    fn from(error: argon2::Error) -> Self {
+        Self::Argon2(error)
+    }
+}
+
+/// Returns the hash-config of new hashes.
+/// It has the format `Argon2id$m=<memory cost>,t=<time cost>,p=<parallelism>`.
+pub fn current_hash_config() -> String {
+    format!(
+        "{}$m={},t={},p={}",
+        ARGON2ID,
+        CONFIG.security.hash_memory_cost,
+        CONFIG.security.hash_time_cost,
+        CONFIG.security.hash_parallelism
+    )
+}
+
+/// Returns the name of the hashing algorithm of a hash-config, e.g. "Argon2id" of "Argon2id$m=19456,t=2,p=1".
+pub fn hash_algorithm(config: &str) -> &str {
+    config.split('$').next().unwrap_or(config)
+}
+
+/// Checks whether the hash has been created with another hash-config than the current one.
+pub fn is_outdated(hash_entry: &HashEntry) -> bool {
+    hash_entry.config != current_hash_config()
+}
+
 /// Creates a HashEntry out of a password with a random salt and the currently most secure Hashing algorithm
 pub fn hash(pw: &str) -> HashEntry {
     let mut pw_salt: [u8; 16] = [0; 16];
 
     fill_rand_array(&mut pw_salt);
 
-    let pw_hash = base64::encode(
-        Blake2b::new()
-            .chain(pw)
-            .chain(b"$")
-            .chain(pw_salt)
-            .chain(b"$")
-            .chain(&CONFIG.security.hash_pepper)
-            .finalize(),
-    );
     let encoded_pw_salt = base64::encode(pw_salt);
+    let config = current_hash_config();
+
+    let pw_hash = hash_with_config(pw, &encoded_pw_salt, &config)
+        .expect("The Argon2id parameters are validated with the config");
 
     HashEntry {
         hash: pw_hash,
         salt: encoded_pw_salt,
-        config: "Blake2b".to_string(),
+        config,
+    }
+}
+
+/// Hashes a password with the encoded salt and the algorithm and parameters of the hash-config.
+pub fn hash_with_config(pw: &str, encoded_salt: &str, config: &str) -> Result<String, HashError> {
+    let (algorithm, params) = config.split_once('$').unwrap_or((config, ""));
+
+    match algorithm {
+        "plain" => Ok(pw.to_string()),
+        "Blake2b" => Ok(base64::encode(
+            Blake2b::new()
+                .chain(pw)
+                .chain(b"$")
+                .chain(base64::decode(encoded_salt)?)
+                .chain(b"$")
+                .chain(CONFIG.security.hash_pepper)
+                .finalize(),
+        )),
+        ARGON2ID => {
+            let mut pw_hash = [0; 32];
+            Argon2::new_with_secret(
+                &CONFIG.security.hash_pepper,
+                Algorithm::Argon2id,
+                Version::V0x13,
+                parse_argon2_params(params)?,
+            )?
+            .hash_password_into(
+                pw.as_bytes(),
+                &base64::decode(encoded_salt)?,
+                &mut pw_hash,
+            )?;
+            Ok(base64::encode(pw_hash))
+        }
+        _ => Err(HashError::UnknownHashConfig),
+    }
+}
+
+/// Parses the Argon2 parameters in the format `m=<memory cost>,t=<time cost>,p=<parallelism>`.
+fn parse_argon2_params(params: &str) -> Result<Params, HashError> {
+    let (mut m_cost, mut t_cost, mut p_cost) = (None, None, None);
+
+    for param in params.split(',') {
+        let (key, value) = param.split_once('=').ok_or(HashError::UnknownHashConfig)?;
+        let value = value
+            .parse::<u32>()
+            .map_err(|_| HashError::UnknownHashConfig)?;
+        match key {
+            "m" => m_cost = Some(value),
+            "t" => t_cost = Some(value),
+            "p" => p_cost = Some(value),
+            _ => return Err(HashError::UnknownHashConfig),
+        }
+    }
+
+    match (m_cost, t_cost, p_cost) {
+        (Some(m_cost), Some(t_cost), Some(p_cost)) => {
+            Ok(Params::new(m_cost, t_cost, p_cost, None)?)
+        }
+        _ => Err(HashError::UnknownHashConfig),
     }
 }
 
+/// Hashes a random token, e.g. a recovery code, with the pepper.
+/// A salt isn't needed as random tokens are unique, so the hash can be looked up in the database.
+pub fn hash_token(token: &str) -> String {
+    base64::encode(
+        Blake2b::new()
+            .chain(token)
+            .chain(b"$")
+            .chain(CONFIG.security.hash_pepper)
+            .finalize(),
+    )
+}
+
+/// Needs as long as the hashing of a password in order to hide whether an user exists.
 pub fn pseudo_hash() {
-    Blake2b::new().finalize();
+    hash("");
 }
 
 type Aes128Pcbc = Pcbc<Aes128, Iso7816>;
@@ -65,3 +65,63 @@ fn symetric_encrypt_mutated_ciphertext_decrypt() {
 
     assert_matches!(decrypted_ciphertext, Err(_));
 }
+
+#[test]
+fn hash_and_rehash_with_config() {
+    let pw_hash = hash("Hello World");
+
+    assert_eq!(hash_algorithm(&pw_hash.config), "Argon2id");
+    assert_eq!(
+        hash_with_config("Hello World", &pw_hash.salt, &pw_hash.config).unwrap(),
+        pw_hash.hash
+    );
+    assert_ne!(
+        hash_with_config("Hello World!", &pw_hash.salt, &pw_hash.config).unwrap(),
+        pw_hash.hash
+    );
+}
+
+#[test]
+fn hash_is_not_outdated() {
+    assert!(!is_outdated(&hash("Hello World")));
+}
+
+#[test]
+fn blake2b_is_outdated() {
+    let pw_hash = HashEntry {
+        hash: hash_with_config("Hello World", "c2FsdA==", "Blake2b").unwrap(),
+        salt: "c2FsdA==".to_string(),
+        config: "Blake2b".to_string(),
+    };
+
+    assert!(is_outdated(&pw_hash));
+}
+
+#[test]
+fn argon2id_with_other_params_is_outdated() {
+    let mut pw_hash = hash("Hello World");
+    pw_hash.config = "Argon2id$m=128,t=1,p=1".to_string();
+
+    assert!(is_outdated(&pw_hash));
+    assert_matches!(
+        hash_with_config("Hello World", &pw_hash.salt, &pw_hash.config),
+        Ok(_)
+    );
+}
+
+#[test]
+fn plain_hash_with_config() {
+    assert_eq!(hash_with_config("admin", "", "plain").unwrap(), "admin");
+}
+
+#[test]
+fn unknown_hash_config() {
+    assert_matches!(
+        hash_with_config("admin", "", "MD5"),
+        Err(HashError::UnknownHashConfig)
+    );
+    assert_matches!(
+        hash_with_config("admin", "c2FsdA==", "Argon2id$m=64,x=1"),
+        Err(HashError::UnknownHashConfig)
+    );
+}
@@ -0,0 +1,104 @@
+//! Protection against cross-site request forgery with a synchronizer token.
+//! The token is stored in a private cookie and has to be sent back with every state-changing request,
+//! either as the `csrf_token` form field or, for requests from JavaScript, as the `X-CSRF-Token` header.
+
+use super::crypto;
+use bool_ext::BoolExt;
+use rocket::http::{Cookie, Status};
+use rocket::request::{self, FromRequest, Request};
+use rocket::Outcome;
+use serde::Serialize;
+
+#[cfg(test)]
+#[path = "./csrf_test.rs"]
+mod csrf_test;
+
+/// The name of the private cookie, which holds the token.
+const CSRF_COOKIE: &str = "csrf_token";
+/// The name of the header, which is used by the JavaScript requests.
+const CSRF_HEADER: &str = "X-CSRF-Token";
+/// The length of the random token in bytes.
+const TOKEN_LENGTH: usize = 32;
+
+/// All errors which could happen during the verification of the token.
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("The form has expired, please try again")]
+    InvalidToken,
+}
+
+/// Struct for forms, which only consist of the token, e.g. a button.
+#[derive(FromForm)]
+pub struct CsrfForm {
+    pub csrf_token: String,
+}
+
+/// A guard which retrieves the token of the client. A new token is created, if the client has none.
+pub struct CsrfToken(pub String);
+
+impl CsrfToken {
+    /// Checks whether the submitted token matches the token of the client
+    pub fn verify(&self, token: &str) -> Result<(), Error> {
+        constant_time_eq(self.0.as_bytes(), token.as_bytes()).err(Error::InvalidToken)
+    }
+
+    /// Adds the token to a template context.
+    /// It's used by the `csrf_field` macro of the forms and by the `X-CSRF-Token` header of the JavaScript requests.
+    pub fn context<C: Serialize>(&self, context: C) -> CsrfContext<C> {
+        CsrfContext {
+            context,
+            csrf_token: self.0.clone(),
+        }
+    }
+}
+
+/// Compares two byte slices in a time, which only depends on the length.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+impl<'a, 'r> FromRequest<'a, 'r> for CsrfToken {
+    type Error = ();
+
+    /// Reads the token from the private cookie or creates it
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
+        let mut cookies = request.cookies();
+        if let Some(cookie) = cookies.get_private(CSRF_COOKIE) {
+            return Outcome::Success(Self(cookie.value().to_string()));
+        }
+
+        let mut token = [0; TOKEN_LENGTH];
+        crypto::fill_rand_array(&mut token);
+        let token = base64::encode(token);
+        cookies.add_private(Cookie::new(CSRF_COOKIE, token.clone()));
+
+        Outcome::Success(Self(token))
+    }
+}
+
+/// A template context with the token of the client.
+#[derive(Serialize)]
+pub struct CsrfContext<C: Serialize> {
+    #[serde(flatten)]
+    context: C,
+    csrf_token: String,
+}
+
+/// A guard which allows only requests with a valid `X-CSRF-Token` header, e.g. the `DELETE` requests.
+pub struct CsrfHeader;
+
+impl<'a, 'r> FromRequest<'a, 'r> for CsrfHeader {
+    type Error = Error;
+
+    /// Checks the header against the token of the client
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
+        let csrf_token = request
+            .guard::<CsrfToken>()
+            .map_failure(|(status, _)| (status, Error::InvalidToken))?;
+
+        match request.headers().get_one(CSRF_HEADER) {
+            Some(token) if csrf_token.verify(token).is_ok() => Outcome::Success(Self),
+            _ => Outcome::Failure((Status::Forbidden, Error::InvalidToken)),
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use super::*;
+use rocket::http::Header;
+use rocket::local::Client;
+
+#[test]
+fn verify_token() {
+    let csrf_token = CsrfToken("token".to_string());
+
+    assert_matches!(csrf_token.verify("token"), Ok(()));
+    assert_matches!(csrf_token.verify("toke"), Err(Error::InvalidToken));
+    assert_matches!(csrf_token.verify("other"), Err(Error::InvalidToken));
+    assert_matches!(csrf_token.verify(""), Err(Error::InvalidToken));
+}
+
+#[test]
+fn context_with_token() {
+    let context = CsrfToken("token".to_string()).context(hashmap! {"message" => "21"});
+
+    assert_eq!(
+        serde_json::to_value(&context).unwrap(),
+        serde_json::json!({"message": "21", "csrf_token": "token"})
+    );
+}
+
+#[test]
+fn new_token_without_cookie() {
+    let client = Client::new(rocket::ignite()).expect("valid rocket");
+    let req = client.get("/");
+
+    assert_matches!(CsrfToken::from_request(req.inner()), Outcome::Success(CsrfToken(token)) if !token.is_empty());
+}
+
+#[test]
+fn token_of_cookie() {
+    let client = Client::new(rocket::ignite()).expect("valid rocket");
+    let req = client
+        .get("/")
+        .private_cookie(Cookie::new(CSRF_COOKIE, "token"));
+
+    assert_matches!(CsrfToken::from_request(req.inner()), Outcome::Success(CsrfToken(token)) if token == "token");
+}
+
+#[test]
+fn valid_header() {
+    let client = Client::new(rocket::ignite()).expect("valid rocket");
+    let req = client
+        .delete("/")
+        .private_cookie(Cookie::new(CSRF_COOKIE, "token"))
+        .header(Header::new(CSRF_HEADER, "token"));
+
+    assert_matches!(CsrfHeader::from_request(req.inner()), Outcome::Success(_));
+}
+
+#[test]
+fn invalid_header() {
+    let client = Client::new(rocket::ignite()).expect("valid rocket");
+    let req = client
+        .delete("/")
+        .private_cookie(Cookie::new(CSRF_COOKIE, "token"))
+        .header(Header::new(CSRF_HEADER, "other"));
+
+    assert_matches!(
+        CsrfHeader::from_request(req.inner()),
+        Outcome::Failure((Status::Forbidden, _))
+    );
+}
+
+#[test]
+fn missing_header() {
+    let client = Client::new(rocket::ignite()).expect("valid rocket");
+    let req = client
+        .delete("/")
+        .private_cookie(Cookie::new(CSRF_COOKIE, "token"));
+
+    assert_matches!(
+        CsrfHeader::from_request(req.inner()),
+        Outcome::Failure((Status::Forbidden, _))
+    );
+}
@@ -0,0 +1,58 @@
+//! Writes every attempt to open a door to the append-only door history and exports it.
+
+use super::audit::csv_field;
+use crate::db_entry::{Connection, DoorEventEntry, DoorEventOutcome, DoorEventSource};
+use chrono::offset::Utc;
+use log::error;
+
+#[cfg(test)]
+#[path = "./door_history_test.rs"]
+mod door_history_test;
+
+/// The header of the CSV export.
+const CSV_HEADER: &str = "id,datetime,door,user,source,outcome";
+
+/// Appends an event to the door history.
+/// A failed write is only logged, as it must not prevent the opening of the door.
+pub fn record(
+    conn: &Connection,
+    door: Option<&str>,
+    user: Option<&str>,
+    flat: Option<u32>,
+    source: DoorEventSource,
+    outcome: DoorEventOutcome,
+) {
+    let event = DoorEventEntry {
+        id: (),
+        datetime: Utc::now(),
+        door: door.map(str::to_string),
+        user: user.map(str::to_string),
+        flat,
+        source,
+        outcome,
+    };
+    if let Err(e) = event.create(conn) {
+        error!(
+            "Can't write the door event {} of {:?} at {:?}: {}",
+            outcome, user, door, e
+        );
+    }
+}
+
+/// Converts the events to CSV with a header line.
+pub fn to_csv(events: &[DoorEventEntry]) -> String {
+    let mut csv = format!("{}\n", CSV_HEADER);
+    for event in events {
+        let fields = [
+            event.id.to_string(),
+            event.datetime.to_rfc3339(),
+            event.door.as_deref().map(csv_field).unwrap_or_default(),
+            event.user.as_deref().map(csv_field).unwrap_or_default(),
+            event.source.to_string(),
+            event.outcome.to_string(),
+        ];
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    csv
+}
@@ -0,0 +1,61 @@
+use super::*;
+use crate::db_entry::DoorEventQuery;
+
+fn get_event() -> DoorEventEntry {
+    DoorEventEntry {
+        id: 1,
+        datetime: "2024-05-01T12:00:00Z".parse().unwrap(),
+        door: Some("House Door".to_string()),
+        user: Some("Alice".to_string()),
+        flat: Some(1),
+        source: DoorEventSource::Web,
+        outcome: DoorEventOutcome::Opened,
+    }
+}
+
+#[test]
+fn record_event() {
+    let sql_scheme = include_str!("../../scheme.sql");
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(sql_scheme).unwrap();
+
+    record(
+        &conn,
+        Some("Gate"),
+        Some("Alice"),
+        Some(1),
+        DoorEventSource::Api,
+        DoorEventOutcome::Refused,
+    );
+
+    let events = DoorEventEntry::get_filtered(&conn, &DoorEventQuery::default(), 10).unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].door.as_deref(), Some("Gate"));
+    assert_eq!(events[0].user.as_deref(), Some("Alice"));
+    assert_eq!(events[0].flat, Some(1));
+    assert_eq!(events[0].source, DoorEventSource::Api);
+    assert_eq!(events[0].outcome, DoorEventOutcome::Refused);
+}
+
+#[test]
+fn csv_export() {
+    let events = vec![
+        get_event(),
+        DoorEventEntry {
+            id: 2,
+            door: None,
+            user: Some("=Party, Friday".to_string()),
+            flat: None,
+            source: DoorEventSource::GuestCode,
+            outcome: DoorEventOutcome::Refused,
+            ..get_event()
+        },
+    ];
+
+    assert_eq!(
+        to_csv(&events),
+        "id,datetime,door,user,source,outcome\n\
+         1,2024-05-01T12:00:00+00:00,House Door,Alice,web,opened\n\
+         2,2024-05-01T12:00:00+00:00,,\"'=Party, Friday\",guest_code,refused\n"
+    );
+}
@@ -0,0 +1,200 @@
+//! Opens the doors for users and guests and records every attempt in the door history.
+
+use super::config::{to_chrono_duration, CONFIG};
+use super::{access_schedule, door_history};
+use crate::db_entry::{
+    rusqlite, Connection, DoorEntry, DoorEventOutcome, DoorEventSource, GuestCodeEntry, UserEntry,
+};
+use crate::iot::{DoorControls, HoldOpen};
+use chrono::{offset::Utc, DateTime};
+
+#[cfg(test)]
+#[path = "./door_opening_test.rs"]
+mod door_opening_test;
+
+/// All errors which could happen during the opening of a door.
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("You may not open this door")]
+    NotPermitted,
+    #[error("The door isn't available")]
+    Unavailable,
+    #[error("The door opener can't be activated")]
+    Opener,
+    #[error(transparent)]
+    Schedule(#[from] access_schedule::Error),
+    #[error(transparent)]
+    Database(#[from] rusqlite::Error),
+}
+
+/// The outcome of an activation of the opener for a guest
+fn outcome(activated: &Result<bool, Error>) -> DoorEventOutcome {
+    match activated {
+        Ok(true) => DoorEventOutcome::Opened,
+        Ok(false) => DoorEventOutcome::AlreadyActive,
+        Err(_) => DoorEventOutcome::Refused,
+    }
+}
+
+/// Activates the opener of the door, returns false if it was already active
+fn activate(door_controls: &DoorControls, door: &DoorEntry) -> Result<bool, Error> {
+    door_controls
+        .get(door.id)
+        .ok_or(Error::Unavailable)?
+        .activate_opener()
+        .map_err(|_| Error::Opener)
+}
+
+/// Checks the grant and the access schedule of the user before the opener of the door is activated by `activate`
+fn open_permitted<F>(
+    conn: &Connection,
+    user: &UserEntry,
+    id: u32,
+    now: DateTime<Utc>,
+    activate: F,
+) -> Result<(DoorEntry, bool), Error>
+where
+    F: FnOnce(&DoorEntry) -> Result<bool, Error>,
+{
+    let door = DoorEntry::get_permitted(conn, user)?
+        .into_iter()
+        .find(|door| door.id == id)
+        .ok_or(Error::NotPermitted)?;
+    access_schedule::check(conn, user.id, now)?;
+
+    let activated = activate(&door)?;
+    Ok((door, activated))
+}
+
+/// Records the attempt of an user to open a door
+fn record_attempt(
+    conn: &Connection,
+    source: DoorEventSource,
+    user: &UserEntry,
+    id: u32,
+    result: &Result<(DoorEntry, bool), Error>,
+) {
+    let (door, outcome) = match result {
+        Ok((door, true)) => (Some(door.name.clone()), DoorEventOutcome::Opened),
+        Ok((door, false)) => (Some(door.name.clone()), DoorEventOutcome::AlreadyActive),
+        // The name of a door, which isn't granted, is recorded as well
+        Err(_) => (
+            DoorEntry::get_by_id(conn, id)
+                .ok()
+                .flatten()
+                .map(|door| door.name),
+            DoorEventOutcome::Refused,
+        ),
+    };
+    door_history::record(
+        conn,
+        door.as_deref(),
+        Some(&user.name),
+        user.flat.as_ref().map(|flat| flat.id),
+        source,
+        outcome,
+    );
+}
+
+/// Opens a door for an user, who has been granted the door and may open it at this time.
+/// Returns the door and whether the opener has been activated, false if it was already active.
+pub fn open(
+    conn: &Connection,
+    door_controls: &DoorControls,
+    source: DoorEventSource,
+    user: &UserEntry,
+    id: u32,
+    now: DateTime<Utc>,
+) -> Result<(DoorEntry, bool), Error> {
+    let result = open_permitted(conn, user, id, now, |door| activate(door_controls, door));
+    record_attempt(conn, source, user, id, &result);
+    result
+}
+
+/// The latest end of a hold-open mode, which starts now.
+/// It is the end of the current access window of the user or the configured maximal duration.
+fn latest_hold_end(
+    conn: &Connection,
+    user: &UserEntry,
+    now: DateTime<Utc>,
+) -> Result<Option<DateTime<Utc>>, Error> {
+    let max_end = CONFIG
+        .iot
+        .max_hold_open_time
+        .map(|max| now + to_chrono_duration(max));
+    let window_end = access_schedule::allowed_until(conn, user.id, now)?;
+    Ok(max_end.into_iter().chain(window_end).min())
+}
+
+/// Keeps a door unlocked for an user, who has been granted the door and may open it at this time.
+/// The hold-open mode ends at the latest with the access window of the user or after the configured maximal duration.
+/// Returns the door and the shortened hold-open mode, which is recorded as opening of the door.
+pub fn hold_open(
+    conn: &Connection,
+    door_controls: &DoorControls,
+    source: DoorEventSource,
+    user: &UserEntry,
+    id: u32,
+    hold: HoldOpen,
+    now: DateTime<Utc>,
+) -> Result<(DoorEntry, HoldOpen), Error> {
+    let mut limited_hold = hold;
+    let result = open_permitted(conn, user, id, now, |door| {
+        limited_hold = hold.limit(latest_hold_end(conn, user, now)?);
+        door_controls
+            .get(door.id)
+            .ok_or(Error::Unavailable)?
+            .hold_open(limited_hold)
+            .map_err(|_| Error::Opener)?;
+        Ok(true)
+    });
+    record_attempt(conn, source, user, id, &result);
+    result.map(|(door, _)| (door, limited_hold))
+}
+
+/// Opens the door of a redeemed guest code, if its issuer may still open the door.
+/// Returns whether the opener has been activated, false if it was already active.
+pub fn open_for_guest(
+    conn: &Connection,
+    door_controls: &DoorControls,
+    guest_code: &GuestCodeEntry,
+) -> Result<bool, Error> {
+    let door = match UserEntry::get_by_id(conn, guest_code.user)? {
+        Some(issuer) => DoorEntry::get_permitted(conn, &issuer)?
+            .into_iter()
+            .find(|door| door.id == guest_code.door),
+        None => None,
+    };
+    let activated = door
+        .as_ref()
+        .ok_or(Error::NotPermitted)
+        .and_then(|door| activate(door_controls, door));
+
+    // The name of a door, which isn't permitted anymore, is recorded as well
+    let door_name = match door {
+        Some(door) => Some(door.name),
+        None => DoorEntry::get_by_id(conn, guest_code.door)?.map(|door| door.name),
+    };
+    door_history::record(
+        conn,
+        door_name.as_deref(),
+        Some(&guest_code.label),
+        Some(guest_code.flat),
+        DoorEventSource::GuestCode,
+        outcome(&activated),
+    );
+    activated
+}
+
+/// Records a guest code, which couldn't be redeemed.
+/// Neither the door nor the guest is known, as the code is only stored as hash.
+pub fn record_refused_guest(conn: &Connection) {
+    door_history::record(
+        conn,
+        None,
+        None,
+        None,
+        DoorEventSource::GuestCode,
+        DoorEventOutcome::Refused,
+    );
+}
@@ -0,0 +1,251 @@
+use super::*;
+use crate::db_entry::{
+    AccessWindowEntry, DoorEventEntry, DoorEventQuery, Entry, FlatEntry, UserType,
+};
+use crate::iot::gpio::{Level, SimulatedGpio};
+use chrono::{Duration, NaiveTime, TimeZone};
+use std::sync::Arc;
+
+fn create_conn() -> Connection {
+    let sql_scheme = include_str!("../../scheme.sql");
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(sql_scheme).unwrap();
+    conn
+}
+
+/// Creates the house door at pin 0 and the gate at pin 1 with their controls
+fn create_doors(conn: &Connection) -> (DoorEntry, DoorEntry, SimulatedGpio, DoorControls) {
+    let door = DoorEntry::default().create(conn).unwrap();
+    let gate = DoorEntry {
+        name: "Gate".to_string(),
+        opener_pin: 1,
+        ..Default::default()
+    }
+    .create(conn)
+    .unwrap();
+
+    let gpio = SimulatedGpio::default();
+    let door_controls = DoorControls::new(Arc::new(gpio.clone()), Arc::new(|_door, _alarm| ()));
+    door_controls
+        .sync(DoorEntry::get_all(conn).unwrap())
+        .unwrap();
+    (door, gate, gpio, door_controls)
+}
+
+/// The recorded doors and outcomes, the newest first
+fn get_events(conn: &Connection) -> Vec<(Option<String>, DoorEventOutcome)> {
+    DoorEventEntry::get_filtered(conn, &DoorEventQuery::default(), 10)
+        .unwrap()
+        .into_iter()
+        .map(|event| (event.door, event.outcome))
+        .collect()
+}
+
+#[test]
+fn open_granted_door() {
+    let conn = create_conn();
+    let flat = FlatEntry::default().create(&conn).unwrap();
+    let user = UserEntry {
+        flat: Some(flat.get_id()),
+        user_type: UserType::User,
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+    let (door, gate, gpio, door_controls) = create_doors(&conn);
+    door.add_flat(&conn, flat.get_id()).unwrap();
+
+    let now = Utc::now();
+    assert_matches!(
+        open(
+            &conn,
+            &door_controls,
+            DoorEventSource::Web,
+            &user,
+            door.id,
+            now
+        ),
+        Ok((_, true))
+    );
+    assert_eq!(gpio.level(0), Level::High);
+    assert_matches!(
+        open(
+            &conn,
+            &door_controls,
+            DoorEventSource::Api,
+            &user,
+            door.id,
+            now
+        ),
+        Ok((_, false))
+    );
+    assert_matches!(
+        open(
+            &conn,
+            &door_controls,
+            DoorEventSource::Web,
+            &user,
+            gate.id,
+            now
+        ),
+        Err(Error::NotPermitted)
+    );
+    assert_eq!(gpio.level(1), Level::Low);
+    assert_matches!(
+        open(&conn, &door_controls, DoorEventSource::Web, &user, 42, now),
+        Err(Error::NotPermitted)
+    );
+
+    assert_eq!(
+        get_events(&conn),
+        vec![
+            (None, DoorEventOutcome::Refused),
+            (Some("Gate".to_string()), DoorEventOutcome::Refused),
+            (
+                Some("House Door".to_string()),
+                DoorEventOutcome::AlreadyActive
+            ),
+            (Some("House Door".to_string()), DoorEventOutcome::Opened),
+        ]
+    );
+    let events = DoorEventEntry::get_filtered(&conn, &DoorEventQuery::default(), 10).unwrap();
+    assert!(events
+        .iter()
+        .all(|event| event.user.as_deref() == Some(user.name.as_str())
+            && event.flat == Some(flat.get_id())));
+    assert_eq!(events[2].source, DoorEventSource::Api);
+}
+
+#[test]
+fn open_chosen_door_for_guest() {
+    let conn = create_conn();
+    let flat = FlatEntry::default().create(&conn).unwrap();
+    let user = UserEntry {
+        user_type: UserType::User,
+        flat: Some(flat.get_id()),
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+    let (door, gate, gpio, door_controls) = create_doors(&conn);
+    door.add_flat(&conn, flat.get_id()).unwrap();
+    gate.add_flat(&conn, flat.get_id()).unwrap();
+
+    let guest_code = GuestCodeEntry {
+        flat: flat.get_id(),
+        user: user.get_id(),
+        door: gate.get_id(),
+        ..Default::default()
+    };
+    assert_matches!(open_for_guest(&conn, &door_controls, &guest_code), Ok(true));
+    assert_eq!(gpio.level(0), Level::Low);
+    assert_eq!(gpio.level(1), Level::High);
+
+    // The door isn't opened anymore, if it has been revoked from the flat of the issuer
+    gate.remove_flat(&conn, flat.get_id()).unwrap();
+    assert_matches!(
+        open_for_guest(&conn, &door_controls, &guest_code),
+        Err(Error::NotPermitted)
+    );
+
+    assert_eq!(
+        get_events(&conn),
+        vec![
+            (Some("Gate".to_string()), DoorEventOutcome::Refused),
+            (Some("Gate".to_string()), DoorEventOutcome::Opened),
+        ]
+    );
+}
+
+#[test]
+fn hold_door_open_until_end_of_access_window() {
+    let conn = create_conn();
+    let user = UserEntry {
+        user_type: UserType::User,
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+    let (door, _, _, door_controls) = create_doors(&conn);
+    door.add_user(&conn, user.id).unwrap();
+    // 8:30 in Berlin during the winter time, the window ends at 9:00
+    let now = Utc.with_ymd_and_hms(2021, 3, 1, 7, 30, 0).unwrap();
+    AccessWindowEntry {
+        weekday: 0,
+        start_time: NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+        end_time: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+        user: user.id,
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+
+    let window_end = Utc.with_ymd_and_hms(2021, 3, 1, 8, 0, 0).unwrap();
+    assert_matches!(
+        hold_open(
+            &conn,
+            &door_controls,
+            DoorEventSource::Web,
+            &user,
+            door.id,
+            HoldOpen::Until(window_end + Duration::hours(1)),
+            now
+        ),
+        Ok((_, HoldOpen::Until(until))) if until == window_end
+    );
+}
+
+#[test]
+fn hold_granted_door_open() {
+    let conn = create_conn();
+    let user = UserEntry {
+        user_type: UserType::User,
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+    let (door, gate, gpio, door_controls) = create_doors(&conn);
+    door.add_user(&conn, user.id).unwrap();
+
+    let now = Utc::now();
+    assert_matches!(
+        hold_open(
+            &conn,
+            &door_controls,
+            DoorEventSource::Web,
+            &user,
+            door.id,
+            HoldOpen::UntilCancelled,
+            now
+        ),
+        Ok(_)
+    );
+    assert_eq!(gpio.level(0), Level::High);
+    // The hold-open mode is limited to the configured maximum
+    let max_end = now + to_chrono_duration(CONFIG.iot.max_hold_open_time.unwrap());
+    assert_eq!(
+        door_controls.get(door.id).unwrap().hold().unwrap(),
+        Some(HoldOpen::Until(max_end))
+    );
+    assert_matches!(
+        hold_open(
+            &conn,
+            &door_controls,
+            DoorEventSource::Web,
+            &user,
+            gate.id,
+            HoldOpen::UntilCancelled,
+            now
+        ),
+        Err(Error::NotPermitted)
+    );
+    assert_eq!(gpio.level(1), Level::Low);
+
+    assert_eq!(
+        get_events(&conn),
+        vec![
+            (Some("Gate".to_string()), DoorEventOutcome::Refused),
+            (Some("House Door".to_string()), DoorEventOutcome::Opened),
+        ]
+    );
+}
@@ -1,16 +1,26 @@
 //! Are used for the authentification and authorization.
 
-use super::{config::CONFIG, crypto};
-use crate::db_entry::{rusqlite, DbConn, Entry, UserEntry, UserSessionEntry};
-use blake2::{Blake2b, Digest};
+use super::config::{to_chrono_duration, Oidc, Webauthn, CONFIG};
+use super::{audit, breached_passwords, crypto, ldap, lockout, oidc, passkey, provisioning, totp};
+use crate::db_entry::{
+    rusqlite, ApiScope, ApiTokenEntry, AuditAction, Connection, DbConn, Entry, FlatEntry,
+    HashEntry, PasswordHistoryEntry, Permission, RecoveryCodeEntry, RoleEntry, UserEntry,
+    UserSessionEntry,
+};
 use bool_ext::BoolExt;
-use chrono::offset::Utc;
+use chrono::{offset::Utc, DateTime, Duration};
+use log::warn;
+use openidconnect::{ureq, url::Url};
 use passwords::{analyzer, scorer};
 use rocket::http::Status;
-use rocket::http::{Cookie, Cookies};
+use rocket::http::{Cookie, Cookies, SameSite};
 use rocket::request::{self, FromRequest, Request};
 use rocket::Outcome;
 use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+use std::net::IpAddr;
+use std::ops::Not;
+use webauthn_rs::prelude::{PublicKeyCredential, RequestChallengeResponse};
 
 #[cfg(test)]
 #[path = "./guards_test.rs"]
@@ -24,23 +34,100 @@ pub enum Error {
     #[error(transparent)]
     Serialization(#[from] serde_json::error::Error),
     #[error(transparent)]
-    Decode(#[from] base64::DecodeError),
+    Hash(#[from] crypto::HashError),
     #[error("The credentials are invalid")]
     InvalidCredentials,
-    #[error("The hash-config is unknown")]
-    UnknownHashConfig,
     #[error("The hash-config is blocked")]
     BlockedHashConfig,
     #[error("The password is to weak")]
     WeakPassword,
+    #[error("The password is in a list of breached passwords, please choose another one")]
+    BreachedPassword,
+    #[error("The password has been used recently, the last {0} passwords can't be reused")]
+    ReusedPassword(u32),
     #[error("There is no database")]
     NoDatabase,
+    #[error("The two-factor code is invalid")]
+    InvalidTwoFactorCode,
+    #[error("The login has expired, please enter your password again")]
+    NoTwoFactorLogin,
+    #[error("Too many invalid two-factor codes, please try again in {0} seconds")]
+    TooManyTwoFactorAttempts(i64),
+    #[error("Too many failed logins, please try again in {0} seconds")]
+    LockedOut(i64),
+    #[error("The API token is invalid")]
+    InvalidApiToken,
+    #[error(transparent)]
+    Oidc(#[from] oidc::Error),
+    #[error(transparent)]
+    Passkey(#[from] passkey::Error),
+    #[error("Only other active residents can be viewed")]
+    InvalidImpersonation,
+}
+
+/// The name of the private cookie, which holds the token of the session.
+const SESSION_COOKIE: &str = "user_session";
+/// The length of the random session token in bytes.
+const SESSION_TOKEN_LENGTH: usize = 32;
+/// The minimal interval in which the last activity of a session is written to the database.
+const LAST_SEEN_UPDATE_INTERVAL_SECONDS: i64 = 60;
+/// The maximal stored length of the user agent.
+const MAX_USER_AGENT_LENGTH: usize = 256;
+
+/// The prefix of the `Authorization` header with an API token.
+const BEARER_PREFIX: &str = "Bearer ";
+/// The length of the random API token in bytes.
+const API_TOKEN_LENGTH: usize = 32;
+
+/// The name of the private cookie, which holds the login between the password and the second factor.
+const TWO_FACTOR_COOKIE: &str = "two_factor_login";
+/// The time in which the second factor has to be entered after the password.
+const TWO_FACTOR_TIMEOUT_MINUTES: i64 = 5;
+/// The number of invalid codes after which every further invalid code locks the user name for the maximal lockout time.
+const TWO_FACTOR_MAX_ATTEMPTS: u32 = 5;
+
+/// The name of the private cookie, which holds the login until the callback of the identity provider.
+const OIDC_COOKIE: &str = "oidc_login";
+
+/// The name of the private cookie, which holds the login until the authenticator has signed the challenge.
+const PASSKEY_COOKIE: &str = "passkey_login";
+
+/// The name of the private cookie, which holds the impersonation of an user by an admin.
+const IMPERSONATION_COOKIE: &str = "impersonation";
+/// The time after which an impersonation ends.
+const IMPERSONATION_TIMEOUT_MINUTES: i64 = 15;
+
+/// The result of a successful password verification.
+#[derive(Debug)]
+pub enum Authentication {
+    /// The user-cookie has been written.
+    LoggedIn,
+    /// The second factor has to be checked with `UserGuard::authenticate_two_factor`.
+    TwoFactorPending,
+}
+
+/// A login which waits for the second factor. It is stored in a private cookie.
+/// The failed attempts are counted in the database, so they can't be reset with an older cookie or a new login.
+#[derive(Serialize, Deserialize)]
+struct PendingTwoFactorLogin {
+    user: u32,
+    expiry: DateTime<Utc>,
+}
+
+/// The impersonation of an user by an admin. It is stored in a private cookie.
+#[derive(Serialize, Deserialize)]
+struct Impersonation {
+    user: u32,
+    expiry: DateTime<Utc>,
 }
 
 /// A guard which allows all authentificated users.
-#[derive(Debug, Serialize, Deserialize)]
+/// The user is loaded from the database on every request, so changes take effect immediately.
+#[derive(Debug)]
 pub struct UserGuard {
     pub user: UserEntry,
+    /// The permissions of the user type and of all roles of the user
+    pub permissions: Vec<Permission>,
     session: UserSessionEntry,
 }
 
@@ -50,88 +137,468 @@ impl UserGuard {
         self.user.user_type.is_user()
     }
 
+    /// Whether the user may use the administration, because of an administrative permission
     #[inline(always)]
     pub fn is_admin(&self) -> bool {
-        self.user.user_type.is_admin()
+        self.permissions.iter().any(Permission::is_administrative)
+    }
+
+    #[inline(always)]
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        self.permissions.contains(&permission)
+    }
+
+    /// The ID of the session, which is used by the request.
+    #[inline(always)]
+    pub fn session_id(&self) -> u32 {
+        self.session.get_id()
+    }
+
+    /// Checks whether the user is an admin who has to enable the second factor first.
+    pub fn lacks_mandatory_two_factor(&self) -> bool {
+        self.is_admin()
+            && CONFIG.security.admin_two_factor_required
+            && self.user.totp_secret.is_none()
     }
 }
 
 impl UserGuard {
-    /// Checks whether the password is secure or errors if it is weak
+    /// Checks whether the password is secure or errors if it is weak or breached
     #[inline]
     pub fn check_password(pw: &str) -> Result<(), Error> {
         (scorer::score(&analyzer::analyze(pw)) >= CONFIG.security.minimal_password_strength_score)
             .err(Error::WeakPassword)?;
 
+        breached_passwords::is_breached(pw)
+            .not()
+            .err(Error::BreachedPassword)?;
+
+        Ok(())
+    }
+    /// Checks whether the new password of an existing user is secure
+    /// and differs from the passwords in the history of the user
+    pub fn check_new_password(conn: &Connection, user_id: u32, pw: &str) -> Result<(), Error> {
+        Self::check_password(pw)?;
+
+        let history_length = CONFIG.security.password_history_length;
+        let reused = PasswordHistoryEntry::get_latest_by_user(conn, user_id, history_length)?
+            .iter()
+            .any(|entry| {
+                crypto::hash_with_config(pw, &entry.pw_hash.salt, &entry.pw_hash.config)
+                    .map_or(false, |pw_hash| pw_hash == entry.pw_hash.hash)
+            });
+        reused.not().err(Error::ReusedPassword(history_length))?;
+
         Ok(())
     }
+    /// Records the new password of the user in the history and forgets the passwords, which can be reused again.
+    /// The current password is always kept, as its age is needed.
+    pub fn record_password(
+        conn: &Connection,
+        user_id: u32,
+        pw_hash: &HashEntry,
+    ) -> Result<(), rusqlite::Error> {
+        PasswordHistoryEntry {
+            id: (),
+            pw_hash: pw_hash.clone(),
+            creation_datetime: Utc::now(),
+            user: user_id,
+        }
+        .create(conn)?;
+
+        PasswordHistoryEntry::delete_old_by_user(
+            conn,
+            user_id,
+            CONFIG.security.password_history_length.max(1),
+        )
+    }
+    /// Checks whether the password of the user is older than `security.max_password_age`.
+    /// The age of a password, which has been set before its history was recorded, is counted from now on.
+    fn is_password_expired(conn: &Connection, user: &UserEntry) -> Result<bool, rusqlite::Error> {
+        let max_age = match CONFIG.security.max_password_age {
+            Some(max_age) => to_chrono_duration(max_age),
+            None => return Ok(false),
+        };
+
+        match PasswordHistoryEntry::get_latest_by_user(conn, user.id, 1)?.pop() {
+            Some(current) => Ok(current
+                .creation_datetime
+                .checked_add_signed(max_age)
+                .map_or(false, |expiry| expiry <= Utc::now())),
+            None => {
+                Self::record_password(conn, user.id, &user.pw_hash)?;
+                Ok(false)
+            }
+        }
+    }
     /// Checks whether the given credentials are valid and writes the user-cookie
+    /// or the cookie of the pending login if the user has enabled the second factor.
+    /// Failed logins lock the user name and the client IP temporarily.
     #[allow(clippy::ptr_arg)]
     pub fn authenticate(
         conn: &DbConn,
         cookies: Cookies,
+        client: &ClientInfo,
         name: &String,
         pw: &str,
+    ) -> Result<Authentication, Error> {
+        let now = Utc::now();
+
+        if let Some(locked_until) = lockout::locked_until(conn, name, client.ip, now)? {
+            return Err(Error::LockedOut((locked_until - now).num_seconds() + 1));
+        }
+
+        let user = match Self::verify_directory_password(conn, name, pw)
+            .transpose()
+            .unwrap_or_else(|| Self::verify_password(conn, name, pw))
+        {
+            Err(Error::InvalidCredentials) => {
+                lockout::record_failure(conn, name, client.ip, now)?;
+                audit::record(conn, Some(name), AuditAction::LoginFailed, None, client.ip);
+                return Err(Error::InvalidCredentials);
+            }
+            result => result?,
+        };
+
+        // With a second factor the failures are only forgotten after the second factor,
+        // so a known password doesn't grant new attempts to guess the code
+        if user.totp_secret.is_none() {
+            lockout::record_success(conn, name)?;
+        }
+
+        Self::login(conn, cookies, client, &user)
+    }
+    /// Writes the user-cookie or the cookie of the pending login if the user has enabled the second factor.
+    fn login(
+        conn: &DbConn,
+        cookies: Cookies,
+        client: &ClientInfo,
+        user: &UserEntry,
+    ) -> Result<Authentication, Error> {
+        if user.totp_secret.is_some() {
+            Self::write_two_factor_login(
+                cookies,
+                &PendingTwoFactorLogin {
+                    user: user.id,
+                    expiry: Utc::now() + Duration::minutes(TWO_FACTOR_TIMEOUT_MINUTES),
+                },
+            )?;
+            return Ok(Authentication::TwoFactorPending);
+        }
+
+        Self::create_user_session(conn, user, client, cookies)?;
+
+        Ok(Authentication::LoggedIn)
+    }
+    /// Writes the cookie of the pending login and returns the URL of the identity provider.
+    pub fn begin_oidc_login(config: &Oidc, mut cookies: Cookies) -> Result<Url, Error> {
+        let (url, pending_login) = oidc::begin_login(config, &ureq::http_client)?;
+
+        // The cookie has to be sent with the redirect of the identity provider
+        cookies.add_private(
+            Cookie::build(OIDC_COOKIE, serde_json::to_string(&pending_login)?)
+                .same_site(SameSite::Lax)
+                .finish(),
+        );
+        Ok(url)
+    }
+    /// Verifies the callback of the identity provider, creates or updates its user
+    /// and writes the user-cookie or the cookie of the pending login if the user has enabled the second factor.
+    pub fn authenticate_oidc(
+        conn: &DbConn,
+        config: &Oidc,
+        mut cookies: Cookies,
+        client: &ClientInfo,
+        code: String,
+        state: &str,
+    ) -> Result<Authentication, Error> {
+        let pending_login = cookies
+            .get_private(OIDC_COOKIE)
+            .map(|cookie| serde_json::from_str::<oidc::PendingLogin>(cookie.value()))
+            .transpose()?
+            .ok_or(oidc::Error::Expired)?;
+        cookies.remove_private(Cookie::named(OIDC_COOKIE));
+
+        let (identity, external_user) =
+            oidc::finish_login(config, &ureq::http_client, pending_login, code, state)?;
+
+        let user = match provisioning::sync_oidc_user(conn, &identity, &external_user)? {
+            Some(user) if user.is_active(Utc::now()) => user,
+            // A deactivated user of the identity provider
+            Some(_) => return Err(Error::InvalidCredentials),
+            None => {
+                warn!(
+                    "OIDC: {} is the name of another account, which isn't taken over",
+                    identity.name
+                );
+                return Err(Error::InvalidCredentials);
+            }
+        };
+
+        Self::login(conn, cookies, client, &user)
+    }
+    /// Writes the cookie of the pending login and returns the challenge,
+    /// which has to be signed with a passkey of the user.
+    #[allow(clippy::ptr_arg)]
+    pub fn begin_passkey_login(
+        conn: &DbConn,
+        config: &Webauthn,
+        mut cookies: Cookies,
+        client: &ClientInfo,
+        name: &String,
+    ) -> Result<RequestChallengeResponse, Error> {
+        let now = Utc::now();
+
+        if let Some(locked_until) = lockout::locked_until(conn, name, client.ip, now)? {
+            return Err(Error::LockedOut((locked_until - now).num_seconds() + 1));
+        }
+
+        // Unknown users can't be distinguished from users without a passkey
+        let user =
+            UserEntry::get_active_by_name(conn, name, now)?.ok_or(passkey::Error::NoPasskey)?;
+        let (challenge, pending_login) = passkey::begin_login(config, conn, &user)?;
+
+        cookies.add_private(Cookie::new(
+            PASSKEY_COOKIE,
+            serde_json::to_string(&pending_login)?,
+        ));
+        Ok(challenge)
+    }
+    /// Verifies the signed challenge and writes the user-cookie.
+    /// A passkey replaces the password and the second factor, as it is bound to a device and unlocked by the user.
+    pub fn authenticate_passkey(
+        conn: &DbConn,
+        config: &Webauthn,
+        mut cookies: Cookies,
+        client: &ClientInfo,
+        credential: &PublicKeyCredential,
     ) -> Result<UserEntry, Error> {
-        // Get UserEntry
-        let user = UserEntry::get_active_by_name(conn, name)?.ok_or_else(|| {
-            crypto::pseudo_hash();
-            Error::InvalidCredentials
-        })?;
+        let now = Utc::now();
+
+        let pending_login = cookies
+            .get_private(PASSKEY_COOKIE)
+            .map(|cookie| serde_json::from_str::<passkey::PendingLogin>(cookie.value()))
+            .transpose()?
+            .ok_or(passkey::Error::Expired)?;
+        cookies.remove_private(Cookie::named(PASSKEY_COOKIE));
+
+        let user = UserEntry::get_by_id(conn, pending_login.user())?
+            .filter(|user| user.is_active(now))
+            .ok_or(Error::InvalidCredentials)?;
+
+        if let Some(locked_until) = lockout::locked_until(conn, &user.name, client.ip, now)? {
+            return Err(Error::LockedOut((locked_until - now).num_seconds() + 1));
+        }
+
+        match passkey::finish_login(config, conn, pending_login, credential) {
+            Err(passkey::Error::Webauthn(e)) => {
+                lockout::record_failure(conn, &user.name, client.ip, now)?;
+                audit::record(
+                    conn,
+                    Some(&user.name),
+                    AuditAction::LoginFailed,
+                    None,
+                    client.ip,
+                );
+                return Err(passkey::Error::Webauthn(e).into());
+            }
+            result => result?,
+        };
+
+        lockout::record_success(conn, &user.name)?;
+        Self::create_user_session(conn, &user, client, cookies)?;
+
+        Ok(user)
+    }
+    /// Checks the password against the LDAP directory, if it's configured, and creates or updates its user.
+    /// Returns `None` if the local accounts have to be checked,
+    /// e.g. because the directory doesn't know the user or isn't reachable.
+    fn verify_directory_password(
+        conn: &DbConn,
+        name: &str,
+        pw: &str,
+    ) -> Result<Option<UserEntry>, Error> {
+        let config = match &CONFIG.ldap {
+            Some(config) => config,
+            None => return Ok(None),
+        };
+
+        let directory_user = match ldap::authenticate(config, name, pw) {
+            Ok(directory_user) => directory_user,
+            Err(ldap::Error::InvalidCredentials) => return Ok(None),
+            Err(e) => {
+                warn!("LDAP: The login of {} failed: {}", name, e);
+                return Ok(None);
+            }
+        };
+
+        match provisioning::sync_user(conn, name, ldap::HASH_CONFIG, &directory_user)? {
+            Some(user) if user.is_active(Utc::now()) => Ok(Some(user)),
+            // A deactivated user of the directory
+            Some(_) => Err(Error::InvalidCredentials),
+            None => {
+                warn!("LDAP: {} is a local account, which isn't taken over", name);
+                Ok(None)
+            }
+        }
+    }
+    /// Checks the password of an active user and replaces an outdated hash
+    #[allow(clippy::ptr_arg)]
+    fn verify_password(conn: &DbConn, name: &String, pw: &str) -> Result<UserEntry, Error> {
+        // Get UserEntry, the users of external providers have no local password
+        let mut user = UserEntry::get_active_by_name(conn, name, Utc::now())?
+            .filter(|user| !provisioning::is_external_user(user))
+            .ok_or_else(|| {
+                crypto::pseudo_hash();
+                Error::InvalidCredentials
+            })?;
 
         CONFIG
             .security
             .allowed_hash_configs
-            .contains(&user.pw_hash.config)
+            .contains(crypto::hash_algorithm(&user.pw_hash.config))
             .err(Error::BlockedHashConfig)?;
 
         // Create hash with matching config
-        let pw_hash = match user.pw_hash.config.as_str() {
-            "plain" => pw.to_string(),
-            "Blake2b" => {
-                let decoded_pw_salt = base64::decode(&user.pw_hash.salt)?;
-                base64::encode(
-                    Blake2b::new()
-                        .chain(pw)
-                        .chain(b"$")
-                        .chain(decoded_pw_salt)
-                        .chain(b"$")
-                        .chain(&CONFIG.security.hash_pepper)
-                        .finalize(),
-                )
-            }
-            _ => return Err(Error::UnknownHashConfig),
-        };
+        let pw_hash = crypto::hash_with_config(pw, &user.pw_hash.salt, &user.pw_hash.config)?;
 
         (user.pw_hash.hash == pw_hash).err(Error::InvalidCredentials)?;
 
-        Self::create_user_session(conn, user.clone(), cookies)?;
+        // Replace outdated hashes transparently as the plain password is only known now
+        if crypto::is_outdated(&user.pw_hash) {
+            user.pw_hash = crypto::hash(pw);
+            user.update_password(conn)?;
+        }
+
+        // An expired password has to be changed like a password, which is known by an admin
+        if !user.must_change_password && Self::is_password_expired(conn, &user)? {
+            user.must_change_password = true;
+            user.update_password(conn)?;
+        }
 
         Ok(user)
     }
-    /// Writes an encrypted cookie with the serialized user data.
+    /// Checks the TOTP or recovery code of the pending login and writes the user-cookie.
+    /// Failed codes lock the user name and the client IP like failed passwords.
+    pub fn authenticate_two_factor(
+        conn: &DbConn,
+        mut cookies: Cookies,
+        client: &ClientInfo,
+        code: &str,
+    ) -> Result<UserEntry, Error> {
+        let now = Utc::now();
+        let pending_login = cookies
+            .get_private(TWO_FACTOR_COOKIE)
+            .map(|cookie| serde_json::from_str::<PendingTwoFactorLogin>(cookie.value()))
+            .transpose()?
+            .filter(|pending_login| pending_login.expiry > now)
+            .ok_or(Error::NoTwoFactorLogin)?;
+
+        let user = UserEntry::get_by_id(conn, pending_login.user)?
+            .filter(|user| user.is_active(Utc::now()))
+            .ok_or(Error::InvalidCredentials)?;
+
+        if let Some(locked_until) = lockout::locked_until(conn, &user.name, client.ip, now)? {
+            return Err(Error::LockedOut((locked_until - now).num_seconds() + 1));
+        }
+        if !Self::verify_second_factor(conn, &user, code)? {
+            lockout::record_failure(conn, &user.name, client.ip, now)?;
+            audit::record(
+                conn,
+                Some(&user.name),
+                AuditAction::LoginFailed,
+                None,
+                client.ip,
+            );
+            if user.record_two_factor_failure(conn)? >= TWO_FACTOR_MAX_ATTEMPTS {
+                let locked_until = lockout::lock_name(conn, &user.name, now)?;
+                cookies.remove_private(Cookie::named(TWO_FACTOR_COOKIE));
+                return Err(Error::TooManyTwoFactorAttempts(
+                    (locked_until - now).num_seconds() + 1,
+                ));
+            }
+            return Err(Error::InvalidTwoFactorCode);
+        }
+
+        user.reset_two_factor_failures(conn)?;
+        lockout::record_success(conn, &user.name)?;
+        cookies.remove_private(Cookie::named(TWO_FACTOR_COOKIE));
+        Self::create_user_session(conn, &user, client, cookies)?;
+
+        Ok(user)
+    }
+    /// Checks the code against the TOTP secret or consumes a matching recovery code.
+    /// A TOTP code is only accepted once.
+    fn verify_second_factor(
+        conn: &DbConn,
+        user: &UserEntry,
+        code: &str,
+    ) -> Result<bool, rusqlite::Error> {
+        match &user.totp_secret {
+            Some(secret) => match totp::step(secret, code) {
+                Some(step) => user.accept_totp_step(conn, step),
+                None => RecoveryCodeEntry::consume(
+                    conn,
+                    user.id,
+                    &crypto::hash_token(&totp::normalize_recovery_code(code)),
+                ),
+            },
+            None => Ok(false),
+        }
+    }
+    /// Writes an encrypted cookie with the pending login.
+    fn write_two_factor_login(
+        mut cookies: Cookies,
+        pending_login: &PendingTwoFactorLogin,
+    ) -> Result<(), Error> {
+        cookies.add_private(Cookie::new(
+            TWO_FACTOR_COOKIE,
+            serde_json::to_string(pending_login)?,
+        ));
+        Ok(())
+    }
+    /// Stores a new session and writes its random token into an encrypted cookie.
     fn create_user_session(
         conn: &DbConn,
-        user: UserEntry,
+        user: &UserEntry,
+        client: &ClientInfo,
         mut cookies: Cookies,
     ) -> Result<(), Error> {
-        let session = UserSessionEntry {
+        let now = Utc::now();
+        let lifetime = to_chrono_duration(CONFIG.security.session_lifetime);
+
+        // Clean up the sessions which can't be used anymore
+        UserSessionEntry::delete_expired(
+            conn,
+            now,
+            now - to_chrono_duration(CONFIG.security.session_idle_timeout),
+        )?;
+
+        let mut token = [0; SESSION_TOKEN_LENGTH];
+        crypto::fill_rand_array(&mut token);
+        let token = base64::encode(token);
+
+        UserSessionEntry {
             id: (),
-            login_datetime: Utc::now(),
+            token_hash: crypto::hash_token(&token),
+            login_datetime: now,
+            expiry_datetime: now + lifetime,
+            last_seen_datetime: now,
+            ip_address: client.ip.map(|ip| ip.to_string()),
+            user_agent: client.user_agent.clone(),
             user: user.id,
         }
         .create(conn)?;
-
-        let session_guard = Self { user, session };
+        audit::record(conn, Some(&user.name), AuditAction::Login, None, client.ip);
 
         cookies.add_private(
-            Cookie::build("user_session_guard", serde_json::to_string(&session_guard)?)
-                .permanent()
+            Cookie::build(SESSION_COOKIE, token)
+                .max_age(lifetime)
                 .finish(),
         );
         Ok(())
     }
-    /// Destroys the private encrypted user cookie.
+    /// Destroys the session and the private encrypted user cookie.
     pub fn destroy_user_session(
         self,
         conn: &DbConn,
@@ -139,45 +606,121 @@ impl UserGuard {
     ) -> Result<(), rusqlite::Error> {
         self.session.delete(conn)?;
 
-        cookies.remove_private(Cookie::named("user_session_guard"));
+        cookies.remove_private(Cookie::named(SESSION_COOKIE));
+        cookies.remove_private(Cookie::named(IMPERSONATION_COOKIE));
         Ok(())
     }
+    /// Loads the session of the token and its user, if the session is still valid.
+    /// Invalid sessions are deleted.
+    fn load_session(
+        conn: &DbConn,
+        token: &str,
+        client: &ClientInfo,
+        now: DateTime<Utc>,
+    ) -> Result<Option<Self>, rusqlite::Error> {
+        let mut session =
+            match UserSessionEntry::get_by_token_hash(conn, &crypto::hash_token(token))? {
+                Some(session) => session,
+                None => return Ok(None),
+            };
+
+        let idle_since = now - to_chrono_duration(CONFIG.security.session_idle_timeout);
+        let user = UserEntry::get_by_id(conn, session.user)?.filter(|user| user.is_active(now));
+
+        let user = match user {
+            Some(user)
+                if session.expiry_datetime > now && session.last_seen_datetime > idle_since =>
+            {
+                user
+            }
+            _ => {
+                session.delete(conn)?;
+                return Ok(None);
+            }
+        };
 
-    fn validate(&self, conn: &DbConn) -> Result<bool, rusqlite::Error> {
-        let session = UserSessionEntry::get_by_id(conn, self.session.get_id())?;
+        // The activity is only written from time to time, as nearly every request uses the guard
+        let ip_address = client.ip.map(|ip| ip.to_string());
+        if now - session.last_seen_datetime >= Duration::seconds(LAST_SEEN_UPDATE_INTERVAL_SECONDS)
+            || session.ip_address != ip_address
+            || session.user_agent != client.user_agent
+        {
+            session.last_seen_datetime = now;
+            session.ip_address = ip_address;
+            session.user_agent = client.user_agent.clone();
+            session.update(conn)?;
+        }
+
+        let permissions = RoleEntry::get_permissions(conn, &user)?;
 
-        Ok(session.map_or(false, |session| {
-            session == self.session && session.user.get_id() == self.user.get_id()
+        Ok(Some(Self {
+            user,
+            permissions,
+            session,
         }))
     }
 }
 
+impl UserGuard {
+    /// Checks for a valid session-cookie in a request, regardless whether the password has to be changed
+    fn from_session_cookie(request: &Request) -> request::Outcome<Self, Error> {
+        let conn = request
+            .guard::<DbConn>()
+            .map_failure(|(status, _)| (status, Error::NoDatabase))?;
+        let client = request.guard::<ClientInfo>().unwrap();
+
+        let mut cookies = request.cookies();
+        let token = match cookies.get_private(SESSION_COOKIE) {
+            Some(cookie) => cookie.value().to_string(),
+            None => return Outcome::Forward(()),
+        };
+
+        match Self::load_session(&conn, &token, &client, Utc::now()) {
+            Ok(Some(user_guard)) => Outcome::Success(user_guard),
+            Ok(None) => {
+                cookies.remove_private(Cookie::named(SESSION_COOKIE));
+                Outcome::Forward(())
+            }
+            Err(e) => Outcome::Failure((Status::BadRequest, Error::from(e))),
+        }
+    }
+}
+
 impl<'a, 'r> FromRequest<'a, 'r> for UserGuard {
     type Error = Error;
 
-    /// Checks for valid user-cookie in a request
+    /// Checks for a valid session-cookie of an user, who doesn't have to change the password
     fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
-        let conn = request
-            .guard::<DbConn>()
-            .map_failure(|(status, _)| (status, Error::NoDatabase))?;
+        let user_guard = Self::from_session_cookie(request)?;
 
-        return request.cookies().get_private("user_session_guard").map_or(
-            Outcome::Forward(()),
-            |cookie| match serde_json::from_str::<Self>(cookie.value()) {
-                Ok(user_guard) => match user_guard.validate(&conn) {
-                    Ok(true) => Outcome::Success(user_guard),
-                    Ok(false) => Outcome::Forward(()),
-                    Err(e) => Outcome::Failure((Status::BadRequest, Error::from(e))),
-                },
-                Err(e) => Outcome::Failure((Status::BadRequest, Error::from(e))),
-            },
-        );
+        if user_guard.user.must_change_password {
+            Outcome::Forward(())
+        } else {
+            Outcome::Success(user_guard)
+        }
+    }
+}
+
+/// A guard which allows all authentificated users, even those who have to change their password first.
+/// It is only used by the routes which are needed to change the password.
+#[derive(Debug)]
+pub struct PasswordChangeGuard {
+    pub user_guard: UserGuard,
+}
+
+impl<'a, 'r> FromRequest<'a, 'r> for PasswordChangeGuard {
+    type Error = Error;
+
+    /// Checks for a valid session-cookie in a request
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
+        UserGuard::from_session_cookie(request).map(|user_guard| Self { user_guard })
     }
 }
 
 /// A guard which allows only users.
 pub struct OnlyUserGuard {
     pub user: UserEntry,
+    pub permissions: Vec<Permission>,
 }
 
 impl<'a, 'r> FromRequest<'a, 'r> for OnlyUserGuard {
@@ -190,6 +733,7 @@ impl<'a, 'r> FromRequest<'a, 'r> for OnlyUserGuard {
         if user_guard.is_user() {
             Outcome::Success(Self {
                 user: user_guard.user,
+                permissions: user_guard.permissions,
             })
         } else {
             Outcome::Forward(())
@@ -197,24 +741,364 @@ impl<'a, 'r> FromRequest<'a, 'r> for OnlyUserGuard {
     }
 }
 
-/// A guard which allows only administrators.
+/// A guard which allows only flat managers, who belong to a flat.
+pub struct FlatManagerGuard {
+    pub user: UserEntry,
+    /// The flat whose users are administered by the flat manager
+    pub flat: FlatEntry,
+}
+
+impl<'a, 'r> FromRequest<'a, 'r> for FlatManagerGuard {
+    type Error = Error;
+
+    /// Checks if a valid client is a flat manager with a flat
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
+        let user_guard = request.guard::<UserGuard>()?;
+
+        match (
+            user_guard.user.user_type.is_flat_manager(),
+            &user_guard.user.flat,
+        ) {
+            (true, Some(flat)) => Outcome::Success(Self {
+                flat: flat.clone(),
+                user: user_guard.user,
+            }),
+            _ => Outcome::Forward(()),
+        }
+    }
+}
+
+/// A guard which allows only users with an administrative permission.
+/// The single routes of the administration check their permission with a `PermissionGuard`.
 pub struct AdminGuard {
     pub user: UserEntry,
+    pub permissions: Vec<Permission>,
 }
 
 impl<'a, 'r> FromRequest<'a, 'r> for AdminGuard {
     type Error = Error;
 
-    /// Checks if a valid client is an admin
+    /// Checks if a valid client is an admin, who has enabled the second factor if it's mandatory
     fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
         let user_guard = request.guard::<UserGuard>()?;
 
-        if user_guard.is_admin() {
+        if user_guard.is_admin() && !user_guard.lacks_mandatory_two_factor() {
             Outcome::Success(Self {
                 user: user_guard.user,
+                permissions: user_guard.permissions,
             })
         } else {
             Outcome::Forward(())
         }
     }
 }
+
+/// A permission, which is required by a `PermissionGuard`.
+pub trait RequiredPermission {
+    const PERMISSION: Permission;
+}
+
+/// The types to require a permission with the `PermissionGuard`, e.g. `PermissionGuard<require::DoorOpen>`.
+pub mod require {
+    use super::{Permission, RequiredPermission};
+
+    pub struct DoorOpen;
+    impl RequiredPermission for DoorOpen {
+        const PERMISSION: Permission = Permission::DoorOpen;
+    }
+
+    pub struct CameraView;
+    impl RequiredPermission for CameraView {
+        const PERMISSION: Permission = Permission::CameraView;
+    }
+
+    pub struct FlatManage;
+    impl RequiredPermission for FlatManage {
+        const PERMISSION: Permission = Permission::FlatManage;
+    }
+
+    pub struct UserManage;
+    impl RequiredPermission for UserManage {
+        const PERMISSION: Permission = Permission::UserManage;
+    }
+
+    pub struct EventsView;
+    impl RequiredPermission for EventsView {
+        const PERMISSION: Permission = Permission::EventsView;
+    }
+
+    pub struct DoorHoldOpen;
+    impl RequiredPermission for DoorHoldOpen {
+        const PERMISSION: Permission = Permission::DoorHoldOpen;
+    }
+}
+
+/// A guard which allows only users with the permission `P`.
+pub struct PermissionGuard<P: RequiredPermission> {
+    pub user: UserEntry,
+    pub permissions: Vec<Permission>,
+    permission: PhantomData<P>,
+}
+
+impl<'a, 'r, P: RequiredPermission> FromRequest<'a, 'r> for PermissionGuard<P> {
+    type Error = Error;
+
+    /// Checks if a valid client has the permission.
+    /// Administrative permissions require the second factor, if it's mandatory.
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
+        let user_guard = request.guard::<UserGuard>()?;
+
+        if user_guard.has_permission(P::PERMISSION)
+            && !(P::PERMISSION.is_administrative() && user_guard.lacks_mandatory_two_factor())
+        {
+            Outcome::Success(Self {
+                user: user_guard.user,
+                permissions: user_guard.permissions,
+                permission: PhantomData,
+            })
+        } else {
+            Outcome::Forward(())
+        }
+    }
+}
+
+/// A guard which allows only admins, who still have to enable the mandatory second factor.
+pub struct TwoFactorSetupGuard {
+    pub user: UserEntry,
+}
+
+impl<'a, 'r> FromRequest<'a, 'r> for TwoFactorSetupGuard {
+    type Error = Error;
+
+    /// Checks if a valid client is an admin without the mandatory second factor
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
+        let user_guard = request.guard::<UserGuard>()?;
+
+        if user_guard.lacks_mandatory_two_factor() {
+            Outcome::Success(Self {
+                user: user_guard.user,
+            })
+        } else {
+            Outcome::Forward(())
+        }
+    }
+}
+
+/// A guard which allows admins during the impersonation of an user.
+/// It only renders the views of the user read-only, all other requests still use the admin's own session.
+pub struct ImpersonationGuard {
+    pub admin: UserEntry,
+    /// The impersonated user
+    pub user: UserEntry,
+    /// The permissions of the impersonated user
+    pub permissions: Vec<Permission>,
+    pub expiry: DateTime<Utc>,
+}
+
+impl ImpersonationGuard {
+    /// Writes the cookie of an impersonation of the user, which ends after a few minutes.
+    /// Only other active residents can be impersonated.
+    pub fn start(
+        conn: &DbConn,
+        admin: &UserEntry,
+        mut cookies: Cookies,
+        client: &ClientInfo,
+        id: u32,
+    ) -> Result<UserEntry, Error> {
+        let now = Utc::now();
+        let user = UserEntry::get_by_id(conn, id)?
+            .filter(|user| user.id != admin.id && user.user_type.is_user() && user.is_active(now))
+            .ok_or(Error::InvalidImpersonation)?;
+
+        let impersonation = Impersonation {
+            user: user.id,
+            expiry: now + Duration::minutes(IMPERSONATION_TIMEOUT_MINUTES),
+        };
+        cookies.add_private(Cookie::new(
+            IMPERSONATION_COOKIE,
+            serde_json::to_string(&impersonation)?,
+        ));
+        audit::record(
+            conn,
+            Some(&admin.name),
+            AuditAction::ImpersonationStart,
+            Some(&user.name),
+            client.ip,
+        );
+
+        Ok(user)
+    }
+
+    /// Removes the cookie of the impersonation
+    pub fn end(self, conn: &DbConn, mut cookies: Cookies, client: &ClientInfo) {
+        cookies.remove_private(Cookie::named(IMPERSONATION_COOKIE));
+        audit::record(
+            conn,
+            Some(&self.admin.name),
+            AuditAction::ImpersonationEnd,
+            Some(&self.user.name),
+            client.ip,
+        );
+    }
+}
+
+impl<'a, 'r> FromRequest<'a, 'r> for ImpersonationGuard {
+    type Error = Error;
+
+    /// Checks for an admin, who may administer users, with a valid impersonation-cookie
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
+        let admin = request.guard::<PermissionGuard<require::UserManage>>()?;
+        let conn = request
+            .guard::<DbConn>()
+            .map_failure(|(status, _)| (status, Error::NoDatabase))?;
+        let now = Utc::now();
+
+        let mut cookies = request.cookies();
+        let impersonation = match cookies
+            .get_private(IMPERSONATION_COOKIE)
+            .and_then(|cookie| serde_json::from_str::<Impersonation>(cookie.value()).ok())
+        {
+            Some(impersonation) if impersonation.expiry > now => impersonation,
+            Some(_) => {
+                cookies.remove_private(Cookie::named(IMPERSONATION_COOKIE));
+                return Outcome::Forward(());
+            }
+            None => return Outcome::Forward(()),
+        };
+
+        let user = match UserEntry::get_by_id(&conn, impersonation.user) {
+            Ok(Some(user)) if user.is_active(now) => user,
+            Ok(_) => {
+                cookies.remove_private(Cookie::named(IMPERSONATION_COOKIE));
+                return Outcome::Forward(());
+            }
+            Err(e) => return Outcome::Failure((Status::BadRequest, Error::from(e))),
+        };
+
+        match RoleEntry::get_permissions(&conn, &user) {
+            Ok(permissions) => Outcome::Success(Self {
+                admin: admin.user,
+                user,
+                permissions,
+                expiry: impersonation.expiry,
+            }),
+            Err(e) => Outcome::Failure((Status::BadRequest, Error::from(e))),
+        }
+    }
+}
+
+/// A guard which allows requests with a valid API token in the `Authorization: Bearer` header.
+pub struct ApiGuard {
+    pub user: UserEntry,
+    pub permissions: Vec<Permission>,
+    pub token: ApiTokenEntry,
+}
+
+impl ApiGuard {
+    /// Creates a random token and its hash, which is stored.
+    pub fn generate_token() -> (String, String) {
+        let mut token = [0; API_TOKEN_LENGTH];
+        crypto::fill_rand_array(&mut token);
+        let token = base64::encode_config(token, base64::URL_SAFE_NO_PAD);
+        let token_hash = crypto::hash_token(&token);
+        (token, token_hash)
+    }
+
+    /// Checks whether the token has the scope, which the user is still allowed to use
+    pub fn has_scope(&self, scope: ApiScope) -> bool {
+        self.token.scopes.contains(&scope) && scope.is_allowed_for(&self.permissions)
+    }
+
+    /// Errors with `Forbidden` if the token lacks the scope
+    pub fn require(&self, scope: ApiScope) -> Result<(), Status> {
+        self.has_scope(scope).err(Status::Forbidden)
+    }
+
+    /// Loads the token and its active user and remembers the usage
+    fn load(
+        conn: &DbConn,
+        token: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Option<Self>, rusqlite::Error> {
+        let mut token = match ApiTokenEntry::get_by_token_hash(conn, &crypto::hash_token(token))? {
+            Some(token) => token,
+            None => return Ok(None),
+        };
+        let user = match UserEntry::get_by_id(conn, token.user)?
+            .filter(|user| user.is_active(now) && !user.must_change_password)
+        {
+            Some(user) => user,
+            None => return Ok(None),
+        };
+
+        let permissions = RoleEntry::get_permissions(conn, &user)?;
+
+        token.last_used_datetime = Some(now);
+        token.update(conn)?;
+
+        Ok(Some(Self {
+            user,
+            permissions,
+            token,
+        }))
+    }
+}
+
+impl<'a, 'r> FromRequest<'a, 'r> for ApiGuard {
+    type Error = Error;
+
+    /// Checks for a valid API token in a request
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
+        let token = match request
+            .headers()
+            .get_one("Authorization")
+            .and_then(|header| header.strip_prefix(BEARER_PREFIX))
+        {
+            Some(token) => token.trim(),
+            None => return Outcome::Forward(()),
+        };
+
+        let conn = request
+            .guard::<DbConn>()
+            .map_failure(|(status, _)| (status, Error::NoDatabase))?;
+
+        match Self::load(&conn, token, Utc::now()) {
+            Ok(Some(api_guard)) => Outcome::Success(api_guard),
+            Ok(None) => Outcome::Failure((Status::Unauthorized, Error::InvalidApiToken)),
+            Err(e) => Outcome::Failure((Status::InternalServerError, Error::from(e))),
+        }
+    }
+}
+
+/// A guard which retrieves the IP address and the user agent of the client, if they are known.
+pub struct ClientInfo {
+    pub ip: Option<IpAddr>,
+    pub user_agent: Option<String>,
+}
+
+impl ClientInfo {
+    /// The IP of the client, which is only taken from the `X-Real-IP` header if the request comes from a trusted proxy
+    fn client_ip(request: &Request) -> Option<IpAddr> {
+        let remote = request.remote()?.ip();
+        if CONFIG.web.trusted_proxies.contains(&remote) {
+            request.real_ip().or(Some(remote))
+        } else {
+            Some(remote)
+        }
+    }
+}
+
+impl<'a, 'r> FromRequest<'a, 'r> for ClientInfo {
+    type Error = ();
+
+    /// Uses the remote address, or the `X-Real-IP` header of a trusted proxy, and the `User-Agent` header
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
+        Outcome::Success(Self {
+            ip: Self::client_ip(request),
+            user_agent: request
+                .headers()
+                .get_one("User-Agent")
+                .map(|user_agent| user_agent.chars().take(MAX_USER_AGENT_LENGTH).collect()),
+        })
+    }
+}
@@ -11,13 +11,27 @@ fn get_session() -> UserSessionEntry {
     }
 }
 
-fn create_session(conn: &DbConn) -> UserSessionEntry {
+/// The token of the sessions, which are created in the database.
+const TOKEN: &str = "token";
+
+/// Creates an user with a session and returns the token for the session cookie.
+fn create_session(conn: &DbConn, user_type: UserType) -> String {
+    let user = UserEntry::<(), u32> {
+        user_type,
+        ..Default::default()
+    }
+    .create(conn)
+    .unwrap();
+
     UserSessionEntry {
-        user: 0,
+        token_hash: crypto::hash_token(TOKEN),
+        user: user.get_id(),
         ..Default::default()
     }
     .create(conn)
-    .unwrap()
+    .unwrap();
+
+    TOKEN.to_string()
 }
 
 fn get_user() -> UserEntry {
@@ -31,17 +45,11 @@ fn get_user() -> UserEntry {
 fn get_user_guard() -> UserGuard {
     UserGuard {
         user: get_user(),
+        permissions: Permission::defaults_for(UserType::User),
         session: get_session(),
     }
 }
 
-fn create_user_guard(conn: &DbConn) -> UserGuard {
-    UserGuard {
-        user: get_user(),
-        session: create_session(conn),
-    }
-}
-
 fn get_admin() -> UserEntry {
     UserEntry {
         id: 0,
@@ -53,17 +61,11 @@ fn get_admin() -> UserEntry {
 fn get_admin_guard() -> UserGuard {
     UserGuard {
         user: get_admin(),
+        permissions: Permission::defaults_for(UserType::Admin),
         session: get_session(),
     }
 }
 
-fn create_admin_guard(conn: &DbConn) -> UserGuard {
-    UserGuard {
-        user: get_admin(),
-        session: create_session(conn),
-    }
-}
-
 #[test]
 fn user_is_user() {
     assert!(get_user_guard().is_user());
@@ -89,13 +91,12 @@ fn user_on_only_user_guard() {
     let server = rocket::ignite().attach(DbConn::fairing());
     let conn = DbConn::get_one(&server).unwrap();
 
-    let user_session =
-        serde_json::to_string(&create_user_guard(&conn)).expect("serialization error");
+    let user_session = create_session(&conn, UserType::User);
 
     let client = Client::new(server).expect("valid rocket");
     let req = client
         .get("/")
-        .private_cookie(Cookie::new("user_session_guard", user_session));
+        .private_cookie(Cookie::new(SESSION_COOKIE, user_session));
 
     assert_matches!(
         OnlyUserGuard::from_request(req.inner()),
@@ -108,13 +109,12 @@ fn admin_on_only_user_guard() {
     let server = rocket::ignite().attach(DbConn::fairing());
     let conn = DbConn::get_one(&server).unwrap();
 
-    let user_session =
-        serde_json::to_string(&create_admin_guard(&conn)).expect("serialization error");
+    let user_session = create_session(&conn, UserType::Admin);
 
     let client = Client::new(server).expect("valid rocket");
     let req = client
         .get("/")
-        .private_cookie(Cookie::new("user_session_guard", user_session));
+        .private_cookie(Cookie::new(SESSION_COOKIE, user_session));
 
     assert_matches!(
         OnlyUserGuard::from_request(req.inner()),
@@ -127,13 +127,12 @@ fn user_on_admin_guard() {
     let server = rocket::ignite().attach(DbConn::fairing());
     let conn = DbConn::get_one(&server).unwrap();
 
-    let user_session =
-        serde_json::to_string(&create_user_guard(&conn)).expect("serialization error");
+    let user_session = create_session(&conn, UserType::User);
 
     let client = Client::new(server).expect("valid rocket");
     let req = client
         .get("/")
-        .private_cookie(Cookie::new("user_session_guard", user_session));
+        .private_cookie(Cookie::new(SESSION_COOKIE, user_session));
 
     assert_matches!(AdminGuard::from_request(req.inner()), Outcome::Forward(_));
 }
@@ -143,27 +142,649 @@ fn admin_on_admin_guard() {
     let server = rocket::ignite().attach(DbConn::fairing());
     let conn = DbConn::get_one(&server).unwrap();
 
-    let user_session =
-        serde_json::to_string(&create_admin_guard(&conn)).expect("serialization error");
+    let user_session = create_session(&conn, UserType::Admin);
 
     let client = Client::new(server).expect("valid rocket");
     let req = client
         .get("/")
-        .private_cookie(Cookie::new("user_session_guard", user_session));
+        .private_cookie(Cookie::new(SESSION_COOKIE, user_session));
 
     assert_matches!(AdminGuard::from_request(req.inner()), Outcome::Success(_));
 }
 
+#[test]
+fn flat_manager_on_two_factor_setup_guard() {
+    let server = rocket::ignite().attach(DbConn::fairing());
+    let conn = DbConn::get_one(&server).unwrap();
+
+    let user_session = create_session(&conn, UserType::FlatManager);
+
+    let client = Client::new(server).expect("valid rocket");
+    let req = client
+        .get("/")
+        .private_cookie(Cookie::new(SESSION_COOKIE, user_session));
+
+    assert_matches!(
+        TwoFactorSetupGuard::from_request(req.inner()),
+        Outcome::Forward(_)
+    );
+}
+
+#[test]
+fn user_on_permission_guard() {
+    let server = rocket::ignite().attach(DbConn::fairing());
+    let conn = DbConn::get_one(&server).unwrap();
+
+    let user_session = create_session(&conn, UserType::User);
+
+    let client = Client::new(server).expect("valid rocket");
+    let req = client
+        .get("/")
+        .private_cookie(Cookie::new(SESSION_COOKIE, user_session));
+
+    assert_matches!(
+        PermissionGuard::<require::DoorOpen>::from_request(req.inner()),
+        Outcome::Success(_)
+    );
+    assert_matches!(
+        PermissionGuard::<require::UserManage>::from_request(req.inner()),
+        Outcome::Forward(_)
+    );
+}
+
+#[test]
+fn admin_on_permission_guard() {
+    let server = rocket::ignite().attach(DbConn::fairing());
+    let conn = DbConn::get_one(&server).unwrap();
+
+    let user_session = create_session(&conn, UserType::Admin);
+
+    let client = Client::new(server).expect("valid rocket");
+    let req = client
+        .get("/")
+        .private_cookie(Cookie::new(SESSION_COOKIE, user_session));
+
+    assert_matches!(
+        PermissionGuard::<require::DoorOpen>::from_request(req.inner()),
+        Outcome::Forward(_)
+    );
+    assert_matches!(
+        PermissionGuard::<require::FlatManage>::from_request(req.inner()),
+        Outcome::Success(_)
+    );
+}
+
+#[test]
+fn user_with_role_on_permission_guard() {
+    let server = rocket::ignite().attach(DbConn::fairing());
+    let conn = DbConn::get_one(&server).unwrap();
+
+    let user_session = create_session(&conn, UserType::User);
+    let user = UserEntry::get_all(&conn).unwrap().pop().unwrap();
+    RoleEntry::<()> {
+        id: (),
+        name: "Caretaker".to_string(),
+        permissions: vec![Permission::UserManage],
+    }
+    .create(&conn)
+    .unwrap()
+    .add_member(&conn, user.get_id())
+    .unwrap();
+
+    let client = Client::new(server).expect("valid rocket");
+    let req = client
+        .get("/")
+        .private_cookie(Cookie::new(SESSION_COOKIE, user_session));
+
+    assert_matches!(
+        PermissionGuard::<require::UserManage>::from_request(req.inner()),
+        Outcome::Success(_)
+    );
+    assert_matches!(
+        PermissionGuard::<require::FlatManage>::from_request(req.inner()),
+        Outcome::Forward(_)
+    );
+    assert_matches!(AdminGuard::from_request(req.inner()), Outcome::Success(_));
+}
+
 #[test]
 fn unknown_session() {
     let server = rocket::ignite().attach(DbConn::fairing());
 
-    let user_session = serde_json::to_string(&get_user_guard()).expect("serialization error");
+    let user_session = TOKEN.to_string();
+
+    let client = Client::new(server).expect("valid rocket");
+    let req = client
+        .get("/")
+        .private_cookie(Cookie::new(SESSION_COOKIE, user_session));
+
+    assert_matches!(UserGuard::from_request(req.inner()), Outcome::Forward(_));
+}
+
+#[test]
+fn expired_session() {
+    let server = rocket::ignite().attach(DbConn::fairing());
+    let conn = DbConn::get_one(&server).unwrap();
+
+    let user_session = create_session(&conn, UserType::User);
+    let later = Utc::now() + to_chrono_duration(CONFIG.security.session_lifetime);
+
+    let client = ClientInfo {
+        ip: None,
+        user_agent: None,
+    };
+    assert_matches!(
+        UserGuard::load_session(&conn, &user_session, &client, later),
+        Ok(None)
+    );
+    assert!(UserSessionEntry::get_all(&conn).unwrap().is_empty());
+}
+
+#[test]
+fn idle_session() {
+    let server = rocket::ignite().attach(DbConn::fairing());
+    let conn = DbConn::get_one(&server).unwrap();
+
+    let user_session = create_session(&conn, UserType::User);
+    let later = Utc::now() + to_chrono_duration(CONFIG.security.session_idle_timeout);
+
+    let client = ClientInfo {
+        ip: None,
+        user_agent: None,
+    };
+    assert_matches!(
+        UserGuard::load_session(&conn, &user_session, &client, later),
+        Ok(None)
+    );
+}
+
+#[test]
+fn session_updates_last_seen() {
+    let server = rocket::ignite().attach(DbConn::fairing());
+    let conn = DbConn::get_one(&server).unwrap();
+
+    let user_session = create_session(&conn, UserType::User);
+    let later = Utc::now() + Duration::seconds(LAST_SEEN_UPDATE_INTERVAL_SECONDS);
+
+    let client = ClientInfo {
+        ip: None,
+        user_agent: Some("Mozilla/5.0".to_string()),
+    };
+    let user_guard = UserGuard::load_session(&conn, &user_session, &client, later)
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(user_guard.session.last_seen_datetime, later);
+    assert_eq!(
+        UserSessionEntry::get_by_id(&conn, user_guard.session.get_id())
+            .unwrap()
+            .unwrap(),
+        user_guard.session
+    );
+}
+
+#[test]
+fn session_of_inactive_user() {
+    let server = rocket::ignite().attach(DbConn::fairing());
+    let conn = DbConn::get_one(&server).unwrap();
+
+    let user_session = create_session(&conn, UserType::User);
+    conn.execute("UPDATE client_user SET active = 0", &[])
+        .unwrap();
+
+    let client = Client::new(server).expect("valid rocket");
+    let req = client
+        .get("/")
+        .private_cookie(Cookie::new(SESSION_COOKIE, user_session));
+
+    assert_matches!(UserGuard::from_request(req.inner()), Outcome::Forward(_));
+}
+
+#[test]
+fn session_of_expired_user() {
+    let server = rocket::ignite().attach(DbConn::fairing());
+    let conn = DbConn::get_one(&server).unwrap();
+
+    let user_session = create_session(&conn, UserType::User);
+    conn.execute(
+        "UPDATE client_user SET valid_until = ?1",
+        &[&(Utc::now() - Duration::minutes(1))],
+    )
+    .unwrap();
+
+    let client = Client::new(server).expect("valid rocket");
+    let req = client
+        .get("/")
+        .private_cookie(Cookie::new(SESSION_COOKIE, user_session));
+
+    assert_matches!(UserGuard::from_request(req.inner()), Outcome::Forward(_));
+}
+
+#[test]
+fn password_of_not_yet_valid_user() {
+    let server = rocket::ignite().attach(DbConn::fairing());
+    let conn = DbConn::get_one(&server).unwrap();
+
+    let user = UserEntry::<(), u32> {
+        pw_hash: crypto::hash("Correct-Horse-Battery-Staple"),
+        valid_from: Some(Utc::now() + Duration::days(1)),
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+
+    assert_matches!(
+        UserGuard::verify_password(&conn, &user.name, "Correct-Horse-Battery-Staple"),
+        Err(Error::InvalidCredentials)
+    );
+}
+
+/// Creates an user with an API token and returns the token for the `Authorization` header.
+fn create_api_token(conn: &DbConn, user_type: UserType, scopes: Vec<ApiScope>) -> String {
+    let user = UserEntry::<(), u32> {
+        user_type,
+        ..Default::default()
+    }
+    .create(conn)
+    .unwrap();
+
+    let (token, token_hash) = ApiGuard::generate_token();
+    ApiTokenEntry {
+        token_hash,
+        scopes,
+        user: user.get_id(),
+        ..Default::default()
+    }
+    .create(conn)
+    .unwrap();
+
+    token
+}
+
+#[test]
+fn valid_api_token() {
+    let server = rocket::ignite().attach(DbConn::fairing());
+    let conn = DbConn::get_one(&server).unwrap();
+
+    let token = create_api_token(&conn, UserType::User, vec![ApiScope::DoorOpen]);
+
+    let client = Client::new(server).expect("valid rocket");
+    let req = client.get("/").header(rocket::http::Header::new(
+        "Authorization",
+        format!("Bearer {}", token),
+    ));
+
+    let api_guard = match ApiGuard::from_request(req.inner()) {
+        Outcome::Success(api_guard) => api_guard,
+        _ => panic!("API token should be valid"),
+    };
+    assert!(api_guard.require(ApiScope::DoorOpen).is_ok());
+    assert_eq!(
+        api_guard.require(ApiScope::EventsRead),
+        Err(Status::Forbidden)
+    );
+    assert!(api_guard.token.last_used_datetime.is_some());
+}
+
+#[test]
+fn invalid_api_token() {
+    let server = rocket::ignite().attach(DbConn::fairing());
+
+    let client = Client::new(server).expect("valid rocket");
+    let req = client
+        .get("/")
+        .header(rocket::http::Header::new("Authorization", "Bearer token"));
+
+    assert_matches!(
+        ApiGuard::from_request(req.inner()),
+        Outcome::Failure((Status::Unauthorized, Error::InvalidApiToken))
+    );
+}
+
+#[test]
+fn missing_api_token() {
+    let server = rocket::ignite().attach(DbConn::fairing());
+
+    let client = Client::new(server).expect("valid rocket");
+    let req = client.get("/");
+
+    assert_matches!(ApiGuard::from_request(req.inner()), Outcome::Forward(_));
+}
+
+#[test]
+fn api_token_scope_not_allowed_for_user_type() {
+    let server = rocket::ignite().attach(DbConn::fairing());
+    let conn = DbConn::get_one(&server).unwrap();
+
+    let token = create_api_token(&conn, UserType::User, vec![ApiScope::Admin]);
+
+    let api_guard = ApiGuard::load(&conn, &token, Utc::now()).unwrap().unwrap();
+    assert!(!api_guard.has_scope(ApiScope::Admin));
+}
+
+#[test]
+fn user_who_must_change_password() {
+    let server = rocket::ignite().attach(DbConn::fairing());
+    let conn = DbConn::get_one(&server).unwrap();
+
+    let user_session = create_session(&conn, UserType::User);
+    conn.execute("UPDATE client_user SET must_change_password = 1", &[])
+        .unwrap();
 
     let client = Client::new(server).expect("valid rocket");
     let req = client
         .get("/")
-        .private_cookie(Cookie::new("user_session_guard", user_session));
+        .private_cookie(Cookie::new(SESSION_COOKIE, user_session));
 
     assert_matches!(UserGuard::from_request(req.inner()), Outcome::Forward(_));
+    assert_matches!(
+        PasswordChangeGuard::from_request(req.inner()),
+        Outcome::Success(_)
+    );
+}
+
+#[test]
+fn reused_password() {
+    let server = rocket::ignite().attach(DbConn::fairing());
+    let conn = DbConn::get_one(&server).unwrap();
+
+    let user = UserEntry::<(), u32>::default().create(&conn).unwrap();
+    let passwords = [
+        "Correct-Horse-Battery-Staple-1",
+        "Correct-Horse-Battery-Staple-2",
+        "Correct-Horse-Battery-Staple-3",
+        "Correct-Horse-Battery-Staple-4",
+    ];
+    for pw in &passwords {
+        UserGuard::check_new_password(&conn, user.id, pw).unwrap();
+        UserGuard::record_password(&conn, user.id, &crypto::hash(pw)).unwrap();
+    }
+
+    // Only the last three passwords are kept
+    assert_matches!(
+        UserGuard::check_new_password(&conn, user.id, passwords[3]),
+        Err(Error::ReusedPassword(3))
+    );
+    assert_matches!(
+        UserGuard::check_new_password(&conn, user.id, passwords[1]),
+        Err(Error::ReusedPassword(3))
+    );
+    assert!(UserGuard::check_new_password(&conn, user.id, passwords[0]).is_ok());
+    assert_eq!(
+        PasswordHistoryEntry::get_latest_by_user(&conn, user.id, 10)
+            .unwrap()
+            .len(),
+        3
+    );
+}
+
+#[test]
+fn expired_password() {
+    let server = rocket::ignite().attach(DbConn::fairing());
+    let conn = DbConn::get_one(&server).unwrap();
+
+    let user = UserEntry::<(), u32>::default().create(&conn).unwrap();
+
+    // The age of a password without history is counted from now on
+    assert!(!UserGuard::is_password_expired(&conn, &user).unwrap());
+    let mut current = PasswordHistoryEntry::get_latest_by_user(&conn, user.id, 1)
+        .unwrap()
+        .pop()
+        .unwrap();
+    assert_eq!(current.pw_hash, user.pw_hash);
+
+    current.creation_datetime = Utc::now() - Duration::days(91);
+    current.update(&conn).unwrap();
+    assert!(UserGuard::is_password_expired(&conn, &user).unwrap());
+}
+
+#[test]
+fn expired_password_has_to_be_changed() {
+    let server = rocket::ignite().attach(DbConn::fairing());
+    let conn = DbConn::get_one(&server).unwrap();
+
+    let user = UserEntry::<(), u32> {
+        pw_hash: crypto::hash("Correct-Horse-Battery-Staple"),
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+    PasswordHistoryEntry {
+        id: (),
+        pw_hash: user.pw_hash.clone(),
+        creation_datetime: Utc::now() - Duration::days(91),
+        user: user.id,
+    }
+    .create(&conn)
+    .unwrap();
+
+    let user =
+        UserGuard::verify_password(&conn, &user.name, "Correct-Horse-Battery-Staple").unwrap();
+    assert!(user.must_change_password);
+    assert!(
+        UserEntry::get_by_id(&conn, user.id)
+            .unwrap()
+            .unwrap()
+            .must_change_password
+    );
+}
+
+/// Creates an active user, who can be impersonated, and returns the cookie of the impersonation.
+fn create_impersonation(conn: &DbConn, expiry: DateTime<Utc>) -> Cookie<'static> {
+    let user = UserEntry::<(), u32> {
+        name: "resident".to_string(),
+        user_type: UserType::User,
+        ..Default::default()
+    }
+    .create(conn)
+    .unwrap();
+
+    let impersonation = Impersonation {
+        user: user.get_id(),
+        expiry,
+    };
+    Cookie::new(
+        IMPERSONATION_COOKIE,
+        serde_json::to_string(&impersonation).unwrap(),
+    )
+}
+
+#[test]
+fn admin_on_impersonation_guard() {
+    let server = rocket::ignite().attach(DbConn::fairing());
+    let conn = DbConn::get_one(&server).unwrap();
+
+    let admin_session = create_session(&conn, UserType::Admin);
+    let impersonation = create_impersonation(&conn, Utc::now() + Duration::minutes(1));
+
+    let client = Client::new(server).expect("valid rocket");
+    let req = client
+        .get("/")
+        .private_cookie(Cookie::new(SESSION_COOKIE, admin_session))
+        .private_cookie(impersonation);
+
+    let guard = ImpersonationGuard::from_request(req.inner());
+    assert_matches!(&guard, Outcome::Success(guard) if guard.user.name == "resident");
+}
+
+#[test]
+fn expired_impersonation() {
+    let server = rocket::ignite().attach(DbConn::fairing());
+    let conn = DbConn::get_one(&server).unwrap();
+
+    let admin_session = create_session(&conn, UserType::Admin);
+    let impersonation = create_impersonation(&conn, Utc::now() - Duration::minutes(1));
+
+    let client = Client::new(server).expect("valid rocket");
+    let req = client
+        .get("/")
+        .private_cookie(Cookie::new(SESSION_COOKIE, admin_session))
+        .private_cookie(impersonation);
+
+    assert_matches!(
+        ImpersonationGuard::from_request(req.inner()),
+        Outcome::Forward(_)
+    );
+}
+
+#[test]
+fn user_on_impersonation_guard() {
+    let server = rocket::ignite().attach(DbConn::fairing());
+    let conn = DbConn::get_one(&server).unwrap();
+
+    let user_session = create_session(&conn, UserType::User);
+    let impersonation = create_impersonation(&conn, Utc::now() + Duration::minutes(1));
+
+    let client = Client::new(server).expect("valid rocket");
+    let req = client
+        .get("/")
+        .private_cookie(Cookie::new(SESSION_COOKIE, user_session))
+        .private_cookie(impersonation);
+
+    assert_matches!(
+        ImpersonationGuard::from_request(req.inner()),
+        Outcome::Forward(_)
+    );
+}
+
+#[test]
+fn forged_real_ip() {
+    let client = Client::new(rocket::ignite()).expect("valid rocket");
+    let req = client
+        .get("/")
+        .remote("192.0.2.1:4242".parse().unwrap())
+        .header(rocket::http::Header::new("X-Real-IP", "198.51.100.1"));
+
+    assert_eq!(
+        ClientInfo::client_ip(req.inner()),
+        Some("192.0.2.1".parse().unwrap())
+    );
+}
+
+#[test]
+fn real_ip_of_trusted_proxy() {
+    let client = Client::new(rocket::ignite()).expect("valid rocket");
+    let req = client
+        .get("/")
+        .remote("127.0.0.1:4242".parse().unwrap())
+        .header(rocket::http::Header::new("X-Real-IP", "198.51.100.1"));
+
+    assert_eq!(
+        ClientInfo::client_ip(req.inner()),
+        Some("198.51.100.1".parse().unwrap())
+    );
+}
+
+#[test]
+fn session_with_overlong_lifetime() {
+    let lifetime = to_chrono_duration(std::time::Duration::from_secs(u64::MAX));
+
+    assert!(Utc::now().checked_add_signed(lifetime).is_some());
+}
+
+fn pending_two_factor_login(user: u32) -> Cookie<'static> {
+    let pending_login = PendingTwoFactorLogin {
+        user,
+        expiry: Utc::now() + Duration::minutes(1),
+    };
+    Cookie::new(
+        TWO_FACTOR_COOKIE,
+        serde_json::to_string(&pending_login).unwrap(),
+    )
+}
+
+fn create_two_factor_user(conn: &DbConn) -> UserEntry {
+    UserEntry::<(), u32> {
+        name: "two_factor".to_string(),
+        pw_hash: crypto::hash("Correct-Horse-Battery-Staple"),
+        totp_secret: Some(totp::generate_secret()),
+        ..Default::default()
+    }
+    .create(conn)
+    .unwrap()
+}
+
+#[test]
+fn password_login_keeps_two_factor_failures() {
+    let server = rocket::ignite().attach(DbConn::fairing());
+    let conn = DbConn::get_one(&server).unwrap();
+    let client = Client::new(server).expect("valid rocket");
+    let client_info = ClientInfo {
+        ip: None,
+        user_agent: None,
+    };
+    let user = create_two_factor_user(&conn);
+
+    let login = |client: &Client| {
+        let req = client.get("/");
+        UserGuard::authenticate(
+            &conn,
+            Cookies::from_request(req.inner()).unwrap(),
+            &client_info,
+            &user.name,
+            "Correct-Horse-Battery-Staple",
+        )
+    };
+    let enter_code = |client: &Client| {
+        let req = client
+            .get("/")
+            .private_cookie(pending_two_factor_login(user.id));
+        UserGuard::authenticate_two_factor(
+            &conn,
+            Cookies::from_request(req.inner()).unwrap(),
+            &client_info,
+            "invalid",
+        )
+    };
+
+    assert_matches!(login(&client), Ok(Authentication::TwoFactorPending));
+    for _ in 1..CONFIG.security.login_lockout_threshold {
+        assert_matches!(enter_code(&client), Err(Error::InvalidTwoFactorCode));
+    }
+
+    // The correct password neither resets the failed codes nor the lockout of the user name
+    assert_matches!(login(&client), Ok(Authentication::TwoFactorPending));
+    assert_eq!(
+        user.get_two_factor_failures(&conn).unwrap(),
+        CONFIG.security.login_lockout_threshold - 1
+    );
+    assert_matches!(enter_code(&client), Err(Error::InvalidTwoFactorCode));
+    assert_matches!(login(&client), Err(Error::LockedOut(_)));
+}
+
+#[test]
+fn too_many_two_factor_codes() {
+    let server = rocket::ignite().attach(DbConn::fairing());
+    let conn = DbConn::get_one(&server).unwrap();
+    let client = Client::new(server).expect("valid rocket");
+    let client_info = ClientInfo {
+        ip: None,
+        user_agent: None,
+    };
+    let user = create_two_factor_user(&conn);
+    for _ in 1..TWO_FACTOR_MAX_ATTEMPTS {
+        user.record_two_factor_failure(&conn).unwrap();
+    }
+
+    let req = client
+        .get("/")
+        .private_cookie(pending_two_factor_login(user.id));
+    assert_matches!(
+        UserGuard::authenticate_two_factor(
+            &conn,
+            Cookies::from_request(req.inner()).unwrap(),
+            &client_info,
+            "invalid",
+        ),
+        Err(Error::TooManyTwoFactorAttempts(_))
+    );
+
+    let locked_until = lockout::locked_until(&conn, &user.name, None, Utc::now())
+        .unwrap()
+        .unwrap();
+    assert!(
+        locked_until
+            > Utc::now() + to_chrono_duration(CONFIG.security.max_login_lockout_time)
+                - Duration::minutes(1)
+    );
 }
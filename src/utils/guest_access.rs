@@ -0,0 +1,145 @@
+//! Guest codes, which residents issue to let in visitors while they are away.
+
+use super::{access_schedule, crypto, door_opening, lockout};
+use crate::db_entry::{
+    rusqlite, Connection, Entry, FlatEntry, GuestCodeEntry, GuestRedemptionEntry, Permission,
+    RoleEntry, UserEntry,
+};
+use chrono::{offset::Utc, DateTime};
+use log::info;
+use std::net::IpAddr;
+
+#[cfg(test)]
+#[path = "./guest_access_test.rs"]
+mod guest_access_test;
+
+/// The number of characters of a guest code.
+const GUEST_CODE_LENGTH: usize = 12;
+/// The characters of a guest code without the easily confused ones like `0` and `O`.
+/// Its length of 32 divides 256, so every character is equally likely.
+const GUEST_CODE_ALPHABET: &[u8; 32] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+
+/// All errors which could happen during the redemption of a guest code.
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Too many invalid guest codes, please try again in {0} seconds")]
+    LockedOut(i64),
+    #[error("The guest code is invalid or expired")]
+    InvalidCode,
+    #[error(transparent)]
+    Schedule(#[from] access_schedule::Error),
+    #[error(transparent)]
+    Door(#[from] door_opening::Error),
+    #[error(transparent)]
+    Database(#[from] rusqlite::Error),
+}
+
+/// Creates a random guest code and its hash, which is stored.
+pub fn generate_code() -> (String, String) {
+    let mut random = [0_u8; GUEST_CODE_LENGTH];
+    crypto::fill_rand_array(&mut random);
+    let code: String = random
+        .iter()
+        .map(|byte| GUEST_CODE_ALPHABET[usize::from(*byte) % GUEST_CODE_ALPHABET.len()] as char)
+        .collect();
+    let code_hash = crypto::hash_token(&code);
+    (code, code_hash)
+}
+
+/// Ignores the case and separators, which a visitor may type.
+fn normalize(code: &str) -> String {
+    code.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+/// Checks a guest code, opens its door with `open` and logs the redemption.
+/// The code is only valid as long as its issuer may open doors at this time.
+/// A use is only counted, if the door has been opened.
+/// Invalid codes are counted as failures of the IP address to prevent brute-forcing.
+pub fn redeem<F>(
+    conn: &Connection,
+    code: &str,
+    client_ip: Option<IpAddr>,
+    now: DateTime<Utc>,
+    open: F,
+) -> Result<GuestCodeEntry, Error>
+where
+    F: FnOnce(&GuestCodeEntry) -> Result<(), door_opening::Error>,
+{
+    if let Some(locked_until) = lockout::ip_locked_until(conn, client_ip, now)? {
+        return Err(Error::LockedOut((locked_until - now).num_seconds() + 1));
+    }
+
+    let mut guest_code = match find_valid_code(conn, code, now)? {
+        Some(guest_code) => guest_code,
+        None => {
+            lockout::record_ip_failure(conn, client_ip, now)?;
+            return Err(Error::InvalidCode);
+        }
+    };
+    access_schedule::check(conn, guest_code.user, now)?;
+
+    // The use is reserved before the door is opened, so concurrent redemptions can't exceed the maximum
+    if !guest_code.increment_uses(conn)? {
+        return Err(Error::InvalidCode);
+    }
+    if let Err(e) = open(&guest_code) {
+        guest_code.decrement_uses(conn)?;
+        return Err(e.into());
+    }
+
+    GuestRedemptionEntry {
+        id: (),
+        label: guest_code.label.clone(),
+        redemption_datetime: now,
+        ip_address: client_ip.map(|ip| ip.to_string()),
+        flat: guest_code.flat,
+    }
+    .create(conn)?;
+    info!(
+        "Guest code \"{}\" of flat {} redeemed",
+        guest_code.label, guest_code.flat
+    );
+
+    Ok(guest_code)
+}
+
+/// Returns the guest code, if it is valid, its flat is active
+/// and its issuer is still an active resident of the flat, who may open doors.
+fn find_valid_code(
+    conn: &Connection,
+    code: &str,
+    now: DateTime<Utc>,
+) -> Result<Option<GuestCodeEntry>, rusqlite::Error> {
+    let guest_code =
+        match GuestCodeEntry::get_by_code_hash(conn, &crypto::hash_token(&normalize(code)))? {
+            Some(guest_code) if guest_code.is_valid(now) => guest_code,
+            _ => return Ok(None),
+        };
+
+    let flat_active =
+        FlatEntry::get_by_id(conn, guest_code.flat)?.map_or(false, |flat| flat.active);
+    if !flat_active || !issuer_may_open(conn, &guest_code, now)? {
+        return Ok(None);
+    }
+
+    Ok(Some(guest_code))
+}
+
+/// Whether the issuer of the guest code still exists, is active, belongs to its flat and may open doors
+fn issuer_may_open(
+    conn: &Connection,
+    guest_code: &GuestCodeEntry,
+    now: DateTime<Utc>,
+) -> Result<bool, rusqlite::Error> {
+    let issuer = match UserEntry::get_by_id(conn, guest_code.user)? {
+        Some(issuer) => issuer,
+        None => return Ok(false),
+    };
+    if !issuer.is_active(now) || issuer.flat.as_ref().map(Entry::get_id) != Some(guest_code.flat) {
+        return Ok(false);
+    }
+    Ok(RoleEntry::get_permissions(conn, &issuer)?.contains(&Permission::DoorOpen))
+}
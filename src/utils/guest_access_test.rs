@@ -0,0 +1,202 @@
+use super::*;
+use crate::db_entry::{AccessWindowEntry, Entry, UserEntry};
+use crate::utils::config::CONFIG;
+use chrono::{Datelike, Duration};
+use std::net::Ipv4Addr;
+
+const CLIENT_IP: Option<IpAddr> = Some(IpAddr::V4(Ipv4Addr::LOCALHOST));
+
+/// Creates a flat with an user and returns a guest code with a single use.
+fn create_guest_code(conn: &Connection, flat_active: bool) -> (String, GuestCodeEntry) {
+    let flat = FlatEntry {
+        active: flat_active,
+        ..Default::default()
+    }
+    .create(conn)
+    .unwrap();
+    let user = UserEntry {
+        flat: Some(flat.get_id()),
+        ..Default::default()
+    }
+    .create(conn)
+    .unwrap();
+
+    let (code, code_hash) = generate_code();
+    let guest_code = GuestCodeEntry {
+        code_hash,
+        flat: flat.get_id(),
+        user: user.get_id(),
+        ..Default::default()
+    }
+    .create(conn)
+    .unwrap();
+
+    (code, guest_code)
+}
+
+fn create_conn() -> Connection {
+    let sql_scheme = include_str!("../../scheme.sql");
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(sql_scheme).unwrap();
+    conn
+}
+
+#[test]
+fn generated_code() {
+    let (code, code_hash) = generate_code();
+
+    assert_eq!(code.len(), GUEST_CODE_LENGTH);
+    assert!(code.bytes().all(|c| GUEST_CODE_ALPHABET.contains(&c)));
+    assert_eq!(crypto::hash_token(&code), code_hash);
+}
+
+#[test]
+fn redeem_code_once() {
+    let conn = create_conn();
+    let (code, guest_code) = create_guest_code(&conn, true);
+    let now = Utc::now();
+
+    let typed_code = format!(" {}-{} ", &code[..6], code[6..].to_lowercase());
+    let redeemed_code = redeem(&conn, &typed_code, CLIENT_IP, now, |_| Ok(())).unwrap();
+    assert_eq!(redeemed_code.uses, 1);
+
+    assert_matches!(
+        redeem(&conn, &code, CLIENT_IP, now, |_| Ok(())),
+        Err(Error::InvalidCode)
+    );
+
+    let redemptions = GuestRedemptionEntry::get_by_flat(&conn, guest_code.flat, 10).unwrap();
+    assert_eq!(redemptions.len(), 1);
+    assert_eq!(redemptions[0].label, guest_code.label);
+}
+
+#[test]
+fn redeem_expired_code() {
+    let conn = create_conn();
+    let (code, guest_code) = create_guest_code(&conn, true);
+
+    assert_matches!(
+        redeem(&conn, &code, CLIENT_IP, guest_code.valid_until, |_| Ok(())),
+        Err(Error::InvalidCode)
+    );
+}
+
+#[test]
+fn redeem_code_of_inactive_flat() {
+    let conn = create_conn();
+    let (code, _) = create_guest_code(&conn, false);
+
+    assert_matches!(
+        redeem(&conn, &code, CLIENT_IP, Utc::now(), |_| Ok(())),
+        Err(Error::InvalidCode)
+    );
+}
+
+#[test]
+fn redeem_code_of_deactivated_issuer() {
+    let conn = create_conn();
+    let (code, guest_code) = create_guest_code(&conn, true);
+    let mut issuer = UserEntry::get_by_id(&conn, guest_code.user)
+        .unwrap()
+        .unwrap();
+    issuer.active = false;
+    issuer.update(&conn).unwrap();
+
+    assert_matches!(
+        redeem(&conn, &code, CLIENT_IP, Utc::now(), |_| Ok(())),
+        Err(Error::InvalidCode)
+    );
+}
+
+#[test]
+fn redeem_code_of_deleted_issuer() {
+    let conn = create_conn();
+    let (code, guest_code) = create_guest_code(&conn, true);
+    UserEntry::get_by_id(&conn, guest_code.user)
+        .unwrap()
+        .unwrap()
+        .delete(&conn)
+        .unwrap();
+
+    assert_matches!(
+        redeem(&conn, &code, CLIENT_IP, Utc::now(), |_| Ok(())),
+        Err(Error::InvalidCode)
+    );
+}
+
+#[test]
+fn redeem_code_outside_schedule_of_issuer() {
+    let conn = create_conn();
+    let (code, guest_code) = create_guest_code(&conn, true);
+    // The issuer may only open the doors on another weekday
+    let now = Utc::now();
+    let local = CONFIG.building.local_time(now);
+    AccessWindowEntry {
+        weekday: (local.weekday().num_days_from_monday() + 1) % 7,
+        user: guest_code.user,
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+
+    assert_matches!(
+        redeem(&conn, &code, CLIENT_IP, now, |_| Ok(())),
+        Err(Error::Schedule(_))
+    );
+    assert_eq!(
+        GuestCodeEntry::get_by_id(&conn, guest_code.id)
+            .unwrap()
+            .unwrap()
+            .uses,
+        0
+    );
+}
+
+#[test]
+fn count_use_only_if_door_opened() {
+    let conn = create_conn();
+    let (code, guest_code) = create_guest_code(&conn, true);
+    let now = Utc::now();
+
+    assert_matches!(
+        redeem(&conn, &code, CLIENT_IP, now, |_| Err(
+            door_opening::Error::Opener
+        )),
+        Err(Error::Door(_))
+    );
+    assert_eq!(
+        GuestCodeEntry::get_by_id(&conn, guest_code.id)
+            .unwrap()
+            .unwrap()
+            .uses,
+        0
+    );
+    assert!(
+        GuestRedemptionEntry::get_by_flat(&conn, guest_code.flat, 10)
+            .unwrap()
+            .is_empty()
+    );
+
+    assert_matches!(redeem(&conn, &code, CLIENT_IP, now, |_| Ok(())), Ok(_));
+}
+
+#[test]
+fn lock_out_after_invalid_codes() {
+    let conn = create_conn();
+    let (code, _) = create_guest_code(&conn, true);
+    let now = Utc::now();
+
+    for _ in 0..CONFIG.security.ip_lockout_threshold {
+        assert_matches!(
+            redeem(&conn, "INVALID", CLIENT_IP, now, |_| Ok(())),
+            Err(Error::InvalidCode)
+        );
+    }
+
+    assert_matches!(
+        redeem(&conn, &code, CLIENT_IP, now + Duration::seconds(1), |_| Ok(
+            ()
+        )),
+        Err(Error::LockedOut(_))
+    );
+}
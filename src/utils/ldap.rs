@@ -0,0 +1,102 @@
+//! The login against an LDAP directory, whose users are created or updated in the database on their login.
+
+use super::config::Ldap;
+use super::provisioning::{self, ExternalUser};
+use crate::db_entry::UserType;
+use ldap3::{dn_escape, LdapConn, LdapConnSettings, Scope, SearchEntry};
+use log::warn;
+
+#[cfg(test)]
+#[path = "./ldap_test.rs"]
+mod ldap_test;
+
+/// The hash-config of users of the directory, as their password isn't stored in the database.
+pub const HASH_CONFIG: &str = "ldap";
+/// The result code of a bind with invalid credentials.
+const INVALID_CREDENTIALS_RC: u32 = 49;
+
+/// All errors which could happen during the login against the directory.
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Ldap(#[from] ldap3::LdapError),
+    #[error("The credentials are invalid")]
+    InvalidCredentials,
+    #[error("The user isn't member of any configured group")]
+    NoGroup,
+}
+
+/// Returns the DN of the user to bind with.
+fn bind_dn(config: &Ldap, name: &str) -> String {
+    config
+        .bind_dn_template
+        .replace("{username}", &dn_escape(name))
+}
+
+/// Returns the most privileged user type of the groups, DNs are compared case-insensitively.
+fn user_type_of_groups(config: &Ldap, groups: &[String]) -> Option<UserType> {
+    let user_types: Vec<UserType> = config
+        .groups
+        .iter()
+        .filter(|group| groups.iter().any(|dn| dn.eq_ignore_ascii_case(&group.dn)))
+        .map(|group| group.user_type)
+        .collect();
+
+    provisioning::most_privileged(&user_types)
+}
+
+/// Binds as the user and reads its groups and flat.
+pub fn authenticate(config: &Ldap, name: &str, pw: &str) -> Result<ExternalUser, Error> {
+    // An empty password would be an unauthenticated bind, which always succeeds
+    if name.is_empty() || pw.is_empty() {
+        return Err(Error::InvalidCredentials);
+    }
+
+    let mut ldap = LdapConn::with_settings(
+        LdapConnSettings::new().set_conn_timeout(config.timeout),
+        &config.server_url,
+    )?;
+
+    let dn = bind_dn(config, name);
+    let bind_result = ldap.simple_bind(&dn, pw)?;
+    if bind_result.rc == INVALID_CREDENTIALS_RC {
+        return Err(Error::InvalidCredentials);
+    }
+    bind_result.success()?;
+
+    let mut attributes = vec![config.group_attribute.as_str()];
+    attributes.extend(config.flat_attribute.as_deref());
+    let (entries, _) = ldap
+        .search(&dn, Scope::Base, "(objectClass=*)", attributes)?
+        .success()?;
+    ldap.unbind()?;
+
+    let entry = entries
+        .into_iter()
+        .next()
+        .map(SearchEntry::construct)
+        .ok_or(Error::InvalidCredentials)?;
+
+    let groups = entry
+        .attrs
+        .get(&config.group_attribute)
+        .cloned()
+        .unwrap_or_default();
+    let flat = config
+        .flat_attribute
+        .as_ref()
+        .and_then(|attribute| entry.attrs.get(attribute))
+        .and_then(|values| values.first())
+        .and_then(|value| match value.trim().parse() {
+            Ok(flat) => Some(flat),
+            Err(_) => {
+                warn!("LDAP: The flat `{}` of {} isn't an ID", value, name);
+                None
+            }
+        });
+
+    Ok(ExternalUser {
+        user_type: user_type_of_groups(config, &groups).ok_or(Error::NoGroup)?,
+        flat,
+    })
+}
@@ -0,0 +1,90 @@
+use super::*;
+use crate::utils::config::LdapGroup;
+use std::time::Duration;
+
+fn get_config() -> Ldap {
+    Ldap {
+        server_url: std::env::var("LDAP_TEST_URL")
+            .unwrap_or_else(|_| "ldap://localhost:389".to_string()),
+        bind_dn_template: "uid={username},ou=people,dc=example,dc=org".to_string(),
+        group_attribute: "memberOf".to_string(),
+        groups: vec![
+            LdapGroup {
+                dn: "cn=residents,ou=groups,dc=example,dc=org".to_string(),
+                user_type: UserType::User,
+            },
+            LdapGroup {
+                dn: "cn=admins,ou=groups,dc=example,dc=org".to_string(),
+                user_type: UserType::Admin,
+            },
+        ],
+        flat_attribute: Some("departmentNumber".to_string()),
+        timeout: Duration::from_secs(1),
+    }
+}
+
+#[test]
+fn escaped_bind_dn() {
+    assert_eq!(
+        bind_dn(&get_config(), "alice"),
+        "uid=alice,ou=people,dc=example,dc=org"
+    );
+    assert_eq!(
+        bind_dn(&get_config(), "bob,ou=admins"),
+        "uid=bob\\2cou\\3dadmins,ou=people,dc=example,dc=org"
+    );
+}
+
+#[test]
+fn most_privileged_user_type() {
+    let config = get_config();
+    assert_eq!(
+        user_type_of_groups(
+            &config,
+            &[
+                "cn=residents,ou=groups,dc=example,dc=org".to_string(),
+                "CN=Admins,OU=Groups,DC=example,DC=org".to_string(),
+            ]
+        ),
+        Some(UserType::Admin)
+    );
+    assert_eq!(
+        user_type_of_groups(
+            &config,
+            &["cn=residents,ou=groups,dc=example,dc=org".to_string()]
+        ),
+        Some(UserType::User)
+    );
+    assert_eq!(
+        user_type_of_groups(&config, &["cn=other,dc=example,dc=org".to_string()]),
+        None
+    );
+}
+
+#[test]
+fn empty_password_is_rejected() {
+    assert_matches!(
+        authenticate(&get_config(), "alice", ""),
+        Err(Error::InvalidCredentials)
+    );
+}
+
+/// Needs an OpenLDAP server with the user `alice` and the password `alice`,
+/// who is member of the admins group. The setup is described in `docs/Setup.md`.
+#[test]
+#[ignore]
+fn authenticate_against_openldap() {
+    let config = get_config();
+
+    assert_eq!(
+        authenticate(&config, "alice", "alice").unwrap(),
+        ExternalUser {
+            user_type: UserType::Admin,
+            flat: Some(1),
+        }
+    );
+    assert_matches!(
+        authenticate(&config, "alice", "wrong"),
+        Err(Error::InvalidCredentials)
+    );
+}
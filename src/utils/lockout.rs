@@ -0,0 +1,174 @@
+//! Brute-force protection of the login with an exponential lockout of user names and IP addresses.
+
+use super::config::{to_chrono_duration, CONFIG};
+use crate::db_entry::{rusqlite, Connection, Entry, LoginThrottleEntry, SubjectType};
+use chrono::{offset::Utc, DateTime, Duration};
+use std::net::IpAddr;
+
+#[cfg(test)]
+#[path = "./lockout_test.rs"]
+mod lockout_test;
+
+/// Calculates the lockout after the number of failed attempts,
+/// which starts at the threshold with the `login_lockout_time` and doubles on every further failure.
+fn lockout_time(failed_attempts: u32, threshold: u32) -> Option<std::time::Duration> {
+    let exponent = failed_attempts.checked_sub(threshold)?;
+    let lockout_time = 2_u32
+        .checked_pow(exponent)
+        .and_then(|factor| CONFIG.security.login_lockout_time.checked_mul(factor))
+        .unwrap_or(CONFIG.security.max_login_lockout_time);
+
+    Some(lockout_time.min(CONFIG.security.max_login_lockout_time))
+}
+
+/// Returns the subjects of a login attempt and their lockout threshold.
+/// Without a name only the IP address is throttled, e.g. for the redemption of guest codes.
+fn subjects(name: Option<&str>, client_ip: Option<IpAddr>) -> Vec<(SubjectType, String, u32)> {
+    let mut subjects = Vec::new();
+    if let Some(name) = name {
+        subjects.push((
+            SubjectType::UserName,
+            name.to_string(),
+            CONFIG.security.login_lockout_threshold,
+        ));
+    }
+    if let Some(client_ip) = client_ip {
+        subjects.push((
+            SubjectType::IpAddress,
+            client_ip.to_string(),
+            CONFIG.security.ip_lockout_threshold,
+        ));
+    }
+    subjects
+}
+
+/// Returns the end of the lockout of the user name or the IP address, if one of them is locked.
+pub fn locked_until(
+    conn: &Connection,
+    name: &str,
+    client_ip: Option<IpAddr>,
+    now: DateTime<Utc>,
+) -> Result<Option<DateTime<Utc>>, rusqlite::Error> {
+    subjects_locked_until(conn, subjects(Some(name), client_ip), now)
+}
+
+/// Returns the end of the lockout of the IP address, if it is locked.
+pub fn ip_locked_until(
+    conn: &Connection,
+    client_ip: Option<IpAddr>,
+    now: DateTime<Utc>,
+) -> Result<Option<DateTime<Utc>>, rusqlite::Error> {
+    subjects_locked_until(conn, subjects(None, client_ip), now)
+}
+
+fn subjects_locked_until(
+    conn: &Connection,
+    subjects: Vec<(SubjectType, String, u32)>,
+    now: DateTime<Utc>,
+) -> Result<Option<DateTime<Utc>>, rusqlite::Error> {
+    let mut locked_until = None;
+    for (subject_type, subject, _) in subjects {
+        let throttle = LoginThrottleEntry::get_by_subject(conn, subject_type, &subject)?;
+        locked_until = throttle
+            .and_then(|throttle| throttle.locked_until)
+            .filter(|until| *until > now)
+            .max(locked_until);
+    }
+    Ok(locked_until)
+}
+
+/// Counts a failed login of the user name and the IP address and locks them if their threshold is exceeded.
+pub fn record_failure(
+    conn: &Connection,
+    name: &str,
+    client_ip: Option<IpAddr>,
+    now: DateTime<Utc>,
+) -> Result<(), rusqlite::Error> {
+    record_subjects_failure(conn, subjects(Some(name), client_ip), now)
+}
+
+/// Counts a failed attempt of the IP address and locks it if its threshold is exceeded.
+pub fn record_ip_failure(
+    conn: &Connection,
+    client_ip: Option<IpAddr>,
+    now: DateTime<Utc>,
+) -> Result<(), rusqlite::Error> {
+    record_subjects_failure(conn, subjects(None, client_ip), now)
+}
+
+fn record_subjects_failure(
+    conn: &Connection,
+    subjects: Vec<(SubjectType, String, u32)>,
+    now: DateTime<Utc>,
+) -> Result<(), rusqlite::Error> {
+    let forget_before = now - to_chrono_duration(CONFIG.security.max_login_lockout_time);
+
+    for (subject_type, subject, threshold) in subjects {
+        match LoginThrottleEntry::get_by_subject(conn, subject_type, &subject)? {
+            Some(mut throttle) => {
+                // Old failures are forgotten if there is no active lockout
+                let is_locked = throttle.locked_until.map_or(false, |until| until > now);
+                if !is_locked && throttle.last_failure_datetime < forget_before {
+                    throttle.failed_attempts = 0;
+                }
+                throttle.failed_attempts += 1;
+                throttle.last_failure_datetime = now;
+                throttle.locked_until = lock(throttle.failed_attempts, threshold, now);
+                throttle.update(conn)?;
+            }
+            None => {
+                LoginThrottleEntry {
+                    id: (),
+                    subject_type,
+                    subject,
+                    failed_attempts: 1,
+                    last_failure_datetime: now,
+                    locked_until: lock(1, threshold, now),
+                }
+                .create(conn)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Calculates the end of the lockout, if the threshold is exceeded.
+fn lock(failed_attempts: u32, threshold: u32, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    lockout_time(failed_attempts, threshold)
+        .and_then(|lockout_time| Duration::from_std(lockout_time).ok())
+        .map(|lockout_time| now + lockout_time)
+}
+
+/// Locks the user name for the maximal lockout time, e.g. after too many invalid second factors.
+/// Returns the end of the lockout.
+pub fn lock_name(
+    conn: &Connection,
+    name: &str,
+    now: DateTime<Utc>,
+) -> Result<DateTime<Utc>, rusqlite::Error> {
+    let locked_until = now + to_chrono_duration(CONFIG.security.max_login_lockout_time);
+    match LoginThrottleEntry::get_by_subject(conn, SubjectType::UserName, name)? {
+        Some(mut throttle) => {
+            throttle.locked_until = Some(locked_until);
+            throttle.update(conn)?;
+        }
+        None => {
+            LoginThrottleEntry {
+                id: (),
+                subject_type: SubjectType::UserName,
+                subject: name.to_string(),
+                failed_attempts: 0,
+                last_failure_datetime: now,
+                locked_until: Some(locked_until),
+            }
+            .create(conn)?;
+        }
+    }
+    Ok(locked_until)
+}
+
+/// Forgets the failed logins of an user name after a successful login.
+/// The failures of the IP address are kept, as an attacker could reset them with an own account.
+pub fn record_success(conn: &Connection, name: &str) -> Result<(), rusqlite::Error> {
+    LoginThrottleEntry::delete_by_subject(conn, SubjectType::UserName, name)
+}
@@ -0,0 +1,146 @@
+use super::*;
+use std::net::Ipv4Addr;
+
+const CLIENT_IP: Option<IpAddr> = Some(IpAddr::V4(Ipv4Addr::LOCALHOST));
+
+fn create_conn() -> Connection {
+    let sql_scheme = include_str!("../../scheme.sql");
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(sql_scheme).unwrap();
+    conn
+}
+
+#[test]
+fn no_lockout_below_threshold() {
+    assert_matches!(lockout_time(2, 3), None);
+}
+
+#[test]
+fn exponential_lockout() {
+    assert_eq!(lockout_time(3, 3), Some(CONFIG.security.login_lockout_time));
+    assert_eq!(
+        lockout_time(5, 3),
+        Some(CONFIG.security.login_lockout_time * 4)
+    );
+}
+
+#[test]
+fn capped_lockout() {
+    assert_eq!(
+        lockout_time(20, 3),
+        Some(CONFIG.security.max_login_lockout_time)
+    );
+    assert_eq!(
+        lockout_time(u32::MAX, 3),
+        Some(CONFIG.security.max_login_lockout_time)
+    );
+}
+
+#[test]
+fn lock_user_name() {
+    let conn = create_conn();
+    let now = Utc::now();
+
+    for _ in 1..CONFIG.security.login_lockout_threshold {
+        record_failure(&conn, "Alice", None, now).unwrap();
+    }
+    assert_matches!(locked_until(&conn, "Alice", None, now), Ok(None));
+
+    record_failure(&conn, "Alice", None, now).unwrap();
+    assert_matches!(locked_until(&conn, "Alice", None, now), Ok(Some(_)));
+    assert_matches!(locked_until(&conn, "Bob", None, now), Ok(None));
+
+    let after_lockout = now + Duration::from_std(CONFIG.security.login_lockout_time).unwrap();
+    assert_matches!(locked_until(&conn, "Alice", None, after_lockout), Ok(None));
+}
+
+#[test]
+fn lock_ip_address() {
+    let conn = create_conn();
+    let now = Utc::now();
+
+    for i in 0..CONFIG.security.ip_lockout_threshold {
+        record_failure(&conn, &format!("User{}", i), CLIENT_IP, now).unwrap();
+    }
+
+    assert_matches!(locked_until(&conn, "Alice", CLIENT_IP, now), Ok(Some(_)));
+    assert_matches!(locked_until(&conn, "Alice", None, now), Ok(None));
+}
+
+#[test]
+fn success_resets_user_name() {
+    let conn = create_conn();
+    let now = Utc::now();
+
+    for _ in 0..CONFIG.security.login_lockout_threshold {
+        record_failure(&conn, "Alice", CLIENT_IP, now).unwrap();
+    }
+    record_success(&conn, "Alice").unwrap();
+
+    assert_matches!(
+        LoginThrottleEntry::get_by_subject(&conn, SubjectType::UserName, "Alice"),
+        Ok(None)
+    );
+    assert_matches!(
+        LoginThrottleEntry::get_by_subject(&conn, SubjectType::IpAddress, "127.0.0.1"),
+        Ok(Some(_))
+    );
+}
+
+#[test]
+fn forget_old_failures() {
+    let conn = create_conn();
+    let now = Utc::now();
+    let long_ago = now - Duration::days(1);
+
+    for _ in 1..CONFIG.security.login_lockout_threshold {
+        record_failure(&conn, "Alice", None, long_ago).unwrap();
+    }
+    record_failure(&conn, "Alice", None, now).unwrap();
+
+    assert_matches!(locked_until(&conn, "Alice", None, now), Ok(None));
+    assert_eq!(
+        LoginThrottleEntry::get_by_subject(&conn, SubjectType::UserName, "Alice")
+            .unwrap()
+            .unwrap()
+            .failed_attempts,
+        1
+    );
+}
+
+#[test]
+fn lock_only_ip_address() {
+    let conn = create_conn();
+    let now = Utc::now();
+
+    for _ in 0..CONFIG.security.ip_lockout_threshold {
+        record_ip_failure(&conn, CLIENT_IP, now).unwrap();
+    }
+
+    assert_matches!(ip_locked_until(&conn, CLIENT_IP, now), Ok(Some(_)));
+    assert_matches!(locked_until(&conn, "Alice", CLIENT_IP, now), Ok(Some(_)));
+    assert_matches!(ip_locked_until(&conn, None, now), Ok(None));
+}
+
+#[test]
+fn lock_name_for_maximal_lockout_time() {
+    let conn = create_conn();
+    let now = Utc::now();
+    let max_lockout_time = Duration::from_std(CONFIG.security.max_login_lockout_time).unwrap();
+
+    assert_eq!(
+        lock_name(&conn, "Alice", now).unwrap(),
+        now + max_lockout_time
+    );
+    assert_eq!(
+        locked_until(&conn, "Alice", None, now).unwrap(),
+        Some(now + max_lockout_time)
+    );
+
+    record_failure(&conn, "Alice", None, now).unwrap();
+    assert_eq!(
+        lock_name(&conn, "Alice", now).unwrap(),
+        now + max_lockout_time
+    );
+    assert_matches!(locked_until(&conn, "Bob", None, now), Ok(None));
+}
@@ -1,9 +1,24 @@
 //! Small helper functions and structs which aren't appropriate in other folders.
 
+pub mod access_schedule;
+pub mod account_validity;
+pub mod audit;
+pub mod breached_passwords;
 pub mod config;
 pub mod crypto;
+pub mod csrf;
+pub mod door_history;
+pub mod door_opening;
 pub mod guards;
+pub mod guest_access;
+pub mod ldap;
+pub mod lockout;
+pub mod oidc;
+pub mod passkey;
+pub mod password_reset;
+pub mod provisioning;
 pub mod serde;
+pub mod totp;
 
 /// This function is used to prevent optimization attempts from the compiler to drop the variable.
 pub fn no_operation<T>(_var: &T) {}
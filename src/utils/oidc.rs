@@ -0,0 +1,258 @@
+//! The single sign-on with an OpenID Connect provider with the authorization code flow and PKCE.
+//! Its users are created or updated in the database on their login.
+
+use super::config::Oidc;
+use super::csrf;
+use super::provisioning::{self, ExternalUser};
+use crate::db_entry::UserType;
+use bool_ext::BoolExt;
+use chrono::{offset::Utc, DateTime, Duration};
+use log::warn;
+use openidconnect::core::{
+    CoreAuthDisplay, CoreAuthPrompt, CoreErrorResponseType, CoreGenderClaim, CoreJsonWebKey,
+    CoreJsonWebKeyType, CoreJsonWebKeyUse, CoreJweContentEncryptionAlgorithm,
+    CoreJwsSigningAlgorithm, CoreProviderMetadata, CoreResponseType, CoreRevocableToken,
+    CoreRevocationErrorResponse, CoreTokenIntrospectionResponse, CoreTokenType,
+};
+use openidconnect::url::Url;
+use openidconnect::{
+    AdditionalClaims, AuthenticationFlow, AuthorizationCode, ClaimsVerificationError, Client,
+    ClientId, ClientSecret, CsrfToken, EmptyExtraTokenFields, HttpRequest, HttpResponse,
+    IdTokenFields, IssuerUrl, Nonce, PkceCodeChallenge, PkceCodeVerifier, RedirectUrl, Scope,
+    StandardErrorResponse, StandardTokenResponse, TokenResponse,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+#[cfg(test)]
+#[path = "./oidc_test.rs"]
+mod oidc_test;
+
+/// The hash-config of users of the identity provider, as they have no password in the database.
+pub const HASH_CONFIG: &str = "oidc";
+/// The time in which the login at the identity provider has to be finished.
+const LOGIN_TIMEOUT_MINUTES: i64 = 10;
+
+/// All errors which could happen during the login with the identity provider.
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("The identity provider failed: {0}")]
+    Provider(String),
+    #[error("The login has expired, please try again")]
+    Expired,
+    #[error("The state of the login is invalid")]
+    InvalidState,
+    #[error("The identity provider returned no ID token")]
+    NoIdToken,
+    #[error(transparent)]
+    ClaimsVerification(#[from] ClaimsVerificationError),
+    #[error("The ID token contains no `preferred_username`")]
+    NoName,
+    #[error("The user isn't member of any configured group")]
+    NoGroup,
+}
+
+/// The user of the identity provider, which is identified by the issuer and the subject,
+/// as its `preferred_username` isn't unique and may be changed by the user.
+#[derive(Debug, PartialEq)]
+pub struct Identity {
+    pub issuer: String,
+    pub subject: String,
+    /// The `preferred_username`, which is only the name shown for the user
+    pub name: String,
+}
+
+/// The non-standard claims of the ID token, e.g. the groups and the flat.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ExtraClaims {
+    #[serde(flatten)]
+    claims: HashMap<String, Value>,
+}
+
+impl AdditionalClaims for ExtraClaims {}
+
+impl ExtraClaims {
+    /// Returns the strings of a claim, which is either a string or an array of strings.
+    fn strings(&self, claim: &str) -> Vec<String> {
+        match self.claims.get(claim) {
+            Some(Value::String(value)) => vec![value.clone()],
+            Some(Value::Array(values)) => values
+                .iter()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns the ID of a claim, which is either a number or a string.
+    fn id(&self, claim: &str) -> Option<u32> {
+        let id = match self.claims.get(claim)? {
+            Value::Number(value) => value.as_u64().and_then(|id| u32::try_from(id).ok()),
+            Value::String(value) => value.trim().parse().ok(),
+            _ => None,
+        };
+        if id.is_none() {
+            warn!("OIDC: The claim `{}` isn't an ID", claim);
+        }
+        id
+    }
+}
+
+type OidcIdTokenFields = IdTokenFields<
+    ExtraClaims,
+    EmptyExtraTokenFields,
+    CoreGenderClaim,
+    CoreJweContentEncryptionAlgorithm,
+    CoreJwsSigningAlgorithm,
+    CoreJsonWebKeyType,
+>;
+
+type OidcTokenResponse = StandardTokenResponse<OidcIdTokenFields, CoreTokenType>;
+
+/// The `CoreClient` with the `ExtraClaims`.
+type OidcClient = Client<
+    ExtraClaims,
+    CoreAuthDisplay,
+    CoreGenderClaim,
+    CoreJweContentEncryptionAlgorithm,
+    CoreJwsSigningAlgorithm,
+    CoreJsonWebKeyType,
+    CoreJsonWebKeyUse,
+    CoreJsonWebKey,
+    CoreAuthPrompt,
+    StandardErrorResponse<CoreErrorResponseType>,
+    OidcTokenResponse,
+    CoreTokenType,
+    CoreTokenIntrospectionResponse,
+    CoreRevocableToken,
+    CoreRevocationErrorResponse,
+>;
+
+/// A login which waits for the callback of the identity provider. It is stored in a private cookie.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PendingLogin {
+    state: String,
+    nonce: String,
+    pkce_verifier: String,
+    expiry: DateTime<Utc>,
+}
+
+/// Discovers the endpoints and keys of the identity provider.
+fn client<HC, RE>(config: &Oidc, http_client: &HC) -> Result<OidcClient, Error>
+where
+    HC: Fn(HttpRequest) -> Result<HttpResponse, RE>,
+    RE: std::error::Error + 'static,
+{
+    let issuer_url =
+        IssuerUrl::new(config.issuer_url.clone()).map_err(|e| Error::Provider(e.to_string()))?;
+    let redirect_url = RedirectUrl::new(config.redirect_url.clone())
+        .map_err(|e| Error::Provider(e.to_string()))?;
+    let provider_metadata = CoreProviderMetadata::discover(&issuer_url, http_client)
+        .map_err(|e| Error::Provider(e.to_string()))?;
+
+    Ok(OidcClient::from_provider_metadata(
+        provider_metadata,
+        ClientId::new(config.client_id.clone()),
+        config.client_secret.clone().map(ClientSecret::new),
+    )
+    .set_redirect_uri(redirect_url))
+}
+
+/// Returns the most privileged user type of the groups.
+fn user_type_of_groups(config: &Oidc, groups: &[String]) -> Option<UserType> {
+    let user_types: Vec<UserType> = config
+        .groups
+        .iter()
+        .filter(|group| groups.contains(&group.name))
+        .map(|group| group.user_type)
+        .collect();
+
+    provisioning::most_privileged(&user_types)
+}
+
+/// Returns the URL of the identity provider, to which the user is redirected,
+/// and the login, which has to be kept until the callback.
+pub fn begin_login<HC, RE>(config: &Oidc, http_client: &HC) -> Result<(Url, PendingLogin), Error>
+where
+    HC: Fn(HttpRequest) -> Result<HttpResponse, RE>,
+    RE: std::error::Error + 'static,
+{
+    let client = client(config, http_client)?;
+    let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
+
+    let (url, state, nonce) = client
+        .authorize_url(
+            AuthenticationFlow::<CoreResponseType>::AuthorizationCode,
+            CsrfToken::new_random,
+            Nonce::new_random,
+        )
+        .add_scope(Scope::new("profile".to_string()))
+        .set_pkce_challenge(pkce_challenge)
+        .url();
+
+    Ok((
+        url,
+        PendingLogin {
+            state: state.secret().clone(),
+            nonce: nonce.secret().clone(),
+            pkce_verifier: pkce_verifier.secret().clone(),
+            expiry: Utc::now() + Duration::minutes(LOGIN_TIMEOUT_MINUTES),
+        },
+    ))
+}
+
+/// Exchanges the code of the callback and verifies the ID token.
+/// Returns the identity of the user and its type and flat.
+pub fn finish_login<HC, RE>(
+    config: &Oidc,
+    http_client: &HC,
+    pending_login: PendingLogin,
+    code: String,
+    state: &str,
+) -> Result<(Identity, ExternalUser), Error>
+where
+    HC: Fn(HttpRequest) -> Result<HttpResponse, RE>,
+    RE: std::error::Error + 'static,
+{
+    (pending_login.expiry > Utc::now()).err(Error::Expired)?;
+    csrf::constant_time_eq(pending_login.state.as_bytes(), state.as_bytes())
+        .err(Error::InvalidState)?;
+
+    let client = client(config, http_client)?;
+    let token_response = client
+        .exchange_code(AuthorizationCode::new(code))
+        .set_pkce_verifier(PkceCodeVerifier::new(pending_login.pkce_verifier))
+        .request(http_client)
+        .map_err(|e| Error::Provider(e.to_string()))?;
+
+    let claims = token_response.id_token().ok_or(Error::NoIdToken)?.claims(
+        &client.id_token_verifier(),
+        &Nonce::new(pending_login.nonce),
+    )?;
+
+    let identity = Identity {
+        issuer: claims.issuer().to_string(),
+        subject: claims.subject().to_string(),
+        name: claims
+            .preferred_username()
+            .ok_or(Error::NoName)?
+            .to_string(),
+    };
+    let extra_claims = claims.additional_claims();
+    let groups = extra_claims.strings(&config.groups_claim);
+    let flat = config
+        .flat_claim
+        .as_ref()
+        .and_then(|claim| extra_claims.id(claim));
+
+    Ok((
+        identity,
+        ExternalUser {
+            user_type: user_type_of_groups(config, &groups).ok_or(Error::NoGroup)?,
+            flat,
+        },
+    ))
+}
@@ -0,0 +1,204 @@
+//! The registration of passkeys and the passwordless login with them via WebAuthn.
+
+use super::config::Webauthn as WebauthnConfig;
+use crate::db_entry::{rusqlite, Connection, Entry, PasskeyEntry, UserEntry};
+use bool_ext::BoolExt;
+use chrono::{offset::Utc, DateTime, Duration};
+use serde::{Deserialize, Serialize};
+use std::ops::Not;
+use webauthn_rs::prelude::{
+    CreationChallengeResponse, Passkey, PasskeyAuthentication, PasskeyRegistration,
+    PublicKeyCredential, RegisterPublicKeyCredential, RequestChallengeResponse, Url, Uuid,
+    Webauthn, WebauthnBuilder, WebauthnError,
+};
+
+#[cfg(test)]
+#[path = "./passkey_test.rs"]
+mod passkey_test;
+
+/// The time in which the authenticator has to answer a challenge.
+const CEREMONY_TIMEOUT_MINUTES: i64 = 5;
+
+/// All errors which could happen during the registration of a passkey or the login with it.
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Db(#[from] rusqlite::Error),
+    #[error(transparent)]
+    Serialization(#[from] serde_json::error::Error),
+    #[error("The passkey is invalid")]
+    Webauthn(#[from] WebauthnError),
+    #[error("The `webauthn.rp_origin` entry isn't an URL")]
+    InvalidOrigin,
+    #[error("The passkey request has expired, please try again")]
+    Expired,
+    #[error("No passkey is registered for this user")]
+    NoPasskey,
+    #[error("The passkey is already registered")]
+    DuplicatePasskey,
+}
+
+/// A registration which waits for the new credential of the authenticator.
+#[derive(Serialize, Deserialize)]
+pub struct PendingRegistration {
+    user: u32,
+    state: PasskeyRegistration,
+    expiry: DateTime<Utc>,
+}
+
+/// A login which waits for the signed challenge of the authenticator.
+#[derive(Serialize, Deserialize)]
+pub struct PendingLogin {
+    user: u32,
+    state: PasskeyAuthentication,
+    expiry: DateTime<Utc>,
+}
+
+impl PendingLogin {
+    /// The ID of the user, who wants to log in
+    pub fn user(&self) -> u32 {
+        self.user
+    }
+}
+
+/// Creates the WebAuthn relying party of the configuration.
+fn webauthn(config: &WebauthnConfig) -> Result<Webauthn, Error> {
+    let rp_origin = Url::parse(&config.rp_origin).map_err(|_| Error::InvalidOrigin)?;
+    Ok(WebauthnBuilder::new(&config.rp_id, &rp_origin)?
+        .rp_name(&config.rp_name)
+        .build()?)
+}
+
+/// The user handle, which is stored in the authenticator. It doesn't contain any personal data.
+fn user_handle(user_id: u32) -> Uuid {
+    Uuid::from_u128(u128::from(user_id))
+}
+
+fn expiry() -> DateTime<Utc> {
+    Utc::now() + Duration::minutes(CEREMONY_TIMEOUT_MINUTES)
+}
+
+/// Deserializes the stored passkeys of the user.
+fn get_passkeys(conn: &Connection, user_id: u32) -> Result<Vec<(PasskeyEntry, Passkey)>, Error> {
+    PasskeyEntry::get_by_user(conn, user_id)?
+        .into_iter()
+        .map(|entry| {
+            let passkey = serde_json::from_str(&entry.credential)?;
+            Ok((entry, passkey))
+        })
+        .collect()
+}
+
+/// Returns the options for the authenticator to create a new passkey
+/// and the registration, which has to be kept until the credential is posted.
+pub fn begin_registration(
+    config: &WebauthnConfig,
+    conn: &Connection,
+    user: &UserEntry,
+) -> Result<(CreationChallengeResponse, PendingRegistration), Error> {
+    // An authenticator can't register a second passkey for the same user
+    let exclude_credentials = get_passkeys(conn, user.id)?
+        .iter()
+        .map(|(_, passkey)| passkey.cred_id().clone())
+        .collect();
+
+    let (options, state) = webauthn(config)?.start_passkey_registration(
+        user_handle(user.id),
+        &user.name,
+        &user.name,
+        Some(exclude_credentials),
+    )?;
+
+    Ok((
+        options,
+        PendingRegistration {
+            user: user.id,
+            state,
+            expiry: expiry(),
+        },
+    ))
+}
+
+/// Verifies the new credential of the authenticator and stores the passkey.
+pub fn finish_registration(
+    config: &WebauthnConfig,
+    conn: &Connection,
+    pending_registration: PendingRegistration,
+    user_id: u32,
+    name: String,
+    credential: &RegisterPublicKeyCredential,
+) -> Result<PasskeyEntry, Error> {
+    (pending_registration.expiry > Utc::now() && pending_registration.user == user_id)
+        .err(Error::Expired)?;
+
+    let passkey =
+        webauthn(config)?.finish_passkey_registration(credential, &pending_registration.state)?;
+    let credential_id = passkey.cred_id().to_string();
+
+    // A credential must not be registered for several users
+    PasskeyEntry::get_by_credential_id(conn, &credential_id)?
+        .is_none()
+        .err(Error::DuplicatePasskey)?;
+
+    Ok(PasskeyEntry {
+        id: (),
+        name,
+        credential_id,
+        credential: serde_json::to_string(&passkey)?,
+        creation_datetime: Utc::now(),
+        last_used_datetime: None,
+        user: user_id,
+    }
+    .create(conn)?)
+}
+
+/// Returns the challenge for the authenticator, which has to sign it with a passkey of the user,
+/// and the login, which has to be kept until the signed challenge is posted.
+pub fn begin_login(
+    config: &WebauthnConfig,
+    conn: &Connection,
+    user: &UserEntry,
+) -> Result<(RequestChallengeResponse, PendingLogin), Error> {
+    let passkeys: Vec<Passkey> = get_passkeys(conn, user.id)?
+        .into_iter()
+        .map(|(_, passkey)| passkey)
+        .collect();
+    passkeys.is_empty().not().err(Error::NoPasskey)?;
+
+    let (options, state) = webauthn(config)?.start_passkey_authentication(&passkeys)?;
+
+    Ok((
+        options,
+        PendingLogin {
+            user: user.id,
+            state,
+            expiry: expiry(),
+        },
+    ))
+}
+
+/// Verifies the signed challenge and updates the signature counter of the passkey.
+/// Returns the ID of the user.
+pub fn finish_login(
+    config: &WebauthnConfig,
+    conn: &Connection,
+    pending_login: PendingLogin,
+    credential: &PublicKeyCredential,
+) -> Result<u32, Error> {
+    (pending_login.expiry > Utc::now()).err(Error::Expired)?;
+
+    let result =
+        webauthn(config)?.finish_passkey_authentication(credential, &pending_login.state)?;
+
+    let (mut entry, mut passkey) = get_passkeys(conn, pending_login.user)?
+        .into_iter()
+        .find(|(_, passkey)| passkey.cred_id() == result.cred_id())
+        .ok_or(Error::NoPasskey)?;
+
+    passkey.update_credential(&result);
+    entry.credential = serde_json::to_string(&passkey)?;
+    entry.last_used_datetime = Some(Utc::now());
+    entry.update(conn)?;
+
+    Ok(pending_login.user)
+}
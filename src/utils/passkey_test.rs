@@ -0,0 +1,126 @@
+use super::*;
+use serde_json::json;
+
+fn config() -> WebauthnConfig {
+    WebauthnConfig {
+        rp_id: "doorcam.example.org".to_string(),
+        rp_origin: "https://doorcam.example.org".to_string(),
+        rp_name: "DoorCam".to_string(),
+    }
+}
+
+fn create_db() -> (Connection, UserEntry) {
+    let sql_scheme = include_str!("../../scheme.sql");
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(sql_scheme).unwrap();
+
+    let user = UserEntry::<(), u32>::default().create(&conn).unwrap();
+    (conn, user)
+}
+
+/// A credential, which is only used for the checks before its verification.
+fn dummy_credential() -> RegisterPublicKeyCredential {
+    serde_json::from_value(json!({
+        "id": "AAAA",
+        "rawId": "AAAA",
+        "type": "public-key",
+        "response": {"attestationObject": "AAAA", "clientDataJSON": "AAAA"},
+        "extensions": {},
+    }))
+    .unwrap()
+}
+
+#[test]
+fn begin_registration_returns_options_of_relying_party() {
+    let (conn, user) = create_db();
+
+    let (options, pending_registration) = begin_registration(&config(), &conn, &user).unwrap();
+    let options = serde_json::to_value(&options).unwrap();
+
+    assert_eq!(options["publicKey"]["rp"]["id"], "doorcam.example.org");
+    assert_eq!(options["publicKey"]["user"]["name"], user.name.as_str());
+    assert_eq!(pending_registration.user, user.id);
+    assert!(pending_registration.expiry > Utc::now());
+}
+
+#[test]
+fn user_handle_is_unique_per_user() {
+    assert_ne!(user_handle(1), user_handle(2));
+    assert_eq!(user_handle(1), user_handle(1));
+}
+
+#[test]
+fn begin_login_without_passkey() {
+    let (conn, user) = create_db();
+
+    assert!(matches!(
+        begin_login(&config(), &conn, &user),
+        Err(Error::NoPasskey)
+    ));
+}
+
+#[test]
+fn finish_registration_of_other_user() {
+    let (conn, user) = create_db();
+    let (_, pending_registration) = begin_registration(&config(), &conn, &user).unwrap();
+
+    assert!(matches!(
+        finish_registration(
+            &config(),
+            &conn,
+            pending_registration,
+            user.id + 1,
+            "Phone".to_string(),
+            &dummy_credential(),
+        ),
+        Err(Error::Expired)
+    ));
+}
+
+#[test]
+fn finish_registration_after_expiry() {
+    let (conn, user) = create_db();
+    let (_, mut pending_registration) = begin_registration(&config(), &conn, &user).unwrap();
+    pending_registration.expiry = Utc::now() - Duration::seconds(1);
+
+    assert!(matches!(
+        finish_registration(
+            &config(),
+            &conn,
+            pending_registration,
+            user.id,
+            "Phone".to_string(),
+            &dummy_credential(),
+        ),
+        Err(Error::Expired)
+    ));
+    assert!(PasskeyEntry::get_by_user(&conn, user.id)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn finish_registration_with_invalid_credential() {
+    let (conn, user) = create_db();
+    let (_, pending_registration) = begin_registration(&config(), &conn, &user).unwrap();
+
+    assert!(matches!(
+        finish_registration(
+            &config(),
+            &conn,
+            pending_registration,
+            user.id,
+            "Phone".to_string(),
+            &dummy_credential(),
+        ),
+        Err(Error::Webauthn(_))
+    ));
+}
+
+#[test]
+fn invalid_origin() {
+    let mut config = config();
+    config.rp_origin = "doorcam".to_string();
+
+    assert!(matches!(webauthn(&config), Err(Error::InvalidOrigin)));
+}
@@ -0,0 +1,109 @@
+//! Single-use password reset codes, which admins issue for users who have forgotten their password.
+
+use super::config::{to_chrono_duration, CONFIG};
+use super::guards::{self, UserGuard};
+use super::{crypto, lockout};
+use crate::db_entry::{rusqlite, Connection, PasswordResetEntry, UserEntry, UserSessionEntry};
+use chrono::{offset::Utc, DateTime};
+use std::net::IpAddr;
+
+#[cfg(test)]
+#[path = "./password_reset_test.rs"]
+mod password_reset_test;
+
+/// The number of random bytes of a reset code.
+const RESET_CODE_LENGTH: usize = 10;
+/// The number of characters in a group of the printable reset code.
+const RESET_CODE_GROUP_LENGTH: usize = 4;
+
+const RESET_CODE_ALPHABET: base32::Alphabet = base32::Alphabet::RFC4648 { padding: false };
+
+/// All errors which could happen during the redemption of a reset code.
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Too many invalid reset codes, please try again in {0} seconds")]
+    LockedOut(i64),
+    #[error("The reset code is invalid or expired")]
+    InvalidCode,
+    #[error(transparent)]
+    Guard(#[from] guards::Error),
+    #[error(transparent)]
+    Database(#[from] rusqlite::Error),
+}
+
+/// Removes the formatting of a reset code, so it can be hashed.
+pub fn normalize(code: &str) -> String {
+    code.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// Generates a random reset code in the printable format `xxxx-xxxx-xxxx-xxxx`.
+fn generate_code() -> String {
+    let mut code = [0; RESET_CODE_LENGTH];
+    crypto::fill_rand_array(&mut code);
+    base32::encode(RESET_CODE_ALPHABET, &code)
+        .to_lowercase()
+        .as_bytes()
+        .chunks(RESET_CODE_GROUP_LENGTH)
+        .map(|group| String::from_utf8_lossy(group).into_owned())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Replaces the reset codes of the user by a new one, which is returned in its printable format.
+pub fn issue(conn: &Connection, user: u32, now: DateTime<Utc>) -> Result<String, rusqlite::Error> {
+    PasswordResetEntry::delete_expired(conn, now)?;
+    PasswordResetEntry::delete_by_user(conn, user)?;
+
+    let code = generate_code();
+    PasswordResetEntry {
+        id: (),
+        code_hash: crypto::hash_token(&normalize(&code)),
+        creation_datetime: now,
+        expiry_datetime: now + to_chrono_duration(CONFIG.security.password_reset_lifetime),
+        user,
+    }
+    .create(conn)?;
+
+    Ok(code)
+}
+
+/// Sets the new password of the user of the reset code and logs the user out everywhere.
+/// Invalid codes are counted as failures of the IP address to prevent brute-forcing.
+pub fn redeem(
+    conn: &Connection,
+    code: &str,
+    pw: &str,
+    client_ip: Option<IpAddr>,
+    now: DateTime<Utc>,
+) -> Result<(), Error> {
+    if let Some(locked_until) = lockout::ip_locked_until(conn, client_ip, now)? {
+        return Err(Error::LockedOut((locked_until - now).num_seconds() + 1));
+    }
+
+    let reset = PasswordResetEntry::get_by_code_hash(conn, &crypto::hash_token(&normalize(code)))?
+        .filter(|reset| reset.expiry_datetime > now);
+    let mut user = match reset.map(|reset| UserEntry::get_by_id(conn, reset.user)) {
+        Some(user) => user?.ok_or(Error::InvalidCode)?,
+        None => {
+            lockout::record_ip_failure(conn, client_ip, now)?;
+            return Err(Error::InvalidCode);
+        }
+    };
+
+    UserGuard::check_new_password(conn, user.id, pw)?;
+
+    user.pw_hash = crypto::hash(pw);
+    user.must_change_password = false;
+    user.update_password(conn)?;
+    UserGuard::record_password(conn, user.id, &user.pw_hash)?;
+
+    // The code can only be used once and the old password may be known to someone else
+    PasswordResetEntry::delete_by_user(conn, user.id)?;
+    UserSessionEntry::delete_by_user(conn, user.id)?;
+    lockout::record_success(conn, &user.name)?;
+
+    Ok(())
+}
@@ -0,0 +1,116 @@
+use super::*;
+use crate::db_entry::{Entry, HashEntry};
+use chrono::Duration;
+use std::net::Ipv4Addr;
+
+const CLIENT_IP: Option<IpAddr> = Some(IpAddr::V4(Ipv4Addr::LOCALHOST));
+const STRONG_PASSWORD: &str = "Correct-Horse-Battery-Staple-42";
+
+fn create_conn() -> Connection {
+    let sql_scheme = include_str!("../../scheme.sql");
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(sql_scheme).unwrap();
+    conn
+}
+
+fn create_user(conn: &Connection) -> UserEntry {
+    UserEntry {
+        pw_hash: HashEntry {
+            hash: "unsecure".to_string(),
+            salt: "salt".to_string(),
+            config: "plain".to_string(),
+        },
+        must_change_password: true,
+        ..Default::default()
+    }
+    .create(conn)
+    .unwrap()
+}
+
+#[test]
+fn printable_code() {
+    let code = generate_code();
+
+    assert_eq!(code.len(), 19);
+    assert_eq!(code.matches('-').count(), 3);
+    assert_eq!(normalize(&code.to_uppercase()).len(), 16);
+}
+
+#[test]
+fn redeem_code_once() {
+    let conn = create_conn();
+    let user = create_user(&conn);
+    let now = Utc::now();
+
+    UserSessionEntry {
+        user: user.get_id(),
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+
+    let code = issue(&conn, user.get_id(), now).unwrap();
+    redeem(&conn, &code, STRONG_PASSWORD, CLIENT_IP, now).unwrap();
+
+    let user = UserEntry::get_by_id(&conn, user.get_id()).unwrap().unwrap();
+    assert!(!user.must_change_password);
+    assert_eq!(
+        crypto::hash_with_config(STRONG_PASSWORD, &user.pw_hash.salt, &user.pw_hash.config)
+            .unwrap(),
+        user.pw_hash.hash
+    );
+    assert!(UserSessionEntry::get_by_user(&conn, user.get_id())
+        .unwrap()
+        .is_empty());
+
+    assert_matches!(
+        redeem(&conn, &code, STRONG_PASSWORD, CLIENT_IP, now),
+        Err(Error::InvalidCode)
+    );
+}
+
+#[test]
+fn new_code_replaces_old_one() {
+    let conn = create_conn();
+    let user = create_user(&conn);
+    let now = Utc::now();
+
+    let old_code = issue(&conn, user.get_id(), now).unwrap();
+    issue(&conn, user.get_id(), now).unwrap();
+
+    assert_matches!(
+        redeem(&conn, &old_code, STRONG_PASSWORD, CLIENT_IP, now),
+        Err(Error::InvalidCode)
+    );
+}
+
+#[test]
+fn redeem_expired_code() {
+    let conn = create_conn();
+    let user = create_user(&conn);
+    let now = Utc::now();
+
+    let code = issue(&conn, user.get_id(), now).unwrap();
+    let later = now + Duration::from_std(CONFIG.security.password_reset_lifetime).unwrap();
+
+    assert_matches!(
+        redeem(&conn, &code, STRONG_PASSWORD, CLIENT_IP, later),
+        Err(Error::InvalidCode)
+    );
+}
+
+#[test]
+fn redeem_with_weak_password() {
+    let conn = create_conn();
+    let user = create_user(&conn);
+    let now = Utc::now();
+
+    let code = issue(&conn, user.get_id(), now).unwrap();
+
+    assert_matches!(
+        redeem(&conn, &code, "123", CLIENT_IP, now),
+        Err(Error::Guard(guards::Error::WeakPassword))
+    );
+    // The code can still be used with a strong password
+    redeem(&conn, &code, STRONG_PASSWORD, CLIENT_IP, now).unwrap();
+}
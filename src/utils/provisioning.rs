@@ -0,0 +1,127 @@
+//! The just-in-time provisioning of users, whose login is managed by an external provider,
+//! e.g. an LDAP directory or an OpenID Connect identity provider.
+
+use super::{ldap, oidc};
+use crate::db_entry::{rusqlite, Connection, Entry, FlatEntry, HashEntry, UserEntry, UserType};
+
+#[cfg(test)]
+#[path = "./provisioning_test.rs"]
+mod provisioning_test;
+
+/// The attributes of an user, which are provided by the external provider.
+#[derive(Debug, PartialEq)]
+pub struct ExternalUser {
+    pub user_type: UserType,
+    pub flat: Option<u32>,
+}
+
+/// Whether the user is managed by an external provider and can't log in with a local password.
+#[inline(always)]
+pub fn is_external_user<FRef: Entry>(user: &UserEntry<u32, FRef>) -> bool {
+    [ldap::HASH_CONFIG, oidc::HASH_CONFIG].contains(&user.pw_hash.config.as_str())
+}
+
+/// Returns the most privileged user type, e.g. of all groups of an user.
+pub fn most_privileged(user_types: &[UserType]) -> Option<UserType> {
+    [UserType::Admin, UserType::FlatManager, UserType::User]
+        .iter()
+        .find(|user_type| user_types.contains(user_type))
+        .copied()
+}
+
+/// Creates or updates the user of the provider with the given hash-config just in time.
+/// Returns `None` if the name belongs to a local account or an user of another provider,
+/// which isn't taken over.
+pub fn sync_user(
+    conn: &Connection,
+    name: &str,
+    hash_config: &str,
+    external_user: &ExternalUser,
+) -> Result<Option<UserEntry>, rusqlite::Error> {
+    let flat = get_flat(conn, external_user)?;
+
+    match UserEntry::get_by_name(conn, name)? {
+        Some(user) if user.pw_hash.config != hash_config => Ok(None),
+        Some(mut user) => {
+            user.user_type = external_user.user_type;
+            user.flat = flat;
+            user.update_without_password(conn)?;
+            Ok(Some(user))
+        }
+        None => create_user(conn, name, hash_config, external_user.user_type, flat).map(Some),
+    }
+}
+
+/// Creates or updates the user of an OpenID Connect provider just in time.
+/// The user is found by its issuer and subject, its name is only changed if it's free.
+/// Returns `None` if a new user has the name of another account, which isn't taken over.
+pub fn sync_oidc_user(
+    conn: &Connection,
+    identity: &oidc::Identity,
+    external_user: &ExternalUser,
+) -> Result<Option<UserEntry>, rusqlite::Error> {
+    let flat = get_flat(conn, external_user)?;
+    let name_is_free = UserEntry::get_by_name(conn, &identity.name)?.is_none();
+
+    match UserEntry::get_by_oidc_subject(conn, &identity.issuer, &identity.subject)? {
+        Some(mut user) => {
+            if name_is_free {
+                user.name = identity.name.clone();
+            }
+            user.user_type = external_user.user_type;
+            user.flat = flat;
+            user.update_without_password(conn)?;
+            Ok(Some(user))
+        }
+        None if name_is_free => {
+            let user = create_user(
+                conn,
+                &identity.name,
+                oidc::HASH_CONFIG,
+                external_user.user_type,
+                flat,
+            )?;
+            user.set_oidc_subject(conn, &identity.issuer, &identity.subject)?;
+            Ok(Some(user))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Returns the flat of the user, unknown flats are ignored, so the user can still log in
+fn get_flat(
+    conn: &Connection,
+    external_user: &ExternalUser,
+) -> Result<Option<FlatEntry>, rusqlite::Error> {
+    match external_user.flat {
+        Some(flat_id) => FlatEntry::get_by_id(conn, flat_id),
+        None => Ok(None),
+    }
+}
+
+/// Creates an user without a local password
+fn create_user(
+    conn: &Connection,
+    name: &str,
+    hash_config: &str,
+    user_type: UserType,
+    flat: Option<FlatEntry>,
+) -> Result<UserEntry, rusqlite::Error> {
+    UserEntry::<(), FlatEntry> {
+        id: (),
+        name: name.to_string(),
+        pw_hash: HashEntry {
+            hash: String::new(),
+            salt: String::new(),
+            config: hash_config.to_string(),
+        },
+        user_type,
+        active: true,
+        flat,
+        totp_secret: None,
+        must_change_password: false,
+        valid_from: None,
+        valid_until: None,
+    }
+    .create(conn)
+}
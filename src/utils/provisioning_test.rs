@@ -0,0 +1,180 @@
+use super::*;
+use crate::db_entry::Connection;
+
+fn get_conn() -> Connection {
+    let sql_scheme = include_str!("../../scheme.sql");
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(sql_scheme).unwrap();
+    conn
+}
+
+#[test]
+fn sync_creates_and_updates_user() {
+    let conn = get_conn();
+    let flat = FlatEntry::<()> {
+        id: (),
+        ..Default::default()
+    }
+    .create(&conn)
+    .unwrap();
+
+    let user = sync_user(
+        &conn,
+        "alice",
+        ldap::HASH_CONFIG,
+        &ExternalUser {
+            user_type: UserType::User,
+            flat: Some(flat.id),
+        },
+    )
+    .unwrap()
+    .unwrap();
+    assert!(is_external_user(&user));
+    assert!(user.active);
+    assert_eq!(user.flat, Some(flat));
+
+    let updated_user = sync_user(
+        &conn,
+        "alice",
+        ldap::HASH_CONFIG,
+        &ExternalUser {
+            user_type: UserType::Admin,
+            flat: Some(42),
+        },
+    )
+    .unwrap()
+    .unwrap();
+    assert_eq!(updated_user.id, user.id);
+    assert_eq!(updated_user.user_type, UserType::Admin);
+    assert_eq!(updated_user.flat, None);
+    assert_eq!(
+        UserEntry::get_by_id(&conn, user.id).unwrap().unwrap(),
+        updated_user
+    );
+}
+
+#[test]
+fn sync_does_not_take_over_local_account() {
+    let conn = get_conn();
+
+    let local_user = sync_user(
+        &conn,
+        "admin",
+        ldap::HASH_CONFIG,
+        &ExternalUser {
+            user_type: UserType::User,
+            flat: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(local_user, None);
+    assert_eq!(
+        UserEntry::get_by_name(&conn, "admin")
+            .unwrap()
+            .unwrap()
+            .user_type,
+        UserType::Admin
+    );
+}
+
+#[test]
+fn sync_does_not_take_over_user_of_other_provider() {
+    let conn = get_conn();
+    let external_user = ExternalUser {
+        user_type: UserType::User,
+        flat: None,
+    };
+
+    let user = sync_user(&conn, "alice", ldap::HASH_CONFIG, &external_user)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        sync_user(&conn, "alice", oidc::HASH_CONFIG, &external_user).unwrap(),
+        None
+    );
+    assert_eq!(
+        UserEntry::get_by_name(&conn, "alice").unwrap().unwrap(),
+        user
+    );
+}
+
+fn oidc_identity(subject: &str, name: &str) -> oidc::Identity {
+    oidc::Identity {
+        issuer: "https://idp.example.org".to_string(),
+        subject: subject.to_string(),
+        name: name.to_string(),
+    }
+}
+
+#[test]
+fn sync_oidc_user_by_subject() {
+    let conn = get_conn();
+    let external_user = ExternalUser {
+        user_type: UserType::User,
+        flat: None,
+    };
+
+    let user = sync_oidc_user(&conn, &oidc_identity("f81d4fae", "alice"), &external_user)
+        .unwrap()
+        .unwrap();
+    assert!(is_external_user(&user));
+    assert_eq!(user.name, "alice");
+
+    // The name is only shown and follows the identity provider
+    let renamed_user = sync_oidc_user(
+        &conn,
+        &oidc_identity("f81d4fae", "alice.smith"),
+        &external_user,
+    )
+    .unwrap()
+    .unwrap();
+    assert_eq!(renamed_user.id, user.id);
+    assert_eq!(renamed_user.name, "alice.smith");
+    assert_eq!(
+        UserEntry::get_by_id(&conn, user.id).unwrap().unwrap(),
+        renamed_user
+    );
+}
+
+#[test]
+fn sync_oidc_user_with_name_of_other_user() {
+    let conn = get_conn();
+    let alice = sync_oidc_user(
+        &conn,
+        &oidc_identity("f81d4fae", "alice"),
+        &ExternalUser {
+            user_type: UserType::Admin,
+            flat: None,
+        },
+    )
+    .unwrap()
+    .unwrap();
+    let external_user = ExternalUser {
+        user_type: UserType::User,
+        flat: None,
+    };
+
+    // Another user of the identity provider renamed itself
+    assert_eq!(
+        sync_oidc_user(&conn, &oidc_identity("9b2c71e0", "alice"), &external_user).unwrap(),
+        None
+    );
+    assert_eq!(
+        sync_oidc_user(&conn, &oidc_identity("9b2c71e0", "admin"), &external_user).unwrap(),
+        None
+    );
+    assert_eq!(
+        UserEntry::get_by_name(&conn, "alice").unwrap().unwrap(),
+        alice
+    );
+
+    // An existing user keeps its name, if the new one is taken
+    let bob = sync_oidc_user(&conn, &oidc_identity("4d5e6f70", "bob"), &external_user)
+        .unwrap()
+        .unwrap();
+    let renamed_bob = sync_oidc_user(&conn, &oidc_identity("4d5e6f70", "alice"), &external_user)
+        .unwrap()
+        .unwrap();
+    assert_eq!(renamed_bob.id, bob.id);
+    assert_eq!(renamed_bob.name, "bob");
+}
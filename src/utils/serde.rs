@@ -1,7 +1,9 @@
-//! Implements utilities for Deserialization
+//! Implements utilities for Serialization and Deserialization
 
+use super::config::CONFIG;
+use chrono::{offset::Utc, DateTime};
 use duration_str::deserialize_duration;
-use serde::{Deserialize, Deserializer};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::time::Duration;
 
 /// Implements the function for `serde(deserialize_with = "$deserialize_name")` where
@@ -28,3 +30,18 @@ deserialize_optional_with! {pub deserialize_optional_duration, Duration, "deseri
 "Implements the function for `serde(deserialize_with = \"deserialize_duration\")` where `Duration` is wrapped in a `Option`.
 To use it, add `serde(default, deserialize_with = \"deserialize_optional_duration\")`."
 }
+
+/// Serializes an optional point in time with the offset of the building's timezone,
+/// so the templates show it in the local time.
+/// To use it, add `serde(serialize_with = "serialize_optional_local_datetime")`.
+pub fn serialize_optional_local_datetime<S>(
+    datetime: &Option<DateTime<Utc>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    datetime
+        .map(|datetime| CONFIG.building.local_datetime(datetime))
+        .serialize(serializer)
+}
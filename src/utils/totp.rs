@@ -0,0 +1,133 @@
+//! Time-based one-time passwords according to [RFC 6238](https://datatracker.ietf.org/doc/html/rfc6238).
+
+use super::crypto;
+use base32::Alphabet;
+use hmac::{Hmac, Mac, NewMac};
+use qrcode::{render::svg, QrCode};
+use rocket::http::uri::Uri;
+use sha1::Sha1;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[cfg(test)]
+#[path = "./totp_test.rs"]
+mod totp_test;
+
+/// The length of the shared secret in bytes, as recommended for HMAC-SHA1.
+const SECRET_LENGTH: usize = 20;
+/// The duration of one time step in seconds.
+const TIME_STEP: u64 = 30;
+/// The number of digits of a code.
+const DIGITS: u32 = 6;
+/// The number of time steps a code may differ to compensate clock drifts.
+const ALLOWED_DRIFT: u64 = 1;
+
+/// The number of recovery codes, which are generated on enrollment.
+const RECOVERY_CODE_COUNT: usize = 10;
+
+const SECRET_ALPHABET: Alphabet = Alphabet::RFC4648 { padding: false };
+
+/// Generates a random shared secret in Base32 representation.
+pub fn generate_secret() -> String {
+    let mut secret = [0; SECRET_LENGTH];
+    crypto::fill_rand_array(&mut secret);
+    base32::encode(SECRET_ALPHABET, &secret)
+}
+
+/// Generates random recovery codes in the format `xxxxx-xxxxx`.
+pub fn generate_recovery_codes() -> Vec<String> {
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let mut code = [0; 6];
+            crypto::fill_rand_array(&mut code);
+            let code = base32::encode(SECRET_ALPHABET, &code).to_lowercase();
+            format!("{}-{}", &code[..5], &code[5..])
+        })
+        .collect()
+}
+
+/// Removes the formatting of a recovery code, so it can be hashed.
+pub fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// Calculates the HMAC-based one-time password of RFC 4226.
+fn hotp(secret: &[u8], counter: u64) -> u32 {
+    let mut mac = Hmac::<Sha1>::new_varkey(secret).expect("HMAC accepts keys of any length");
+    mac.update(&counter.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+
+    // Dynamic truncation
+    let offset = (hash[hash.len() - 1] & 0xf) as usize;
+    let code = u32::from_be_bytes([
+        hash[offset] & 0x7f,
+        hash[offset + 1],
+        hash[offset + 2],
+        hash[offset + 3],
+    ]);
+
+    code % 10_u32.pow(DIGITS)
+}
+
+/// Calculates the code of the Base32 secret at a UNIX timestamp.
+pub fn code_at(secret: &str, timestamp: u64) -> Option<String> {
+    let secret = base32::decode(SECRET_ALPHABET, secret)?;
+    Some(format!(
+        "{:0width$}",
+        hotp(&secret, timestamp / TIME_STEP),
+        width = DIGITS as usize
+    ))
+}
+
+/// Returns the time step of the code, if it matches the Base32 secret at a UNIX timestamp.
+pub fn step_at(secret: &str, code: &str, timestamp: u64) -> Option<u64> {
+    let code = code.trim();
+    (0..=2 * ALLOWED_DRIFT)
+        .filter_map(|step| (timestamp + step * TIME_STEP).checked_sub(ALLOWED_DRIFT * TIME_STEP))
+        .find(|timestamp| code_at(secret, *timestamp).map_or(false, |expected| expected == code))
+        .map(|timestamp| timestamp / TIME_STEP)
+}
+
+/// Checks whether the code matches the Base32 secret at a UNIX timestamp.
+pub fn verify_at(secret: &str, code: &str, timestamp: u64) -> bool {
+    step_at(secret, code, timestamp).is_some()
+}
+
+/// The current UNIX timestamp
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+/// Returns the time step of the code, if it matches the Base32 secret now.
+/// The step has to be stored to reject a replay of the code.
+pub fn step(secret: &str, code: &str) -> Option<u64> {
+    step_at(secret, code, now())
+}
+
+/// Checks whether the code matches the Base32 secret now.
+pub fn verify(secret: &str, code: &str) -> bool {
+    verify_at(secret, code, now())
+}
+
+/// Creates the URI which is understood by authenticator apps.
+pub fn provisioning_uri(secret: &str, account: &str) -> String {
+    format!(
+        "otpauth://totp/DoorCam:{}?secret={}&issuer=DoorCam&digits={}&period={}",
+        Uri::percent_encode(account),
+        secret,
+        DIGITS,
+        TIME_STEP
+    )
+}
+
+/// Renders the provisioning URI as a SVG QR code.
+pub fn qr_code_svg(provisioning_uri: &str) -> Result<String, qrcode::types::QrError> {
+    Ok(QrCode::new(provisioning_uri.as_bytes())?
+        .render::<svg::Color>()
+        .min_dimensions(200, 200)
+        .build())
+}
@@ -0,0 +1,69 @@
+use super::*;
+
+/// The secret "12345678901234567890" of the RFC 6238 test vectors in Base32
+const RFC_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+#[test]
+fn rfc_6238_test_vectors() {
+    assert_eq!(code_at(RFC_SECRET, 59).unwrap(), "287082");
+    assert_eq!(code_at(RFC_SECRET, 1_111_111_109).unwrap(), "081804");
+    assert_eq!(code_at(RFC_SECRET, 1_234_567_890).unwrap(), "005924");
+    assert_eq!(code_at(RFC_SECRET, 20_000_000_000).unwrap(), "353130");
+}
+
+#[test]
+fn invalid_secret() {
+    assert_matches!(code_at("1nv4l1d!", 59), None);
+    assert!(!verify_at("1nv4l1d!", "287082", 59));
+}
+
+#[test]
+fn verify_with_drift() {
+    assert!(verify_at(RFC_SECRET, "287082", 59));
+    assert!(verify_at(RFC_SECRET, "287082", 59 + 30));
+    assert!(verify_at(RFC_SECRET, " 287082 ", 59 - 30));
+    assert!(!verify_at(RFC_SECRET, "287082", 59 + 60));
+}
+
+#[test]
+fn step_of_code() {
+    assert_eq!(step_at(RFC_SECRET, "287082", 59), Some(1));
+    assert_eq!(step_at(RFC_SECRET, "287082", 59 + 30), Some(1));
+    assert_eq!(step_at(RFC_SECRET, "287082", 59 + 60), None);
+}
+
+#[test]
+fn verify_generated_secret() {
+    let secret = generate_secret();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    assert!(verify(&secret, &code_at(&secret, now).unwrap()));
+    assert!(!verify(&secret, "abcdef"));
+}
+
+#[test]
+fn qr_code_of_provisioning_uri() {
+    let uri = provisioning_uri(RFC_SECRET, "Alice & Bob");
+
+    assert!(uri.starts_with("otpauth://totp/DoorCam:Alice%20%26%20Bob?secret="));
+    assert!(qr_code_svg(&uri).unwrap().starts_with("<?xml"));
+}
+
+#[test]
+fn recovery_codes() {
+    let codes = generate_recovery_codes();
+
+    assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+    for code in codes {
+        assert_eq!(code.len(), 11);
+        assert_eq!(normalize_recovery_code(&code.to_uppercase()).len(), 10);
+    }
+}
+
+#[test]
+fn normalize_formatted_recovery_code() {
+    assert_eq!(normalize_recovery_code(" AbCde-fGhij\n"), "abcdefghij");
+}
@@ -3,4 +3,120 @@ function hideMessage() {
     setTimeout(function() {
         $('.flash').fadeOut("slow", () => {});
     }, 5000);
-}
\ No newline at end of file
+}
+
+function csrfToken() {
+    //Token which has to be sent with every state-changing request
+    return $('meta[name="csrf-token"]').attr('content');
+}
+
+function base64UrlToBuffer(value) {
+    //WebAuthn transfers binary data as base64url in JSON
+    let base64 = value.replace(/-/g, '+').replace(/_/g, '/');
+    return Uint8Array.from(atob(base64), c => c.charCodeAt(0)).buffer;
+}
+
+function bufferToBase64Url(buffer) {
+    let base64 = btoa(String.fromCharCode(...new Uint8Array(buffer)));
+    return base64.replace(/\+/g, '-').replace(/\//g, '_').replace(/=/g, '');
+}
+
+function postJson(url, body) {
+    //Errors are shown as flash message after a reload
+    return fetch(url, {
+        method: 'POST',
+        headers: {'Content-Type': 'application/json', 'X-CSRF-Token': csrfToken()},
+        body: JSON.stringify(body),
+    }).then(response => {
+        if (!response.ok) {
+            window.location.reload();
+            throw new Error(response.statusText);
+        }
+        return response;
+    });
+}
+
+function registerPasskey(url, name) {
+    //Lets the authenticator create a passkey for the options of the server
+    postJson(url, {})
+        .then(response => response.json())
+        .then(options => {
+            let publicKey = options.publicKey;
+            publicKey.challenge = base64UrlToBuffer(publicKey.challenge);
+            publicKey.user.id = base64UrlToBuffer(publicKey.user.id);
+            (publicKey.excludeCredentials || []).forEach(c => c.id = base64UrlToBuffer(c.id));
+            return navigator.credentials.create({publicKey});
+        })
+        .then(credential => postJson(url + '/finish', {
+            name,
+            credential: {
+                id: credential.id,
+                rawId: bufferToBase64Url(credential.rawId),
+                type: credential.type,
+                extensions: credential.getClientExtensionResults(),
+                response: {
+                    attestationObject: bufferToBase64Url(credential.response.attestationObject),
+                    clientDataJSON: bufferToBase64Url(credential.response.clientDataJSON),
+                },
+            },
+        }))
+        .then(() => window.location.reload())
+        .catch(error => console.log(error));
+}
+
+function loginWithPasskey(url, name) {
+    //Lets the authenticator sign the challenge of the server
+    postJson(url, {name})
+        .then(response => response.json())
+        .then(options => {
+            let publicKey = options.publicKey;
+            publicKey.challenge = base64UrlToBuffer(publicKey.challenge);
+            (publicKey.allowCredentials || []).forEach(c => c.id = base64UrlToBuffer(c.id));
+            return navigator.credentials.get({publicKey});
+        })
+        .then(credential => postJson(url + '/finish', {
+            id: credential.id,
+            rawId: bufferToBase64Url(credential.rawId),
+            type: credential.type,
+            extensions: credential.getClientExtensionResults(),
+            response: {
+                authenticatorData: bufferToBase64Url(credential.response.authenticatorData),
+                clientDataJSON: bufferToBase64Url(credential.response.clientDataJSON),
+                signature: bufferToBase64Url(credential.response.signature),
+                userHandle: credential.response.userHandle && bufferToBase64Url(credential.response.userHandle),
+            },
+        }))
+        .then(response => response.json())
+        .then(login => window.location.href = login.redirect_url)
+        .catch(error => console.log(error));
+}
+
+function watchDoorState(url) {
+    //Polls the live states of the doors, which are keyed by their ID
+    setInterval(function() {
+        fetch(url)
+            .then(response => response.json())
+            .then(states => $.each(states, (id, state) => $('#door-state-' + id).text(state)))
+            .catch(error => console.log(error));
+    }, 2000);
+}
+
+function showCountdowns() {
+    //Counts down the remaining time of the held open doors and reloads the page at their end
+    function update() {
+        $('.door-countdown').each(function() {
+            const remaining = Math.max(0, Math.floor((new Date($(this).data('until')) - Date.now()) / 1000));
+            if (remaining === 0) {
+                // The opener has been deactivated, give the door a moment to lock
+                $(this).removeClass('door-countdown');
+                setTimeout(() => location.reload(), 1000);
+            }
+            const hours = Math.floor(remaining / 3600);
+            const minutes = String(Math.floor(remaining / 60) % 60).padStart(2, '0');
+            const seconds = String(remaining % 60).padStart(2, '0');
+            $(this).text(hours + ':' + minutes + ':' + seconds);
+        });
+    }
+    update();
+    setInterval(update, 1000);
+}